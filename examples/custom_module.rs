@@ -76,7 +76,7 @@ where
         &self,
         device: &D,
         len: usize,
-        parents: impl custos::Parents<NUM_PARENTS>,
+        parents: &impl custos::Parents<NUM_PARENTS>,
     ) -> custos::Result<Self::Wrap<T, <D>::Base<T, S>>>
     where
         S: Shape,
@@ -87,12 +87,15 @@ where
         self.mods.retrieve(device, len, parents)
     }
 
-    fn on_retrieve_finish(&self, _retrieved_buf: &custos::prelude::Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl custos::Parents<NUM_PARENTS>,
+        retrieved_buf: &mut custos::prelude::Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
         // inject custom behaviour in this body
 
-        self.mods.on_retrieve_finish(_retrieved_buf)
+        self.mods.on_retrieve_finish(parents, retrieved_buf)
     }
 }
@@ -53,7 +53,7 @@ where
     S: Shape,
     D: Device,
     D::Base<T, S>: Deref<Target = [T]>,
-    Mods: Retrieve<Self, T, S>,
+    Mods: Retrieve<Self, T, S> + Default,
 {
     fn add(&self, lhs: &Buffer<T, D, S>, rhs: &Buffer<T, D, S>) -> Buffer<T, Self, S> {
         let mut out = self.retrieve(S::LEN, ()).unwrap(); // this works as well and in this case (Stack), does exactly the same as the line above.
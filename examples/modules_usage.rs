@@ -109,7 +109,8 @@ where
                 let cpu_out = unsafe { &mut *(out as *mut Buffer<_, OpenCL<Mods>, _>) };
                 dev.use_cpu_or_gpu(
                     (file!(), line!(), column!()).into(),
-                    &[lhs.len()],
+                    &[lhs.len(), rhs.len()],
+                    &[out.len()],
                     || add_ew_slice(lhs, rhs, cpu_out),
                     || try_add_ew_cl(dev, lhs, rhs, out).unwrap(),
                 );
@@ -7,6 +7,9 @@ pub trait Replicate {
     type Replication<'r>;
     type Downcast<'r>: 'r;
 
+    /// Whether the replicated argument may be written to by an operation.
+    const MUTABLE: bool = false;
+
     #[cfg(feature = "std")]
     unsafe fn replicate_borrowed<'r, B: Downcast>(
         id: &Id,
@@ -34,6 +37,9 @@ pub trait Replicate2<D: Device> {
 pub trait AnyOp: Sized {
     type Replicated<'a>;
 
+    /// Marks which of the arguments may be written to, in argument order.
+    const MUTABLE_ARGS: &'static [bool];
+
     #[cfg(feature = "std")]
     fn replication_fn<B: Downcast>(
         op: impl for<'a> Fn(Self::Replicated<'a>) -> crate::Result<()> + 'static,
@@ -76,6 +82,8 @@ impl<'a, T: 'static, D: Device + 'static, S: crate::Shape> Replicate
     type Replication<'r> = &'r mut Self::Downcast<'r>;
     type Downcast<'r> = Buffer<'r, T, D, S>;
 
+    const MUTABLE: bool = true;
+
     #[cfg(feature = "std")]
     unsafe fn replicate_borrowed<'r, B: Downcast>(
         id: &Id,
@@ -103,6 +111,8 @@ impl<'a, T: 'static, D: Device + 'static, S: crate::Shape> Replicate
 }
 
 impl<R: crate::HasId + Replicate> AnyOp for R {
+    const MUTABLE_ARGS: &'static [bool] = &[R::MUTABLE];
+
     #[cfg(feature = "std")]
     fn replication_fn<B: Downcast>(
         op: impl for<'a> Fn(Self::Replicated<'a>) -> crate::Result<()> + 'static,
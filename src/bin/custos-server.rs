@@ -0,0 +1,15 @@
+//! Serves `Remote` devices with the host CPU.
+//!
+//! Usage: `custos-server [address]`, the address defaults to `127.0.0.1:11001`.
+use custos::{network::RemoteServer, Base, CPU};
+
+fn main() -> custos::Result<()> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:11001".to_string());
+
+    let server = RemoteServer::bind(&addr)?;
+    println!("custos server listening on {}", server.local_addr()?);
+
+    server.serve(&CPU::<Base>::new())
+}
@@ -0,0 +1,26 @@
+use crate::{Buffer, Device, Resolve, Shape, TwoWay, Unit};
+
+/// Applies a function to two buffers element-wise and returns a new buffer.
+pub trait ApplyBinaryFunction<T: Unit, S: Shape = (), D: Device = Self>: Device {
+    /// Applies a function to two buffers element-wise and returns a new buffer.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{CPU, ApplyBinaryFunction, Combiner, Base, Device};
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let lhs = device.buffer([1., 2., 3.]);
+    /// let rhs = device.buffer([4., 5., 6.]);
+    ///
+    /// let out = device.apply_binary_fn(&lhs, &rhs, |x, y| x.mul(y).add(1.));
+    /// assert_eq!(out.read(), [5., 11., 19.]);
+    /// ```
+    fn apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<T> + 'static;
+}
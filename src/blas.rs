@@ -0,0 +1,190 @@
+use crate::{Buffer, Device, DeviceError, Unit};
+
+/// BLAS level 1 and level 2 routines on [`Buffer`]s.
+/// The lengths of the buffers are checked against each other and the given dimensions.
+///
+/// The CPU calls into [`GenericBlas`](crate::GenericBlas) if the `blas` feature is enabled and uses pure-Rust loops otherwise.
+pub trait Blas<T: Unit>: Device {
+    /// Computes `y = alpha * x + y`.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Base, Blas, Device, CPU};
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let x = device.buffer([1., 2., 3.]);
+    /// let mut y = device.buffer([1., 1., 1.]);
+    ///
+    /// device.axpy(2., &x, &mut y).unwrap();
+    /// assert_eq!(y.read(), [3., 5., 7.]);
+    /// ```
+    fn axpy(&self, alpha: T, x: &Buffer<T, Self>, y: &mut Buffer<T, Self>) -> crate::Result<()>;
+
+    /// Returns the dot product of `x` and `y`.
+    fn dot(&self, x: &Buffer<T, Self>, y: &Buffer<T, Self>) -> crate::Result<T>;
+
+    /// Returns the euclidean norm of `x`.
+    fn nrm2(&self, x: &Buffer<T, Self>) -> crate::Result<T>;
+
+    /// Computes `x = alpha * x`.
+    fn scal(&self, alpha: T, x: &mut Buffer<T, Self>) -> crate::Result<()>;
+
+    /// Computes the matrix-vector product `y = alpha * a * x + beta * y` for the row-major `m x n` matrix `a`.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Base, Blas, Device, CPU};
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let a = device.buffer([1., 2., 3., 4., 5., 6.]);
+    /// let x = device.buffer([1., 0., 1.]);
+    /// let mut y = device.buffer([0.; 2]);
+    ///
+    /// device.gemv(2, 3, 1., &a, &x, 0., &mut y).unwrap();
+    /// assert_eq!(y.read(), [4., 10.]);
+    /// ```
+    #[allow(clippy::too_many_arguments)]
+    fn gemv(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &Buffer<T, Self>,
+        x: &Buffer<T, Self>,
+        beta: T,
+        y: &mut Buffer<T, Self>,
+    ) -> crate::Result<()>;
+
+    /// Computes the rank-1 update `a = alpha * x * y^T + a` for the row-major `m x n` matrix `a`.
+    fn ger(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        x: &Buffer<T, Self>,
+        y: &Buffer<T, Self>,
+        a: &mut Buffer<T, Self>,
+    ) -> crate::Result<()>;
+}
+
+/// Returns [`DeviceError::BlasLengthMismatch`] if a buffer length does not match its expected length.
+/// `lengths` contains buffer lengths and the rows and columns of the expected lengths.
+#[inline]
+pub(crate) fn check_blas_lengths(lengths: &[(usize, usize, usize)]) -> crate::Result<()> {
+    let matches = |&(len, rows, cols): &(usize, usize, usize)| rows.checked_mul(cols) == Some(len);
+    if !lengths.iter().all(matches) {
+        return Err(DeviceError::BlasLengthMismatch.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_blas_level1_cpu() {
+        use crate::{Base, Blas, Device, CPU};
+
+        let device = CPU::<Base>::new();
+        let x = device.buffer([3f32, 4.]);
+        let mut y = device.buffer([1f32, 2.]);
+
+        assert_eq!(device.dot(&x, &y).unwrap(), 11.);
+        assert_eq!(device.nrm2(&x).unwrap(), 5.);
+
+        device.axpy(-1., &x, &mut y).unwrap();
+        assert_eq!(y.read(), [-2., -2.]);
+
+        device.scal(0.5, &mut y).unwrap();
+        assert_eq!(y.read(), [-1., -1.]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_blas_level2_cpu() {
+        use crate::{Base, Blas, Device, CPU};
+
+        let device = CPU::<Base>::new();
+        let a = device.buffer([1f64, 2., 3., 4., 5., 6.]);
+        let x = device.buffer([1f64, 2., 3.]);
+        let mut y = device.buffer([1f64, 1.]);
+
+        device.gemv(2, 3, 2., &a, &x, 3., &mut y).unwrap();
+        assert_eq!(y.read(), [31., 67.]);
+
+        let mut a = device.buffer([1f64; 6]);
+        let x = device.buffer([1f64, 2.]);
+        let y = device.buffer([1f64, 0., -1.]);
+        device.ger(2, 3, 2., &x, &y, &mut a).unwrap();
+        assert_eq!(a.read(), [3., 1., -1., 5., 1., -3.]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_blas_length_mismatch() {
+        use crate::{Base, Blas, Device, DeviceError, ErrorKind, CPU};
+
+        let device = CPU::<Base>::new();
+        let a = device.buffer([1f32; 6]);
+        let x = device.buffer([1f32; 2]);
+        let mut y = device.buffer([1f32; 3]);
+
+        let err = device.axpy(1., &x, &mut y).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::BlasLengthMismatch));
+
+        let err = device.gemv(2, 3, 1., &a, &x, 0., &mut y).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::BlasLengthMismatch));
+
+        let err = device
+            .gemv(usize::MAX, 3, 1., &a, &x, 0., &mut y)
+            .unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::BlasLengthMismatch));
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_blas_lazy_cpu() {
+        use crate::{Base, Blas, Device, Lazy, Run, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let x = device.buffer([3f32, 4.]);
+        let mut y = device.buffer([1f32, 2.]);
+
+        device.axpy(-1., &x, &mut y).unwrap();
+        device.scal(0.5, &mut y).unwrap();
+        assert_eq!(y.read(), [1., 2.]);
+
+        device.run().unwrap();
+        assert_eq!(y.read(), [-1., -1.]);
+    }
+
+    #[cfg(feature = "cuda")]
+    #[test]
+    fn test_blas_cu() {
+        use crate::{Base, Blas, Device, CUDA};
+
+        let device = CUDA::<Base>::new(0).unwrap();
+        let x = device.buffer([3f32, 4.]);
+        let mut y = device.buffer([1f32, 2.]);
+
+        assert_eq!(device.dot(&x, &y).unwrap(), 11.);
+        assert_eq!(device.nrm2(&x).unwrap(), 5.);
+
+        device.axpy(-1., &x, &mut y).unwrap();
+        device.scal(0.5, &mut y).unwrap();
+        assert_eq!(y.read(), [-1., -1.]);
+
+        let a = device.buffer([1f32, 2., 3., 4., 5., 6.]);
+        let x = device.buffer([1f32, 2., 3.]);
+        let mut y = device.buffer([1f32, 1.]);
+        device.gemv(2, 3, 2., &a, &x, 3., &mut y).unwrap();
+        assert_eq!(y.read(), [31., 67.]);
+
+        let mut a = device.buffer([1f32; 6]);
+        let x = device.buffer([1f32, 2.]);
+        let y = device.buffer([1f32, 0., -1.]);
+        device.ger(2, 3, 2., &x, &y, &mut a).unwrap();
+        assert_eq!(a.read(), [3., 1., -1., 5., 1., -3.]);
+    }
+}
@@ -19,6 +19,8 @@ pub use self::num::Num;
 pub use impl_from_const::*;
 
 mod impl_autograd;
+#[cfg(feature = "forward")]
+mod impl_forward;
 mod impl_from;
 mod impl_from_const;
 mod num;
@@ -129,6 +131,47 @@ impl<'a, T: Unit, D: Device, S: Shape> Buffer<'a, T, D, S> {
     {
         self.set_require_grad(false)
     }
+
+    /// Returns an alias of this `Buffer` that shares its memory, but is cut from the gradient computation.
+    /// The alias does not require a gradient, and operations using it do not compute a gradient for `self`.
+    /// Writing to the alias changes `self` as well.
+    ///
+    /// With a [`Lazy`](crate::Lazy) module, lazily allocated buffers must be replaced (see [`Buffer::replace`]) after running the graph before they can be detached.
+    /// # Example
+    #[cfg_attr(all(feature = "cpu", feature = "autograd"), doc = "```")]
+    #[cfg_attr(not(all(feature = "cpu", feature = "autograd")), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Combiner, Device, HasId, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([1., 2., 3.]).require_grad();
+    ///
+    /// let detached = x.detach();
+    /// assert!(!detached.requires_grad());
+    /// assert_eq!(detached.read(), [1., 2., 3.]);
+    ///
+    /// let out = device.unary_ew(&detached, |x| x.mul(x), |x| x.mul(2.));
+    /// out.backward().unwrap();
+    /// assert_eq!(x.grad().read(), [0.; 3]);
+    /// ```
+    pub fn detach<'b>(&'b self) -> Buffer<'b, T, D, S>
+    where
+        D: OnNewBuffer<'b, T, D, S>,
+        D::Data<T, S>: ShallowCopy,
+    {
+        // the alias borrows `self`, hence it cannot outlive the memory
+        let mut detached = unsafe { self.shallow() };
+        #[cfg(feature = "autograd")]
+        detached.data.set_detached_id(crate::next_reserved_id());
+        detached.set_requires_grad(false);
+
+        // modules that track buffers by id have to know about the new id
+        if detached.id() != self.id() {
+            if let Some(device) = detached.device {
+                unsafe { device.on_new_buffer(device, &detached) };
+            }
+        }
+        detached
+    }
 }
 
 // DO NOT implement!
@@ -154,6 +197,16 @@ impl<'a, T: Unit, D: Device, S: Shape> HasId for Buffer<'a, T, D, S> {
     fn set_requires_grad(&mut self, requires_grad: bool) {
         self.data.set_requires_grad(requires_grad);
     }
+
+    #[inline]
+    fn set_detached_id(&mut self, id: crate::UniqueId) {
+        self.data.set_detached_id(id);
+    }
+
+    #[inline]
+    fn alias_of(&self) -> Option<crate::UniqueId> {
+        self.data.alias_of()
+    }
 }
 
 impl<'a, T: Unit, D: Device, S: Shape> HasId for &Buffer<'a, T, D, S> {
@@ -171,6 +224,11 @@ impl<'a, T: Unit, D: Device, S: Shape> HasId for &Buffer<'a, T, D, S> {
     fn set_requires_grad(&mut self, _requires_grad: bool) {
         unimplemented!("Cannot use on &Buffer. Use on &mut Buffer.");
     }
+
+    #[inline]
+    fn alias_of(&self) -> Option<crate::UniqueId> {
+        self.data.alias_of()
+    }
 }
 
 impl<'a, T: Unit, D: Device, S: Shape> HasId for &mut Buffer<'a, T, D, S> {
@@ -188,12 +246,22 @@ impl<'a, T: Unit, D: Device, S: Shape> HasId for &mut Buffer<'a, T, D, S> {
     fn set_requires_grad(&mut self, requires_grad: bool) {
         self.data.set_requires_grad(requires_grad);
     }
+
+    #[inline]
+    fn set_detached_id(&mut self, id: crate::UniqueId) {
+        self.data.set_detached_id(id);
+    }
+
+    #[inline]
+    fn alias_of(&self) -> Option<crate::UniqueId> {
+        self.data.alias_of()
+    }
 }
 
 impl<'a, T: Unit, D: Device, S: Shape> Drop for Buffer<'a, T, D, S> {
     #[inline]
     fn drop(&mut self) {
-        if self.data.flag() != AllocFlag::None {
+        if !self.data.flag().notify_on_drop() {
             return;
         }
 
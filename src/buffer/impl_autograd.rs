@@ -54,6 +54,62 @@ where
             Ok(())
         }
     }
+
+    /// Like [`Buffer::backward`], but the gradient computations are recorded as well (see [`Tape::backward_create_graph`]).
+    /// Hence, gradients can be used in further (differentiable) operations and `backward` can be called again.
+    ///
+    /// Afterwards, gradients of type `Buffer<T, D, S>` can be used as arguments of operations.
+    /// The next backward pass computes new gradients, i.e. `x.grad()` returns the second-order gradient afterwards.
+    /// The first-order gradients stay valid until the backward pass after that.
+    /// Gradients of a higher order than two are not supported.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([1., 2., 3.]).require_grad();
+    ///
+    /// // out = x^3
+    /// let out = device.unary_ew_second_order(&x, |x| x.mul(x).mul(x), |x| x.mul(x).mul(3.), |x| x.mul(6.));
+    /// out.backward_create_graph().unwrap();
+    ///
+    /// let grad = x.grad().read_to_vec();
+    /// assert_eq!(grad, [3., 12., 27.]);
+    ///
+    /// // d(sum(3x^2)) / dx = 6x
+    /// let first_order = x.grad();
+    /// first_order.backward().unwrap();
+    /// assert_eq!(x.grad().read(), [6., 12., 18.]);
+    /// assert_eq!(first_order.read(), [3., 12., 27.]);
+    /// ```
+    pub fn backward_create_graph<'b>(&self) -> crate::Result<()>
+    where
+        T: Clone + One + 'static,
+        D: CachedBuffers
+            + TapeActions<'b>
+            + GradActions
+            + ZeroGrad<T>
+            + WriteBuf<T, S, D>
+            + Alloc<T>
+            + AddOperation
+            + IsShapeIndep
+            + 'static,
+        D::Data<T, S>: ShallowCopy,
+    {
+        let Some(tape) = (unsafe { self.device().tape_mut() }) else {
+            return Ok(());
+        };
+        let mut buffers = unsafe { self.device().buffers_mut() };
+        let seed = vec![T::one(); self.len()];
+        tape.backward_seeded_create_graph(self, &seed, buffers.as_deref_mut())?;
+
+        // gradients are not registered by default, as they are not created via retrieve or Buffer::new
+        if let Some(gradients) = unsafe { self.device().gradients_mut() } {
+            gradients.register_grads::<T, D, S>(buffers.as_deref_mut());
+        }
+        Ok(())
+    }
 }
 
 impl<'a, T, D, S> Buffer<'a, T, D, S>
@@ -124,6 +180,36 @@ where
         unsafe { self.grad_mut_unbound() }
     }
 
+    /// Registers a hook that is called with the gradient of this buffer once it was computed during a backward pass (e.g. for logging or an all-reduce across devices).
+    /// The hook is called in every backward pass until this buffer is dropped.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([1., 2., 3.]).require_grad();
+    ///
+    /// // e.g. averages the gradient with the gradients of other workers
+    /// x.register_grad_hook(|grad| {
+    ///     for value in grad.iter_mut() {
+    ///         *value /= 2.;
+    ///     }
+    /// });
+    ///
+    /// let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+    /// out.backward().unwrap();
+    /// assert_eq!(x.grad().read(), [1., 2., 3.]);
+    /// ```
+    #[cfg(feature = "autograd")]
+    pub fn register_grad_hook(&self, hook: impl for<'b> FnMut(&mut Buffer<'b, T, D, S>) + 'static)
+    where
+        D: GradActions,
+    {
+        let gradients = unsafe { self.device().gradients_mut() }.expect(AUTOGRAD_NOT_AVAILABLE);
+        gradients.add_grad_hook(self.id(), hook);
+    }
+
     /// Returns a mutable reference to the gradient of this buffer.
     /// This allocates a gradient buffer if it wasn't previously.
     #[inline]
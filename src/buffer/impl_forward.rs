@@ -0,0 +1,70 @@
+use crate::{Alloc, Buffer, HasId, Shape, TangentActions, Unit, WriteBuf, ZeroGrad};
+
+const FORWARD_NOT_AVAILABLE: &str = "Forward<> is not available.";
+
+impl<'a, T, D, S> Buffer<'a, T, D, S>
+where
+    T: Unit + 'static,
+    D: TangentActions + Alloc<T> + 'static,
+    S: Shape,
+{
+    /// Returns a reference to the tangent of this buffer.
+    /// This allocates a zeroed tangent buffer if it wasn't previously.
+    /// # Panics
+    /// Panics if the [`Forward`](crate::Forward) module is not available.
+    #[inline]
+    pub fn tangent(&self) -> &Self
+    where
+        D: ZeroGrad<T>,
+    {
+        let mut tangents = self.device().tangents_mut().expect(FORWARD_NOT_AVAILABLE);
+        let tangent: *const Self = tangents.get_ref(self.device(), self.id());
+        // Safety: tangents are boxed, the tangent stays at the same address until it is removed.
+        unsafe { &*tangent }
+    }
+
+    /// Returns a reference to the tangent of this buffer.
+    /// Returns `None` if no tangent was set or computed, or if the [`Forward`](crate::Forward) module is not available.
+    #[inline]
+    pub fn try_tangent(&self) -> Option<&Self> {
+        let tangent: *const Self = self
+            .device()
+            .tangents()?
+            .may_get_ref(self.device(), self.id())?;
+        // Safety: see `tangent`
+        Some(unsafe { &*tangent })
+    }
+
+    /// Sets the tangent of this buffer. Operations using this buffer propagate the tangent to their outputs.
+    /// # Panics
+    /// Panics if the [`Forward`](crate::Forward) module is not available.
+    #[inline]
+    pub fn set_tangent(&self, tangent: &[T])
+    where
+        D: ZeroGrad<T> + WriteBuf<T, S, D>,
+    {
+        self.device()
+            .tangents_mut()
+            .expect(FORWARD_NOT_AVAILABLE)
+            .get_mut(self.device(), self.id())
+            .write(tangent)
+    }
+
+    /// Sets the tangent of this buffer and returns it. See [`Buffer::set_tangent`].
+    #[inline]
+    pub fn with_tangent(self, tangent: &[T]) -> Self
+    where
+        D: ZeroGrad<T> + WriteBuf<T, S, D>,
+    {
+        self.set_tangent(tangent);
+        self
+    }
+
+    /// Removes the tangent of this buffer.
+    #[inline]
+    pub fn remove_tangent(&self) {
+        if let Some(mut tangents) = self.device().tangents_mut() {
+            tangents.remove(*self.id());
+        }
+    }
+}
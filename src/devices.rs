@@ -15,6 +15,9 @@ pub mod opencl;
 #[cfg(feature = "stack")]
 pub mod stack;
 
+#[cfg(feature = "arena")]
+pub mod arena;
+
 #[cfg(feature = "wgsl")]
 pub mod wgsl;
 
@@ -27,6 +30,12 @@ pub mod nnapi;
 #[cfg(feature = "untyped")]
 pub mod untyped;
 
+#[cfg(feature = "network")]
+pub mod network;
+
+#[cfg(feature = "recorder")]
+pub mod recorder;
+
 mod stack_array;
 pub use stack_array::*;
 
@@ -36,7 +45,7 @@ pub use cdatatype::*;
 mod alloc;
 pub use alloc::*;
 
-#[cfg(any(feature = "cpu", feature = "stack"))]
+#[cfg(any(feature = "cpu", feature = "stack", feature = "arena"))]
 pub mod cpu_stack_ops;
 
 pub mod fusing;
@@ -143,6 +152,8 @@ macro_rules! impl_device_traits {
 
         $crate::pass_down_grad_fn!($device);
         $crate::pass_down_tape_actions!($device);
+        $crate::pass_down_tangent_actions!($device);
+        $crate::pass_down_thread_pool_actions!($device);
 
         $crate::pass_down_replace_buf_dev!($device);
         $crate::pass_down_cursor!($device);
@@ -173,12 +184,12 @@ macro_rules! impl_retriever {
             ) -> $crate::Result<Buffer<T, Self, S>> {
                 let data = unsafe { self
                     .modules
-                    .retrieve::<NUM_PARENTS>(self, len, parents)? };
-                let buf = Buffer {
+                    .retrieve::<NUM_PARENTS>(self, len, &parents)? };
+                let mut buf = Buffer {
                     data,
                     device: Some(self),
                 };
-                self.modules.on_retrieve_finish(&buf);
+                self.modules.on_retrieve_finish(&parents, &mut buf);
                 Ok(buf)
             }
         }
@@ -0,0 +1,335 @@
+use core::{
+    cell::Cell,
+    convert::Infallible,
+    mem::{align_of, size_of},
+};
+
+use crate::{
+    flag::AllocFlag, impl_retriever, impl_wrapped_data, pass_down_add_operation,
+    pass_down_cached_buffers, pass_down_grad_fn, pass_down_replace_buf_dev, pass_down_tape_actions,
+    pass_down_use_gpu_or_cpu, Alloc, Base, Buffer, Cursor, Device, DeviceError, HasId, Module,
+    OnDropBuffer, OnNewBuffer, PtrType, Setup, Shape, Unit, WrappedData,
+};
+
+use super::ArenaPtr;
+
+/// A heapless host device that allocates [`Buffer`]s of runtime length inside a user-provided arena.
+///
+/// Allocations bump an offset into the arena. Memory is never freed individually.
+/// Instead, the cursor of an `Arena` is the offset of the next allocation.
+/// Therefore, every epoch of [`Cursor::range`] reuses the memory allocated during the previous epoch.
+/// The cursor is never moved below the memory of buffers that are still alive:
+/// buffers that outlive their epoch are not overwritten, their memory is only reused once all buffers of the arena are dropped.
+///
+/// Modules that rely on the cursor, e.g. [`Cached`](crate::Cached), are not supported.
+///
+/// # Example
+/// ```
+/// use custos::{Arena, Base, Buffer, Cursor};
+///
+/// static mut MEMORY: [u8; 64] = [0; 64];
+///
+/// let device = Arena::<Base>::new(unsafe { &mut *core::ptr::addr_of_mut!(MEMORY) });
+/// let buf = Buffer::<f32, _>::from_slice(&device, &[1., 2., 3.]);
+/// let epoch_start = device.cursor();
+///
+/// for _ in device.range(0..10) {
+///     // reuses the memory of the previous epoch
+///     let _tmp = Buffer::<f32, _>::from_slice(&device, &[4., 5., 6., 7.]);
+///     assert_eq!(device.cursor(), epoch_start + 16);
+/// }
+/// assert_eq!(buf.read(), [1., 2., 3.]);
+/// ```
+#[derive(Debug)]
+pub struct Arena<Mods = Base> {
+    pub modules: Mods,
+    memory: *mut u8,
+    capacity: usize,
+    offset: Cell<usize>,
+    /// The number of allocations that were not dropped yet.
+    live: Cell<usize>,
+    /// An upper bound of the end of the live allocations.
+    live_end: Cell<usize>,
+}
+
+impl_retriever!(Arena);
+impl_wrapped_data!(Arena);
+pass_down_grad_fn!(Arena);
+pass_down_tape_actions!(Arena);
+crate::pass_down_tangent_actions!(Arena);
+crate::pass_down_thread_pool_actions!(Arena);
+pass_down_replace_buf_dev!(Arena);
+pass_down_cached_buffers!(Arena);
+pass_down_use_gpu_or_cpu!(Arena);
+pass_down_add_operation!(Arena);
+#[cfg(feature = "graph")]
+crate::pass_down_optimize_mem_graph!(Arena);
+
+impl<SimpleMods> Arena<SimpleMods> {
+    /// Creates an `Arena` device that allocates its buffers inside `memory`.
+    #[inline]
+    pub fn new<'a, NewMods>(memory: &'static mut [u8]) -> Arena<NewMods>
+    where
+        Self: 'a,
+        SimpleMods: Module<'a, Arena, Module = NewMods>,
+        NewMods: Setup<Arena<NewMods>>,
+    {
+        let mut arena = Arena {
+            modules: SimpleMods::new(),
+            memory: memory.as_mut_ptr(),
+            capacity: memory.len(),
+            offset: Cell::new(0),
+            live: Cell::new(0),
+            live_end: Cell::new(0),
+        };
+        NewMods::setup(&mut arena).unwrap();
+        arena
+    }
+}
+
+impl<Mods> Arena<Mods> {
+    /// Returns the size of the arena in bytes.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bytes that can still be allocated, ignoring alignment.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.capacity - self.offset.get()
+    }
+
+    /// Reserves `len` zero-initialized elements of type `T` at the next suitably aligned offset.
+    fn bump<T>(&self, len: usize) -> crate::Result<*mut T> {
+        let size = size_of::<T>()
+            .checked_mul(len)
+            .ok_or(DeviceError::ArenaOutOfMemory)?;
+
+        let base = self.memory as usize;
+        let start = (base + self.offset.get()).next_multiple_of(align_of::<T>()) - base;
+        let end = start
+            .checked_add(size)
+            .filter(|end| *end <= self.capacity)
+            .ok_or(DeviceError::ArenaOutOfMemory)?;
+
+        self.offset.set(end);
+        self.live.set(self.live.get() + 1);
+        self.live_end.set(end);
+
+        let ptr = unsafe { self.memory.add(start) };
+        unsafe { ptr.write_bytes(0, size) };
+        Ok(ptr.cast())
+    }
+}
+
+impl<Mods> Cursor for Arena<Mods> {
+    #[inline]
+    fn cursor(&self) -> usize {
+        self.offset.get()
+    }
+
+    /// Moves the bump offset to `cursor`, but not below the memory of live buffers.
+    #[inline]
+    unsafe fn set_cursor(&self, cursor: usize) {
+        self.offset
+            .set(cursor.min(self.capacity).max(self.live_end.get()))
+    }
+}
+
+impl<'dev, T: Unit, D: Device, S: Shape, Mods: OnNewBuffer<'dev, T, D, S>>
+    OnNewBuffer<'dev, T, D, S> for Arena<Mods>
+where
+    Self: 'dev,
+{
+    #[inline]
+    unsafe fn on_new_buffer(&self, device: &'dev D, new_buf: &Buffer<'dev, T, D, S>) {
+        unsafe { self.modules.on_new_buffer(device, new_buf) }
+    }
+}
+
+impl<Mods: OnDropBuffer> OnDropBuffer for Arena<Mods> {
+    fn on_drop_buffer<T: Unit, D: Device, S: Shape>(&self, device: &D, buf: &Buffer<T, D, S>) {
+        // only buffers with this flag own their allocation,
+        // e.g. buffers allocated by modules are never released
+        if buf.data.flag() == AllocFlag::None {
+            let id = buf.data.id();
+            let start = (id.id as usize).wrapping_sub(self.memory as usize);
+            if start < self.capacity {
+                let live = self.live.get() - 1;
+                self.live.set(live);
+
+                // allocations below the dropped one end before its start
+                if live == 0 {
+                    self.live_end.set(0);
+                } else if start + id.len * size_of::<T>() == self.live_end.get() {
+                    self.live_end.set(start);
+                }
+            }
+        }
+        self.modules.on_drop_buffer(device, buf)
+    }
+}
+
+impl<Mods: OnDropBuffer> Device for Arena<Mods> {
+    type Base<T: Unit, S: Shape> = ArenaPtr<T>;
+    type Data<T: Unit, S: Shape> = Self::Wrap<T, Self::Base<T, S>>;
+
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn base_to_data<T: Unit, S: Shape>(&self, base: Self::Base<T, S>) -> Self::Data<T, S> {
+        self.wrap_in_base(base)
+    }
+
+    #[inline(always)]
+    fn wrap_to_data<T: Unit, S: Shape>(
+        &self,
+        wrap: Self::Wrap<T, Self::Base<T, S>>,
+    ) -> Self::Data<T, S> {
+        wrap
+    }
+
+    #[inline(always)]
+    fn data_as_wrap<T: Unit, S: Shape>(
+        data: &Self::Data<T, S>,
+    ) -> &Self::Wrap<T, Self::Base<T, S>> {
+        data
+    }
+
+    #[inline(always)]
+    fn data_as_wrap_mut<T: Unit, S: Shape>(
+        data: &mut Self::Data<T, S>,
+    ) -> &mut Self::Wrap<T, Self::Base<T, S>> {
+        data
+    }
+}
+
+impl<T: Unit, Mods: OnDropBuffer> Alloc<T> for Arena<Mods> {
+    fn alloc<S: Shape>(&self, mut len: usize, flag: AllocFlag) -> crate::Result<Self::Base<T, S>> {
+        if len == 0 {
+            return Err(DeviceError::ZeroLengthBuffer.into());
+        }
+
+        if S::LEN > len {
+            len = S::LEN
+        }
+
+        Ok(ArenaPtr {
+            ptr: self.bump(len)?,
+            len,
+            flag,
+        })
+    }
+
+    fn alloc_from_slice<S>(&self, data: &[T]) -> crate::Result<Self::Base<T, S>>
+    where
+        S: Shape,
+        T: Clone,
+    {
+        if data.is_empty() {
+            return Err(DeviceError::ZeroLengthBuffer.into());
+        }
+        if !(S::LEN == data.len() || S::LEN == 0) {
+            return Err(DeviceError::ShapeLengthMismatch.into());
+        }
+
+        let mut ptr = self.alloc::<S>(data.len(), AllocFlag::None)?;
+        ptr.clone_from_slice(data);
+        Ok(ptr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        flag::AllocFlag, Alloc, Arena, Base, Buffer, Cursor, DeviceError, ErrorKind, HostPtr,
+    };
+
+    macro_rules! arena_memory {
+        ($len:literal) => {{
+            static mut MEMORY: [u8; $len] = [0; $len];
+            unsafe { &mut *core::ptr::addr_of_mut!(MEMORY) }
+        }};
+    }
+
+    #[test]
+    fn test_arena_alloc_aligned() {
+        let device = Arena::<Base>::new(arena_memory!(64));
+
+        let bytes = Buffer::<u8, _>::from_slice(&device, &[1, 2, 3]);
+        let floats = Buffer::<f64, _>::from_slice(&device, &[4., 5.]);
+
+        assert_eq!(
+            floats.base().ptr() as usize % core::mem::align_of::<f64>(),
+            0
+        );
+        assert_eq!(bytes.read(), [1, 2, 3]);
+        assert_eq!(floats.read(), [4., 5.]);
+        assert!(device.remaining() <= 64 - 3 - 16);
+    }
+
+    #[test]
+    fn test_arena_out_of_memory() {
+        let device = Arena::<Base>::new(arena_memory!(16));
+
+        let _buf = Buffer::<u8, _>::new(&device, 16);
+        assert_eq!(device.remaining(), 0);
+
+        let err = Alloc::<u8>::alloc::<()>(&device, 1, AllocFlag::None).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::ArenaOutOfMemory));
+    }
+
+    #[test]
+    fn test_arena_epochs_reset_bump_offset() {
+        let device = Arena::<Base>::new(arena_memory!(64));
+        let buf = Buffer::<i32, _>::from_slice(&device, &[1, 2, 3, 4]);
+        let start = device.cursor();
+
+        for epoch in device.range(0..100) {
+            assert_eq!(device.cursor(), start);
+            let tmp = Buffer::<i32, _>::from_slice(&device, &[epoch as i32; 8]);
+            assert_eq!(tmp.read(), [epoch as i32; 8]);
+        }
+        assert_eq!(buf.read(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_arena_epochs_keep_live_buffers() {
+        let device = Arena::<Base>::new(arena_memory!(64));
+
+        let mut previous: Option<Buffer<u8, Arena>> = None;
+        for epoch in device.range(0..4) {
+            let buf = Buffer::<u8, _>::from_slice(&device, &[epoch as u8; 16]);
+            if let Some(previous) = &previous {
+                assert_eq!(previous.read(), [epoch as u8 - 1; 16]);
+            }
+            previous = Some(buf);
+        }
+        assert_eq!(device.remaining(), 0);
+
+        // the memory is reused once every buffer is dropped
+        drop(previous);
+        unsafe { device.set_cursor(0) };
+        assert_eq!(device.cursor(), 0);
+        let _buf = Buffer::<u8, _>::new(&device, 64);
+    }
+
+    #[test]
+    fn test_arena_set_cursor_keeps_live_buffers() {
+        let device = Arena::<Base>::new(arena_memory!(64));
+        let buf = Buffer::<u8, _>::from_slice(&device, &[1; 8]);
+        let tmp = Buffer::<u8, _>::from_slice(&device, &[2; 8]);
+
+        unsafe { device.set_cursor(0) };
+        assert_eq!(device.cursor(), 16);
+
+        drop(tmp);
+        unsafe { device.set_cursor(0) };
+        assert_eq!(device.cursor(), 8);
+
+        let tmp = Buffer::<u8, _>::from_slice(&device, &[3; 8]);
+        assert_eq!(buf.read(), [1; 8]);
+        assert_eq!(tmp.read(), [3; 8]);
+    }
+}
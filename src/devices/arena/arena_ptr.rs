@@ -0,0 +1,90 @@
+use core::ops::{Deref, DerefMut};
+
+use crate::{flag::AllocFlag, HasId, HostPtr, Id, PtrType, ShallowCopy, WrappedCopy};
+
+/// The pointer used for [`Arena`](super::Arena) [`Buffer`](crate::Buffer)s.
+/// The memory is owned by the arena, hence dropping an `ArenaPtr` does not free anything.
+#[derive(Debug)]
+pub struct ArenaPtr<T> {
+    /// The pointer to the data inside the arena
+    pub ptr: *mut T,
+    /// The length of the data
+    pub len: usize,
+    /// Allocation flag for the pointer
+    pub flag: AllocFlag,
+}
+
+impl<T> HostPtr<T> for ArenaPtr<T> {
+    #[inline]
+    fn ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    #[inline]
+    fn ptr_mut(&mut self) -> *mut T {
+        self.ptr
+    }
+}
+
+impl<T> HasId for ArenaPtr<T> {
+    #[inline]
+    fn id(&self) -> Id {
+        Id {
+            id: self.ptr as u64,
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Deref for ArenaPtr<T> {
+    type Target = [T];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        unsafe { core::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<T> DerefMut for ArenaPtr<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<T> PtrType for ArenaPtr<T> {
+    #[inline]
+    fn size(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn flag(&self) -> AllocFlag {
+        self.flag
+    }
+
+    #[inline]
+    unsafe fn set_flag(&mut self, flag: AllocFlag) {
+        self.flag = flag
+    }
+}
+
+impl<T> WrappedCopy for ArenaPtr<T> {
+    type Base = Self;
+
+    #[inline]
+    fn wrapped_copy(&self, to_wrap: Self::Base) -> Self {
+        to_wrap
+    }
+}
+
+impl<T> ShallowCopy for ArenaPtr<T> {
+    #[inline]
+    unsafe fn shallow(&self) -> Self {
+        ArenaPtr {
+            ptr: self.ptr,
+            len: self.len,
+            flag: AllocFlag::Wrapper,
+        }
+    }
+}
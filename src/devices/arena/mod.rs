@@ -0,0 +1,9 @@
+//! The Arena module provides a heapless host device for custos.
+//! Buffers of runtime length are carved out of a user-provided `&'static mut [u8]` arena.
+
+mod arena_device;
+mod arena_ptr;
+mod ops;
+
+pub use arena_device::*;
+pub use arena_ptr::*;
@@ -0,0 +1,216 @@
+use core::ops::{Deref, DerefMut};
+
+#[cfg(feature = "std")]
+use crate::op_hint::unary;
+use crate::{
+    cpu_stack_ops::{add_gemm_slice, apply_fn_slice, clear_slice, gemm_slice},
+    gemm::{check_gemm_lengths, gemm_out_len},
+    AddOperation, ApplyFunction, Buffer, ClearBuf, Device, Gemm, GemmGrad, Number, OnDropBuffer,
+    Read, Resolve, Retrieve, Retriever, SetOpHint, Shape, ToVal, TwoWay, Unit, WriteBuf,
+};
+
+use super::Arena;
+
+impl<Mods, T, D, S> ApplyFunction<T, S, D> for Arena<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + 'static,
+    T: Unit + Copy + Default + ToVal + 'static,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn apply_fn<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<T> + 'static,
+    {
+        let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+        self.add_op((&mut out, buf), move |(out, buf)| {
+            apply_fn_slice(buf, out, f);
+            Ok(())
+        })
+        .unwrap();
+
+        #[cfg(feature = "std")]
+        self.set_op_hint(unary(f));
+
+        out
+    }
+}
+
+impl<Mods, T, D> Gemm<T, D> for Arena<Mods>
+where
+    Mods: Retrieve<Self, T> + AddOperation + 'static,
+    T: Number,
+    D: Device + 'static,
+    D::Base<T, ()>: Deref<Target = [T]>,
+{
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs: &Buffer<T, D>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            gemm_slice(m, k, n, lhs, false, rhs, false, out);
+            Ok(())
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<Mods, T, D> GemmGrad<T, D> for Arena<Mods>
+where
+    Mods: AddOperation + OnDropBuffer,
+    T: Number,
+    D: Device + 'static,
+    D::Base<T, ()>: Deref<Target = [T]> + DerefMut<Target = [T]>,
+{
+    #[inline]
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, D>,
+        lhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                add_gemm_slice(m, n, k, out_grad, false, rhs, true, lhs_grad);
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
+    #[inline]
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                add_gemm_slice(k, m, n, lhs, true, out_grad, false, rhs_grad);
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+}
+
+impl<Mods, T, D, S> Read<T, S, D> for Arena<Mods>
+where
+    T: Unit,
+    Mods: OnDropBuffer,
+    D: Device,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    type Read<'a> = &'a [T] where T: 'a, D: 'a, S: 'a;
+
+    #[inline]
+    fn read<'a>(&self, buf: &'a D::Base<T, S>) -> Self::Read<'a>
+    where
+        D: 'a,
+    {
+        &**buf
+    }
+
+    #[inline]
+    #[cfg(feature = "std")]
+    fn read_to_vec(&self, buf: &D::Base<T, S>) -> Vec<T>
+    where
+        T: Default + Clone,
+    {
+        buf.to_vec()
+    }
+}
+
+impl<Mods, T, D, S> WriteBuf<T, S, D> for Arena<Mods>
+where
+    Mods: OnDropBuffer,
+    T: Unit + Copy,
+    D: Device,
+    D::Base<T, S>: DerefMut<Target = [T]>,
+    S: Shape,
+{
+    #[inline]
+    fn write(&self, buf: &mut Buffer<T, D, S>, data: &[T]) {
+        buf.copy_from_slice(data)
+    }
+
+    #[inline]
+    fn write_buf(&self, dst: &mut Buffer<T, D, S>, src: &Buffer<T, D, S>) {
+        self.write(dst, src)
+    }
+}
+
+impl<Mods, T, D, S> ClearBuf<T, S, D> for Arena<Mods>
+where
+    Mods: OnDropBuffer,
+    T: Unit + Default,
+    D: Device,
+    D::Base<T, S>: DerefMut<Target = [T]>,
+    S: Shape,
+{
+    #[inline]
+    fn clear(&self, buf: &mut Buffer<T, D, S>) {
+        clear_slice(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ApplyFunction, Arena, Base, Buffer, ClearBuf, Combiner, Gemm, WriteBuf};
+
+    #[test]
+    fn test_arena_apply_fn_write_clear() {
+        static mut MEMORY: [u8; 128] = [0; 128];
+        let device = Arena::<Base>::new(unsafe { &mut *core::ptr::addr_of_mut!(MEMORY) });
+
+        let mut buf = Buffer::<f32, _>::new(&device, 4);
+        device.write(&mut buf, &[1., 2., 3., 4.]);
+
+        let out = device.apply_fn(&buf, |x| x.mul(2.).add(1.));
+        assert_eq!(out.read(), [3., 5., 7., 9.]);
+
+        device.clear(&mut buf);
+        assert_eq!(buf.read(), [0.; 4]);
+    }
+
+    #[test]
+    fn test_arena_gemm() {
+        static mut MEMORY: [u8; 128] = [0; 128];
+        let device = Arena::<Base>::new(unsafe { &mut *core::ptr::addr_of_mut!(MEMORY) });
+
+        let lhs = Buffer::<i16, _>::from_slice(&device, &[1, 2, 3, 4, 5, 6]);
+        let rhs = Buffer::<i16, _>::from_slice(&device, &[7, 8, 9, 10, 11, 12]);
+
+        let out = device.gemm(2, 3, 2, &lhs, &rhs);
+        assert_eq!(out.read(), [58, 64, 139, 154]);
+    }
+}
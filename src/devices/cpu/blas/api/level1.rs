@@ -0,0 +1,46 @@
+#[cfg_attr(target_os = "windows", link(name = "BLAS"))]
+#[cfg_attr(target_os = "macos", link(name = "blas"))]
+#[cfg_attr(target_os = "linux", link(name = "openblas"))]
+extern "C" {
+    pub(crate) fn cblas_saxpy(
+        n: usize,
+        alpha: f32,
+        x: *const f32,
+        incx: usize,
+        y: *mut f32,
+        incy: usize,
+    );
+
+    pub(crate) fn cblas_daxpy(
+        n: usize,
+        alpha: f64,
+        x: *const f64,
+        incx: usize,
+        y: *mut f64,
+        incy: usize,
+    );
+
+    pub(crate) fn cblas_sdot(
+        n: usize,
+        x: *const f32,
+        incx: usize,
+        y: *const f32,
+        incy: usize,
+    ) -> f32;
+
+    pub(crate) fn cblas_ddot(
+        n: usize,
+        x: *const f64,
+        incx: usize,
+        y: *const f64,
+        incy: usize,
+    ) -> f64;
+
+    pub(crate) fn cblas_snrm2(n: usize, x: *const f32, incx: usize) -> f32;
+
+    pub(crate) fn cblas_dnrm2(n: usize, x: *const f64, incx: usize) -> f64;
+
+    pub(crate) fn cblas_sscal(n: usize, alpha: f32, x: *mut f32, incx: usize);
+
+    pub(crate) fn cblas_dscal(n: usize, alpha: f64, x: *mut f64, incx: usize);
+}
@@ -0,0 +1,62 @@
+use crate::devices::cpu::{Order, Transpose};
+
+#[cfg_attr(target_os = "windows", link(name = "BLAS"))]
+#[cfg_attr(target_os = "macos", link(name = "blas"))]
+#[cfg_attr(target_os = "linux", link(name = "openblas"))]
+extern "C" {
+    pub(crate) fn cblas_sgemv(
+        order: Order,
+        trans: Transpose,
+        m: usize,
+        n: usize,
+        alpha: f32,
+        a: *const f32,
+        lda: usize,
+        x: *const f32,
+        incx: usize,
+        beta: f32,
+        y: *mut f32,
+        incy: usize,
+    );
+
+    pub(crate) fn cblas_dgemv(
+        order: Order,
+        trans: Transpose,
+        m: usize,
+        n: usize,
+        alpha: f64,
+        a: *const f64,
+        lda: usize,
+        x: *const f64,
+        incx: usize,
+        beta: f64,
+        y: *mut f64,
+        incy: usize,
+    );
+
+    pub(crate) fn cblas_sger(
+        order: Order,
+        m: usize,
+        n: usize,
+        alpha: f32,
+        x: *const f32,
+        incx: usize,
+        y: *const f32,
+        incy: usize,
+        a: *mut f32,
+        lda: usize,
+    );
+
+    pub(crate) fn cblas_dger(
+        order: Order,
+        m: usize,
+        n: usize,
+        alpha: f64,
+        x: *const f64,
+        incx: usize,
+        y: *const f64,
+        incy: usize,
+        a: *mut f64,
+        lda: usize,
+    );
+}
@@ -1,3 +1,7 @@
+mod level1;
+mod level2;
 mod level3;
 
+pub(crate) use level1::*;
+pub(crate) use level2::*;
 pub(crate) use level3::*;
@@ -1,9 +1,9 @@
 use core::convert::Infallible;
 
 use crate::{
-    cpu::CPUPtr, flag::AllocFlag, impl_device_traits, AddLayer, Alloc, Base, Buffer, CloneBuf,
-    Device, DeviceError, DevicelessAble, HasModules, IsShapeIndep, Module, OnDropBuffer,
-    OnNewBuffer, RemoveLayer, Setup, Shape, UnaryFusing, Unit, WrappedData,
+    cpu::CPUPtr, flag::AllocFlag, impl_device_traits, pass_down_use_gpu_or_cpu, AddLayer, Alloc,
+    Base, Buffer, CloneBuf, Device, DeviceError, DevicelessAble, HasModules, IsShapeIndep, Module,
+    OnDropBuffer, OnNewBuffer, RemoveLayer, Setup, Shape, UnaryFusing, Unit, WrappedData,
 };
 
 pub trait IsCPU {}
@@ -29,6 +29,7 @@ pub struct CPU<Mods = Base> {
 }
 
 impl_device_traits!(CPU);
+pass_down_use_gpu_or_cpu!(CPU);
 
 impl<Mods> IsCPU for CPU<Mods> {}
 
@@ -172,7 +173,15 @@ impl<T: Unit, Mods: OnDropBuffer> Alloc<T> for CPU<Mods> {
 }
 
 #[cfg(feature = "lazy")]
-impl<Mods> crate::LazyRun for CPU<Mods> {}
+impl<Mods> crate::LazyRun for CPU<Mods> {
+    #[inline]
+    fn supports_parallel_exec(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "lazy")]
+impl<Mods: OnDropBuffer + 'static> crate::RunCaptured for CPU<Mods> {}
 
 impl<Mods: crate::RunModule<Self>> crate::Run for CPU<Mods> {
     #[inline]
@@ -204,9 +213,8 @@ impl<Mods: OnDropBuffer + 'static> UnaryFusing for CPU<Mods> {
     #[inline]
     fn unary_fuse_op<T: Unit + Copy + 'static>(
         &self,
-        ops_to_fuse: Vec<std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>>,
-    ) -> Box<dyn Fn((&mut Buffer<'_, T, Self, ()>, &Buffer<'_, T, Self, ()>)) -> crate::Result<()>>
-    {
+        ops_to_fuse: Vec<crate::UnaryFn<T>>,
+    ) -> crate::FusedUnaryOp<T, Self> {
         Box::new(move |(out, buf)| {
             for (out, buf) in out.iter_mut().zip(buf.iter()) {
                 let mut current_val = *buf;
@@ -224,6 +232,29 @@ impl<Mods: OnDropBuffer + 'static> UnaryFusing for CPU<Mods> {
     }
 }
 
+impl<Mods: OnDropBuffer + 'static> crate::ElementWiseFusing for CPU<Mods> {
+    #[cfg(feature = "lazy")]
+    fn fused_ew<T: crate::CDatatype + crate::Numeric>(
+        &self,
+        fused: &crate::FusedElementWise<T>,
+        inputs: &[&Buffer<'_, T, Self, ()>],
+        outputs: &mut [&mut Buffer<'_, T, Self, ()>],
+    ) -> crate::Result<()> {
+        let Some(len) = outputs.first().map(|out| out.len()) else {
+            return Ok(());
+        };
+        let mut vals = vec![T::default(); fused.vars().len()];
+
+        for idx in 0..len {
+            fused.eval(|input_idx| inputs[input_idx][idx], &mut vals);
+            for (out, var) in outputs.iter_mut().zip(fused.outputs()) {
+                out[idx] = vals[*var];
+            }
+        }
+        Ok(())
+    }
+}
+
 unsafe impl<Mods: OnDropBuffer> IsShapeIndep for CPU<Mods> {}
 
 #[cfg(test)]
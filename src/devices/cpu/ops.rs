@@ -1,11 +1,19 @@
 use core::ops::{AddAssign, Deref, DerefMut, Index, Range, RangeBounds};
 
+#[cfg(not(feature = "blas"))]
+use crate::cpu_stack_ops::{axpy_slice, dot_slice, gemv_slice, ger_slice, scal_slice};
 use crate::{
+    blas::check_blas_lengths,
     bounds_to_range,
-    cpu_stack_ops::{apply_fn_slice, clear_slice},
-    op_hint::unary,
-    pass_down_add_operation, pass_down_exec_now, AddOperation, ApplyFunction, Buffer, ClearBuf,
-    CopySlice, Device, Eval, MayToCLSource, OnDropBuffer, Read, Resolve, Retrieve, Retriever,
+    cpu_stack_ops::{
+        add_gemm_slice, apply_binary_fn_slice, apply_fn_slice, clear_slice, gemm_slice,
+        scale_slice, squared_norm_slice,
+    },
+    gemm::{check_gemm_lengths, gemm_out_len},
+    op_hint::{binary, unary},
+    pass_down_add_operation, pass_down_exec_now, AddOperation, ApplyBinaryFunction, ApplyFunction,
+    Blas, Buffer, ClearBuf, CopySlice, Device, Eval, Float, Gemm, GemmGrad, GenericBlas,
+    MayTangentActions, MayToCLSource, Number, OnDropBuffer, Read, Resolve, Retrieve, Retriever,
     SetOpHint, Shape, ToVal, TwoWay, UnaryGrad, Unit, WriteBuf, ZeroGrad, CPU,
 };
 
@@ -15,6 +23,7 @@ pass_down_exec_now!(CPU);
 impl<Mods, T, D, S> ApplyFunction<T, S, D> for CPU<Mods>
 where
     Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + 'static,
+    Self: MayTangentActions,
     T: Unit + Copy + Default + ToVal + 'static,
     D: Device + 'static,
     D::Base<T, S>: Deref<Target = [T]>,
@@ -31,6 +40,13 @@ where
         let mut out = self.retrieve(buf.len(), buf).unwrap();
 
         self.add_op((&mut out, buf), move |(out, buf)| {
+            #[cfg(feature = "forward")]
+            if let Some(mut tangents) = crate::TangentActions::tangents_mut(out.device()) {
+                if let Some((tangent, out_tangent)) = tangents.tangent_pair(buf, out) {
+                    crate::cpu_stack_ops::apply_fn_tangent_slice(buf, tangent, out, out_tangent, f);
+                    return Ok(());
+                }
+            }
             apply_fn_slice(buf, out, f);
             Ok(())
         })
@@ -42,6 +58,267 @@ where
     }
 }
 
+impl<Mods, T, D, S> ApplyBinaryFunction<T, S, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + 'static,
+    T: Unit + Copy + Default + ToVal + 'static,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<T> + 'static,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            apply_binary_fn_slice(lhs, rhs, out, f);
+            Ok(())
+        })
+        .unwrap();
+
+        self.set_op_hint(binary(f));
+
+        out
+    }
+}
+
+#[cfg(feature = "half")]
+impl<Mods, T, D, S> crate::ApplyFunctionF32<T, S, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + 'static,
+    T: crate::HalfFloat,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn apply_fn_f32<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32> + 'static,
+    {
+        let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+        self.add_op((&mut out, buf), move |(out, buf)| {
+            crate::cpu_stack_ops::apply_fn_f32_slice(buf, out, f);
+            Ok(())
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+#[cfg(feature = "half")]
+impl<Mods, T, D, S> crate::ApplyBinaryFunctionF32<T, S, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + 'static,
+    T: crate::HalfFloat,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn apply_binary_fn_f32<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<f32>, Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32> + 'static,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            crate::cpu_stack_ops::apply_binary_fn_f32_slice(lhs, rhs, out, f);
+            Ok(())
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<Mods, T, D> Gemm<T, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T> + AddOperation + 'static,
+    T: Number,
+    D: Device + 'static,
+    D::Base<T, ()>: Deref<Target = [T]>,
+{
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs: &Buffer<T, D>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            gemm_slice(m, k, n, lhs, false, rhs, false, out);
+            Ok(())
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<Mods, T, D> GemmGrad<T, D> for CPU<Mods>
+where
+    Mods: AddOperation + OnDropBuffer,
+    T: Number,
+    D: Device + 'static,
+    D::Base<T, ()>: Deref<Target = [T]> + DerefMut<Target = [T]>,
+{
+    #[inline]
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, D>,
+        lhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                add_gemm_slice(m, n, k, out_grad, false, rhs, true, lhs_grad);
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+
+    #[inline]
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                add_gemm_slice(k, m, n, lhs, true, out_grad, false, rhs_grad);
+                Ok(())
+            },
+        )
+        .unwrap();
+    }
+}
+
+impl<Mods, T> Blas<T> for CPU<Mods>
+where
+    Mods: AddOperation + OnDropBuffer + 'static,
+    T: GenericBlas + Float + 'static,
+{
+    fn axpy(&self, alpha: T, x: &Buffer<T, Self>, y: &mut Buffer<T, Self>) -> crate::Result<()> {
+        check_blas_lengths(&[(y.len(), x.len(), 1)])?;
+
+        self.add_op((x, y), move |(x, y)| {
+            #[cfg(feature = "blas")]
+            T::axpy(alpha, x, y);
+
+            #[cfg(not(feature = "blas"))]
+            axpy_slice(alpha, x, y);
+            Ok(())
+        })
+    }
+
+    fn dot(&self, x: &Buffer<T, Self>, y: &Buffer<T, Self>) -> crate::Result<T> {
+        check_blas_lengths(&[(y.len(), x.len(), 1)])?;
+
+        #[cfg(feature = "blas")]
+        return Ok(T::dot(x, y));
+
+        #[cfg(not(feature = "blas"))]
+        Ok(dot_slice(x, y))
+    }
+
+    fn nrm2(&self, x: &Buffer<T, Self>) -> crate::Result<T> {
+        #[cfg(feature = "blas")]
+        return Ok(T::nrm2(x));
+
+        #[cfg(not(feature = "blas"))]
+        Ok(dot_slice(x, x).sqrt())
+    }
+
+    fn scal(&self, alpha: T, x: &mut Buffer<T, Self>) -> crate::Result<()> {
+        self.add_op(x, move |x| {
+            #[cfg(feature = "blas")]
+            T::scal(alpha, x);
+
+            #[cfg(not(feature = "blas"))]
+            scal_slice(alpha, x);
+            Ok(())
+        })
+    }
+
+    fn gemv(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &Buffer<T, Self>,
+        x: &Buffer<T, Self>,
+        beta: T,
+        y: &mut Buffer<T, Self>,
+    ) -> crate::Result<()> {
+        check_blas_lengths(&[(a.len(), m, n), (x.len(), n, 1), (y.len(), m, 1)])?;
+
+        self.add_op((a, x, y), move |(a, x, y)| {
+            #[cfg(feature = "blas")]
+            T::gemv(m, n, alpha, a, x, beta, y);
+
+            #[cfg(not(feature = "blas"))]
+            gemv_slice(m, n, alpha, a, x, beta, y);
+            Ok(())
+        })
+    }
+
+    fn ger(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        x: &Buffer<T, Self>,
+        y: &Buffer<T, Self>,
+        a: &mut Buffer<T, Self>,
+    ) -> crate::Result<()> {
+        check_blas_lengths(&[(a.len(), m, n), (x.len(), m, 1), (y.len(), n, 1)])?;
+
+        self.add_op((x, y, a), move |(x, y, a)| {
+            #[cfg(feature = "blas")]
+            T::ger(m, n, alpha, x, y, a);
+
+            #[cfg(not(feature = "blas"))]
+            ger_slice(m, n, alpha, x, y, a);
+            Ok(())
+        })
+    }
+}
+
 impl<Mods, T, D, S> UnaryGrad<T, S, D> for CPU<Mods>
 where
     Mods: AddOperation + OnDropBuffer,
@@ -138,6 +415,22 @@ where
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>) {
         clear_slice(data)
     }
+
+    #[inline]
+    fn grad_squared_norm<S: Shape>(&self, data: &Self::Base<T, S>) -> crate::Result<f64>
+    where
+        T: 'static,
+    {
+        squared_norm_slice(data).ok_or_else(|| crate::DeviceError::GradOpUnsupported.into())
+    }
+
+    #[inline]
+    fn scale_grad<S: Shape>(&self, data: &mut Self::Base<T, S>, factor: f64) -> crate::Result<()>
+    where
+        T: 'static,
+    {
+        scale_slice(data, factor).ok_or_else(|| crate::DeviceError::GradOpUnsupported.into())
+    }
 }
 
 impl<Mods, T, D> CopySlice<T, D> for CPU<Mods>
@@ -177,3 +470,36 @@ where
         }
     }
 }
+
+#[cfg(feature = "autograd")]
+impl<Mods, T, D, S> crate::optim::ApplyUpdate<T, S, D> for CPU<Mods>
+where
+    Mods: OnDropBuffer,
+    T: Unit + Copy + Default + 'static,
+    D: Device,
+    D::Base<T, S>: Deref<Target = [T]> + DerefMut<Target = [T]>,
+    S: Shape,
+{
+    fn apply_update(
+        &self,
+        rule: &crate::optim::UpdateRule<T>,
+        param: &mut Buffer<T, D, S>,
+        grad: &Buffer<T, D, S>,
+        states: &mut [Buffer<T, D, S>],
+        hyper: &[T],
+    ) -> crate::Result<()> {
+        let mut state_vals = [T::default(); crate::optim::MAX_STATES];
+        let state_vals = &mut state_vals[..states.len()];
+
+        for (idx, (param, grad)) in param.iter_mut().zip(grad.iter()).enumerate() {
+            for (val, state) in state_vals.iter_mut().zip(states.iter()) {
+                *val = state[idx];
+            }
+            rule.eval(param, *grad, state_vals, hyper);
+            for (val, state) in state_vals.iter().zip(states.iter_mut()) {
+                state[idx] = *val;
+            }
+        }
+        Ok(())
+    }
+}
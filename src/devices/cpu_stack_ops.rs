@@ -1,7 +1,9 @@
+use core::any::TypeId;
+use core::cmp::min;
 use core::ops::AddAssign;
 use core::ops::Mul;
 
-use crate::{Eval, ToVal};
+use crate::{gemm::check_gemm_lengths, Eval, EvalTangent, Number, ToVal};
 
 #[inline]
 pub fn apply_fn_slice<T, O>(x: &[T], out: &mut [T], f: impl Fn(crate::Resolve<T>) -> O)
@@ -14,6 +16,69 @@ where
     }
 }
 
+/// Like [`apply_fn_slice`], but additionally writes the tangents of the outputs (forward-mode derivative).
+#[inline]
+pub fn apply_fn_tangent_slice<T, O>(
+    x: &[T],
+    tangent: &[T],
+    out: &mut [T],
+    out_tangent: &mut [T],
+    f: impl Fn(crate::Resolve<T>) -> O,
+) where
+    T: Copy,
+    O: EvalTangent<T>,
+{
+    let outs = out.iter_mut().zip(out_tangent.iter_mut());
+    for ((x, tangent), (out, out_tangent)) in x.iter().zip(tangent).zip(outs) {
+        (*out, *out_tangent) = f((*x).to_val()).eval_tangent(*tangent);
+    }
+}
+
+#[inline]
+pub fn apply_binary_fn_slice<T, O>(
+    lhs: &[T],
+    rhs: &[T],
+    out: &mut [T],
+    f: impl Fn(crate::Resolve<T>, crate::Resolve<T>) -> O,
+) where
+    T: Copy,
+    O: Eval<T>,
+{
+    for ((lhs, rhs), out) in lhs.iter().zip(rhs.iter()).zip(out.iter_mut()) {
+        *out = f((*lhs).to_val(), (*rhs).to_val()).eval();
+    }
+}
+
+/// Like [`apply_fn_slice`], but loads every element as `f32`, evaluates `f` in `f32` and stores the result as `T`.
+#[cfg(feature = "half")]
+#[inline]
+pub fn apply_fn_f32_slice<T, O>(x: &[T], out: &mut [T], f: impl Fn(crate::Resolve<f32>) -> O)
+where
+    T: crate::HalfFloat,
+    O: Eval<f32>,
+{
+    for (x, out) in x.iter().zip(out.iter_mut()) {
+        *out = T::from_f32(f(x.to_f32().to_val()).eval());
+    }
+}
+
+/// Like [`apply_binary_fn_slice`], but loads every element as `f32`, evaluates `f` in `f32` and stores the result as `T`.
+#[cfg(feature = "half")]
+#[inline]
+pub fn apply_binary_fn_f32_slice<T, O>(
+    lhs: &[T],
+    rhs: &[T],
+    out: &mut [T],
+    f: impl Fn(crate::Resolve<f32>, crate::Resolve<f32>) -> O,
+) where
+    T: crate::HalfFloat,
+    O: Eval<f32>,
+{
+    for ((lhs, rhs), out) in lhs.iter().zip(rhs.iter()).zip(out.iter_mut()) {
+        *out = T::from_f32(f(lhs.to_f32().to_val(), rhs.to_f32().to_val()).eval());
+    }
+}
+
 #[inline]
 pub fn add_unary_grad<T, O>(
     lhs: &[T],
@@ -35,3 +100,409 @@ pub fn clear_slice<T: Default>(input: &mut [T]) {
         *value = T::default();
     }
 }
+
+/// Calls `$f` with `$slice` reinterpreted as a slice of the floating point type `T` is (`f32`, `f64`, or `f16`/`bf16` with the `half` feature).
+/// Evaluates to `None` if `T` is not a floating point type.
+macro_rules! with_float_slice {
+    ($t:ty, $slice:expr, $ptr_cast:ident, $from_raw_parts:ident, $f:expr) => {{
+        let slice = $slice;
+        macro_rules! try_float {
+            ($float:ty) => {
+                if TypeId::of::<$t>() == TypeId::of::<$float>() {
+                    // SAFETY: `$t` and `$float` are the same type
+                    let slice = unsafe {
+                        core::slice::$from_raw_parts(slice.$ptr_cast() as *mut $float, slice.len())
+                    };
+                    return Some($f(slice));
+                }
+            };
+        }
+        try_float!(f32);
+        try_float!(f64);
+        #[cfg(feature = "half")]
+        try_float!(half::f16);
+        #[cfg(feature = "half")]
+        try_float!(half::bf16);
+        None
+    }};
+}
+
+trait AsF64: Copy {
+    fn to_f64(self) -> f64;
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_as_f64 {
+    ($($t:ty),*) => {
+        $(
+            impl AsF64 for $t {
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_as_f64!(f32, f64);
+
+#[cfg(feature = "half")]
+macro_rules! impl_as_f64_half {
+    ($($t:ty),*) => {
+        $(
+            impl AsF64 for $t {
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    <$t>::to_f64(self)
+                }
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    <$t>::from_f64(value)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "half")]
+impl_as_f64_half!(half::f16, half::bf16);
+
+#[inline]
+fn squared_norm<F: AsF64>(input: &[F]) -> f64 {
+    input
+        .iter()
+        .map(|value| value.to_f64() * value.to_f64())
+        .sum()
+}
+
+#[inline]
+fn scale<F: AsF64>(input: &mut [F], factor: f64) {
+    for value in input {
+        *value = F::from_f64(value.to_f64() * factor);
+    }
+}
+
+/// Returns the sum of the squared elements, accumulated in `f64`.
+/// Returns `None` if `T` is not a floating point type.
+pub fn squared_norm_slice<T: 'static>(input: &[T]) -> Option<f64> {
+    with_float_slice!(T, input, as_ptr, from_raw_parts, squared_norm)
+}
+
+/// Multiplies every element by `factor`.
+/// Returns `None` if `T` is not a floating point type.
+pub fn scale_slice<T: 'static>(input: &mut [T], factor: f64) -> Option<()> {
+    with_float_slice!(T, input, as_mut_ptr, from_raw_parts_mut, |slice| scale(
+        slice, factor
+    ))
+}
+
+/// Length of the shared dimension per block of the cache-blocked gemm kernel.
+const GEMM_BLOCK_K: usize = 256;
+/// Number of columns of `rhs` per block of the cache-blocked gemm kernel.
+const GEMM_BLOCK_N: usize = 512;
+
+/// Computes the row-major matrix product `out = op(lhs) * op(rhs)`, where `op(lhs)` is `m x k` and `op(rhs)` is `k x n`.
+/// If `lhs_trans` (`rhs_trans`) is `true`, `lhs` (`rhs`) is stored transposed, i.e. `op` transposes it.
+///
+/// `f32` and `f64` matrices are multiplied by [`GenericBlas`](crate::GenericBlas) if the `blas` feature is enabled.
+#[allow(clippy::too_many_arguments)]
+pub fn gemm_slice<T: Number>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) {
+    assert_gemm_lengths(m, k, n, lhs, rhs, out);
+
+    #[cfg(all(feature = "blas", feature = "cpu"))]
+    if try_blas_gemm(m, k, n, lhs, lhs_trans, rhs, rhs_trans, out).is_some() {
+        return;
+    }
+
+    clear_slice(out);
+    add_gemm_blocked(m, k, n, lhs, lhs_trans, rhs, rhs_trans, out)
+}
+
+/// Like [`gemm_slice`], but adds the matrix product to `out`.
+#[allow(clippy::too_many_arguments)]
+pub fn add_gemm_slice<T: Number>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) {
+    assert_gemm_lengths(m, k, n, lhs, rhs, out);
+
+    #[cfg(all(feature = "blas", feature = "cpu"))]
+    if TypeId::of::<T>() == TypeId::of::<f32>() || TypeId::of::<T>() == TypeId::of::<f64>() {
+        let mut product = std::vec![T::zero(); out.len()];
+        if try_blas_gemm(m, k, n, lhs, lhs_trans, rhs, rhs_trans, &mut product).is_some() {
+            for (out, product) in out.iter_mut().zip(product) {
+                *out += product;
+            }
+            return;
+        }
+    }
+
+    add_gemm_blocked(m, k, n, lhs, lhs_trans, rhs, rhs_trans, out)
+}
+
+/// Panics if `lhs`, `rhs` or `out` does not hold the `m * k`, `k * n` or `m * n` elements of a matrix product.
+#[track_caller]
+fn assert_gemm_lengths<T>(m: usize, k: usize, n: usize, lhs: &[T], rhs: &[T], out: &[T]) {
+    assert!(
+        check_gemm_lengths(m, k, n, lhs.len(), rhs.len(), out.len()).is_ok(),
+        "gemm: `lhs`, `rhs` and `out` must hold m * k, k * n and m * n elements (m = {m}, k = {k}, n = {n}), but hold {}, {} and {} elements",
+        lhs.len(),
+        rhs.len(),
+        out.len(),
+    );
+}
+
+/// Dispatches to [`GenericBlas`](crate::GenericBlas) if `T` is `f32` or `f64`.
+/// Returns `None` if `T` is not supported by BLAS.
+#[cfg(all(feature = "blas", feature = "cpu"))]
+#[allow(clippy::too_many_arguments)]
+fn try_blas_gemm<T: 'static>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) -> Option<()> {
+    use crate::GenericBlas;
+
+    assert_gemm_lengths(m, k, n, lhs, rhs, out);
+
+    macro_rules! try_blas {
+        ($float:ty) => {
+            if TypeId::of::<T>() == TypeId::of::<$float>() {
+                let gemm = match (lhs_trans, rhs_trans) {
+                    (false, false) => <$float as GenericBlas>::gemm,
+                    (false, true) => <$float as GenericBlas>::gemmT,
+                    (true, false) => <$float as GenericBlas>::Tgemm,
+                    (true, true) => return None,
+                };
+                // SAFETY: `T` and `$float` are the same type
+                let (lhs, rhs, out) = unsafe {
+                    (
+                        core::slice::from_raw_parts(lhs.as_ptr() as *const $float, lhs.len()),
+                        core::slice::from_raw_parts(rhs.as_ptr() as *const $float, rhs.len()),
+                        core::slice::from_raw_parts_mut(out.as_mut_ptr() as *mut $float, out.len()),
+                    )
+                };
+                gemm(m, n, k, lhs, rhs, out);
+                return Some(());
+            }
+        };
+    }
+    try_blas!(f32);
+    try_blas!(f64);
+    None
+}
+
+/// The cache-blocked pure-Rust kernel of [`add_gemm_slice`].
+#[allow(clippy::too_many_arguments)]
+fn add_gemm_blocked<T: Number>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) {
+    #[cfg(feature = "half")]
+    if try_add_gemm_half(m, k, n, lhs, lhs_trans, rhs, rhs_trans, out).is_some() {
+        return;
+    }
+
+    let lhs_at = |row: usize, col: usize| {
+        if lhs_trans {
+            lhs[col * m + row]
+        } else {
+            lhs[row * k + col]
+        }
+    };
+
+    for col_start in (0..n).step_by(GEMM_BLOCK_N) {
+        let cols = col_start..min(col_start + GEMM_BLOCK_N, n);
+
+        for shared_start in (0..k).step_by(GEMM_BLOCK_K) {
+            let shared = shared_start..min(shared_start + GEMM_BLOCK_K, k);
+
+            for row in 0..m {
+                let out_row = &mut out[row * n + cols.start..row * n + cols.end];
+
+                if rhs_trans {
+                    // the rows of the stored rhs are contiguous in the shared dimension
+                    for (col, out) in cols.clone().zip(out_row) {
+                        let rhs_row = &rhs[col * k..col * k + k];
+                        for idx in shared.clone() {
+                            *out += lhs_at(row, idx) * rhs_row[idx];
+                        }
+                    }
+                } else {
+                    for idx in shared.clone() {
+                        let lhs_value = lhs_at(row, idx);
+                        let rhs_row = &rhs[idx * n + cols.start..idx * n + cols.end];
+                        for (out, rhs) in out_row.iter_mut().zip(rhs_row) {
+                            *out += lhs_value * *rhs;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Dispatches to [`add_gemm_f32_acc`] if `T` is `f16` or `bf16`.
+/// Returns `None` if `T` is not a half precision floating point type.
+#[cfg(feature = "half")]
+#[allow(clippy::too_many_arguments)]
+fn try_add_gemm_half<T: 'static>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) -> Option<()> {
+    macro_rules! try_half {
+        ($half:ty) => {
+            if TypeId::of::<T>() == TypeId::of::<$half>() {
+                // SAFETY: `T` and `$half` are the same type
+                let (lhs, rhs, out) = unsafe {
+                    (
+                        core::slice::from_raw_parts(lhs.as_ptr() as *const $half, lhs.len()),
+                        core::slice::from_raw_parts(rhs.as_ptr() as *const $half, rhs.len()),
+                        core::slice::from_raw_parts_mut(out.as_mut_ptr() as *mut $half, out.len()),
+                    )
+                };
+                add_gemm_f32_acc(m, k, n, lhs, lhs_trans, rhs, rhs_trans, out);
+                return Some(());
+            }
+        };
+    }
+    try_half!(half::f16);
+    try_half!(half::bf16);
+    None
+}
+
+/// Like [`add_gemm_blocked`], but accumulates the products of the half precision matrices in `f32`.
+/// Each block of an output row is rounded to `T` once, after the whole shared dimension has been summed up.
+#[cfg(feature = "half")]
+#[allow(clippy::too_many_arguments)]
+fn add_gemm_f32_acc<T: crate::HalfFloat>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &[T],
+    lhs_trans: bool,
+    rhs: &[T],
+    rhs_trans: bool,
+    out: &mut [T],
+) {
+    let lhs_at = |row: usize, col: usize| {
+        if lhs_trans {
+            lhs[col * m + row].to_f32()
+        } else {
+            lhs[row * k + col].to_f32()
+        }
+    };
+
+    let mut acc = [0f32; GEMM_BLOCK_N];
+
+    for row in 0..m {
+        for col_start in (0..n).step_by(GEMM_BLOCK_N) {
+            let cols = col_start..min(col_start + GEMM_BLOCK_N, n);
+            let acc = &mut acc[..cols.len()];
+            acc.fill(0.);
+
+            if rhs_trans {
+                for (col, acc) in cols.clone().zip(acc.iter_mut()) {
+                    let rhs_row = &rhs[col * k..col * k + k];
+                    for (idx, rhs) in rhs_row.iter().enumerate() {
+                        *acc += lhs_at(row, idx) * rhs.to_f32();
+                    }
+                }
+            } else {
+                for idx in 0..k {
+                    let lhs_value = lhs_at(row, idx);
+                    let rhs_row = &rhs[idx * n + cols.start..idx * n + cols.end];
+                    for (acc, rhs) in acc.iter_mut().zip(rhs_row) {
+                        *acc += lhs_value * rhs.to_f32();
+                    }
+                }
+            }
+
+            let out_row = &mut out[row * n + cols.start..row * n + cols.end];
+            for (out, acc) in out_row.iter_mut().zip(acc.iter()) {
+                *out = T::from_f32(out.to_f32() + *acc);
+            }
+        }
+    }
+}
+
+/// Computes `y = alpha * x + y`.
+#[inline]
+pub fn axpy_slice<T: Number>(alpha: T, x: &[T], y: &mut [T]) {
+    for (x, y) in x.iter().zip(y.iter_mut()) {
+        *y += alpha * *x;
+    }
+}
+
+/// Returns the dot product of `x` and `y`.
+#[inline]
+pub fn dot_slice<T: Number>(x: &[T], y: &[T]) -> T {
+    x.iter().zip(y).map(|(x, y)| *x * *y).sum()
+}
+
+/// Computes `x = alpha * x`.
+#[inline]
+pub fn scal_slice<T: Number>(alpha: T, x: &mut [T]) {
+    for x in x {
+        *x *= alpha;
+    }
+}
+
+/// Computes `y = alpha * a * x + beta * y` for the row-major `m x n` matrix `a`.
+pub fn gemv_slice<T: Number>(m: usize, n: usize, alpha: T, a: &[T], x: &[T], beta: T, y: &mut [T]) {
+    for (row, y) in a.chunks_exact(n).take(m).zip(y) {
+        let product = alpha * dot_slice(row, x);
+        // like BLAS, `y` is not read if `beta` is zero
+        *y = if beta == T::zero() {
+            product
+        } else {
+            product + beta * *y
+        };
+    }
+}
+
+/// Computes `a = alpha * x * y^T + a` for the row-major `m x n` matrix `a`.
+pub fn ger_slice<T: Number>(m: usize, n: usize, alpha: T, x: &[T], y: &[T], a: &mut [T]) {
+    for (row, x) in a.chunks_exact_mut(n).take(m).zip(x) {
+        axpy_slice(alpha * *x, y, row);
+    }
+}
@@ -107,4 +107,132 @@ extern "C" {
         c: *mut f64,
         ldc: i32,
     ) -> cublasStatus_t;
+
+    pub fn cublasSaxpy_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        alpha: *const f32,
+        x: *const f32,
+        incx: i32,
+        y: *mut f32,
+        incy: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDaxpy_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        alpha: *const f64,
+        x: *const f64,
+        incx: i32,
+        y: *mut f64,
+        incy: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasSdot_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        x: *const f32,
+        incx: i32,
+        y: *const f32,
+        incy: i32,
+        result: *mut f32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDdot_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        x: *const f64,
+        incx: i32,
+        y: *const f64,
+        incy: i32,
+        result: *mut f64,
+    ) -> cublasStatus_t;
+
+    pub fn cublasSnrm2_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        x: *const f32,
+        incx: i32,
+        result: *mut f32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDnrm2_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        x: *const f64,
+        incx: i32,
+        result: *mut f64,
+    ) -> cublasStatus_t;
+
+    pub fn cublasSscal_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        alpha: *const f32,
+        x: *mut f32,
+        incx: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDscal_v2(
+        handle: cublasHandle_t,
+        n: i32,
+        alpha: *const f64,
+        x: *mut f64,
+        incx: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasSgemv_v2(
+        handle: cublasHandle_t,
+        trans: cublasOperation_t,
+        m: i32,
+        n: i32,
+        alpha: *const f32,
+        A: *const f32,
+        lda: i32,
+        x: *const f32,
+        incx: i32,
+        beta: *const f32,
+        y: *mut f32,
+        incy: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDgemv_v2(
+        handle: cublasHandle_t,
+        trans: cublasOperation_t,
+        m: i32,
+        n: i32,
+        alpha: *const f64,
+        A: *const f64,
+        lda: i32,
+        x: *const f64,
+        incx: i32,
+        beta: *const f64,
+        y: *mut f64,
+        incy: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasSger_v2(
+        handle: cublasHandle_t,
+        m: i32,
+        n: i32,
+        alpha: *const f32,
+        x: *const f32,
+        incx: i32,
+        y: *const f32,
+        incy: i32,
+        A: *mut f32,
+        lda: i32,
+    ) -> cublasStatus_t;
+
+    pub fn cublasDger_v2(
+        handle: cublasHandle_t,
+        m: i32,
+        n: i32,
+        alpha: *const f64,
+        x: *const f64,
+        incx: i32,
+        y: *const f64,
+        incy: i32,
+        A: *mut f64,
+        lda: i32,
+    ) -> cublasStatus_t;
 }
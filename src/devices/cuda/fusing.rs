@@ -1,4 +1,59 @@
-use crate::{OnDropBuffer, UnaryFusing, CUDA};
+use crate::{ElementWiseFusing, OnDropBuffer, UnaryFusing, CUDA};
+
+impl<Mods: OnDropBuffer> ElementWiseFusing for CUDA<Mods> {
+    #[cfg(feature = "lazy")]
+    fn fused_ew<T: crate::CDatatype + crate::Numeric>(
+        &self,
+        fused: &crate::FusedElementWise<T>,
+        inputs: &[&crate::Buffer<'_, T, Self, ()>],
+        outputs: &mut [&mut crate::Buffer<'_, T, Self, ()>],
+    ) -> crate::Result<()> {
+        use super::AsCudaCvoidPtr;
+
+        let Some(len) = outputs.first().map(|out| out.len()) else {
+            return Ok(());
+        };
+
+        let params = (0..fused.inputs())
+            .map(|idx| format!("{}* in{idx}, ", T::C_DTYPE_STR))
+            .chain((0..fused.outputs().len()).map(|idx| format!("{}* out{idx}, ", T::C_DTYPE_STR)))
+            .collect::<String>();
+
+        let fused_operations = fused.to_c_src(
+            T::C_DTYPE_STR,
+            |idx| format!("in{idx}[idx]"),
+            |idx| format!("out{idx}[idx]"),
+        );
+
+        let src = format!(
+            r#"extern "C" __global__ void fusedEw({params}int numElements)
+                {{
+                    int idx = blockDim.x * blockIdx.x + threadIdx.x;
+                    if (idx >= numElements) {{
+                        return;
+                    }}
+                    {fused_operations}
+                }}
+            "#
+        );
+
+        let mut args = inputs
+            .iter()
+            .map(|buf| *buf as &dyn AsCudaCvoidPtr)
+            .chain(outputs.iter().map(|buf| &**buf as &dyn AsCudaCvoidPtr))
+            .collect::<Vec<_>>();
+        args.push(&len);
+
+        self.launch_kernel(
+            &src,
+            "fusedEw",
+            [(len as u32 / 32 + 1) * 32, 1, 1],
+            [32, 1, 1],
+            0,
+            &args,
+        )
+    }
+}
 
 impl<Mods: OnDropBuffer> UnaryFusing for CUDA<Mods> {
     #[cfg(feature = "lazy")]
@@ -6,15 +61,8 @@ impl<Mods: OnDropBuffer> UnaryFusing for CUDA<Mods> {
     #[inline]
     fn unary_fuse_op<T: crate::CDatatype + crate::Numeric>(
         &self,
-        ops_to_fuse: Vec<std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>>,
-    ) -> Box<
-        dyn Fn(
-            (
-                &mut crate::Buffer<'_, T, Self, ()>,
-                &crate::Buffer<'_, T, Self, ()>,
-            ),
-        ) -> crate::Result<()>,
-    > {
+        ops_to_fuse: Vec<crate::UnaryFn<T>>,
+    ) -> crate::FusedUnaryOp<T, Self> {
         use crate::operations_to_fused_src;
         Box::new(move |(out, buf)| {
             if ops_to_fuse.is_empty() {
@@ -4,7 +4,7 @@ use crate::CUDA;
 
 use super::api::{
     create_graph_execution, create_graph_from_captured_stream, cuGraphLaunch, cuStreamBeginCapture,
-    CUStreamCaptureMode, CudaErrorKind, Graph, GraphExec, Stream,
+    CUStreamCaptureMode, CUstreamCaptureStatus, CudaErrorKind, Graph, GraphExec, Stream,
 };
 
 pub struct LazyCudaGraph {
@@ -48,6 +48,53 @@ impl<Mods> crate::LazyRun for CUDA<Mods> {
     }
 }
 
+#[cfg(feature = "lazy")]
+impl<Mods: crate::OnDropBuffer + 'static> crate::RunCaptured for CUDA<Mods> {
+    /// Records the captured operations into a [`LazyCudaGraph`] on the first run.
+    /// The CUDA graph works on the recorded buffers, hence bound inputs and outputs are copied.
+    fn run_captured<T>(&self, captured: &mut crate::CapturedGraph<T>) -> crate::Result<()> {
+        if captured.backend_state::<LazyCudaGraph>().is_none() {
+            let was_capturing = self.stream().capture_status()?
+                != CUstreamCaptureStatus::CU_STREAM_CAPTURE_STATUS_NONE;
+
+            // end a global lazy capture in order to record only the captured operations
+            if was_capturing {
+                drop(LazyCudaGraph::new(self.stream())?);
+            }
+
+            unsafe {
+                cuStreamBeginCapture(
+                    self.stream.0,
+                    CUStreamCaptureMode::CU_STREAM_CAPTURE_MODE_GLOBAL,
+                )
+            }
+            .to_result()?;
+            captured.replay_recorded(self)?;
+            let graph = LazyCudaGraph::new(self.stream())?;
+            captured.set_backend_state(graph);
+
+            if was_capturing {
+                unsafe {
+                    cuStreamBeginCapture(
+                        self.stream.0,
+                        CUStreamCaptureMode::CU_STREAM_CAPTURE_MODE_GLOBAL,
+                    )
+                }
+                .to_result()?;
+            }
+        }
+
+        captured.copy_bound_inputs(self);
+        captured
+            .backend_state::<LazyCudaGraph>()
+            .unwrap()
+            .launch(self.stream())?;
+        self.stream().sync()?;
+        captured.copy_bound_outputs(self);
+        Ok(())
+    }
+}
+
 impl<Mods: crate::RunModule<Self>> crate::Run for CUDA<Mods> {
     #[inline]
     fn run(&self) -> crate::Result<()> {
@@ -1,12 +1,18 @@
-use core::ops::{Range, RangeBounds};
+use core::{
+    any::TypeId,
+    ops::{Range, RangeBounds},
+};
 
 use crate::{
+    blas::check_blas_lengths,
     bounds_to_range,
     cuda::api::{cu_read_async, CUstreamCaptureStatus},
-    op_hint::unary,
-    pass_down_add_operation, pass_down_exec_now, AddOperation, ApplyFunction, Buffer, CDatatype,
-    ClearBuf, CopySlice, OnDropBuffer, Read, Resolve, Retrieve, Retriever, SetOpHint, Shape,
-    ToCLSource, ToMarker, UnaryGrad, Unit, WriteBuf, ZeroGrad, CUDA,
+    gemm::{check_gemm_lengths, gemm_index_src, gemm_out_len, GEMM_TILE},
+    op_hint::{binary, unary},
+    pass_down_add_operation, pass_down_exec_now, AddOperation, ApplyBinaryFunction, ApplyFunction,
+    Blas, Buffer, CDatatype, ClearBuf, CopySlice, Gemm, GemmGrad, GenericBlas, MayTangentActions,
+    Number, OnDropBuffer, Read, Resolve, Retrieve, Retriever, SetOpHint, Shape, ToCLSource,
+    ToMarker, UnaryGrad, Unit, WriteBuf, ZeroGrad, CUDA,
 };
 
 use super::{
@@ -62,6 +68,78 @@ impl<Mods: OnDropBuffer, T: CDatatype> ZeroGrad<T> for CUDA<Mods> {
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>) {
         cu_clear(self, data).unwrap()
     }
+
+    #[inline]
+    fn grad_squared_norm<S: Shape>(&self, data: &Self::Base<T, S>) -> crate::Result<f64>
+    where
+        T: 'static,
+    {
+        cu_squared_norm(self, data)
+    }
+
+    #[inline]
+    fn scale_grad<S: Shape>(&self, data: &mut Self::Base<T, S>, factor: f64) -> crate::Result<()>
+    where
+        T: 'static,
+    {
+        cu_scale(self, data, factor as f32)
+    }
+}
+
+/// Computes the sum of the squared elements of a CUDA buffer.
+pub fn cu_squared_norm<T: CDatatype>(device: &CudaDevice, x: &CUDAPtr<T>) -> crate::Result<f64> {
+    let src = format!(
+        r#"extern "C" __global__ void squaredNorm(const {datatype}* x, float* sum, int numElements)
+            {{
+                float partial = 0;
+                for (int idx = blockDim.x * blockIdx.x + threadIdx.x; idx < numElements; idx += blockDim.x * gridDim.x) {{
+                    float value = (float) x[idx];
+                    partial += value * value;
+                }}
+                atomicAdd(sum, partial);
+            }}
+    "#,
+        datatype = T::C_DTYPE_STR
+    );
+
+    let mut sum = CUDAPtr::<f32>::new(1, crate::flag::AllocFlag::None)?;
+    cu_clear(device, &mut sum)?;
+
+    let blocks = (x.len as u32).div_ceil(256).clamp(1, 64);
+    device.launch_kernel(
+        &src,
+        "squaredNorm",
+        [blocks, 1, 1],
+        [256, 1, 1],
+        0,
+        &[x, &sum, &x.len],
+    )?;
+    device.stream().sync()?;
+
+    let mut read = [0f32];
+    cu_read_async(&mut read, sum.ptr, &device.mem_transfer_stream)?;
+    device.mem_transfer_stream.sync()?;
+    Ok(read[0] as f64)
+}
+
+/// Multiplies every element of a CUDA buffer by `factor`.
+pub fn cu_scale<T: CDatatype>(
+    device: &CudaDevice,
+    x: &mut CUDAPtr<T>,
+    factor: f32,
+) -> crate::Result<()> {
+    let src = format!(
+        r#"extern "C" __global__ void scale({datatype}* x, float factor, int numElements)
+            {{
+                int idx = blockDim.x * blockIdx.x + threadIdx.x;
+                if (idx < numElements) {{
+                    x[idx] = ({datatype}) (x[idx] * factor);
+                }}
+            }}
+    "#,
+        datatype = T::C_DTYPE_STR
+    );
+    device.launch_kernel1d(x.len, &src, "scale", &[x, &factor, &x.len])
 }
 
 impl<Mods: OnDropBuffer, T: Unit> CopySlice<T> for CUDA<Mods> {
@@ -122,6 +200,7 @@ impl<Mods, T, S> ApplyFunction<T, S> for CUDA<Mods>
 where
     T: CDatatype + Default,
     Mods: AddOperation + Retrieve<Self, T, S> + SetOpHint<T> + 'static,
+    Self: MayTangentActions,
     S: Shape,
 {
     #[inline]
@@ -135,6 +214,19 @@ where
     {
         let mut out = self.retrieve(buf.len(), buf).unwrap();
         self.add_op((&mut out, buf), move |(out, buf)| {
+            #[cfg(feature = "forward")]
+            if let Some(mut tangents) = crate::TangentActions::tangents_mut(buf.device()) {
+                if let Some((tangent, out_tangent)) = tangents.tangent_pair(buf, out) {
+                    return try_cu_apply_fn_tangent_mut(
+                        buf.device(),
+                        buf,
+                        tangent,
+                        out,
+                        out_tangent,
+                        f,
+                    );
+                }
+            }
             try_cu_apply_fn_mut(buf.device(), buf, out, f)
         })
         .unwrap();
@@ -178,6 +270,336 @@ where
     Ok(())
 }
 
+#[cfg(feature = "forward")]
+pub fn try_cu_apply_fn_tangent_mut<T, F>(
+    device: &CudaDevice,
+    x: &CUDAPtr<T>,
+    x_tangent: &CUDAPtr<T>,
+    out: &mut CUDAPtr<T>,
+    out_tangent: &mut CUDAPtr<T>,
+    f: impl Fn(Resolve<T>) -> F,
+) -> crate::Result<()>
+where
+    F: crate::ToCLTangentSource,
+    T: CDatatype + Default,
+{
+    let op = f("x[idx]".to_marker());
+    let src = format!(
+        r#"extern "C" __global__ void applyFnTangent({datatype}* x, {datatype}* tangent, {datatype}* out, {datatype}* outTangent, int numElements)
+            {{
+                int idx = blockDim.x * blockIdx.x + threadIdx.x;
+                if (idx >= numElements) {{
+                    return;
+                }}
+                out[idx] = {op};
+                outTangent[idx] = {tangent_op};
+            }}
+    "#,
+        datatype = T::C_DTYPE_STR,
+        op = op.to_cl_source(),
+        tangent_op = op.to_cl_tangent_source("tangent[idx]"),
+    );
+
+    device.launch_kernel(
+        &src,
+        "applyFnTangent",
+        [(x.len as u32 / 32 + 1) * 32, 1, 1],
+        [32, 1, 1],
+        0,
+        &[x, x_tangent, out, out_tangent, &x.len],
+    )?;
+    Ok(())
+}
+
+impl<Mods, T, S> ApplyBinaryFunction<T, S> for CUDA<Mods>
+where
+    T: CDatatype + Default,
+    Mods: AddOperation + Retrieve<Self, T, S> + SetOpHint<T> + 'static,
+    S: Shape,
+{
+    #[inline]
+    fn apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, Self, S>,
+        rhs: &Buffer<T, Self, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: crate::TwoWay<T>,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            try_cu_apply_binary_fn_mut(lhs.device(), lhs, rhs, out, f)
+        })
+        .unwrap();
+        self.set_op_hint(binary(f));
+        out
+    }
+}
+
+pub fn try_cu_apply_binary_fn_mut<T, F>(
+    device: &CudaDevice,
+    lhs: &CUDAPtr<T>,
+    rhs: &CUDAPtr<T>,
+    out: &mut CUDAPtr<T>,
+    f: impl Fn(Resolve<T>, Resolve<T>) -> F,
+) -> crate::Result<()>
+where
+    F: ToCLSource,
+    T: CDatatype + Default,
+{
+    let src = format!(
+        r#"extern "C" __global__ void applyBinaryFn({datatype}* lhs, {datatype}* rhs, {datatype}* out, int numElements)
+            {{
+                int idx = blockDim.x * blockIdx.x + threadIdx.x;
+                if (idx >= numElements) {{
+                    return;
+                }}
+                out[idx] = {op};
+            }}
+    "#,
+        datatype = T::C_DTYPE_STR,
+        op = f("lhs[idx]".to_marker(), "rhs[idx]".to_marker()).to_cl_source()
+    );
+
+    device.launch_kernel(
+        &src,
+        "applyBinaryFn",
+        [(lhs.len as u32 / 32 + 1) * 32, 1, 1],
+        [32, 1, 1],
+        0,
+        &[lhs, rhs, out, &lhs.len],
+    )?;
+    Ok(())
+}
+
+impl<Mods, T> Blas<T> for CUDA<Mods>
+where
+    Mods: OnDropBuffer,
+    T: GenericBlas + Unit,
+{
+    fn axpy(&self, alpha: T, x: &Buffer<T, Self>, y: &mut Buffer<T, Self>) -> crate::Result<()> {
+        check_blas_lengths(&[(y.len(), x.len(), 1)])?;
+        T::cuaxpy(
+            self.cublas_handle(),
+            x.len(),
+            alpha,
+            x.base().ptr,
+            y.base().ptr,
+        )
+    }
+
+    fn dot(&self, x: &Buffer<T, Self>, y: &Buffer<T, Self>) -> crate::Result<T> {
+        check_blas_lengths(&[(y.len(), x.len(), 1)])?;
+        T::cudot(self.cublas_handle(), x.len(), x.base().ptr, y.base().ptr)
+    }
+
+    #[inline]
+    fn nrm2(&self, x: &Buffer<T, Self>) -> crate::Result<T> {
+        T::cunrm2(self.cublas_handle(), x.len(), x.base().ptr)
+    }
+
+    #[inline]
+    fn scal(&self, alpha: T, x: &mut Buffer<T, Self>) -> crate::Result<()> {
+        T::cuscal(self.cublas_handle(), x.len(), alpha, x.base().ptr)
+    }
+
+    fn gemv(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        a: &Buffer<T, Self>,
+        x: &Buffer<T, Self>,
+        beta: T,
+        y: &mut Buffer<T, Self>,
+    ) -> crate::Result<()> {
+        check_blas_lengths(&[(a.len(), m, n), (x.len(), n, 1), (y.len(), m, 1)])?;
+        T::cugemv(
+            self.cublas_handle(),
+            m,
+            n,
+            alpha,
+            a.base().ptr,
+            x.base().ptr,
+            beta,
+            y.base().ptr,
+        )
+    }
+
+    fn ger(
+        &self,
+        m: usize,
+        n: usize,
+        alpha: T,
+        x: &Buffer<T, Self>,
+        y: &Buffer<T, Self>,
+        a: &mut Buffer<T, Self>,
+    ) -> crate::Result<()> {
+        check_blas_lengths(&[(a.len(), m, n), (x.len(), m, 1), (y.len(), n, 1)])?;
+        T::cuger(
+            self.cublas_handle(),
+            m,
+            n,
+            alpha,
+            x.base().ptr,
+            y.base().ptr,
+            a.base().ptr,
+        )
+    }
+}
+
+impl<Mods, T> Gemm<T> for CUDA<Mods>
+where
+    T: CDatatype + Number,
+    Mods: AddOperation + Retrieve<Self, T> + 'static,
+{
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs: &Buffer<T, Self>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            let device = lhs.device();
+            let (a, b, c) = (lhs.base().ptr, rhs.base().ptr, out.base().ptr);
+
+            // f32 and f64 matrices are multiplied by cuBLAS
+            if TypeId::of::<T>() == TypeId::of::<f32>() {
+                return f32::cugemm(device.cublas_handle(), m, n, k, a, b, c);
+            }
+            if TypeId::of::<T>() == TypeId::of::<f64>() {
+                return f64::cugemm(device.cublas_handle(), m, n, k, a, b, c);
+            }
+            try_cu_gemm(device, m, k, n, lhs, false, rhs, false, out, false)
+        })
+        .unwrap();
+        out
+    }
+}
+
+impl<Mods, T> GemmGrad<T> for CUDA<Mods>
+where
+    T: CDatatype + Number,
+    Mods: OnDropBuffer + AddOperation + 'static,
+{
+    #[inline]
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, Self>,
+        lhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                try_cu_gemm(rhs.device(), m, n, k, out_grad, false, rhs, true, lhs_grad, true)
+            },
+        )
+        .unwrap();
+    }
+
+    #[inline]
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                try_cu_gemm(lhs.device(), k, m, n, lhs, true, out_grad, false, rhs_grad, true)
+            },
+        )
+        .unwrap();
+    }
+}
+
+/// Computes `out = op(lhs) * op(rhs)` with a generated tiled kernel, where `op(lhs)` is `m x k` and `op(rhs)` is `k x n`.
+/// If `lhs_trans` (`rhs_trans`) is `true`, `lhs` (`rhs`) is stored transposed.
+/// If `accumulate` is `true`, the product is added to `out`.
+#[allow(clippy::too_many_arguments)]
+pub fn try_cu_gemm<T>(
+    device: &CudaDevice,
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &CUDAPtr<T>,
+    lhs_trans: bool,
+    rhs: &CUDAPtr<T>,
+    rhs_trans: bool,
+    out: &mut CUDAPtr<T>,
+    accumulate: bool,
+) -> crate::Result<()>
+where
+    T: CDatatype + Number,
+{
+    check_gemm_lengths(m, k, n, lhs.len, rhs.len, out.len)?;
+
+    let src = format!(
+        r#"#define TILE {GEMM_TILE}
+        extern "C" __global__ void gemm({datatype}* lhs, {datatype}* rhs, {datatype}* out, size_t m, size_t k, size_t n)
+            {{
+                __shared__ {datatype} lhsTile[TILE][TILE];
+                __shared__ {datatype} rhsTile[TILE][TILE];
+
+                size_t localRow = threadIdx.y;
+                size_t localCol = threadIdx.x;
+                size_t row = blockIdx.y * TILE + localRow;
+                size_t col = blockIdx.x * TILE + localCol;
+
+                {datatype} acc = 0;
+                for (size_t tile = 0; tile < k; tile += TILE) {{
+                    size_t lhsCol = tile + localCol;
+                    size_t rhsRow = tile + localRow;
+                    lhsTile[localRow][localCol] = row < m && lhsCol < k ? lhs[{lhs_idx}] : 0;
+                    rhsTile[localRow][localCol] = rhsRow < k && col < n ? rhs[{rhs_idx}] : 0;
+                    __syncthreads();
+
+                    for (size_t idx = 0; idx < TILE; idx++) {{
+                        acc += lhsTile[localRow][idx] * rhsTile[idx][localCol];
+                    }}
+                    __syncthreads();
+                }}
+
+                if (row < m && col < n) {{
+                    out[row * n + col] {assign} acc;
+                }}
+            }}
+    "#,
+        datatype = T::C_DTYPE_STR,
+        lhs_idx = gemm_index_src("row", "lhsCol", "m", "k", lhs_trans),
+        rhs_idx = gemm_index_src("rhsRow", "col", "k", "n", rhs_trans),
+        assign = if accumulate { "+=" } else { "=" },
+    );
+
+    let blocks = |len: usize| len.div_ceil(GEMM_TILE) as u32;
+    device.launch_kernel(
+        &src,
+        "gemm",
+        [blocks(n), blocks(m), 1],
+        [GEMM_TILE as u32, GEMM_TILE as u32, 1],
+        0,
+        &[lhs, rhs, out, &m, &k, &n],
+    )?;
+    Ok(())
+}
+
 impl<T, S, Mods> UnaryGrad<T, S> for CUDA<Mods>
 where
     T: CDatatype + Default,
@@ -235,6 +657,76 @@ where
     Ok(())
 }
 
+#[cfg(feature = "autograd")]
+impl<Mods, T, S> crate::optim::ApplyUpdate<T, S> for CUDA<Mods>
+where
+    Mods: OnDropBuffer,
+    T: CDatatype + crate::Number,
+    S: Shape,
+{
+    fn apply_update(
+        &self,
+        rule: &crate::optim::UpdateRule<T>,
+        param: &mut Buffer<T, Self, S>,
+        grad: &Buffer<T, Self, S>,
+        states: &mut [Buffer<T, Self, S>],
+        hyper: &[T],
+    ) -> crate::Result<()> {
+        use super::AsCudaCvoidPtr;
+
+        let datatype = T::C_DTYPE_STR;
+        let state_params = (0..states.len())
+            .map(|idx| format!("{datatype}* state{idx}, "))
+            .collect::<String>();
+        let hyper_params = (0..rule.hyper())
+            .map(|idx| format!("{datatype} hp{idx}, "))
+            .collect::<String>();
+        let load_states = (0..states.len())
+            .map(|idx| format!("{datatype} s{idx} = state{idx}[idx];\n"))
+            .collect::<String>();
+        let store_states = (0..states.len())
+            .map(|idx| format!("state{idx}[idx] = s{idx};\n"))
+            .collect::<String>();
+
+        let src = format!(
+            r#"extern "C" __global__ void applyUpdate({datatype}* param, const {datatype}* grad, {state_params}{hyper_params}int numElements)
+                {{
+                    int idx = blockDim.x * blockIdx.x + threadIdx.x;
+                    if (idx >= numElements) {{
+                        return;
+                    }}
+                    {datatype} p = param[idx];
+                    {datatype} g = grad[idx];
+                    {load_states}
+                    {update}
+                    param[idx] = p;
+                    {store_states}
+                }}
+            "#,
+            update = rule.to_c_src()
+        );
+
+        let len = param.len();
+        let mut args = vec![&*param as &dyn AsCudaCvoidPtr, grad];
+        args.extend(states.iter().map(|state| state as &dyn AsCudaCvoidPtr));
+        args.extend(
+            hyper[..rule.hyper()]
+                .iter()
+                .map(|hyper| hyper as &dyn AsCudaCvoidPtr),
+        );
+        args.push(&len);
+
+        self.launch_kernel(
+            &src,
+            "applyUpdate",
+            [(len as u32 / 32 + 1) * 32, 1, 1],
+            [32, 1, 1],
+            0,
+            &args,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
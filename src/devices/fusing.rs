@@ -1,9 +1,7 @@
 use crate::IsShapeIndep;
 
 #[cfg(feature = "std")]
-pub fn operations_to_fused_src<T: Default + Copy>(
-    ops: &[std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>],
-) -> String {
+pub fn operations_to_fused_src<T: Default + Copy>(ops: &[UnaryFn<T>]) -> String {
     ops.iter().fold(String::new(), |acc, op| {
         let resolve = crate::Resolve {
             val: T::default(),
@@ -18,20 +16,212 @@ pub fn operations_to_fused_src<T: Default + Copy>(
     })
 }
 
+/// A type-erased unary element-wise operation.
+#[cfg(feature = "std")]
+pub type UnaryFn<T> = std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>;
+/// A type-erased binary element-wise operation.
+#[cfg(feature = "std")]
+pub type BinaryFn<T> =
+    std::rc::Rc<dyn Fn(crate::Resolve<T>, crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>;
+/// A fused chain of unary operations, called with the output and the input buffer.
+#[cfg(feature = "std")]
+pub type FusedUnaryOp<T, D> = Box<
+    dyn Fn((&mut crate::Buffer<'_, T, D, ()>, &crate::Buffer<'_, T, D, ()>)) -> crate::Result<()>,
+>;
+
+/// Variable names used in generated fused element-wise kernels.
+/// [`Resolve`](crate::Resolve) markers require `'static` strings.
+#[cfg(feature = "std")]
+const FUSED_VARS: [&str; 32] = [
+    "v0", "v1", "v2", "v3", "v4", "v5", "v6", "v7", "v8", "v9", "v10", "v11", "v12", "v13", "v14",
+    "v15", "v16", "v17", "v18", "v19", "v20", "v21", "v22", "v23", "v24", "v25", "v26", "v27",
+    "v28", "v29", "v30", "v31",
+];
+
+/// A variable of a [`FusedElementWise`] expression.
+#[cfg(feature = "std")]
+pub enum FusedVar<T> {
+    /// Loaded from the input buffer with the given index.
+    Input(usize),
+    /// Applies a unary operation to a previously defined variable.
+    Unary(UnaryFn<T>, usize),
+    /// Applies a binary operation to two previously defined variables.
+    Binary(BinaryFn<T>, usize, usize),
+}
+
+/// An element-wise sub-graph with several inputs and outputs,
+/// which is executed as one generated kernel or as one loop on the CPU.
+/// Every variable is defined exactly once, in order.
+#[cfg(feature = "std")]
+pub struct FusedElementWise<T> {
+    vars: Vec<FusedVar<T>>,
+    inputs: usize,
+    outputs: Vec<usize>,
+}
+
+#[cfg(feature = "std")]
+impl<T> Default for FusedElementWise<T> {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            vars: Vec::new(),
+            inputs: 0,
+            outputs: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> FusedElementWise<T> {
+    /// The maximum amount of variables a fused expression can contain.
+    pub const MAX_VARS: usize = FUSED_VARS.len();
+
+    fn push(&mut self, var: FusedVar<T>) -> Option<usize> {
+        if self.vars.len() >= Self::MAX_VARS {
+            return None;
+        }
+        self.vars.push(var);
+        Some(self.vars.len() - 1)
+    }
+
+    /// Adds a variable that is loaded from a new input buffer.
+    /// Returns `None` if the maximum amount of variables is reached.
+    pub fn input(&mut self) -> Option<usize> {
+        let var = self.push(FusedVar::Input(self.inputs))?;
+        self.inputs += 1;
+        Some(var)
+    }
+
+    /// Adds a variable that is the result of `op` applied to `x`.
+    #[inline]
+    pub fn unary(&mut self, op: UnaryFn<T>, x: usize) -> Option<usize> {
+        self.push(FusedVar::Unary(op, x))
+    }
+
+    /// Adds a variable that is the result of `op` applied to `lhs` and `rhs`.
+    #[inline]
+    pub fn binary(&mut self, op: BinaryFn<T>, lhs: usize, rhs: usize) -> Option<usize> {
+        self.push(FusedVar::Binary(op, lhs, rhs))
+    }
+
+    /// Writes the variable `var` to a new output buffer.
+    #[inline]
+    pub fn output(&mut self, var: usize) {
+        self.outputs.push(var);
+    }
+
+    #[inline]
+    pub fn vars(&self) -> &[FusedVar<T>] {
+        &self.vars
+    }
+
+    /// The amount of input buffers.
+    #[inline]
+    pub fn inputs(&self) -> usize {
+        self.inputs
+    }
+
+    /// The variables written to the output buffers, in output order.
+    #[inline]
+    pub fn outputs(&self) -> &[usize] {
+        &self.outputs
+    }
+
+    /// Evaluates all variables for a single element. `vals` must contain at least [`vars().len()`](Self::vars) values.
+    pub fn eval(&self, input: impl Fn(usize) -> T, vals: &mut [T])
+    where
+        T: Copy + 'static,
+    {
+        use crate::ToVal;
+
+        for (idx, var) in self.vars.iter().enumerate() {
+            vals[idx] = match var {
+                FusedVar::Input(input_idx) => input(*input_idx),
+                FusedVar::Unary(op, x) => op(vals[*x].to_val()).eval(),
+                FusedVar::Binary(op, lhs, rhs) => {
+                    op(vals[*lhs].to_val(), vals[*rhs].to_val()).eval()
+                }
+            }
+        }
+    }
+
+    fn to_src(
+        &self,
+        decl: &str,
+        src: impl Fn(&dyn crate::TwoWay<T>) -> String,
+        load: impl Fn(usize) -> String,
+        store: impl Fn(usize) -> String,
+    ) -> String
+    where
+        T: Default,
+    {
+        use crate::Resolve;
+
+        let mut fused_src = String::new();
+        for (idx, var) in self.vars.iter().enumerate() {
+            let val = match var {
+                FusedVar::Input(input_idx) => load(*input_idx),
+                FusedVar::Unary(op, x) => src(&*op(Resolve::with_marker(FUSED_VARS[*x]))),
+                FusedVar::Binary(op, lhs, rhs) => src(&*op(
+                    Resolve::with_marker(FUSED_VARS[*lhs]),
+                    Resolve::with_marker(FUSED_VARS[*rhs]),
+                )),
+            };
+            fused_src.push_str(&format!("{decl} {} = {val};\n", FUSED_VARS[idx]));
+        }
+        for (output_idx, var) in self.outputs.iter().enumerate() {
+            fused_src.push_str(&format!("{} = {};\n", store(output_idx), FUSED_VARS[*var]));
+        }
+        fused_src
+    }
+
+    /// Generates C (OpenCL / CUDA) statements. `load` and `store` return the expression used to access an input or output buffer.
+    #[inline]
+    pub fn to_c_src(
+        &self,
+        datatype: &str,
+        load: impl Fn(usize) -> String,
+        store: impl Fn(usize) -> String,
+    ) -> String
+    where
+        T: Default,
+    {
+        self.to_src(datatype, |op| op.to_cl_source(), load, store)
+    }
+
+    /// Generates WGSL statements. `load` and `store` return the expression used to access an input or output buffer.
+    #[inline]
+    pub fn to_wgsl_src(
+        &self,
+        load: impl Fn(usize) -> String,
+        store: impl Fn(usize) -> String,
+    ) -> String
+    where
+        T: Default,
+    {
+        self.to_src("let", |op| op.to_wgsl_source(), load, store)
+    }
+}
+
+/// Executes a [`FusedElementWise`] expression on a device.
+pub trait ElementWiseFusing: IsShapeIndep {
+    /// Evaluates `fused` for every element. All buffers have the same length.
+    #[cfg(feature = "lazy")]
+    fn fused_ew<T: crate::CDatatype + crate::Numeric>(
+        &self,
+        fused: &FusedElementWise<T>,
+        inputs: &[&crate::Buffer<'_, T, Self, ()>],
+        outputs: &mut [&mut crate::Buffer<'_, T, Self, ()>],
+    ) -> crate::Result<()>;
+}
+
 pub trait UnaryFusing: IsShapeIndep {
     #[cfg(feature = "lazy")]
     #[cfg(feature = "graph")]
     fn unary_fuse_op<T: crate::CDatatype + crate::Numeric>(
         &self,
-        ops_to_fuse: Vec<std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>>,
-    ) -> Box<
-        dyn Fn(
-            (
-                &mut crate::Buffer<'_, T, Self, ()>,
-                &crate::Buffer<'_, T, Self, ()>,
-            ),
-        ) -> crate::Result<()>,
-    >;
+        ops_to_fuse: Vec<crate::UnaryFn<T>>,
+    ) -> crate::FusedUnaryOp<T, Self>;
 
     #[cfg(feature = "lazy")]
     #[cfg(feature = "graph")]
@@ -40,10 +230,7 @@ pub trait UnaryFusing: IsShapeIndep {
     unsafe fn fuse_unary_ops<'a, T: crate::CDatatype + crate::Numeric>(
         &'a self,
         lazy_graph: &'a crate::LazyGraph<Box<dyn crate::BoxedShallowCopy>, T>,
-        ops: (
-            Vec<std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>>,
-            Vec<usize>,
-        ),
+        ops: (Vec<UnaryFn<T>>, Vec<usize>),
         buffers: &mut crate::Buffers<Box<dyn crate::BoxedShallowCopy>>,
     ) -> (usize, crate::Operation<Box<dyn crate::BoxedShallowCopy>, T>)
     where
@@ -116,4 +303,42 @@ mod tests {
         let src = operations_to_fused_src(&ops);
         assert_eq!(src, "x = sin(x);\nx = -(x);\nx = cos(x);\n")
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_fused_element_wise_src_and_eval() {
+        use crate::{
+            op_hint::{binary, unary, OpHint},
+            Combiner, FusedElementWise, Resolve,
+        };
+
+        let OpHint::Binary(mul) = binary(|a: Resolve<f32>, b| a.mul(b)) else {
+            panic!()
+        };
+        let OpHint::Unary(relu) = unary(|x: Resolve<f32>| x.max(0.)) else {
+            panic!()
+        };
+
+        let mut fused = FusedElementWise::default();
+        let a = fused.input().unwrap();
+        let b = fused.input().unwrap();
+        let ab = fused.binary(mul, a, b).unwrap();
+        let out = fused.unary(relu, ab).unwrap();
+        fused.output(ab);
+        fused.output(out);
+
+        let src = fused.to_c_src(
+            "float",
+            |idx| format!("in{idx}[id]"),
+            |idx| format!("out{idx}[id]"),
+        );
+        assert_eq!(
+            src,
+            "float v0 = in0[id];\nfloat v1 = in1[id];\nfloat v2 = (v0 * v1);\nfloat v3 = max(v2, 0.0);\nout0[id] = v2;\nout1[id] = v3;\n"
+        );
+
+        let mut vals = [0.; 4];
+        fused.eval(|idx| [-2., 3.][idx], &mut vals);
+        assert_eq!(vals, [-2., 3., -6., 0.]);
+    }
 }
@@ -3,13 +3,21 @@
 #[cfg(feature = "blas")]
 #[cfg(feature = "cpu")]
 use super::cpu::{
-    api::{cblas_dgemm, cblas_sgemm},
+    api::{
+        cblas_daxpy, cblas_ddot, cblas_dgemm, cblas_dgemv, cblas_dger, cblas_dnrm2, cblas_dscal,
+        cblas_saxpy, cblas_sdot, cblas_sgemm, cblas_sgemv, cblas_sger, cblas_snrm2, cblas_sscal,
+    },
     Order, Transpose,
 };
 
 #[cfg(feature = "cuda")]
 use super::cuda::api::{
-    cublas::{cublasDgemm_v2, cublasOperation_t, cublasSgemm_v2, CublasHandle},
+    cublas::{
+        cublasDaxpy_v2, cublasDdot_v2, cublasDgemm_v2, cublasDgemv_v2, cublasDger_v2,
+        cublasDnrm2_v2, cublasDscal_v2, cublasOperation_t, cublasSaxpy_v2, cublasSdot_v2,
+        cublasSgemm_v2, cublasSgemv_v2, cublasSger_v2, cublasSnrm2_v2, cublasSscal_v2,
+        CublasHandle,
+    },
     CUdeviceptr,
 };
 
@@ -104,6 +112,167 @@ where
         )
     }
 
+    /// Computes `y = alpha * x + y`
+    /// # Safety
+    /// `x` and `y` must hold at least `1 + (n - 1) * incx` and `1 + (n - 1) * incy` elements.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    unsafe fn blas_axpy(
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &mut [Self],
+        incy: usize,
+    );
+
+    /// Returns the dot product of `x` and `y`
+    /// # Safety
+    /// `x` and `y` must hold at least `1 + (n - 1) * incx` and `1 + (n - 1) * incy` elements.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    unsafe fn blas_dot(n: usize, x: &[Self], incx: usize, y: &[Self], incy: usize) -> Self;
+
+    /// Returns the euclidean norm of `x`
+    /// # Safety
+    /// `x` must hold at least `1 + (n - 1) * incx` elements.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    unsafe fn blas_nrm2(n: usize, x: &[Self], incx: usize) -> Self;
+
+    /// Computes `x = alpha * x`
+    /// # Safety
+    /// `x` must hold at least `1 + (n - 1) * incx` elements.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    unsafe fn blas_scal(n: usize, alpha: Self, x: &mut [Self], incx: usize);
+
+    /// Computes the matrix-vector product `y = alpha * op(a) * x + beta * y`
+    /// # Safety
+    /// `a`, `x` and `y` must hold all elements addressed by `order`, `trans`, `m`, `n`, `lda`, `incx` and `incy`.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn blas_gemv(
+        order: Order,
+        trans: Transpose,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: &[Self],
+        lda: usize,
+        x: &[Self],
+        incx: usize,
+        beta: Self,
+        y: &mut [Self],
+        incy: usize,
+    );
+
+    /// Computes the rank-1 update `a = alpha * x * y^T + a`
+    /// # Safety
+    /// `a`, `x` and `y` must hold all elements addressed by `order`, `m`, `n`, `lda`, `incx` and `incy`.
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn blas_ger(
+        order: Order,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &[Self],
+        incy: usize,
+        a: &mut [Self],
+        lda: usize,
+    );
+
+    /// A shortened wrapper around [`GenericBlas::blas_axpy`] for contiguous slices
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    fn axpy(alpha: Self, x: &[Self], y: &mut [Self]) {
+        // SAFETY: both slices hold at least `n` contiguous elements
+        unsafe { Self::blas_axpy(x.len().min(y.len()), alpha, x, 1, y, 1) }
+    }
+
+    /// A shortened wrapper around [`GenericBlas::blas_dot`] for contiguous slices
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    fn dot(x: &[Self], y: &[Self]) -> Self {
+        // SAFETY: both slices hold at least `n` contiguous elements
+        unsafe { Self::blas_dot(x.len().min(y.len()), x, 1, y, 1) }
+    }
+
+    /// A shortened wrapper around [`GenericBlas::blas_nrm2`] for contiguous slices
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    fn nrm2(x: &[Self]) -> Self {
+        // SAFETY: `x` holds `n` contiguous elements
+        unsafe { Self::blas_nrm2(x.len(), x, 1) }
+    }
+
+    /// A shortened wrapper around [`GenericBlas::blas_scal`] for contiguous slices
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    fn scal(alpha: Self, x: &mut [Self]) {
+        // SAFETY: `x` holds `n` contiguous elements
+        unsafe { Self::blas_scal(x.len(), alpha, x, 1) }
+    }
+
+    /// A shortened wrapper around [`GenericBlas::blas_gemv`] for a row-major `m x n` matrix `a`
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn gemv(m: usize, n: usize, alpha: Self, a: &[Self], x: &[Self], beta: Self, y: &mut [Self]) {
+        assert_eq!(
+            m.checked_mul(n),
+            Some(a.len()),
+            "gemv: `a` must hold m * n elements"
+        );
+        assert_eq!(x.len(), n, "gemv: `x` must hold n elements");
+        assert_eq!(y.len(), m, "gemv: `y` must hold m elements");
+
+        // SAFETY: the slice lengths were checked against `m` and `n` above
+        unsafe {
+            Self::blas_gemv(
+                Order::RowMajor,
+                Transpose::NoTrans,
+                m,
+                n,
+                alpha,
+                a,
+                n,
+                x,
+                1,
+                beta,
+                y,
+                1,
+            )
+        }
+    }
+
+    /// A shortened wrapper around [`GenericBlas::blas_ger`] for a row-major `m x n` matrix `a`
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    fn ger(m: usize, n: usize, alpha: Self, x: &[Self], y: &[Self], a: &mut [Self]) {
+        assert_eq!(
+            m.checked_mul(n),
+            Some(a.len()),
+            "ger: `a` must hold m * n elements"
+        );
+        assert_eq!(x.len(), m, "ger: `x` must hold m elements");
+        assert_eq!(y.len(), n, "ger: `y` must hold n elements");
+
+        // SAFETY: the slice lengths were checked against `m` and `n` above
+        unsafe { Self::blas_ger(Order::RowMajor, m, n, alpha, x, 1, y, 1, a, n) }
+    }
+
     /// Access to cublas matrix multiplication
     #[cfg(feature = "cuda")]
     fn cugemm(
@@ -115,6 +284,59 @@ where
         b: CUdeviceptr,
         c: CUdeviceptr,
     ) -> crate::Result<()>;
+
+    /// Access to cublas `y = alpha * x + y`
+    #[cfg(feature = "cuda")]
+    fn cuaxpy(
+        handle: &CublasHandle,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<()>;
+
+    /// Access to the cublas dot product
+    #[cfg(feature = "cuda")]
+    fn cudot(
+        handle: &CublasHandle,
+        n: usize,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<Self>;
+
+    /// Access to the cublas euclidean norm
+    #[cfg(feature = "cuda")]
+    fn cunrm2(handle: &CublasHandle, n: usize, x: CUdeviceptr) -> crate::Result<Self>;
+
+    /// Access to cublas `x = alpha * x`
+    #[cfg(feature = "cuda")]
+    fn cuscal(handle: &CublasHandle, n: usize, alpha: Self, x: CUdeviceptr) -> crate::Result<()>;
+
+    /// Access to the cublas matrix-vector product `y = alpha * a * x + beta * y` for a row-major `m x n` matrix `a`
+    #[cfg(feature = "cuda")]
+    #[allow(clippy::too_many_arguments)]
+    fn cugemv(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: CUdeviceptr,
+        x: CUdeviceptr,
+        beta: Self,
+        y: CUdeviceptr,
+    ) -> crate::Result<()>;
+
+    /// Access to the cublas rank-1 update `a = alpha * x * y^T + a` for a row-major `m x n` matrix `a`
+    #[cfg(feature = "cuda")]
+    fn cuger(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+        a: CUdeviceptr,
+    ) -> crate::Result<()>;
 }
 
 impl GenericBlas for f32 {
@@ -154,6 +376,107 @@ impl GenericBlas for f32 {
             )
         };
     }
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_axpy(
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &mut [Self],
+        incy: usize,
+    ) {
+        unsafe { cblas_saxpy(n, alpha, x.as_ptr(), incx, y.as_mut_ptr(), incy) };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_dot(n: usize, x: &[Self], incx: usize, y: &[Self], incy: usize) -> Self {
+        unsafe { cblas_sdot(n, x.as_ptr(), incx, y.as_ptr(), incy) }
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_nrm2(n: usize, x: &[Self], incx: usize) -> Self {
+        unsafe { cblas_snrm2(n, x.as_ptr(), incx) }
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_scal(n: usize, alpha: Self, x: &mut [Self], incx: usize) {
+        unsafe { cblas_sscal(n, alpha, x.as_mut_ptr(), incx) };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_gemv(
+        order: Order,
+        trans: Transpose,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: &[Self],
+        lda: usize,
+        x: &[Self],
+        incx: usize,
+        beta: Self,
+        y: &mut [Self],
+        incy: usize,
+    ) {
+        unsafe {
+            cblas_sgemv(
+                order,
+                trans,
+                m,
+                n,
+                alpha,
+                a.as_ptr(),
+                lda,
+                x.as_ptr(),
+                incx,
+                beta,
+                y.as_mut_ptr(),
+                incy,
+            )
+        };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_ger(
+        order: Order,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &[Self],
+        incy: usize,
+        a: &mut [Self],
+        lda: usize,
+    ) {
+        unsafe {
+            cblas_sger(
+                order,
+                m,
+                n,
+                alpha,
+                x.as_ptr(),
+                incx,
+                y.as_ptr(),
+                incy,
+                a.as_mut_ptr(),
+                lda,
+            )
+        };
+    }
+
     #[cfg(feature = "cuda")]
     #[inline]
     fn cugemm(
@@ -186,6 +509,150 @@ impl GenericBlas for f32 {
         .to_result()?;
         Ok(())
     }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuaxpy(
+        handle: &CublasHandle,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<()> {
+        unsafe {
+            cublasSaxpy_v2(
+                handle.0,
+                n as i32,
+                &alpha as *const f32,
+                x as *const u64 as *const f32,
+                1,
+                y as *mut u64 as *mut f32,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cudot(
+        handle: &CublasHandle,
+        n: usize,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<Self> {
+        let mut result = 0f32;
+        unsafe {
+            cublasSdot_v2(
+                handle.0,
+                n as i32,
+                x as *const u64 as *const f32,
+                1,
+                y as *const u64 as *const f32,
+                1,
+                &mut result as *mut f32,
+            )
+        }
+        .to_result()?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cunrm2(handle: &CublasHandle, n: usize, x: CUdeviceptr) -> crate::Result<Self> {
+        let mut result = 0f32;
+        unsafe {
+            cublasSnrm2_v2(
+                handle.0,
+                n as i32,
+                x as *const u64 as *const f32,
+                1,
+                &mut result as *mut f32,
+            )
+        }
+        .to_result()?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuscal(handle: &CublasHandle, n: usize, alpha: Self, x: CUdeviceptr) -> crate::Result<()> {
+        unsafe {
+            cublasSscal_v2(
+                handle.0,
+                n as i32,
+                &alpha as *const f32,
+                x as *mut u64 as *mut f32,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cugemv(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: CUdeviceptr,
+        x: CUdeviceptr,
+        beta: Self,
+        y: CUdeviceptr,
+    ) -> crate::Result<()> {
+        // a row-major m x n matrix is a column-major n x m matrix
+        unsafe {
+            cublasSgemv_v2(
+                handle.0,
+                cublasOperation_t::CUBLAS_OP_T,
+                n as i32,
+                m as i32,
+                &alpha as *const f32,
+                a as *const u64 as *const f32,
+                n as i32,
+                x as *const u64 as *const f32,
+                1,
+                &beta as *const f32,
+                y as *mut u64 as *mut f32,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuger(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+        a: CUdeviceptr,
+    ) -> crate::Result<()> {
+        // a^T += alpha * y * x^T in column-major order
+        unsafe {
+            cublasSger_v2(
+                handle.0,
+                n as i32,
+                m as i32,
+                &alpha as *const f32,
+                y as *const u64 as *const f32,
+                1,
+                x as *const u64 as *const f32,
+                1,
+                a as *mut u64 as *mut f32,
+                n as i32,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
 }
 
 impl GenericBlas for f64 {
@@ -225,6 +692,107 @@ impl GenericBlas for f64 {
             )
         };
     }
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_axpy(
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &mut [Self],
+        incy: usize,
+    ) {
+        unsafe { cblas_daxpy(n, alpha, x.as_ptr(), incx, y.as_mut_ptr(), incy) };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_dot(n: usize, x: &[Self], incx: usize, y: &[Self], incy: usize) -> Self {
+        unsafe { cblas_ddot(n, x.as_ptr(), incx, y.as_ptr(), incy) }
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_nrm2(n: usize, x: &[Self], incx: usize) -> Self {
+        unsafe { cblas_dnrm2(n, x.as_ptr(), incx) }
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_scal(n: usize, alpha: Self, x: &mut [Self], incx: usize) {
+        unsafe { cblas_dscal(n, alpha, x.as_mut_ptr(), incx) };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_gemv(
+        order: Order,
+        trans: Transpose,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: &[Self],
+        lda: usize,
+        x: &[Self],
+        incx: usize,
+        beta: Self,
+        y: &mut [Self],
+        incy: usize,
+    ) {
+        unsafe {
+            cblas_dgemv(
+                order,
+                trans,
+                m,
+                n,
+                alpha,
+                a.as_ptr(),
+                lda,
+                x.as_ptr(),
+                incx,
+                beta,
+                y.as_mut_ptr(),
+                incy,
+            )
+        };
+    }
+
+    #[cfg(feature = "blas")]
+    #[cfg(feature = "cpu")]
+    #[inline]
+    unsafe fn blas_ger(
+        order: Order,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: &[Self],
+        incx: usize,
+        y: &[Self],
+        incy: usize,
+        a: &mut [Self],
+        lda: usize,
+    ) {
+        unsafe {
+            cblas_dger(
+                order,
+                m,
+                n,
+                alpha,
+                x.as_ptr(),
+                incx,
+                y.as_ptr(),
+                incy,
+                a.as_mut_ptr(),
+                lda,
+            )
+        };
+    }
+
     #[cfg(feature = "cuda")]
     #[inline]
     fn cugemm(
@@ -257,4 +825,148 @@ impl GenericBlas for f64 {
         .to_result()?;
         Ok(())
     }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuaxpy(
+        handle: &CublasHandle,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<()> {
+        unsafe {
+            cublasDaxpy_v2(
+                handle.0,
+                n as i32,
+                &alpha as *const f64,
+                x as *const u64 as *const f64,
+                1,
+                y as *mut u64 as *mut f64,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cudot(
+        handle: &CublasHandle,
+        n: usize,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+    ) -> crate::Result<Self> {
+        let mut result = 0f64;
+        unsafe {
+            cublasDdot_v2(
+                handle.0,
+                n as i32,
+                x as *const u64 as *const f64,
+                1,
+                y as *const u64 as *const f64,
+                1,
+                &mut result as *mut f64,
+            )
+        }
+        .to_result()?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cunrm2(handle: &CublasHandle, n: usize, x: CUdeviceptr) -> crate::Result<Self> {
+        let mut result = 0f64;
+        unsafe {
+            cublasDnrm2_v2(
+                handle.0,
+                n as i32,
+                x as *const u64 as *const f64,
+                1,
+                &mut result as *mut f64,
+            )
+        }
+        .to_result()?;
+        Ok(result)
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuscal(handle: &CublasHandle, n: usize, alpha: Self, x: CUdeviceptr) -> crate::Result<()> {
+        unsafe {
+            cublasDscal_v2(
+                handle.0,
+                n as i32,
+                &alpha as *const f64,
+                x as *mut u64 as *mut f64,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cugemv(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        a: CUdeviceptr,
+        x: CUdeviceptr,
+        beta: Self,
+        y: CUdeviceptr,
+    ) -> crate::Result<()> {
+        // a row-major m x n matrix is a column-major n x m matrix
+        unsafe {
+            cublasDgemv_v2(
+                handle.0,
+                cublasOperation_t::CUBLAS_OP_T,
+                n as i32,
+                m as i32,
+                &alpha as *const f64,
+                a as *const u64 as *const f64,
+                n as i32,
+                x as *const u64 as *const f64,
+                1,
+                &beta as *const f64,
+                y as *mut u64 as *mut f64,
+                1,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "cuda")]
+    #[inline]
+    fn cuger(
+        handle: &CublasHandle,
+        m: usize,
+        n: usize,
+        alpha: Self,
+        x: CUdeviceptr,
+        y: CUdeviceptr,
+        a: CUdeviceptr,
+    ) -> crate::Result<()> {
+        // a^T += alpha * y * x^T in column-major order
+        unsafe {
+            cublasDger_v2(
+                handle.0,
+                n as i32,
+                m as i32,
+                &alpha as *const f64,
+                y as *const u64 as *const f64,
+                1,
+                x as *const u64 as *const f64,
+                1,
+                a as *mut u64 as *mut f64,
+                n as i32,
+            )
+        }
+        .to_result()?;
+        Ok(())
+    }
 }
@@ -0,0 +1,848 @@
+//! Parses the OpenCL C expressions generated by [`ToCLSource`].
+//! A [`RemoteServer`](super::RemoteServer) compiles them to an [`ExprOp`] and passes it to the `apply_fn` of the served device.
+
+#[cfg(feature = "forward")]
+use crate::two_way_ops::tangent_src as src;
+use crate::{Resolve, ToCLSource, ToWgslSource};
+
+use super::RemoteType;
+
+/// The variable name used for the input element of an `apply_fn` expression.
+pub const EXPR_VAR: &str = "x";
+
+/// The maximum number of nodes of an [`ExprProgram`].
+pub const MAX_EXPR_NODES: usize = 64;
+
+/// The maximum nesting depth of an expression accepted by [`Expr::parse`].
+pub const MAX_EXPR_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Lt,
+    Gt,
+    LEq,
+    GEq,
+    Eq,
+    NEq,
+}
+
+impl BinOp {
+    /// Returns the operator as written in OpenCL C and WGSL.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Lt => "<",
+            BinOp::Gt => ">",
+            BinOp::LEq => "<=",
+            BinOp::GEq => ">=",
+            BinOp::Eq => "==",
+            BinOp::NEq => "!=",
+        }
+    }
+
+    /// Comparisons evaluate to `1` or `0`.
+    fn apply(self, lhs: f64, rhs: f64) -> f64 {
+        match self {
+            BinOp::Add => lhs + rhs,
+            BinOp::Sub => lhs - rhs,
+            BinOp::Mul => lhs * rhs,
+            BinOp::Div => lhs / rhs,
+            BinOp::Lt => (lhs < rhs) as u8 as f64,
+            BinOp::Gt => (lhs > rhs) as u8 as f64,
+            BinOp::LEq => (lhs <= rhs) as u8 as f64,
+            BinOp::GEq => (lhs >= rhs) as u8 as f64,
+            BinOp::Eq => (lhs == rhs) as u8 as f64,
+            BinOp::NEq => (lhs != rhs) as u8 as f64,
+        }
+    }
+
+    /// Comparisons have a tangent of zero.
+    #[cfg(feature = "forward")]
+    fn apply_tangent(self, (a, da): (f64, f64), (b, db): (f64, f64)) -> (f64, f64) {
+        match self {
+            BinOp::Add => (a + b, da + db),
+            BinOp::Sub => (a - b, da - db),
+            BinOp::Mul => (a * b, da * b + a * db),
+            BinOp::Div => (a / b, (da * b - a * db) / (b * b)),
+            _ => (self.apply(a, b), 0.),
+        }
+    }
+
+    #[cfg(feature = "forward")]
+    fn tangent_source(self, [(a, da), (b, db)]: [(String, String); 2]) -> String {
+        match self {
+            BinOp::Add => src::add(da, db),
+            BinOp::Sub => src::sub(da, db),
+            BinOp::Mul => src::add(src::mul(da, b), src::mul(a, db)),
+            BinOp::Div => src::div(
+                src::sub(src::mul(da, b.clone()), src::mul(a, db)),
+                format!("({b} * {b})"),
+            ),
+            _ => "0".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Func {
+    Exp,
+    Sin,
+    Cos,
+    Tan,
+    Tanh,
+    Log,
+    Abs,
+    Sqrt,
+    Sign,
+    Pow,
+    Min,
+    Max,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Func> {
+        Some(match name {
+            "exp" => Func::Exp,
+            "sin" => Func::Sin,
+            "cos" => Func::Cos,
+            "tan" => Func::Tan,
+            "tanh" => Func::Tanh,
+            "log" => Func::Log,
+            "abs" | "fabs" => Func::Abs,
+            "sqrt" => Func::Sqrt,
+            "sign" => Func::Sign,
+            "pow" => Func::Pow,
+            "min" | "fmin" => Func::Min,
+            "max" | "fmax" => Func::Max,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the function in OpenCL C and WGSL.
+    pub fn name(self) -> &'static str {
+        match self {
+            Func::Exp => "exp",
+            Func::Sin => "sin",
+            Func::Cos => "cos",
+            Func::Tan => "tan",
+            Func::Tanh => "tanh",
+            Func::Log => "log",
+            Func::Abs => "abs",
+            Func::Sqrt => "sqrt",
+            Func::Sign => "sign",
+            Func::Pow => "pow",
+            Func::Min => "min",
+            Func::Max => "max",
+        }
+    }
+
+    /// Returns the number of arguments of the function.
+    pub fn arity(self) -> usize {
+        match self {
+            Func::Pow | Func::Min | Func::Max => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        let arg = |idx: usize| args[idx];
+        match self {
+            Func::Exp => arg(0).exp(),
+            Func::Sin => arg(0).sin(),
+            Func::Cos => arg(0).cos(),
+            Func::Tan => arg(0).tan(),
+            Func::Tanh => arg(0).tanh(),
+            Func::Log => arg(0).ln(),
+            Func::Abs => arg(0).abs(),
+            Func::Sqrt => arg(0).sqrt(),
+            Func::Sign => sign(arg(0)),
+            Func::Pow => arg(0).powf(arg(1)),
+            Func::Min => arg(0).min(arg(1)),
+            Func::Max => arg(0).max(arg(1)),
+        }
+    }
+
+    #[cfg(feature = "forward")]
+    fn apply_tangent(self, args: &[(f64, f64)]) -> (f64, f64) {
+        let (a, da) = args[0];
+        match self {
+            Func::Exp => (a.exp(), a.exp() * da),
+            Func::Sin => (a.sin(), a.cos() * da),
+            Func::Cos => (a.cos(), -a.sin() * da),
+            Func::Tan => (a.tan(), da / (a.cos() * a.cos())),
+            Func::Tanh => (a.tanh(), (1. - a.tanh() * a.tanh()) * da),
+            Func::Log => (a.ln(), da / a),
+            Func::Abs => (a.abs(), sign(a) * da),
+            Func::Sqrt => (a.sqrt(), da / (2. * a.sqrt())),
+            Func::Sign => (sign(a), 0.),
+            Func::Pow => {
+                let (b, db) = args[1];
+                let val = a.powf(b);
+                let mut dval = b * a.powf(b - 1.) * da;
+                // avoids NaNs for negative bases raised to constant exponents
+                if db != 0. {
+                    dval += val * a.ln() * db;
+                }
+                (val, dval)
+            }
+            Func::Min if a <= args[1].0 => (a, da),
+            Func::Max if a >= args[1].0 => (a, da),
+            Func::Min | Func::Max => args[1],
+        }
+    }
+
+    #[cfg(feature = "forward")]
+    fn tangent_source(self, lang: Lang, args: &mut [(String, String)]) -> String {
+        let (a, da) = core::mem::take(&mut args[0]);
+        match self {
+            Func::Exp => src::mul(format!("exp({a})"), da),
+            Func::Sin => src::mul(format!("cos({a})"), da),
+            Func::Cos => src::neg(src::mul(format!("sin({a})"), da)),
+            Func::Tan => src::div(da, format!("(cos({a}) * cos({a}))")),
+            Func::Tanh => src::mul(format!("(1 - (tanh({a}) * tanh({a})))"), da),
+            Func::Log => src::div(da, a),
+            Func::Abs => src::mul(format!("sign({a})"), da),
+            Func::Sqrt => src::div(da, format!("(2 * sqrt({a}))")),
+            Func::Sign => "0".to_string(),
+            Func::Pow => {
+                let (b, db) = core::mem::take(&mut args[1]);
+                src::add(
+                    src::mul(format!("({b} * pow({a}, ({b} - 1)))"), da),
+                    src::mul(format!("(pow({a}, {b}) * log({a}))"), db),
+                )
+            }
+            Func::Min | Func::Max => {
+                let (b, db) = core::mem::take(&mut args[1]);
+                let cmp = if self == Func::Min { "<=" } else { ">=" };
+                lang.select(format!("({a} {cmp} {b})"), da, db)
+            }
+        }
+    }
+}
+
+fn sign(val: f64) -> f64 {
+    if val == 0. {
+        0.
+    } else {
+        val.signum()
+    }
+}
+
+/// A parsed `apply_fn` expression over [`EXPR_VAR`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Const(f64),
+    Var,
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+    Select(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression like `exp((x * 2.0))`.
+    pub fn parse(src: &str) -> Result<Expr, String> {
+        let mut parser = Parser {
+            src: src.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.ternary()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.src.len() {
+            return Err(format!("Unexpected input at position {}.", parser.pos));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluates the expression with `x` as the value of [`EXPR_VAR`].
+    /// Comparisons evaluate to `1` or `0`.
+    pub fn eval(&self, x: f64) -> f64 {
+        match self {
+            Expr::Const(val) => *val,
+            Expr::Var => x,
+            Expr::Neg(expr) => -expr.eval(x),
+            Expr::Binary(op, lhs, rhs) => op.apply(lhs.eval(x), rhs.eval(x)),
+            Expr::Call(func, args) => {
+                func.apply(&args.iter().map(|arg| arg.eval(x)).collect::<Vec<_>>())
+            }
+            Expr::Select(cond, lhs, rhs) => {
+                if cond.eval(x) != 0. {
+                    lhs.eval(x)
+                } else {
+                    rhs.eval(x)
+                }
+            }
+        }
+    }
+
+    /// Flattens the expression to an [`ExprProgram`].
+    /// Returns `None` if the expression has more than [`MAX_EXPR_NODES`] nodes.
+    pub fn compile(&self) -> Option<ExprProgram> {
+        let mut program = ExprProgram {
+            nodes: [ExprNode::Var; MAX_EXPR_NODES],
+            len: 0,
+        };
+        self.push_nodes(&mut program)?;
+        Some(program)
+    }
+
+    fn push_nodes(&self, program: &mut ExprProgram) -> Option<()> {
+        let node = match self {
+            Expr::Const(val) => ExprNode::Const(*val),
+            Expr::Var => ExprNode::Var,
+            Expr::Neg(expr) => {
+                expr.push_nodes(program)?;
+                ExprNode::Neg
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                lhs.push_nodes(program)?;
+                rhs.push_nodes(program)?;
+                ExprNode::Binary(*op)
+            }
+            Expr::Call(func, args) => {
+                for arg in args {
+                    arg.push_nodes(program)?;
+                }
+                ExprNode::Call(*func)
+            }
+            Expr::Select(cond, lhs, rhs) => {
+                cond.push_nodes(program)?;
+                lhs.push_nodes(program)?;
+                rhs.push_nodes(program)?;
+                ExprNode::Select
+            }
+        };
+        *program.nodes.get_mut(program.len)? = node;
+        program.len += 1;
+        Some(())
+    }
+}
+
+/// A node of an [`ExprProgram`]. Operators and functions take their operands from the preceding nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExprNode {
+    Const(f64),
+    Var,
+    Neg,
+    Binary(BinOp),
+    Call(Func),
+    Select,
+}
+
+impl ExprNode {
+    /// Returns the number of operands of the node.
+    pub fn arity(self) -> usize {
+        match self {
+            ExprNode::Const(_) | ExprNode::Var => 0,
+            ExprNode::Neg => 1,
+            ExprNode::Binary(_) => 2,
+            ExprNode::Call(func) => func.arity(),
+            ExprNode::Select => 3,
+        }
+    }
+}
+
+/// An [`Expr`] flattened to postfix order.
+/// Unlike [`Expr`], it is `Copy` and can therefore be captured by the function passed to [`ApplyFunction::apply_fn`](crate::ApplyFunction::apply_fn).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExprProgram {
+    nodes: [ExprNode; MAX_EXPR_NODES],
+    len: usize,
+}
+
+impl ExprProgram {
+    /// Returns the nodes in postfix order.
+    #[inline]
+    pub fn nodes(&self) -> &[ExprNode] {
+        &self.nodes[..self.len]
+    }
+
+    /// Folds the nodes in postfix order. `apply` receives a node and the values of its operands.
+    fn fold<V: Default>(&self, mut apply: impl FnMut(ExprNode, &mut [V]) -> V) -> V {
+        let mut stack: [V; MAX_EXPR_NODES] = core::array::from_fn(|_| V::default());
+        let mut len = 0;
+        for &node in self.nodes() {
+            // compiled programs always provide the operands of a node
+            len -= node.arity();
+            stack[len] = apply(node, &mut stack[len..len + node.arity()]);
+            len += 1;
+        }
+        core::mem::take(&mut stack[0])
+    }
+
+    /// Evaluates the program with `x` as the value of [`EXPR_VAR`].
+    /// Comparisons evaluate to `1` or `0`.
+    pub fn eval(&self, x: f64) -> f64 {
+        self.fold(|node, args: &mut [f64]| match node {
+            ExprNode::Const(val) => val,
+            ExprNode::Var => x,
+            ExprNode::Neg => -args[0],
+            ExprNode::Binary(op) => op.apply(args[0], args[1]),
+            ExprNode::Call(func) => func.apply(args),
+            ExprNode::Select => {
+                if args[0] != 0. {
+                    args[1]
+                } else {
+                    args[2]
+                }
+            }
+        })
+    }
+
+    /// Evaluates the value and the tangent of the program. `dx` is the tangent of [`EXPR_VAR`].
+    #[cfg(feature = "forward")]
+    pub fn eval_tangent(&self, x: f64, dx: f64) -> (f64, f64) {
+        self.fold(|node, args: &mut [(f64, f64)]| match node {
+            ExprNode::Const(val) => (val, 0.),
+            ExprNode::Var => (x, dx),
+            ExprNode::Neg => (-args[0].0, -args[0].1),
+            ExprNode::Binary(op) => op.apply_tangent(args[0], args[1]),
+            ExprNode::Call(func) => func.apply_tangent(args),
+            ExprNode::Select => {
+                if args[0].0 != 0. {
+                    args[1]
+                } else {
+                    args[2]
+                }
+            }
+        })
+    }
+
+    /// Returns the source of the program, `constant` converts the constants to the element type.
+    fn source(&self, lang: Lang, var: &str, constant: impl Fn(f64) -> String) -> String {
+        self.fold(|node, args| match node {
+            ExprNode::Const(val) => constant(val),
+            ExprNode::Var => var.to_string(),
+            _ => lang.node_source(node, args),
+        })
+    }
+
+    /// Returns the source of the tangent of the program. `tangent` is the variable name of the tangent of [`EXPR_VAR`].
+    #[cfg(feature = "forward")]
+    fn tangent_source(
+        &self,
+        lang: Lang,
+        var: &str,
+        tangent: &str,
+        constant: impl Fn(f64) -> String,
+    ) -> String {
+        let (_, tangent) = self.fold(|node, args: &mut [(String, String)]| match node {
+            ExprNode::Const(val) => (constant(val), "0".to_string()),
+            ExprNode::Var => (var.to_string(), tangent.to_string()),
+            _ => {
+                let mut vals = args.iter().map(|(val, _)| val.clone()).collect::<Vec<_>>();
+                let val = lang.node_source(node, &mut vals);
+                let tangent = match node {
+                    ExprNode::Neg => src::neg(core::mem::take(&mut args[0].1)),
+                    ExprNode::Binary(op) => op.tangent_source([
+                        core::mem::take(&mut args[0]),
+                        core::mem::take(&mut args[1]),
+                    ]),
+                    ExprNode::Call(func) => func.tangent_source(lang, args),
+                    _ => lang.select(
+                        core::mem::take(&mut args[0].0),
+                        core::mem::take(&mut args[1].1),
+                        core::mem::take(&mut args[2].1),
+                    ),
+                };
+                (val, tangent)
+            }
+        });
+        tangent
+    }
+}
+
+/// The language of a generated source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    CL,
+    Wgsl,
+}
+
+impl Lang {
+    fn select(self, cond: String, lhs: String, rhs: String) -> String {
+        match self {
+            Lang::CL => format!("({cond} ? {lhs} : {rhs})"),
+            Lang::Wgsl => format!("select({rhs}, {lhs}, {cond})"),
+        }
+    }
+
+    /// Returns the source of an operator, function or select node.
+    fn node_source(self, node: ExprNode, args: &mut [String]) -> String {
+        match node {
+            ExprNode::Neg => format!("-({})", args[0]),
+            ExprNode::Binary(op) => format!("({} {} {})", args[0], op.symbol(), args[1]),
+            ExprNode::Call(func) => format!("{}({})", func.name(), args.join(", ")),
+            ExprNode::Select => self.select(
+                core::mem::take(&mut args[0]),
+                core::mem::take(&mut args[1]),
+                core::mem::take(&mut args[2]),
+            ),
+            ExprNode::Const(_) | ExprNode::Var => unreachable!("leaves are handled by the caller"),
+        }
+    }
+}
+
+/// Applies an [`ExprProgram`] to the input element `x`.
+/// It implements [`TwoWay`](crate::TwoWay), hence a [`RemoteServer`](super::RemoteServer) passes it to the `apply_fn` of the served device.
+#[derive(Debug, Clone, Copy)]
+pub struct ExprOp<T> {
+    pub program: ExprProgram,
+    pub x: Resolve<T>,
+}
+
+impl<T: RemoteType> crate::Eval<T> for ExprOp<T> {
+    #[inline]
+    fn eval(&self) -> T {
+        T::from_f64(self.program.eval(self.x.val.as_f64()))
+    }
+}
+
+impl<T: RemoteType> ToCLSource for ExprOp<T> {
+    #[inline]
+    fn to_cl_source(&self) -> String {
+        self.program.source(Lang::CL, self.x.marker, |val| {
+            T::from_f64(val).to_cl_source()
+        })
+    }
+}
+
+impl<T: RemoteType> ToWgslSource for ExprOp<T> {
+    #[inline]
+    fn to_wgsl_source(&self) -> String {
+        self.program.source(Lang::Wgsl, self.x.marker, |val| {
+            T::from_f64(val).to_wgsl_source()
+        })
+    }
+}
+
+#[cfg(feature = "forward")]
+impl<T: RemoteType> crate::EvalTangent<T> for ExprOp<T> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (val, tangent) = self
+            .program
+            .eval_tangent(self.x.val.as_f64(), tangent.as_f64());
+        (T::from_f64(val), T::from_f64(tangent))
+    }
+}
+
+#[cfg(feature = "forward")]
+impl<T: RemoteType> crate::ToCLTangentSource for ExprOp<T> {
+    #[inline]
+    fn to_cl_tangent_source(&self, tangent: &str) -> String {
+        self.program
+            .tangent_source(Lang::CL, self.x.marker, tangent, |val| {
+                T::from_f64(val).to_cl_source()
+            })
+    }
+}
+
+#[cfg(feature = "forward")]
+impl<T: RemoteType> crate::ToWgslTangentSource for ExprOp<T> {
+    #[inline]
+    fn to_wgsl_tangent_source(&self, tangent: &str) -> String {
+        self.program
+            .tangent_source(Lang::Wgsl, self.x.marker, tangent, |val| {
+                T::from_f64(val).to_wgsl_source()
+            })
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.src.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.src[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{token}' at position {}.", self.pos))
+        }
+    }
+
+    /// Runs `parse` one nesting level deeper. Limits the recursion of the parser to [`MAX_EXPR_DEPTH`].
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        if self.depth == MAX_EXPR_DEPTH {
+            return Err(format!(
+                "Expression is nested deeper than {MAX_EXPR_DEPTH} levels."
+            ));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn ternary(&mut self) -> Result<Expr, String> {
+        self.nested(Self::select)
+    }
+
+    // cond ? lhs : rhs
+    fn select(&mut self) -> Result<Expr, String> {
+        let cond = self.comparison()?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let lhs = self.ternary()?;
+        self.expect(":")?;
+        let rhs = self.ternary()?;
+        Ok(Expr::Select(Box::new(cond), Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.additive()?;
+        loop {
+            // two character operators first
+            let op = [
+                ("<=", BinOp::LEq),
+                (">=", BinOp::GEq),
+                ("==", BinOp::Eq),
+                ("!=", BinOp::NEq),
+                ("<", BinOp::Lt),
+                (">", BinOp::Gt),
+            ]
+            .into_iter()
+            .find(|(token, _)| self.eat(token));
+
+            let Some((_, op)) = op else {
+                return Ok(lhs);
+            };
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.additive()?));
+        }
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(b'+') => BinOp::Add,
+                Some(b'-') => BinOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(b'*') => BinOp::Mul,
+                Some(b'/') => BinOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.nested(Self::negation)
+    }
+
+    fn negation(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(b'(') => {
+                self.pos += 1;
+                let expr = self.ternary()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || c == b'.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => self.identifier(),
+            Some(c) => Err(format!(
+                "Unexpected character '{}' at position {}.",
+                c as char, self.pos
+            )),
+            None => Err("Unexpected end of expression.".into()),
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, String> {
+        let start = self.pos;
+        while let Some(&c) = self.src.get(self.pos) {
+            let exponent_sign =
+                (c == b'-' || c == b'+') && matches!(self.src[self.pos - 1], b'e' | b'E');
+            if c.is_ascii_digit() || c == b'.' || c == b'e' || c == b'E' || exponent_sign {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        // OpenCL float suffix
+        let end = self.pos;
+        if self.src.get(self.pos) == Some(&b'f') {
+            self.pos += 1;
+        }
+
+        let number = core::str::from_utf8(&self.src[start..end]).unwrap();
+        number
+            .parse()
+            .map(Expr::Const)
+            .map_err(|_| format!("Invalid number '{number}'."))
+    }
+
+    fn identifier(&mut self) -> Result<Expr, String> {
+        let start = self.pos;
+        while self
+            .src
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'_')
+        {
+            self.pos += 1;
+        }
+        let name = core::str::from_utf8(&self.src[start..self.pos]).unwrap();
+
+        match name {
+            EXPR_VAR => return Ok(Expr::Var),
+            "inf" => return Ok(Expr::Const(f64::INFINITY)),
+            "NaN" => return Ok(Expr::Const(f64::NAN)),
+            _ => (),
+        }
+
+        let func = Func::from_name(name).ok_or_else(|| format!("Unknown identifier '{name}'."))?;
+
+        self.expect("(")?;
+        let mut args = vec![self.ternary()?];
+        while self.eat(",") {
+            args.push(self.ternary()?);
+        }
+        self.expect(")")?;
+
+        if args.len() != func.arity() {
+            return Err(format!(
+                "'{name}' expects {} arguments, but {} were given.",
+                func.arity(),
+                args.len()
+            ));
+        }
+        Ok(Expr::Call(func, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Combiner, Eval, Resolve, ToCLSource, ToMarker, ToVal, ToWgslSource};
+
+    use super::{Expr, ExprOp, EXPR_VAR, MAX_EXPR_DEPTH, MAX_EXPR_NODES};
+
+    fn generated(f: impl Fn(Resolve<f64>) -> String) -> Expr {
+        Expr::parse(&f(EXPR_VAR.to_marker())).unwrap()
+    }
+
+    #[test]
+    fn test_eval_generated_sources() {
+        let expr = generated(|x| x.mul(2.).add(1.).exp().to_cl_source());
+        assert_eq!(expr.eval(0.5), 2f64.exp());
+
+        let expr = generated(|x| x.pow(2.).sub(x.div(4.)).neg().to_cl_source());
+        assert_eq!(expr.eval(2.), -3.5);
+
+        let expr = generated(|x| x.geq(1.).mul(x).to_cl_source());
+        assert_eq!(expr.eval(2.), 2.);
+        assert_eq!(expr.eval(0.5), 0.);
+
+        let expr = generated(|x| x.tanh().min(0.5).abs().to_cl_source());
+        assert_eq!(expr.eval(-3.), 3f64.tanh());
+    }
+
+    #[test]
+    fn test_parse_literals_and_ternary() {
+        let expr = Expr::parse("(x < 0.0 ? -(x) : x * 1e-1f)").unwrap();
+        assert_eq!(expr.eval(-2.), 2.);
+        assert!((expr.eval(3.) - 0.3).abs() < 1e-12);
+
+        assert_eq!(Expr::parse("sign(x) + 2").unwrap().eval(-4.), 1.);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expr::parse("(x + 1").is_err());
+        assert!(Expr::parse("y * 2").is_err());
+        assert!(Expr::parse("pow(x)").is_err());
+        assert!(Expr::parse("x x").is_err());
+    }
+
+    #[test]
+    fn test_compiled_program() {
+        let expr = generated(|x| x.mul(2.).add(1.).geq(5.).mul(x).to_cl_source());
+        let program = expr.compile().unwrap();
+        for x in [-1., 1., 2., 3.5] {
+            assert_eq!(program.eval(x), expr.eval(x));
+        }
+
+        let op = ExprOp {
+            program,
+            x: 3f32.to_val(),
+        };
+        assert_eq!(Eval::<f32>::eval(&op), 3.);
+
+        let op = ExprOp {
+            program: Expr::parse("x < 0 ? -x : x * 2")
+                .unwrap()
+                .compile()
+                .unwrap(),
+            x: Resolve::<f32>::with_marker("y"),
+        };
+        assert_eq!(op.to_cl_source(), "((y < 0.0) ? -(y) : (y * 2.0))");
+        assert_eq!(
+            op.to_wgsl_source(),
+            "select((y * f32(2.0)), -(y), (y < f32(0.0)))"
+        );
+    }
+
+    #[test]
+    fn test_expression_limits() {
+        let nested = "(".repeat(MAX_EXPR_DEPTH) + "x" + &")".repeat(MAX_EXPR_DEPTH);
+        assert!(Expr::parse(&nested).is_err());
+        let quarter = MAX_EXPR_DEPTH / 4;
+        let nested = "(".repeat(quarter) + "x" + &")".repeat(quarter);
+        assert!(Expr::parse(&nested).is_ok());
+
+        let sum = vec!["x"; MAX_EXPR_NODES].join(" + ");
+        assert!(Expr::parse(&sum).unwrap().compile().is_none());
+        let sum = vec!["x"; MAX_EXPR_NODES / 2].join(" + ");
+        assert!(Expr::parse(&sum).unwrap().compile().is_some());
+    }
+}
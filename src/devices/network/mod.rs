@@ -0,0 +1,287 @@
+//! The network module provides the [`Remote`] device, which executes operations on a [`RemoteServer`] over TCP.
+//! A server wraps any local device, e.g. a [`CPU`](crate::CPU).
+mod expr;
+mod ops;
+mod protocol;
+mod remote_device;
+mod remote_ptr;
+mod server;
+
+pub use expr::*;
+pub use ops::*;
+pub use protocol::*;
+pub use remote_device::*;
+pub use remote_ptr::*;
+pub use server::*;
+
+#[cfg(test)]
+#[cfg(feature = "cpu")]
+mod tests {
+    use std::{
+        io::Write,
+        net::{TcpListener, TcpStream},
+        thread::JoinHandle,
+        time::Duration,
+    };
+
+    use crate::{
+        ApplyFunction, Base, Buffer, ClearBuf, Combiner, CopySlice, Device, DeviceError, ErrorKind,
+        Remote, WriteBuf, CPU,
+    };
+
+    use super::{
+        read_handshake, write_handshake, Op, RemoteError, RemoteErrorCode, RemoteServer,
+        ServerLimits, PROTOCOL_VERSION,
+    };
+
+    fn spawn_server() -> (std::net::SocketAddr, JoinHandle<crate::Result<()>>) {
+        let server = RemoteServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = std::thread::spawn(move || server.serve_once(&CPU::<Base>::new()));
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_remote_alloc_write_read() {
+        let (addr, server) = spawn_server();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+
+            let mut buf: Buffer<i32, _> = device.buffer([1, 2, 3, 4]);
+            assert_eq!(buf.read(), [1, 2, 3, 4]);
+
+            device.write(&mut buf, &[4, 3, 2, 1]);
+            assert_eq!(device.try_read(&buf).unwrap(), [4, 3, 2, 1]);
+
+            device.clear(&mut buf);
+            assert_eq!(buf.read(), [0; 4]);
+
+            let zeros = Buffer::<f64, _>::new(&device, 3);
+            assert_eq!(zeros.read(), [0.; 3]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_apply_fn_and_copy_slice() {
+        let (addr, server) = spawn_server();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+
+            let buf: Buffer<f32, _> = device.buffer([1., 2., 3., 4.]);
+            let out = device.apply_fn(&buf, |x| x.mul(2.).add(1.).geq(5.).mul(x));
+            assert_eq!(out.read(), [0., 2., 3., 4.]);
+
+            let slice = device.copy_slice(&buf, 1..3);
+            assert_eq!(slice.read(), [2., 3.]);
+
+            let mut dst: Buffer<f32, _> = device.buffer([0.; 4]);
+            device.write_buf(&mut dst, &buf);
+            assert_eq!(dst.read(), [1., 2., 3., 4.]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_error_propagation() {
+        let (addr, server) = spawn_server();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+
+            let err = device
+                .client
+                .request(Op::Read, &42u64.to_le_bytes())
+                .unwrap_err();
+            let err = err.downcast_ref::<RemoteError>().unwrap();
+            assert_eq!(err.code, RemoteErrorCode::UnknownBuffer);
+
+            let buf: Buffer<u8, _> = device.buffer([1, 2]);
+            let mut payload = buf.base().id.to_le_bytes().to_vec();
+            payload.extend_from_slice(&[1, 2, 3]);
+            let err = device.client.request(Op::Write, &payload).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RemoteError>().unwrap().code,
+                RemoteErrorCode::OutOfBounds
+            );
+
+            let mut payload = buf.base().id.to_le_bytes().to_vec();
+            payload.extend_from_slice(&buf.base().id.to_le_bytes());
+            payload.extend_from_slice(b"foo(x)");
+            let err = device.client.request(Op::ApplyFn, &payload).unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RemoteError>().unwrap().code,
+                RemoteErrorCode::InvalidExpression
+            );
+
+            // the connection is still usable
+            assert_eq!(buf.read(), [1, 2]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    fn assert_remote_err<T: core::fmt::Debug>(res: crate::Result<T>, code: RemoteErrorCode) {
+        let err = res.unwrap_err();
+        assert_eq!(err.downcast_ref::<RemoteError>().unwrap().code, code);
+    }
+
+    #[test]
+    fn test_remote_limits() {
+        let server = RemoteServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_limits(ServerLimits {
+                max_frame_len: 64,
+                max_alloc_bytes: 32,
+                ..ServerLimits::default()
+            });
+        let addr = server.local_addr().unwrap();
+        let server = std::thread::spawn(move || server.serve_once(&CPU::<Base>::new()));
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+
+            assert_remote_err(device.client.alloc::<f32>(9), RemoteErrorCode::OutOfBounds);
+            assert_remote_err(
+                device.client.alloc::<u8>(usize::MAX),
+                RemoteErrorCode::OutOfBounds,
+            );
+
+            let buf: Buffer<f32, _> = device.buffer([1.; 8]);
+            let mut payload = buf.base().id.to_le_bytes().to_vec();
+            payload.extend_from_slice(&[0; 64]);
+            assert_remote_err(
+                device.client.request(Op::Write, &payload),
+                RemoteErrorCode::OutOfBounds,
+            );
+
+            // the connection is still usable
+            assert_eq!(buf.read(), [1.; 8]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_session_limit() {
+        let server = RemoteServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_limits(ServerLimits {
+                max_alloc_bytes: 32,
+                max_session_bytes: 48,
+                ..ServerLimits::default()
+            });
+        let addr = server.local_addr().unwrap();
+        let server = std::thread::spawn(move || server.serve_once(&CPU::<Base>::new()));
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+
+            let a: Buffer<f32, _> = device.buffer([1.; 8]);
+            let b: Buffer<u8, _> = device.buffer([2; 16]);
+            assert_remote_err(device.client.alloc::<u8>(1), RemoteErrorCode::OutOfBounds);
+
+            // freed buffers no longer count towards the limit
+            drop(a);
+            let c: Buffer<f64, _> = device.buffer([3.; 4]);
+            assert_remote_err(device.client.alloc::<u8>(1), RemoteErrorCode::OutOfBounds);
+
+            assert_eq!(b.read(), [2; 16]);
+            assert_eq!(c.read(), [3.; 4]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_read_timeout() {
+        let server = RemoteServer::bind("127.0.0.1:0")
+            .unwrap()
+            .with_limits(ServerLimits {
+                read_timeout: Some(Duration::from_millis(50)),
+                ..ServerLimits::default()
+            });
+        let addr = server.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let res = server.serve_once(&CPU::<Base>::new());
+            // the next client is served after the idle one timed out
+            server.serve_once(&CPU::<Base>::new())?;
+            Ok::<_, crate::Error>(res)
+        });
+
+        // never sends the handshake
+        let idle = TcpStream::connect(addr).unwrap();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+            let buf: Buffer<i32, _> = device.buffer([1, 2, 3]);
+            assert_eq!(buf.read(), [1, 2, 3]);
+        }
+        assert!(server.join().unwrap().unwrap().is_err());
+        drop(idle);
+    }
+
+    #[test]
+    fn test_remote_apply_fn_expression_limits() {
+        let (addr, server) = spawn_server();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+            let buf: Buffer<f32, _> = device.buffer([1., 2.]);
+
+            for src in [
+                "(".repeat(100) + "x" + &")".repeat(100),
+                vec!["x"; 40].join(" + "),
+            ] {
+                let mut payload = buf.base().id.to_le_bytes().to_vec();
+                payload.extend_from_slice(&buf.base().id.to_le_bytes());
+                payload.extend_from_slice(src.as_bytes());
+                assert_remote_err(
+                    device.client.request(Op::ApplyFn, &payload),
+                    RemoteErrorCode::InvalidExpression,
+                );
+            }
+
+            let out: Buffer<f32, _> = device.buffer([0.; 3]);
+            let mut payload = buf.base().id.to_le_bytes().to_vec();
+            payload.extend_from_slice(&out.base().id.to_le_bytes());
+            payload.extend_from_slice(b"x");
+            assert_remote_err(
+                device.client.request(Op::ApplyFn, &payload),
+                RemoteErrorCode::OutOfBounds,
+            );
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_apply_fn_integer_unsupported() {
+        let (addr, server) = spawn_server();
+        {
+            let device = Remote::<Base>::new(addr).unwrap();
+            let buf: Buffer<i32, _> = device.buffer([1, 2, 3]);
+
+            let mut payload = buf.base().id.to_le_bytes().to_vec();
+            payload.extend_from_slice(&buf.base().id.to_le_bytes());
+            payload.extend_from_slice(b"x / 2");
+            assert_remote_err(
+                device.client.request(Op::ApplyFn, &payload),
+                RemoteErrorCode::UnsupportedDType,
+            );
+            assert_eq!(buf.read(), [1, 2, 3]);
+        }
+        server.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_remote_version_mismatch() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            read_handshake(&mut stream).unwrap();
+            write_handshake(&mut stream, PROTOCOL_VERSION + 1).unwrap();
+            stream.flush().unwrap();
+        });
+
+        let err = Remote::<Base>::new(addr).err().unwrap();
+        assert_eq!(
+            err.kind::<DeviceError>(),
+            Some(&DeviceError::RemoteVersionMismatch)
+        );
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,144 @@
+use core::ops::{Range, RangeBounds};
+
+use crate::{
+    bounds_to_range, pass_down_add_operation, pass_down_exec_now, ApplyFunction, Buffer, ClearBuf,
+    CopySlice, Float, OnDropBuffer, Read, Remote, Resolve, Retrieve, Retriever, Shape, ToMarker,
+    TwoWay, WriteBuf,
+};
+
+use super::{Op, RemoteType, EXPR_VAR};
+
+pass_down_add_operation!(Remote);
+pass_down_exec_now!(Remote);
+
+impl<Mods: OnDropBuffer, T: RemoteType, S: Shape> Read<T, S> for Remote<Mods> {
+    type Read<'a> = Vec<T>
+    where
+        T: 'a,
+        Self: 'a,
+        S: 'a;
+
+    #[inline]
+    fn read<'a>(&self, buf: &'a Self::Base<T, S>) -> Self::Read<'a>
+    where
+        Self: 'a,
+    {
+        self.client.read(buf.id).unwrap()
+    }
+
+    #[inline]
+    fn read_to_vec(&self, buf: &Self::Base<T, S>) -> Vec<T>
+    where
+        T: Default + Clone,
+    {
+        self.client.read(buf.id).unwrap()
+    }
+}
+
+impl<Mods: OnDropBuffer, T: RemoteType, S: Shape> WriteBuf<T, S> for Remote<Mods> {
+    #[inline]
+    fn write(&self, buf: &mut Buffer<T, Self, S>, data: &[T]) {
+        self.client.write(buf.base().id, data).unwrap()
+    }
+
+    #[inline]
+    fn write_buf(&self, dst: &mut Buffer<T, Self, S>, src: &Buffer<T, Self, S>) {
+        copy_remote(self, src.base().id, 0..src.len(), dst.base().id, 0).unwrap()
+    }
+}
+
+impl<Mods: OnDropBuffer, T: RemoteType> ClearBuf<T> for Remote<Mods> {
+    #[inline]
+    fn clear(&self, buf: &mut Buffer<T, Self>) {
+        self.client
+            .request(Op::Clear, &buf.base().id.to_le_bytes())
+            .unwrap();
+    }
+}
+
+/// Copies `src[src_range]` to `dst[dst_start..]` on the server.
+pub fn copy_remote<Mods>(
+    device: &Remote<Mods>,
+    src: u64,
+    src_range: Range<usize>,
+    dst: u64,
+    dst_start: usize,
+) -> crate::Result<()> {
+    let mut payload = Vec::with_capacity(40);
+    for field in [
+        src,
+        src_range.start as u64,
+        src_range.end as u64,
+        dst,
+        dst_start as u64,
+    ] {
+        payload.extend_from_slice(&field.to_le_bytes());
+    }
+    device.client.request(Op::CopySlice, &payload)?;
+    Ok(())
+}
+
+impl<Mods: OnDropBuffer, T: RemoteType> CopySlice<T> for Remote<Mods> {
+    fn copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+        &self,
+        source: &Buffer<T, Self>,
+        source_range: SR,
+        dest: &mut Buffer<T, Self>,
+        dest_range: DR,
+    ) {
+        let source_range = bounds_to_range(source_range, source.len());
+        let dest_range = bounds_to_range(dest_range, dest.len());
+
+        assert_eq!(
+            source_range.end - source_range.start,
+            dest_range.end - dest_range.start,
+        );
+
+        copy_remote(
+            self,
+            source.base().id,
+            source_range,
+            dest.base().id,
+            dest_range.start,
+        )
+        .unwrap()
+    }
+
+    fn copy_slice_all<I: IntoIterator<Item = (Range<usize>, Range<usize>)>>(
+        &self,
+        source: &Buffer<T, Self>,
+        dest: &mut Buffer<T, Self>,
+        ranges: I,
+    ) {
+        for (source_range, dest_range) in ranges {
+            self.copy_slice_to(source, source_range, dest, dest_range);
+        }
+    }
+}
+
+// The server evaluates expressions in floating point, therefore integer buffers are rejected.
+impl<Mods, T, S> ApplyFunction<T, S> for Remote<Mods>
+where
+    Mods: Retrieve<Self, T, S> + 'static,
+    T: RemoteType + Float,
+    S: Shape,
+{
+    fn apply_fn<F>(
+        &self,
+        buf: &Buffer<T, Self, S>,
+        f: impl Fn(Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<T> + 'static,
+    {
+        let out = self.retrieve(buf.len(), buf).unwrap();
+
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&buf.base().id.to_le_bytes());
+        payload.extend_from_slice(&out.base().id.to_le_bytes());
+        payload.extend_from_slice(f(EXPR_VAR.to_marker()).to_cl_source().as_bytes());
+
+        self.client.request(Op::ApplyFn, &payload).unwrap();
+        out
+    }
+}
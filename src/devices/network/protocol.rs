@@ -0,0 +1,389 @@
+use std::io::{Read, Write};
+
+/// Sent by both peers at the start of a connection.
+pub const MAGIC: [u8; 4] = *b"CSTS";
+
+/// The version of the wire protocol.
+/// A server only accepts clients with the same version.
+pub const PROTOCOL_VERSION: u16 = 1;
+
+/// The size of a frame header: one byte for the op code / status and four bytes for the payload length.
+const HEADER_LEN: usize = 5;
+
+/// Operations understood by a [`RemoteServer`](super::RemoteServer).
+///
+/// Payloads (all integers are little endian):
+/// - `Alloc`: dtype `u8`, len `u64` -> id `u64`
+/// - `Write`: id `u64`, element bytes -> nothing
+/// - `Read`: id `u64` -> element bytes
+/// - `Free`: id `u64` -> nothing
+/// - `Clear`: id `u64` -> nothing
+/// - `CopySlice`: src id `u64`, src start `u64`, src end `u64`, dst id `u64`, dst start `u64` -> nothing
+/// - `ApplyFn`: src id `u64`, out id `u64`, OpenCL C expression over `x` -> nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Op {
+    Alloc = 0,
+    Write = 1,
+    Read = 2,
+    Free = 3,
+    Clear = 4,
+    CopySlice = 5,
+    ApplyFn = 6,
+}
+
+impl Op {
+    pub fn from_u8(op: u8) -> Option<Op> {
+        Some(match op {
+            0 => Op::Alloc,
+            1 => Op::Write,
+            2 => Op::Read,
+            3 => Op::Free,
+            4 => Op::Clear,
+            5 => Op::CopySlice,
+            6 => Op::ApplyFn,
+            _ => return None,
+        })
+    }
+}
+
+/// The element types that can be stored on a [`RemoteServer`](super::RemoteServer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DType {
+    F32 = 0,
+    F64 = 1,
+    I32 = 2,
+    I64 = 3,
+    U8 = 4,
+    U32 = 5,
+}
+
+impl DType {
+    pub fn from_u8(dtype: u8) -> Option<DType> {
+        Some(match dtype {
+            0 => DType::F32,
+            1 => DType::F64,
+            2 => DType::I32,
+            3 => DType::I64,
+            4 => DType::U8,
+            5 => DType::U32,
+            _ => return None,
+        })
+    }
+
+    /// Returns the size of an element in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            DType::U8 => 1,
+            DType::F32 | DType::I32 | DType::U32 => 4,
+            DType::F64 | DType::I64 => 8,
+        }
+    }
+}
+
+/// An element type that can be sent to a [`RemoteServer`](super::RemoteServer).
+pub trait RemoteType:
+    crate::Unit + Copy + Default + crate::ToCLSource + crate::ToWgslSource + 'static
+{
+    const DTYPE: DType;
+    const SIZE: usize;
+
+    fn write_le(self, out: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+
+    /// Used to evaluate the expressions of `apply_fn` requests.
+    fn as_f64(self) -> f64;
+    fn from_f64(val: f64) -> Self;
+}
+
+macro_rules! impl_remote_type {
+    ($($t:ident => $dtype:ident),*) => {
+        $(
+            impl RemoteType for $t {
+                const DTYPE: DType = DType::$dtype;
+                const SIZE: usize = core::mem::size_of::<$t>();
+
+                #[inline]
+                fn write_le(self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes())
+                }
+
+                #[inline]
+                fn read_le(bytes: &[u8]) -> Self {
+                    $t::from_le_bytes(bytes.try_into().unwrap())
+                }
+
+                #[inline]
+                fn as_f64(self) -> f64 {
+                    self as f64
+                }
+
+                #[inline]
+                fn from_f64(val: f64) -> Self {
+                    val as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_remote_type!(f32 => F32, f64 => F64, i32 => I32, i64 => I64, u8 => U8, u32 => U32);
+
+pub fn encode_slice<T: RemoteType>(data: &[T], out: &mut Vec<u8>) {
+    out.reserve(data.len() * T::SIZE);
+    for value in data {
+        value.write_le(out);
+    }
+}
+
+pub fn decode_slice<T: RemoteType>(bytes: &[u8]) -> Option<Vec<T>> {
+    if bytes.len() % T::SIZE != 0 {
+        return None;
+    }
+    Some(bytes.chunks_exact(T::SIZE).map(T::read_le).collect())
+}
+
+/// Error codes sent by a [`RemoteServer`](super::RemoteServer) alongside a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
+pub enum RemoteErrorCode {
+    /// The frame could not be decoded.
+    Malformed = 1,
+    UnknownOp = 2,
+    UnknownDType = 3,
+    UnknownBuffer = 4,
+    /// The buffers of an operation have different element types.
+    DTypeMismatch = 5,
+    /// A length or range does not fit the buffer.
+    OutOfBounds = 6,
+    /// The `apply_fn` expression could not be parsed.
+    InvalidExpression = 7,
+    /// The device of the server returned an error.
+    Device = 8,
+    /// The operation is not supported for the element type of the buffer.
+    UnsupportedDType = 9,
+}
+
+impl RemoteErrorCode {
+    pub fn from_u16(code: u16) -> RemoteErrorCode {
+        match code {
+            2 => RemoteErrorCode::UnknownOp,
+            3 => RemoteErrorCode::UnknownDType,
+            4 => RemoteErrorCode::UnknownBuffer,
+            5 => RemoteErrorCode::DTypeMismatch,
+            6 => RemoteErrorCode::OutOfBounds,
+            7 => RemoteErrorCode::InvalidExpression,
+            8 => RemoteErrorCode::Device,
+            9 => RemoteErrorCode::UnsupportedDType,
+            _ => RemoteErrorCode::Malformed,
+        }
+    }
+}
+
+/// An error that occured on a [`RemoteServer`](super::RemoteServer), propagated to the [`Remote`](super::Remote) device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteError {
+    pub code: RemoteErrorCode,
+    pub message: String,
+}
+
+impl RemoteError {
+    #[inline]
+    pub fn new(code: RemoteErrorCode, message: impl Into<String>) -> Self {
+        RemoteError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 + self.message.len());
+        out.extend_from_slice(&(self.code as u16).to_le_bytes());
+        out.extend_from_slice(self.message.as_bytes());
+        out
+    }
+
+    pub fn decode(payload: &[u8]) -> RemoteError {
+        if payload.len() < 2 {
+            return RemoteError::new(RemoteErrorCode::Malformed, "Truncated error response.");
+        }
+        RemoteError {
+            code: RemoteErrorCode::from_u16(u16::from_le_bytes([payload[0], payload[1]])),
+            message: String::from_utf8_lossy(&payload[2..]).into_owned(),
+        }
+    }
+}
+
+impl core::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Remote error {:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+/// The status byte of a response frame.
+pub const STATUS_OK: u8 = 0;
+pub const STATUS_ERR: u8 = 1;
+
+/// Writes a frame: a tag (op code or status), the payload length as `u32` and the payload.
+pub fn write_frame(stream: &mut impl Write, tag: u8, payload: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "Frame too large."))?;
+
+    let mut header = [0; HEADER_LEN];
+    header[0] = tag;
+    header[1..].copy_from_slice(&len.to_le_bytes());
+
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Reads a frame written by [`write_frame`].
+/// A payload longer than `max_len` bytes is skipped and returned as `None`, hence the stream stays usable.
+pub fn read_frame(
+    stream: &mut impl Read,
+    max_len: usize,
+) -> std::io::Result<(u8, Option<Vec<u8>>)> {
+    let mut header = [0; HEADER_LEN];
+    stream.read_exact(&mut header)?;
+
+    let len = u32::from_le_bytes(header[1..].try_into().unwrap()) as u64;
+    let mut payload = stream.by_ref().take(len);
+
+    if len > max_len as u64 {
+        if std::io::copy(&mut payload, &mut std::io::sink())? != len {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok((header[0], None));
+    }
+
+    // grows with the received bytes instead of allocating the announced length upfront
+    let mut buf = Vec::new();
+    if payload.read_to_end(&mut buf)? as u64 != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok((header[0], Some(buf)))
+}
+
+/// Writes the magic bytes and the protocol version.
+pub fn write_handshake(stream: &mut impl Write, version: u16) -> std::io::Result<()> {
+    let mut handshake = [0; 6];
+    handshake[..4].copy_from_slice(&MAGIC);
+    handshake[4..].copy_from_slice(&version.to_le_bytes());
+    stream.write_all(&handshake)?;
+    stream.flush()
+}
+
+/// Reads the handshake of the peer and returns its protocol version.
+/// Returns `None` if the magic bytes do not match.
+pub fn read_handshake(stream: &mut impl Read) -> std::io::Result<Option<u16>> {
+    let mut handshake = [0; 6];
+    stream.read_exact(&mut handshake)?;
+    if handshake[..4] != MAGIC {
+        return Ok(None);
+    }
+    Ok(Some(u16::from_le_bytes([handshake[4], handshake[5]])))
+}
+
+/// Reads little endian fields from a payload.
+pub struct PayloadReader<'a> {
+    payload: &'a [u8],
+}
+
+impl<'a> PayloadReader<'a> {
+    #[inline]
+    pub fn new(payload: &'a [u8]) -> Self {
+        PayloadReader { payload }
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        let (&first, rest) = self.payload.split_first()?;
+        self.payload = rest;
+        Some(first)
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        if self.payload.len() < 8 {
+            return None;
+        }
+        let (bytes, rest) = self.payload.split_at(8);
+        self.payload = rest;
+        Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    /// Returns the remaining bytes.
+    #[inline]
+    pub fn rest(self) -> &'a [u8] {
+        self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_slice, encode_slice, read_frame, read_handshake, write_frame, write_handshake,
+        PayloadReader, RemoteError, RemoteErrorCode, PROTOCOL_VERSION,
+    };
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let mut wire = Vec::new();
+        write_frame(&mut wire, 3, &[1, 2, 3]).unwrap();
+        write_frame(&mut wire, 4, &[]).unwrap();
+
+        let mut wire = &wire[..];
+        assert_eq!(read_frame(&mut wire, 3).unwrap(), (3, Some(vec![1, 2, 3])));
+        assert_eq!(read_frame(&mut wire, 3).unwrap(), (4, Some(vec![])));
+        assert!(read_frame(&mut wire, 3).is_err());
+    }
+
+    #[test]
+    fn test_frame_max_len() {
+        let mut wire = Vec::new();
+        write_frame(&mut wire, 3, &[1, 2, 3]).unwrap();
+        write_frame(&mut wire, 4, &[5]).unwrap();
+
+        // the oversized payload is skipped
+        let mut wire = &wire[..];
+        assert_eq!(read_frame(&mut wire, 2).unwrap(), (3, None));
+        assert_eq!(read_frame(&mut wire, 2).unwrap(), (4, Some(vec![5])));
+
+        // announces 16 bytes, but only sends 3
+        let mut truncated = vec![0, 16, 0, 0, 0, 1, 2, 3];
+        assert!(read_frame(&mut &truncated[..], 32).is_err());
+        truncated[1] = u8::MAX;
+        assert!(read_frame(&mut &truncated[..], 32).is_err());
+    }
+
+    #[test]
+    fn test_handshake() {
+        let mut wire = Vec::new();
+        write_handshake(&mut wire, PROTOCOL_VERSION).unwrap();
+        assert_eq!(
+            read_handshake(&mut &wire[..]).unwrap(),
+            Some(PROTOCOL_VERSION)
+        );
+
+        wire[0] = b'X';
+        assert_eq!(read_handshake(&mut &wire[..]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_slice_and_error_encoding() {
+        let mut bytes = Vec::new();
+        encode_slice(&[1.5f32, -2., 3.], &mut bytes);
+        assert_eq!(decode_slice::<f32>(&bytes).unwrap(), [1.5, -2., 3.]);
+        assert!(decode_slice::<f64>(&bytes[1..]).is_none());
+
+        let err = RemoteError::new(RemoteErrorCode::UnknownBuffer, "No buffer with id 3.");
+        assert_eq!(RemoteError::decode(&err.encode()), err);
+
+        let mut reader = PayloadReader::new(&[7, 1, 0, 0, 0, 0, 0, 0, 0, 9]);
+        assert_eq!(reader.u8(), Some(7));
+        assert_eq!(reader.u64(), Some(1));
+        assert_eq!(reader.rest(), &[9]);
+    }
+}
@@ -0,0 +1,233 @@
+use core::{cell::RefCell, convert::Infallible};
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    rc::Rc,
+};
+
+use crate::{
+    flag::AllocFlag, impl_buffer_hook_traits, impl_retriever, impl_wrapped_data,
+    pass_down_cached_buffers, pass_down_cursor, pass_down_grad_fn, pass_down_replace_buf_dev,
+    pass_down_tape_actions, Alloc, Base, Buffer, Device, DeviceError, HasModules, IsShapeIndep,
+    Module, OnDropBuffer, Setup, Shape, Unit, WrappedData,
+};
+
+use super::{
+    read_frame, read_handshake, write_frame, write_handshake, Op, PayloadReader, RemoteError,
+    RemotePtr, RemoteType, PROTOCOL_VERSION, STATUS_ERR, STATUS_OK,
+};
+
+/// The connection of a [`Remote`] device to a [`RemoteServer`](super::RemoteServer).
+#[derive(Debug)]
+pub struct RemoteClient {
+    stream: RefCell<TcpStream>,
+}
+
+impl RemoteClient {
+    /// Connects to a server and checks that both peers speak the same protocol version.
+    pub fn connect(addr: impl ToSocketAddrs) -> crate::Result<Self> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        write_handshake(&mut stream, PROTOCOL_VERSION)?;
+        match read_handshake(&mut stream)? {
+            Some(PROTOCOL_VERSION) => Ok(RemoteClient {
+                stream: RefCell::new(stream),
+            }),
+            Some(_) => Err(DeviceError::RemoteVersionMismatch.into()),
+            None => Err(DeviceError::RemoteHandshake.into()),
+        }
+    }
+
+    /// Sends a request and waits for its response.
+    /// Errors of the server are returned as [`RemoteError`].
+    pub fn request(&self, op: Op, payload: &[u8]) -> crate::Result<Vec<u8>> {
+        let mut stream = self.stream.borrow_mut();
+        write_frame(&mut *stream, op as u8, payload)?;
+
+        match read_frame(&mut *stream, usize::MAX)? {
+            (STATUS_OK, Some(response)) => Ok(response),
+            (STATUS_ERR, Some(response)) => Err(RemoteError::decode(&response).into()),
+            _ => Err(DeviceError::RemoteInvalidResponse.into()),
+        }
+    }
+
+    pub(crate) fn alloc<T: RemoteType>(&self, len: usize) -> crate::Result<u64> {
+        let mut payload = vec![T::DTYPE as u8];
+        payload.extend_from_slice(&(len as u64).to_le_bytes());
+
+        let response = self.request(Op::Alloc, &payload)?;
+        PayloadReader::new(&response)
+            .u64()
+            .ok_or_else(|| DeviceError::RemoteInvalidResponse.into())
+    }
+
+    pub(crate) fn write<T: RemoteType>(&self, id: u64, data: &[T]) -> crate::Result<()> {
+        let mut payload = id.to_le_bytes().to_vec();
+        super::encode_slice(data, &mut payload);
+        self.request(Op::Write, &payload)?;
+        Ok(())
+    }
+
+    pub(crate) fn read<T: RemoteType>(&self, id: u64) -> crate::Result<Vec<T>> {
+        let response = self.request(Op::Read, &id.to_le_bytes())?;
+        super::decode_slice(&response).ok_or_else(|| DeviceError::RemoteInvalidResponse.into())
+    }
+}
+
+/// A device that executes operations on another machine running a [`RemoteServer`](super::RemoteServer).
+///
+/// # Example
+#[cfg_attr(feature = "cpu", doc = "```")]
+#[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+/// use custos::{network::RemoteServer, ApplyFunction, Base, Combiner, Device, Remote, CPU};
+///
+/// let server = RemoteServer::bind("127.0.0.1:0").unwrap();
+/// let addr = server.local_addr().unwrap();
+/// std::thread::spawn(move || server.serve_once(&CPU::<Base>::new()).unwrap());
+///
+/// let device = Remote::<Base>::new(addr).unwrap();
+/// let buf = device.buffer([1f32, 2., 3.]);
+/// let out = device.apply_fn(&buf, |x| x.mul(2.).add(1.));
+/// assert_eq!(out.read(), [3., 5., 7.]);
+/// ```
+pub struct Remote<Mods = Base> {
+    pub modules: Mods,
+    pub client: Rc<RemoteClient>,
+}
+
+impl_buffer_hook_traits!(Remote);
+impl_retriever!(Remote, RemoteType);
+impl_wrapped_data!(Remote);
+pass_down_cursor!(Remote);
+pass_down_grad_fn!(Remote);
+pass_down_tape_actions!(Remote);
+crate::pass_down_tangent_actions!(Remote);
+crate::pass_down_thread_pool_actions!(Remote);
+pass_down_replace_buf_dev!(Remote);
+pass_down_cached_buffers!(Remote);
+#[cfg(feature = "graph")]
+crate::pass_down_optimize_mem_graph!(Remote);
+
+impl<SimpleMods> Remote<SimpleMods> {
+    /// Connects to the [`RemoteServer`](super::RemoteServer) listening on `addr`.
+    #[inline]
+    pub fn new<'a, NewMods>(addr: impl ToSocketAddrs) -> crate::Result<Remote<NewMods>>
+    where
+        Self: 'a,
+        SimpleMods: Module<'a, Remote, Module = NewMods>,
+        NewMods: Setup<Remote<NewMods>>,
+    {
+        let mut remote = Remote {
+            modules: SimpleMods::new(),
+            client: Rc::new(RemoteClient::connect(addr)?),
+        };
+        NewMods::setup(&mut remote)?;
+        Ok(remote)
+    }
+
+    /// Connects to the address given by the environment variable `CUSTOS_REMOTE_ADDR`.
+    pub fn from_env<'a, NewMods>() -> crate::Result<Remote<NewMods>>
+    where
+        Self: 'a,
+        SimpleMods: Module<'a, Remote, Module = NewMods>,
+        NewMods: Setup<Remote<NewMods>>,
+    {
+        let addr = std::env::var("CUSTOS_REMOTE_ADDR").map_err(|_| DeviceError::MissingAddress)?;
+        Remote::<SimpleMods>::new(addr)
+    }
+}
+
+impl<Mods: OnDropBuffer> Device for Remote<Mods> {
+    type Base<T: Unit, S: Shape> = RemotePtr<T>;
+    type Data<T: Unit, S: Shape> = Self::Wrap<T, Self::Base<T, S>>;
+
+    type Error = Infallible;
+
+    #[inline]
+    fn base_to_data<T: Unit, S: Shape>(&self, base: Self::Base<T, S>) -> Self::Data<T, S> {
+        self.wrap_in_base(base)
+    }
+
+    #[inline]
+    fn wrap_to_data<T: Unit, S: Shape>(
+        &self,
+        wrap: Self::Wrap<T, Self::Base<T, S>>,
+    ) -> Self::Data<T, S> {
+        wrap
+    }
+
+    #[inline]
+    fn data_as_wrap<T: Unit, S: Shape>(
+        data: &Self::Data<T, S>,
+    ) -> &Self::Wrap<T, Self::Base<T, S>> {
+        data
+    }
+
+    #[inline]
+    fn data_as_wrap_mut<T: Unit, S: Shape>(
+        data: &mut Self::Data<T, S>,
+    ) -> &mut Self::Wrap<T, Self::Base<T, S>> {
+        data
+    }
+}
+
+impl<Mods> HasModules for Remote<Mods> {
+    type Mods = Mods;
+
+    #[inline]
+    fn modules(&self) -> &Mods {
+        &self.modules
+    }
+}
+
+unsafe impl<Mods: OnDropBuffer> IsShapeIndep for Remote<Mods> {}
+
+impl<T: RemoteType, Mods: OnDropBuffer> Alloc<T> for Remote<Mods> {
+    fn alloc<S: Shape>(&self, mut len: usize, flag: AllocFlag) -> crate::Result<Self::Base<T, S>> {
+        if len == 0 {
+            return Err(DeviceError::ZeroLengthBuffer.into());
+        }
+
+        if S::LEN > len {
+            len = S::LEN
+        }
+
+        let id = self.client.alloc::<T>(len)?;
+        Ok(RemotePtr::new(id, len, flag, self.client.clone()))
+    }
+
+    fn alloc_from_slice<S: Shape>(&self, data: &[T]) -> crate::Result<Self::Base<T, S>>
+    where
+        T: Clone,
+    {
+        if data.is_empty() {
+            return Err(DeviceError::ZeroLengthBuffer.into());
+        }
+        if !(S::LEN == data.len() || S::LEN == 0) {
+            return Err(DeviceError::ShapeLengthMismatch.into());
+        }
+
+        let ptr = self.alloc::<S>(data.len(), AllocFlag::None)?;
+        self.client.write(ptr.id, data)?;
+        Ok(ptr)
+    }
+}
+
+impl<Mods: crate::RunModule<Self>> crate::Run for Remote<Mods> {
+    #[inline]
+    fn run(&self) -> crate::Result<()> {
+        self.modules.run(self)
+    }
+}
+
+impl<Mods: OnDropBuffer> Remote<Mods> {
+    /// Reads the buffer from the server.
+    /// Unlike [`Read`](crate::Read), errors of the server are returned.
+    #[inline]
+    pub fn try_read<T: RemoteType, S: Shape>(
+        &self,
+        buf: &Buffer<T, Self, S>,
+    ) -> crate::Result<Vec<T>> {
+        self.client.read(buf.base().id)
+    }
+}
@@ -0,0 +1,84 @@
+use core::marker::PhantomData;
+use std::rc::Rc;
+
+use crate::{flag::AllocFlag, HasId, Id, PtrType, ShallowCopy, WrappedCopy};
+
+use super::{Op, RemoteClient};
+
+/// The pointer used for [`Remote`](super::Remote) [`Buffer`](crate::Buffer)s.
+/// The memory lives on the server and is freed when the pointer is dropped.
+#[derive(Debug)]
+pub struct RemotePtr<T> {
+    /// The id of the buffer on the server.
+    pub id: u64,
+    pub len: usize,
+    pub flag: AllocFlag,
+    pub(crate) client: Rc<RemoteClient>,
+    pub(crate) _pd: PhantomData<T>,
+}
+
+impl<T> RemotePtr<T> {
+    #[inline]
+    pub(crate) fn new(id: u64, len: usize, flag: AllocFlag, client: Rc<RemoteClient>) -> Self {
+        RemotePtr {
+            id,
+            len,
+            flag,
+            client,
+            _pd: PhantomData,
+        }
+    }
+}
+
+impl<T> HasId for RemotePtr<T> {
+    #[inline]
+    fn id(&self) -> Id {
+        Id {
+            id: self.id,
+            len: self.len,
+        }
+    }
+}
+
+impl<T> PtrType for RemotePtr<T> {
+    #[inline]
+    fn size(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn flag(&self) -> AllocFlag {
+        self.flag
+    }
+
+    #[inline]
+    unsafe fn set_flag(&mut self, flag: AllocFlag) {
+        self.flag = flag
+    }
+}
+
+impl<T> ShallowCopy for RemotePtr<T> {
+    #[inline]
+    unsafe fn shallow(&self) -> Self {
+        RemotePtr::new(self.id, self.len, AllocFlag::Wrapper, self.client.clone())
+    }
+}
+
+impl<T> WrappedCopy for RemotePtr<T> {
+    type Base = Self;
+
+    #[inline]
+    fn wrapped_copy(&self, to_wrap: Self::Base) -> Self {
+        to_wrap
+    }
+}
+
+impl<T> Drop for RemotePtr<T> {
+    fn drop(&mut self) {
+        if !self.flag.continue_deallocation() {
+            return;
+        }
+        // the server frees all buffers of a connection when it is closed, hence a failed request leaks nothing
+        let _ = self.client.request(Op::Free, &self.id.to_le_bytes());
+    }
+}
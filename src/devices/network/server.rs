@@ -0,0 +1,513 @@
+use std::{
+    collections::HashMap,
+    io::ErrorKind,
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{
+    flag::AllocFlag, Alloc, ApplyFunction, Buffer, ClearBuf, CopySlice, DeviceError, OnNewBuffer,
+    Read, Unit, WriteBuf,
+};
+
+use super::{
+    decode_slice, encode_slice, read_frame, read_handshake, write_frame, write_handshake, DType,
+    Expr, ExprOp, ExprProgram, Op, PayloadReader, RemoteError, RemoteErrorCode, RemoteType,
+    MAX_EXPR_NODES, PROTOCOL_VERSION, STATUS_ERR, STATUS_OK,
+};
+
+/// The operations a device needs to provide for every element type to be served by a [`RemoteServer`].
+pub trait RemoteBackend<'a, T: Unit>:
+    Alloc<T> + OnNewBuffer<'a, T, Self> + Read<T> + WriteBuf<T> + ClearBuf<T> + CopySlice<T> + 'a
+{
+}
+
+impl<'a, T: Unit, D> RemoteBackend<'a, T> for D where
+    D: Alloc<T> + OnNewBuffer<'a, T, D> + Read<T> + WriteBuf<T> + ClearBuf<T> + CopySlice<T> + 'a
+{
+}
+
+/// A device that can be wrapped by a [`RemoteServer`].
+/// `apply_fn` is only served for floating point buffers.
+pub trait ServeDevice<'a>:
+    RemoteBackend<'a, f32>
+    + RemoteBackend<'a, f64>
+    + RemoteBackend<'a, i32>
+    + RemoteBackend<'a, i64>
+    + RemoteBackend<'a, u8>
+    + RemoteBackend<'a, u32>
+    + ApplyFunction<f32>
+    + ApplyFunction<f64>
+{
+}
+
+impl<'a, D> ServeDevice<'a> for D where
+    D: RemoteBackend<'a, f32>
+        + RemoteBackend<'a, f64>
+        + RemoteBackend<'a, i32>
+        + RemoteBackend<'a, i64>
+        + RemoteBackend<'a, u8>
+        + RemoteBackend<'a, u32>
+        + ApplyFunction<f32>
+        + ApplyFunction<f64>
+{
+}
+
+/// Limits the requests a [`RemoteServer`] accepts from its clients.
+/// Requests exceeding a limit are answered with [`RemoteErrorCode::OutOfBounds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerLimits {
+    /// The maximum payload length of a request in bytes.
+    pub max_frame_len: usize,
+    /// The maximum size of a buffer allocated by a request in bytes.
+    pub max_alloc_bytes: usize,
+    /// The maximum size of all buffers a connection holds at the same time in bytes.
+    pub max_session_bytes: usize,
+    /// A connection is closed if the client sends nothing for this long.
+    /// As connections are served one after another, this keeps an idle client from blocking the server.
+    /// `None` waits forever.
+    pub read_timeout: Option<Duration>,
+}
+
+impl Default for ServerLimits {
+    /// Accepts requests up to 64 MiB, buffers up to 1 GiB and 2 GiB per connection.
+    /// Connections idling for 60 seconds are closed.
+    #[inline]
+    fn default() -> Self {
+        ServerLimits {
+            max_frame_len: 64 << 20,
+            max_alloc_bytes: 1 << 30,
+            max_session_bytes: 2 << 30,
+            read_timeout: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+/// Executes the requests of [`Remote`](super::Remote) devices on a local device.
+/// The buffers of a connection are freed when the connection is closed.
+pub struct RemoteServer {
+    listener: TcpListener,
+    limits: ServerLimits,
+}
+
+impl RemoteServer {
+    /// Binds the server to `addr` with the default [`ServerLimits`].
+    #[inline]
+    pub fn bind(addr: impl ToSocketAddrs) -> crate::Result<Self> {
+        Ok(RemoteServer {
+            listener: TcpListener::bind(addr)?,
+            limits: ServerLimits::default(),
+        })
+    }
+
+    /// Sets the limits for the requests of the clients.
+    #[inline]
+    pub fn with_limits(mut self, limits: ServerLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    #[inline]
+    pub fn limits(&self) -> ServerLimits {
+        self.limits
+    }
+
+    #[inline]
+    pub fn local_addr(&self) -> crate::Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves connections one after another until accepting a connection fails.
+    /// A failing or timed out connection does not stop the server.
+    pub fn serve<'a, D: ServeDevice<'a>>(&self, device: &'a D) -> crate::Result<()> {
+        loop {
+            let (stream, _) = self.listener.accept()?;
+            let _ = serve_connection(device, stream, self.limits);
+        }
+    }
+
+    /// Serves the next connection until it is closed by the client.
+    pub fn serve_once<'a, D: ServeDevice<'a>>(&self, device: &'a D) -> crate::Result<()> {
+        let (stream, _) = self.listener.accept()?;
+        serve_connection(device, stream, self.limits)
+    }
+}
+
+/// Executes the requests of a single client.
+/// Returns an error if the client does not send a request within the `read_timeout` of the `limits`.
+pub fn serve_connection<'a, D: ServeDevice<'a>>(
+    device: &'a D,
+    mut stream: TcpStream,
+    limits: ServerLimits,
+) -> crate::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(limits.read_timeout)?;
+
+    let Some(version) = read_handshake(&mut stream)? else {
+        return Err(DeviceError::RemoteHandshake.into());
+    };
+    // the client checks the version as well
+    write_handshake(&mut stream, PROTOCOL_VERSION)?;
+    if version != PROTOCOL_VERSION {
+        return Err(DeviceError::RemoteVersionMismatch.into());
+    }
+
+    let mut session = Session::new(device, limits);
+    loop {
+        let (op, payload) = match read_frame(&mut stream, limits.max_frame_len) {
+            Ok(frame) => frame,
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let response = match payload {
+            Some(payload) => session.handle(op, &payload),
+            None => Err(RemoteError::new(
+                RemoteErrorCode::OutOfBounds,
+                format!("The request is longer than {} bytes.", limits.max_frame_len),
+            )),
+        };
+        match response {
+            Ok(response) => write_frame(&mut stream, STATUS_OK, &response)?,
+            Err(err) => write_frame(&mut stream, STATUS_ERR, &err.encode())?,
+        }
+    }
+}
+
+enum StoredBuf<'a, D: ServeDevice<'a>> {
+    F32(Buffer<'a, f32, D>),
+    F64(Buffer<'a, f64, D>),
+    I32(Buffer<'a, i32, D>),
+    I64(Buffer<'a, i64, D>),
+    U8(Buffer<'a, u8, D>),
+    U32(Buffer<'a, u32, D>),
+}
+
+/// Runs `$body` with `$buf` bound to the typed buffer.
+macro_rules! typed {
+    ($stored:expr, $buf:ident => $body:expr) => {
+        match $stored {
+            StoredBuf::F32($buf) => $body,
+            StoredBuf::F64($buf) => $body,
+            StoredBuf::I32($buf) => $body,
+            StoredBuf::I64($buf) => $body,
+            StoredBuf::U8($buf) => $body,
+            StoredBuf::U32($buf) => $body,
+        }
+    };
+}
+
+impl<'a, D: ServeDevice<'a>> StoredBuf<'a, D> {
+    fn alloc(device: &'a D, dtype: DType, len: usize) -> crate::Result<Self> {
+        fn alloc<'a, T: Unit, D: RemoteBackend<'a, T>>(
+            device: &'a D,
+            len: usize,
+        ) -> crate::Result<Buffer<'a, T, D>> {
+            let base = device.alloc(len, AllocFlag::None)?;
+            Ok(Buffer::from_new_alloc(device, base))
+        }
+
+        Ok(match dtype {
+            DType::F32 => StoredBuf::F32(alloc(device, len)?),
+            DType::F64 => StoredBuf::F64(alloc(device, len)?),
+            DType::I32 => StoredBuf::I32(alloc(device, len)?),
+            DType::I64 => StoredBuf::I64(alloc(device, len)?),
+            DType::U8 => StoredBuf::U8(alloc(device, len)?),
+            DType::U32 => StoredBuf::U32(alloc(device, len)?),
+        })
+    }
+
+    fn dtype(&self) -> DType {
+        match self {
+            StoredBuf::F32(_) => DType::F32,
+            StoredBuf::F64(_) => DType::F64,
+            StoredBuf::I32(_) => DType::I32,
+            StoredBuf::I64(_) => DType::I64,
+            StoredBuf::U8(_) => DType::U8,
+            StoredBuf::U32(_) => DType::U32,
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        typed!(self, buf => buf.len())
+    }
+}
+
+fn read<'a, T: RemoteType, D: RemoteBackend<'a, T>>(buf: &Buffer<'a, T, D>) -> Vec<T> {
+    buf.device().read_to_vec(buf.base())
+}
+
+fn write<'a, T: RemoteType, D: RemoteBackend<'a, T>>(
+    buf: &mut Buffer<'a, T, D>,
+    data: &[T],
+) -> Result<(), RemoteError> {
+    if data.len() != buf.len() {
+        return Err(RemoteError::new(
+            RemoteErrorCode::OutOfBounds,
+            format!(
+                "Cannot write {} elements to a buffer of length {}.",
+                data.len(),
+                buf.len()
+            ),
+        ));
+    }
+    buf.device().write(buf, data);
+    Ok(())
+}
+
+fn apply<'a, T: RemoteType, D: RemoteBackend<'a, T> + ApplyFunction<T>>(
+    buf: &Buffer<'a, T, D>,
+    program: ExprProgram,
+) -> Buffer<'a, T, D> {
+    buf.device().apply_fn(buf, move |x| ExprOp { program, x })
+}
+
+struct Session<'a, D: ServeDevice<'a>> {
+    device: &'a D,
+    limits: ServerLimits,
+    buffers: HashMap<u64, StoredBuf<'a, D>>,
+    /// The size of all buffers in `buffers` in bytes.
+    allocated_bytes: usize,
+    next_id: u64,
+}
+
+fn malformed() -> RemoteError {
+    RemoteError::new(RemoteErrorCode::Malformed, "Truncated request.")
+}
+
+impl<'a, D: ServeDevice<'a>> Session<'a, D> {
+    fn new(device: &'a D, limits: ServerLimits) -> Self {
+        Session {
+            device,
+            limits,
+            buffers: HashMap::new(),
+            allocated_bytes: 0,
+            next_id: 0,
+        }
+    }
+
+    fn buf(&self, id: u64) -> Result<&StoredBuf<'a, D>, RemoteError> {
+        self.buffers.get(&id).ok_or_else(|| unknown_buffer(id))
+    }
+
+    fn buf_mut(&mut self, id: u64) -> Result<&mut StoredBuf<'a, D>, RemoteError> {
+        self.buffers.get_mut(&id).ok_or_else(|| unknown_buffer(id))
+    }
+
+    fn handle(&mut self, op: u8, payload: &[u8]) -> Result<Vec<u8>, RemoteError> {
+        let op = Op::from_u8(op).ok_or_else(|| {
+            RemoteError::new(RemoteErrorCode::UnknownOp, format!("Unknown op code {op}."))
+        })?;
+        let mut payload = PayloadReader::new(payload);
+
+        match op {
+            Op::Alloc => {
+                let dtype = payload.u8().ok_or_else(malformed)?;
+                let len = payload.u64().ok_or_else(malformed)?;
+                let dtype = DType::from_u8(dtype).ok_or_else(|| {
+                    RemoteError::new(
+                        RemoteErrorCode::UnknownDType,
+                        format!("Unknown dtype {dtype}."),
+                    )
+                })?;
+
+                let max_bytes = self.limits.max_alloc_bytes;
+                let len = usize::try_from(len)
+                    .ok()
+                    .filter(|len| len.checked_mul(dtype.size()).is_some_and(|bytes| bytes <= max_bytes))
+                    .ok_or_else(|| {
+                        RemoteError::new(
+                            RemoteErrorCode::OutOfBounds,
+                            format!("Cannot allocate {len} elements of type {dtype:?}, the limit is {max_bytes} bytes."),
+                        )
+                    })?;
+
+                // bytes <= max_alloc_bytes was checked above
+                let bytes = len * dtype.size();
+                let max_session_bytes = self.limits.max_session_bytes;
+                if self.allocated_bytes.saturating_add(bytes) > max_session_bytes {
+                    return Err(RemoteError::new(
+                        RemoteErrorCode::OutOfBounds,
+                        format!(
+                            "Cannot allocate {bytes} bytes, the connection already holds {} of {max_session_bytes} bytes.",
+                            self.allocated_bytes
+                        ),
+                    ));
+                }
+
+                let buf = StoredBuf::alloc(self.device, dtype, len)
+                    .map_err(|err| RemoteError::new(RemoteErrorCode::Device, err.to_string()))?;
+
+                let id = self.next_id;
+                self.next_id += 1;
+                self.allocated_bytes += bytes;
+                self.buffers.insert(id, buf);
+                Ok(id.to_le_bytes().to_vec())
+            }
+            Op::Write => {
+                let id = payload.u64().ok_or_else(malformed)?;
+                let bytes = payload.rest();
+                typed!(self.buf_mut(id)?, buf => {
+                    let data = decode_slice(bytes).ok_or_else(malformed)?;
+                    write(buf, &data)?;
+                });
+                Ok(Vec::new())
+            }
+            Op::Read => {
+                let id = payload.u64().ok_or_else(malformed)?;
+                let mut response = Vec::new();
+                typed!(self.buf(id)?, buf => encode_slice(&read(buf), &mut response));
+                Ok(response)
+            }
+            Op::Free => {
+                let id = payload.u64().ok_or_else(malformed)?;
+                let buf = self.buffers.remove(&id).ok_or_else(|| unknown_buffer(id))?;
+                self.allocated_bytes -= buf.len() * buf.dtype().size();
+                Ok(Vec::new())
+            }
+            Op::Clear => {
+                let id = payload.u64().ok_or_else(malformed)?;
+                typed!(self.buf_mut(id)?, buf => buf.device().clear(buf));
+                Ok(Vec::new())
+            }
+            Op::CopySlice => {
+                let mut field = || payload.u64().ok_or_else(malformed);
+                let (src, src_start, src_end, dst, dst_start) =
+                    (field()?, field()?, field()?, field()?, field()?);
+                let src_range = src_start as usize..src_end as usize;
+                let dst_end = (dst_start as usize)
+                    .checked_add(src_range.len())
+                    .ok_or_else(|| {
+                        RemoteError::new(RemoteErrorCode::OutOfBounds, "Copy range overflows.")
+                    })?;
+                let dst_range = dst_start as usize..dst_end;
+                self.copy_slice(src, src_range, dst, dst_range)?;
+                Ok(Vec::new())
+            }
+            Op::ApplyFn => {
+                let src = payload.u64().ok_or_else(malformed)?;
+                let out = payload.u64().ok_or_else(malformed)?;
+                let src_expr = core::str::from_utf8(payload.rest()).map_err(|_| {
+                    RemoteError::new(RemoteErrorCode::InvalidExpression, "Invalid UTF-8.")
+                })?;
+                self.apply_fn(src, out, src_expr)?;
+                Ok(Vec::new())
+            }
+        }
+    }
+
+    fn copy_slice(
+        &mut self,
+        src: u64,
+        src_range: core::ops::Range<usize>,
+        dst: u64,
+        dst_range: core::ops::Range<usize>,
+    ) -> Result<(), RemoteError> {
+        if src == dst {
+            return Err(RemoteError::new(
+                RemoteErrorCode::OutOfBounds,
+                "Source and destination of copy_slice must be different buffers.",
+            ));
+        }
+
+        // removed temporarily to borrow source and destination at once
+        let mut dst_buf = self
+            .buffers
+            .remove(&dst)
+            .ok_or_else(|| unknown_buffer(dst))?;
+        let res = self.buf(src).and_then(|src_buf| {
+            if src_range.start > src_range.end
+                || src_range.end > src_buf.len()
+                || dst_range.end > dst_buf.len()
+            {
+                return Err(RemoteError::new(
+                    RemoteErrorCode::OutOfBounds,
+                    format!(
+                        "Cannot copy {src_range:?} of a buffer of length {} to {dst_range:?} of a buffer of length {}.",
+                        src_buf.len(),
+                        dst_buf.len()
+                    ),
+                ));
+            }
+
+            match (src_buf, &mut dst_buf) {
+                (StoredBuf::F32(src), StoredBuf::F32(dst)) => copy(src, src_range, dst, dst_range),
+                (StoredBuf::F64(src), StoredBuf::F64(dst)) => copy(src, src_range, dst, dst_range),
+                (StoredBuf::I32(src), StoredBuf::I32(dst)) => copy(src, src_range, dst, dst_range),
+                (StoredBuf::I64(src), StoredBuf::I64(dst)) => copy(src, src_range, dst, dst_range),
+                (StoredBuf::U8(src), StoredBuf::U8(dst)) => copy(src, src_range, dst, dst_range),
+                (StoredBuf::U32(src), StoredBuf::U32(dst)) => copy(src, src_range, dst, dst_range),
+                (src, dst) => return Err(dtype_mismatch(src.dtype(), dst.dtype())),
+            }
+            Ok(())
+        });
+        self.buffers.insert(dst, dst_buf);
+        res
+    }
+
+    /// Applies the expression on the device and replaces the `out` buffer with the result.
+    fn apply_fn(&mut self, src: u64, out: u64, src_expr: &str) -> Result<(), RemoteError> {
+        let program = Expr::parse(src_expr)
+            .and_then(|expr| {
+                expr.compile()
+                    .ok_or_else(|| format!("Expression has more than {MAX_EXPR_NODES} nodes."))
+            })
+            .map_err(|msg| RemoteError::new(RemoteErrorCode::InvalidExpression, msg))?;
+
+        let (src_buf, out_buf) = (self.buf(src)?, self.buf(out)?);
+        if out_buf.dtype() != src_buf.dtype() {
+            return Err(dtype_mismatch(src_buf.dtype(), out_buf.dtype()));
+        }
+        if out_buf.len() != src_buf.len() {
+            return Err(RemoteError::new(
+                RemoteErrorCode::OutOfBounds,
+                format!(
+                    "Cannot apply a function to a buffer of length {} and write it to a buffer of length {}.",
+                    src_buf.len(),
+                    out_buf.len()
+                ),
+            ));
+        }
+
+        let applied = match src_buf {
+            StoredBuf::F32(buf) => StoredBuf::F32(apply(buf, program)),
+            StoredBuf::F64(buf) => StoredBuf::F64(apply(buf, program)),
+            // expressions are evaluated in floating point, integer results would differ from local devices
+            buf => {
+                return Err(RemoteError::new(
+                    RemoteErrorCode::UnsupportedDType,
+                    format!(
+                        "apply_fn is not supported for buffers of type {:?}.",
+                        buf.dtype()
+                    ),
+                ))
+            }
+        };
+        self.buffers.insert(out, applied);
+        Ok(())
+    }
+}
+
+fn copy<'a, T: RemoteType, D: RemoteBackend<'a, T>>(
+    src: &Buffer<'a, T, D>,
+    src_range: core::ops::Range<usize>,
+    dst: &mut Buffer<'a, T, D>,
+    dst_range: core::ops::Range<usize>,
+) {
+    dst.device().copy_slice_to(src, src_range, dst, dst_range)
+}
+
+fn unknown_buffer(id: u64) -> RemoteError {
+    RemoteError::new(
+        RemoteErrorCode::UnknownBuffer,
+        format!("No buffer with id {id}."),
+    )
+}
+
+fn dtype_mismatch(src: DType, dst: DType) -> RemoteError {
+    RemoteError::new(
+        RemoteErrorCode::DTypeMismatch,
+        format!("Buffers have different types: {src:?} and {dst:?}."),
+    )
+}
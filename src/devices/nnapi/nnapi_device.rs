@@ -106,12 +106,12 @@ impl<U, Mods: Retrieve<Self, T, S>, T: AsOperandCode, S: Shape> Retriever<T, S>
         len: usize,
         parents: impl crate::Parents<NUM_PARENTS>,
     ) -> crate::Result<Buffer<T, Self, S>> {
-        let data = unsafe { self.modules.retrieve::<NUM_PARENTS>(self, len, parents) }?;
-        let buf = Buffer {
+        let data = unsafe { self.modules.retrieve::<NUM_PARENTS>(self, len, &parents) }?;
+        let mut buf = Buffer {
             data,
             device: Some(self),
         };
-        self.modules.on_retrieve_finish(&buf);
+        self.modules.on_retrieve_finish(&parents, &mut buf);
         Ok(buf)
     }
 }
@@ -2,7 +2,7 @@ use min_cl::CLDevice;
 
 use min_cl::api::{create_buffer, enqueue_full_copy_buffer, MemFlags};
 
-use super::{AsClCvoidPtr, CLPtr};
+use super::{check_cl_dtype, AsClCvoidPtr, CLPtr};
 use crate::{flag::AllocFlag, opencl::KernelLaunch};
 use crate::{impl_device_traits, Shape, Unit};
 use crate::{
@@ -224,6 +224,8 @@ impl<Mods> Debug for OpenCL<Mods> {
 
 impl<Mods: OnDropBuffer, T: Unit> Alloc<T> for OpenCL<Mods> {
     fn alloc<S: Shape>(&self, mut len: usize, flag: AllocFlag) -> crate::Result<CLPtr<T>> {
+        check_cl_dtype::<T>()?;
+
         if S::LEN > len {
             len = S::LEN
         }
@@ -246,6 +248,8 @@ impl<Mods: OnDropBuffer, T: Unit> Alloc<T> for OpenCL<Mods> {
     }
 
     fn alloc_from_slice<S: Shape>(&self, data: &[T]) -> crate::Result<CLPtr<T>> {
+        check_cl_dtype::<T>()?;
+
         let ptr = unsafe {
             create_buffer::<T>(
                 self.ctx(),
@@ -316,6 +320,9 @@ impl<Mods> crate::LazySetup for OpenCL<Mods> {}
 #[cfg(feature = "lazy")]
 impl<Mods> crate::LazyRun for OpenCL<Mods> {}
 
+#[cfg(feature = "lazy")]
+impl<Mods: OnDropBuffer + 'static> crate::RunCaptured for OpenCL<Mods> {}
+
 #[cfg(test)]
 mod tests {
     use min_cl::api::OCLErrorKind;
@@ -329,6 +336,24 @@ mod tests {
         let _device = OpenCL::<Base>::fastest().unwrap();
     }
 
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_cl_bf16_unsupported() {
+        use crate::{flag::AllocFlag, DeviceError, ErrorKind};
+
+        let device = OpenCL::<Base>::new(0).unwrap();
+
+        let err = Alloc::<half::bf16>::alloc::<()>(&device, 4, AllocFlag::None).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::CLDatatypeUnsupported));
+
+        let err = Alloc::alloc_from_slice::<()>(&device, &[half::bf16::ONE]).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::CLDatatypeUnsupported));
+
+        // f16 is still supported
+        let buf = Buffer::from_slice(&device, &[half::f16::ONE, half::f16::from_f32(2.)]);
+        assert_eq!(buf.read(), [half::f16::ONE, half::f16::from_f32(2.)]);
+    }
+
     #[test]
     fn test_multiplie_queues() -> crate::Result<()> {
         let device = CLDevice::new(0)?;
@@ -1,4 +1,57 @@
-use crate::{OnDropBuffer, OpenCL, UnaryFusing};
+use crate::{ElementWiseFusing, OnDropBuffer, OpenCL, UnaryFusing};
+
+impl<Mods: OnDropBuffer> ElementWiseFusing for OpenCL<Mods> {
+    #[cfg(feature = "lazy")]
+    fn fused_ew<T: crate::CDatatype + crate::Numeric>(
+        &self,
+        fused: &crate::FusedElementWise<T>,
+        inputs: &[&crate::Buffer<'_, T, Self, ()>],
+        outputs: &mut [&mut crate::Buffer<'_, T, Self, ()>],
+    ) -> crate::Result<()> {
+        use super::AsClCvoidPtr;
+
+        let Some(len) = outputs.first().map(|out| out.len()) else {
+            return Ok(());
+        };
+
+        let params = (0..fused.inputs())
+            .map(|idx| format!("__global const {}* in{idx}, ", T::C_DTYPE_STR))
+            .chain(
+                (0..fused.outputs().len())
+                    .map(|idx| format!("__global {}* out{idx}, ", T::C_DTYPE_STR)),
+            )
+            .collect::<String>();
+
+        let fused_operations = fused.to_c_src(
+            T::C_DTYPE_STR,
+            |idx| format!("in{idx}[id]"),
+            |idx| format!("out{idx}[id]"),
+        );
+
+        let src = format!(
+            "
+            {extensions}
+            __kernel void fused_ew({params}long len) {{
+                size_t id = get_global_id(0);
+                if (id >= len) {{
+                    return;
+                }}
+                {fused_operations}
+            }}
+        ",
+            extensions = super::cl_extensions::<T>()
+        );
+
+        let mut args = inputs
+            .iter()
+            .map(|buf| *buf as &dyn AsClCvoidPtr)
+            .chain(outputs.iter().map(|buf| &**buf as &dyn AsClCvoidPtr))
+            .collect::<Vec<_>>();
+        args.push(&len);
+
+        self.launch_kernel(&src, [(len / 32 + 1) * 32, 0, 0], Some([32, 0, 0]), &args)
+    }
+}
 
 impl<Mods: OnDropBuffer> UnaryFusing for OpenCL<Mods> {
     #[cfg(feature = "lazy")]
@@ -6,15 +59,8 @@ impl<Mods: OnDropBuffer> UnaryFusing for OpenCL<Mods> {
     #[inline]
     fn unary_fuse_op<T: crate::CDatatype + crate::Numeric>(
         &self,
-        ops_to_fuse: Vec<std::rc::Rc<dyn Fn(crate::Resolve<T>) -> Box<dyn crate::TwoWay<T>>>>,
-    ) -> Box<
-        dyn Fn(
-            (
-                &mut crate::Buffer<'_, T, Self, ()>,
-                &crate::Buffer<'_, T, Self, ()>,
-            ),
-        ) -> crate::Result<()>,
-    > {
+        ops_to_fuse: Vec<crate::UnaryFn<T>>,
+    ) -> crate::FusedUnaryOp<T, Self> {
         use crate::operations_to_fused_src;
 
         Box::new(move |(out, buf)| {
@@ -26,6 +72,7 @@ impl<Mods: OnDropBuffer> UnaryFusing for OpenCL<Mods> {
 
             let src = format!(
                 "
+                {extensions}
                 __kernel void apply_fn(__global const {datatype}* lhs, __global {datatype}* out, long len) {{
                     size_t id = get_global_id(0);
                     if (id >= len) {{
@@ -36,6 +83,7 @@ impl<Mods: OnDropBuffer> UnaryFusing for OpenCL<Mods> {
                     out[id] = x;
                 }}
             ",
+                extensions = super::cl_extensions::<T>(),
                 datatype = T::C_DTYPE_STR,
             );
 
@@ -35,3 +35,43 @@ pub fn chosen_cl_idx() -> usize {
             "Environment variable 'CUSTOS_CL_DEVICE_IDX' contains an invalid opencl device index!",
         )
 }
+
+/// Returns `true` if `T` is `f16`, which requires the `cl_khr_fp16` extension.
+#[inline]
+fn is_half<T: 'static>() -> bool {
+    #[cfg(feature = "half")]
+    if core::any::TypeId::of::<T>() == core::any::TypeId::of::<half::f16>() {
+        return true;
+    }
+    false
+}
+
+/// Fails if OpenCL kernels cannot operate on `T`.
+/// `bf16` has no OpenCL counterpart, its bits would be read as `half`.
+#[inline]
+pub fn check_cl_dtype<T: 'static>() -> crate::Result<()> {
+    #[cfg(feature = "half")]
+    if core::any::TypeId::of::<T>() == core::any::TypeId::of::<half::bf16>() {
+        return Err(crate::DeviceError::CLDatatypeUnsupported.into());
+    }
+    Ok(())
+}
+
+/// Returns the `#pragma` enabling the OpenCL extension a kernel operating on `T` requires, e.g. `cl_khr_fp16` for half precision floats.
+#[inline]
+pub fn cl_extensions<T: 'static>() -> &'static str {
+    if is_half::<T>() {
+        return "#pragma OPENCL EXTENSION cl_khr_fp16 : enable\n";
+    }
+    ""
+}
+
+/// Returns the OpenCL type used to accumulate values of type `T`.
+/// Half precision floats are accumulated in `float`.
+#[inline]
+pub fn cl_acc_dtype<T: crate::CDatatype>() -> &'static str {
+    if is_half::<T>() {
+        return "float";
+    }
+    T::C_DTYPE_STR
+}
@@ -6,13 +6,20 @@ use min_cl::{
 };
 
 use crate::{
-    bounds_to_range, cpu_stack_ops::clear_slice, location, op_hint::unary, pass_down_add_operation,
-    pass_down_exec_now, prelude::Number, AddOperation, ApplyFunction, Buffer, CDatatype, ClearBuf,
-    CopySlice, OnDropBuffer, OpenCL, Read, Resolve, Retrieve, Retriever, SetOpHint, Shape,
-    ToCLSource, ToMarker, TwoWay, UnaryGrad, Unit, UseGpuOrCpu, WriteBuf, ZeroGrad,
+    bounds_to_range,
+    cpu_stack_ops::clear_slice,
+    gemm::{check_gemm_lengths, gemm_index_src, gemm_out_len, GEMM_TILE},
+    location,
+    op_hint::{binary, unary},
+    pass_down_add_operation, pass_down_exec_now,
+    prelude::Number,
+    AddOperation, ApplyBinaryFunction, ApplyFunction, Buffer, CDatatype, ClearBuf, CopySlice, Gemm,
+    GemmGrad, MayTangentActions, OnDropBuffer, OpenCL, Read, Resolve, Retrieve, Retriever,
+    SetOpHint, Shape, ToCLSource, ToMarker, TwoWay, UnaryGrad, Unit, UseGpuOrCpu, WriteBuf,
+    ZeroGrad,
 };
 
-use super::{enqueue_kernel, CLPtr};
+use super::{cl_acc_dtype, cl_extensions, enqueue_kernel, CLPtr};
 
 /*impl<Mods: OnDropBuffer, T: CDatatype> ClearBuf<T> for OpenCL<Mods> {
     #[inline]
@@ -38,6 +45,7 @@ impl<Mods: OnDropBuffer + UseGpuOrCpu, T: CDatatype + Default> ClearBuf<T> for O
             let cpu_buf = unsafe { &mut *(buf as *mut Buffer<_, OpenCL<Mods>, _>) };
             self.use_cpu_or_gpu(
                 location!(),
+                &[],
                 &[buf.len()],
                 || clear_slice(cpu_buf),
                 || try_cl_clear(self, buf).unwrap(),
@@ -54,6 +62,112 @@ impl<Mods: OnDropBuffer, T: CDatatype> ZeroGrad<T> for OpenCL<Mods> {
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>) {
         try_cl_clear(self, data).unwrap()
     }
+
+    #[inline]
+    fn grad_squared_norm<S: Shape>(&self, data: &Self::Base<T, S>) -> crate::Result<f64>
+    where
+        T: 'static,
+    {
+        try_cl_squared_norm(self, data)
+    }
+
+    #[inline]
+    fn scale_grad<S: Shape>(&self, data: &mut Self::Base<T, S>, factor: f64) -> crate::Result<()>
+    where
+        T: 'static,
+    {
+        try_cl_scale(self, data, factor as f32)
+    }
+}
+
+/// Computes the sum of the squared elements of an OpenCL buffer.
+/// Every work group writes a partial sum, which are summed up on the host.
+pub fn try_cl_squared_norm<T: CDatatype>(device: &CLDevice, x: &CLPtr<T>) -> crate::Result<f64> {
+    const LOCAL_SIZE: usize = 64;
+
+    let src = format!(
+        "
+        {extensions}
+        __kernel void squared_norm(__global const {datatype}* x, __global float* partial, long len) {{
+            __local float sums[{LOCAL_SIZE}];
+            size_t lid = get_local_id(0);
+
+            float sum = 0;
+            for (size_t i = get_global_id(0); i < len; i += get_global_size(0)) {{
+                float value = (float) x[i];
+                sum += value * value;
+            }}
+            sums[lid] = sum;
+            barrier(CLK_LOCAL_MEM_FENCE);
+
+            for (size_t stride = {LOCAL_SIZE} / 2; stride > 0; stride >>= 1) {{
+                if (lid < stride) {{
+                    sums[lid] += sums[lid + stride];
+                }}
+                barrier(CLK_LOCAL_MEM_FENCE);
+            }}
+            if (lid == 0) {{
+                partial[get_group_id(0)] = sums[0];
+            }}
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+    );
+
+    let groups = x.len.div_ceil(LOCAL_SIZE).clamp(1, LOCAL_SIZE);
+    let partial = unsafe {
+        min_cl::api::create_buffer::<f32>(
+            device.ctx(),
+            min_cl::api::MemFlags::MemReadWrite as u64,
+            groups,
+            None,
+        )?
+    };
+    let partial = CLPtr {
+        ptr: partial,
+        host_ptr: core::ptr::null_mut(),
+        len: groups,
+        flag: crate::flag::AllocFlag::None,
+    };
+
+    enqueue_kernel(
+        device,
+        &src,
+        [groups * LOCAL_SIZE, 0, 0],
+        Some([LOCAL_SIZE, 0, 0]),
+        &[x, &partial, &x.len],
+    )?;
+    Ok(try_read_cl_buf_to_vec(device, &partial)?
+        .iter()
+        .map(|sum| *sum as f64)
+        .sum())
+}
+
+/// Multiplies every element of an OpenCL buffer by `factor`.
+pub fn try_cl_scale<T: CDatatype>(
+    device: &CLDevice,
+    x: &mut CLPtr<T>,
+    factor: f32,
+) -> crate::Result<()> {
+    let src = format!(
+        "
+        {extensions}
+        __kernel void scale(__global {datatype}* x, float factor, long len) {{
+            size_t id = get_global_id(0);
+            if (id >= len) {{
+                return;
+            }}
+            x[id] = ({datatype}) (x[id] * factor);
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+    );
+
+    let gws = [(x.len / 32 + 1) * 32, 0, 0];
+    enqueue_kernel(device, &src, gws, Some([32, 0, 0]), &[x, &factor, &x.len])?;
+    Ok(())
 }
 
 /// Sets the elements of an OpenCL Buffer to zero.
@@ -74,6 +188,7 @@ impl<Mods: OnDropBuffer, T: CDatatype> ZeroGrad<T> for OpenCL<Mods> {
 pub fn try_cl_clear<T: CDatatype>(device: &CLDevice, lhs: &mut CLPtr<T>) -> crate::Result<()> {
     let src = format!(
         "
+        {extensions}
         __kernel void clear(__global {datatype}* self, long len) {{
             size_t id = get_global_id(0);
             if (id >= len) {{
@@ -82,7 +197,8 @@ pub fn try_cl_clear<T: CDatatype>(device: &CLDevice, lhs: &mut CLPtr<T>) -> crat
             self[id] = 0;
         }}
     ",
-        datatype = T::C_DTYPE_STR
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
     );
 
     let gws = [(lhs.len() / 32 + 1) * 32, 0, 0];
@@ -220,6 +336,7 @@ where
     T: CDatatype + Number,
     S: Shape,
     Mods: AddOperation + Retrieve<Self, T, S> + UseGpuOrCpu + SetOpHint<T> + 'static,
+    Self: MayTangentActions,
 {
     #[inline]
     fn apply_fn<F>(
@@ -236,12 +353,19 @@ where
             let dev = buf.device();
             // let out: &mut Buffer<'_, T, OpenCL<Mods>, S> = out.as_mut().unwrap();
             let out = &mut *out;
+            #[cfg(feature = "forward")]
+            if let Some(mut tangents) = crate::TangentActions::tangents_mut(dev) {
+                if let Some((tangent, out_tangent)) = tangents.tangent_pair(buf, out) {
+                    return try_cl_apply_fn_tangent_mut(dev, buf, tangent, out, out_tangent, f);
+                }
+            }
             #[cfg(unified_cl)]
             {
                 let cpu_out = unsafe { &mut *(out as *mut Buffer<_, OpenCL<Mods>, _>) };
                 dev.use_cpu_or_gpu(
                     (file!(), line!(), column!()).into(),
                     &[buf.len()],
+                    &[out.len()],
                     || crate::devices::cpu_stack_ops::apply_fn_slice(buf, cpu_out, f),
                     || try_cl_apply_fn_mut(dev, buf, out, f).unwrap(),
                 );
@@ -268,11 +392,12 @@ pub fn try_cl_apply_fn_mut<T, F>(
     f: impl Fn(Resolve<T>) -> F,
 ) -> crate::Result<()>
 where
-    T: CDatatype + Number,
+    T: CDatatype + Default,
     F: ToCLSource,
 {
     let src = format!(
         "
+        {extensions}
         __kernel void apply_fn(__global const {datatype}* lhs, __global {datatype}* out, long len) {{
             size_t id = get_global_id(0);
             if (id >= len) {{
@@ -281,6 +406,7 @@ where
             out[id] = {operation};
         }}
     ",
+        extensions = cl_extensions::<T>(),
         datatype = T::C_DTYPE_STR,
         operation = f("lhs[id]".to_marker()).to_cl_source()
     );
@@ -295,6 +421,397 @@ where
     Ok(())
 }
 
+/// A failable OpenCL version of [`apply_fn`](ApplyFunction::apply_fn) that also computes the tangent of the output.
+/// The value and the tangent are evaluated in one kernel (see [`ToCLTangentSource`](crate::ToCLTangentSource)).
+#[cfg(feature = "forward")]
+pub fn try_cl_apply_fn_tangent_mut<T, F>(
+    device: &CLDevice,
+    x: &CLPtr<T>,
+    x_tangent: &CLPtr<T>,
+    out: &mut CLPtr<T>,
+    out_tangent: &mut CLPtr<T>,
+    f: impl Fn(Resolve<T>) -> F,
+) -> crate::Result<()>
+where
+    T: CDatatype + Number,
+    F: crate::ToCLTangentSource,
+{
+    let op = f("lhs[id]".to_marker());
+    let src = format!(
+        "
+        {extensions}
+        __kernel void apply_fn_tangent(__global const {datatype}* lhs, __global const {datatype}* tangent, __global {datatype}* out, __global {datatype}* out_tangent, long len) {{
+            size_t id = get_global_id(0);
+            if (id >= len) {{
+                return;
+            }}
+            out[id] = {operation};
+            out_tangent[id] = {tangent_operation};
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+        operation = op.to_cl_source(),
+        tangent_operation = op.to_cl_tangent_source("tangent[id]"),
+    );
+
+    enqueue_kernel(
+        device,
+        &src,
+        [(x.len() / 32 + 1) * 32, 0, 0],
+        Some([32, 0, 0]),
+        &[x, x_tangent, out, out_tangent, &x.len()],
+    )?;
+    Ok(())
+}
+
+impl<T, S, Mods> ApplyBinaryFunction<T, S> for OpenCL<Mods>
+where
+    T: CDatatype + Number,
+    S: Shape,
+    Mods: AddOperation + Retrieve<Self, T, S> + SetOpHint<T> + 'static,
+{
+    #[inline]
+    fn apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, Self, S>,
+        rhs: &Buffer<T, Self, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<T>,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            try_cl_apply_binary_fn_mut(lhs.device(), lhs, rhs, out, f)
+        })
+        .unwrap();
+        self.set_op_hint(binary(f));
+        out
+    }
+}
+
+/// A failable OpenCL version of [`apply_binary_fn`](ApplyBinaryFunction::apply_binary_fn).
+pub fn try_cl_apply_binary_fn_mut<T, F>(
+    device: &CLDevice,
+    lhs: &CLPtr<T>,
+    rhs: &CLPtr<T>,
+    out: &mut CLPtr<T>,
+    f: impl Fn(Resolve<T>, Resolve<T>) -> F,
+) -> crate::Result<()>
+where
+    T: CDatatype + Number,
+    F: ToCLSource,
+{
+    let src = format!(
+        "
+        {extensions}
+        __kernel void apply_binary_fn(__global const {datatype}* lhs, __global const {datatype}* rhs, __global {datatype}* out, long len) {{
+            size_t id = get_global_id(0);
+            if (id >= len) {{
+                return;
+            }}
+            out[id] = {operation};
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+        operation = f("lhs[id]".to_marker(), "rhs[id]".to_marker()).to_cl_source()
+    );
+
+    enqueue_kernel(
+        device,
+        &src,
+        [(lhs.len() / 32 + 1) * 32, 0, 0],
+        Some([32, 0, 0]),
+        &[lhs, rhs, out, &lhs.len()],
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "half")]
+impl<T, S, Mods> crate::ApplyFunctionF32<T, S> for OpenCL<Mods>
+where
+    T: crate::HalfFloat + CDatatype,
+    S: Shape,
+    Mods: AddOperation + Retrieve<Self, T, S> + 'static,
+{
+    #[inline]
+    fn apply_fn_f32<F>(
+        &self,
+        buf: &Buffer<T, Self, S>,
+        f: impl Fn(Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32>,
+    {
+        let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+        self.add_op((&mut out, buf), move |(out, buf)| {
+            try_cl_apply_fn_f32_mut(buf.device(), buf, out, f)
+        })
+        .unwrap();
+        out
+    }
+}
+
+/// A failable OpenCL version of [`apply_fn_f32`](crate::ApplyFunctionF32::apply_fn_f32).
+/// Every element is loaded as `float`, `f` is evaluated in `float` and the result is stored as `T`.
+#[cfg(feature = "half")]
+pub fn try_cl_apply_fn_f32_mut<T, F>(
+    device: &CLDevice,
+    x: &CLPtr<T>,
+    out: &mut CLPtr<T>,
+    f: impl Fn(Resolve<f32>) -> F,
+) -> crate::Result<()>
+where
+    T: CDatatype,
+    F: ToCLSource,
+{
+    let src = format!(
+        "
+        {extensions}
+        __kernel void apply_fn_f32(__global const {datatype}* lhs, __global {datatype}* out, long len) {{
+            size_t id = get_global_id(0);
+            if (id >= len) {{
+                return;
+            }}
+            out[id] = ({datatype})({operation});
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+        operation = f("((float)lhs[id])".to_marker()).to_cl_source()
+    );
+
+    enqueue_kernel(
+        device,
+        &src,
+        [(x.len() / 32 + 1) * 32, 0, 0],
+        Some([32, 0, 0]),
+        &[x, out, &x.len()],
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "half")]
+impl<T, S, Mods> crate::ApplyBinaryFunctionF32<T, S> for OpenCL<Mods>
+where
+    T: crate::HalfFloat + CDatatype,
+    S: Shape,
+    Mods: AddOperation + Retrieve<Self, T, S> + 'static,
+{
+    #[inline]
+    fn apply_binary_fn_f32<F>(
+        &self,
+        lhs: &Buffer<T, Self, S>,
+        rhs: &Buffer<T, Self, S>,
+        f: impl Fn(Resolve<f32>, Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32>,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            try_cl_apply_binary_fn_f32_mut(lhs.device(), lhs, rhs, out, f)
+        })
+        .unwrap();
+        out
+    }
+}
+
+/// A failable OpenCL version of [`apply_binary_fn_f32`](crate::ApplyBinaryFunctionF32::apply_binary_fn_f32).
+/// Every element is loaded as `float`, `f` is evaluated in `float` and the result is stored as `T`.
+#[cfg(feature = "half")]
+pub fn try_cl_apply_binary_fn_f32_mut<T, F>(
+    device: &CLDevice,
+    lhs: &CLPtr<T>,
+    rhs: &CLPtr<T>,
+    out: &mut CLPtr<T>,
+    f: impl Fn(Resolve<f32>, Resolve<f32>) -> F,
+) -> crate::Result<()>
+where
+    T: CDatatype,
+    F: ToCLSource,
+{
+    let src = format!(
+        "
+        {extensions}
+        __kernel void apply_binary_fn_f32(__global const {datatype}* lhs, __global const {datatype}* rhs, __global {datatype}* out, long len) {{
+            size_t id = get_global_id(0);
+            if (id >= len) {{
+                return;
+            }}
+            out[id] = ({datatype})({operation});
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+        operation = f(
+            "((float)lhs[id])".to_marker(),
+            "((float)rhs[id])".to_marker()
+        )
+        .to_cl_source()
+    );
+
+    enqueue_kernel(
+        device,
+        &src,
+        [(lhs.len() / 32 + 1) * 32, 0, 0],
+        Some([32, 0, 0]),
+        &[lhs, rhs, out, &lhs.len()],
+    )?;
+    Ok(())
+}
+
+impl<T, Mods> Gemm<T> for OpenCL<Mods>
+where
+    T: CDatatype + Number,
+    Mods: AddOperation + Retrieve<Self, T> + 'static,
+{
+    #[inline]
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs: &Buffer<T, Self>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            try_cl_gemm(lhs.device(), m, k, n, lhs, false, rhs, false, out, false)
+        })
+        .unwrap();
+        out
+    }
+}
+
+impl<T, Mods> GemmGrad<T> for OpenCL<Mods>
+where
+    T: CDatatype + Number,
+    Mods: OnDropBuffer + AddOperation + 'static,
+{
+    #[inline]
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, Self>,
+        lhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                try_cl_gemm(rhs.device(), m, n, k, out_grad, false, rhs, true, lhs_grad, true)
+            },
+        )
+        .unwrap();
+    }
+
+    #[inline]
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                try_cl_gemm(lhs.device(), k, m, n, lhs, true, out_grad, false, rhs_grad, true)
+            },
+        )
+        .unwrap();
+    }
+}
+
+/// A failable OpenCL version of [`gemm`](Gemm::gemm) using a generated tiled kernel.
+/// Computes `out = op(lhs) * op(rhs)`, where `op(lhs)` is `m x k` and `op(rhs)` is `k x n`.
+/// If `lhs_trans` (`rhs_trans`) is `true`, `lhs` (`rhs`) is stored transposed.
+/// If `accumulate` is `true`, the product is added to `out`.
+#[allow(clippy::too_many_arguments)]
+pub fn try_cl_gemm<T>(
+    device: &CLDevice,
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: &CLPtr<T>,
+    lhs_trans: bool,
+    rhs: &CLPtr<T>,
+    rhs_trans: bool,
+    out: &mut CLPtr<T>,
+    accumulate: bool,
+) -> crate::Result<()>
+where
+    T: CDatatype + Number,
+{
+    check_gemm_lengths(m, k, n, lhs.len, rhs.len, out.len)?;
+
+    let src = format!(
+        "
+        {extensions}
+        #define TILE {GEMM_TILE}
+        __kernel void gemm(__global const {datatype}* lhs, __global const {datatype}* rhs, __global {datatype}* out, long m, long k, long n) {{
+            __local {datatype} lhsTile[TILE][TILE];
+            __local {datatype} rhsTile[TILE][TILE];
+
+            long localRow = get_local_id(1);
+            long localCol = get_local_id(0);
+            long row = get_global_id(1);
+            long col = get_global_id(0);
+
+            {acc_datatype} acc = 0;
+            for (long tile = 0; tile < k; tile += TILE) {{
+                long lhsCol = tile + localCol;
+                long rhsRow = tile + localRow;
+                lhsTile[localRow][localCol] = row < m && lhsCol < k ? lhs[{lhs_idx}] : 0;
+                rhsTile[localRow][localCol] = rhsRow < k && col < n ? rhs[{rhs_idx}] : 0;
+                barrier(CLK_LOCAL_MEM_FENCE);
+
+                for (long idx = 0; idx < TILE; idx++) {{
+                    acc += ({acc_datatype})lhsTile[localRow][idx] * ({acc_datatype})rhsTile[idx][localCol];
+                }}
+                barrier(CLK_LOCAL_MEM_FENCE);
+            }}
+
+            if (row < m && col < n) {{
+                out[row * n + col] = ({datatype})({prev}acc);
+            }}
+        }}
+    ",
+        extensions = cl_extensions::<T>(),
+        datatype = T::C_DTYPE_STR,
+        acc_datatype = cl_acc_dtype::<T>(),
+        lhs_idx = gemm_index_src("row", "lhsCol", "m", "k", lhs_trans),
+        rhs_idx = gemm_index_src("rhsRow", "col", "k", "n", rhs_trans),
+        prev = if accumulate { "out[row * n + col] + " } else { "" },
+    );
+
+    let groups = |len: usize| len.div_ceil(GEMM_TILE) * GEMM_TILE;
+    enqueue_kernel(
+        device,
+        &src,
+        [groups(n), groups(m), 0],
+        Some([GEMM_TILE, GEMM_TILE, 0]),
+        &[lhs, rhs, out, &m, &k, &n],
+    )?;
+    Ok(())
+}
+
 impl<T, S, Mods: OnDropBuffer + AddOperation + 'static> UnaryGrad<T, S> for OpenCL<Mods>
 where
     T: CDatatype + Number,
@@ -333,6 +850,7 @@ where
 {
     let src = format!(
         "
+        {extensions}
         __kernel void add_unary_grad(__global const {datatype}* lhs, __global {datatype}* lhs_grad, __global const {datatype}* out, long len) {{
             size_t id = get_global_id(0);
             if (id >= len) {{
@@ -341,6 +859,7 @@ where
             lhs_grad[id] += out[id] * {operation};
         }}
     ",
+        extensions = cl_extensions::<T>(),
         datatype = T::C_DTYPE_STR,
         operation = lhs_grad_fn("lhs[id]".to_marker()).to_cl_source()
     );
@@ -355,6 +874,71 @@ where
     Ok(())
 }
 
+#[cfg(feature = "autograd")]
+impl<Mods, T, S> crate::optim::ApplyUpdate<T, S> for OpenCL<Mods>
+where
+    Mods: OnDropBuffer,
+    T: CDatatype + Number,
+    S: Shape,
+{
+    fn apply_update(
+        &self,
+        rule: &crate::optim::UpdateRule<T>,
+        param: &mut Buffer<T, Self, S>,
+        grad: &Buffer<T, Self, S>,
+        states: &mut [Buffer<T, Self, S>],
+        hyper: &[T],
+    ) -> crate::Result<()> {
+        use super::AsClCvoidPtr;
+
+        let datatype = T::C_DTYPE_STR;
+        let state_params = (0..states.len())
+            .map(|idx| format!("__global {datatype}* state{idx}, "))
+            .collect::<String>();
+        let hyper_params = (0..rule.hyper())
+            .map(|idx| format!("{datatype} hp{idx}, "))
+            .collect::<String>();
+        let load_states = (0..states.len())
+            .map(|idx| format!("{datatype} s{idx} = state{idx}[id];\n"))
+            .collect::<String>();
+        let store_states = (0..states.len())
+            .map(|idx| format!("state{idx}[id] = s{idx};\n"))
+            .collect::<String>();
+
+        let src = format!(
+            "
+            {extensions}
+            __kernel void apply_update(__global {datatype}* param, __global const {datatype}* grad, {state_params}{hyper_params}long len) {{
+                size_t id = get_global_id(0);
+                if (id >= len) {{
+                    return;
+                }}
+                {datatype} p = param[id];
+                {datatype} g = grad[id];
+                {load_states}
+                {update}
+                param[id] = p;
+                {store_states}
+            }}
+        ",
+            extensions = cl_extensions::<T>(),
+            update = rule.to_c_src()
+        );
+
+        let len = param.len();
+        let mut args = vec![&*param as &dyn AsClCvoidPtr, grad];
+        args.extend(states.iter().map(|state| state as &dyn AsClCvoidPtr));
+        args.extend(
+            hyper[..rule.hyper()]
+                .iter()
+                .map(|hyper| hyper as &dyn AsClCvoidPtr),
+        );
+        args.push(&len);
+
+        self.launch_kernel(&src, [(len / 32 + 1) * 32, 0, 0], Some([32, 0, 0]), &args)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -374,6 +958,19 @@ mod test {
         Ok(())
     }
 
+    #[cfg(feature = "forward")]
+    #[test]
+    fn test_cl_apply_fn_tangent() -> crate::Result<()> {
+        let device = OpenCL::<crate::Forward<Base>>::new(chosen_cl_idx())?;
+
+        let x = Buffer::from((&device, [1f32, 2., 3.])).with_tangent(&[1., 1., 0.]);
+        let out = device.apply_fn(&x, |x| x.mul(x).add(x.mul(3f32)));
+        assert_eq!(out.read(), [4., 10., 18.]);
+        assert_eq!(out.tangent().read(), [5., 7., 0.]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_cl_add_unary_grad() -> crate::Result<()> {
         let device = OpenCL::<Base>::new(chosen_cl_idx())?;
@@ -0,0 +1,136 @@
+//! The recorder module provides the [`Recorder`] and [`GpuRecorder`] devices.
+//! Both use host memory and log every allocation, transfer and operation, including the sources generated for `apply_fn`.
+//! The [`RecordLog`] can be compared against golden files with [`RecordLog::assert_golden`].
+//! This allows testing operation sequences without real hardware.
+mod ops;
+mod record;
+mod recorder_device;
+mod recorder_ptr;
+
+pub use record::*;
+pub use recorder_device::*;
+pub use recorder_ptr::*;
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        exec_on_cpu::cpu_exec_unary, ApplyFunction, Base, Buffer, ClearBuf, Combiner, CopySlice,
+        Device, Retriever, WriteBuf,
+    };
+
+    use super::{GpuRecorder, Record, Recorder};
+
+    fn golden(name: &str) -> String {
+        format!(
+            "{}/src/devices/recorder/golden/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        )
+    }
+
+    #[test]
+    fn test_recorder_logs_ops() {
+        let device = Recorder::<Base>::new();
+
+        let mut buf: Buffer<f32, _> = device.buffer([1., 2., 3., 4.]);
+        device.write(&mut buf, &[4., 3., 2., 1.]);
+
+        let slice = device.copy_slice(&buf, 1..3);
+        assert_eq!(&**slice, [3., 2.]);
+
+        let out = device.apply_fn(&buf, |x| x.mul(2.).add(1.).exp());
+        let mut copy: Buffer<f32, _> = device.buffer([0.; 4]);
+        device.write_buf(&mut copy, &out);
+        device.clear(&mut copy);
+        assert_eq!(copy.read(), [0.; 4]);
+
+        device.log().assert_golden(golden("recorder_ops.log"));
+    }
+
+    #[test]
+    fn test_gpu_recorder_exec_on_cpu() {
+        let device = GpuRecorder::<Base>::new();
+
+        let buf: Buffer<i32, _> = device.buffer([1, 2, 3]);
+        let out = cpu_exec_unary(&device, &buf, |cpu, buf| {
+            let mut out = cpu.retrieve(buf.len(), ()).unwrap();
+            for (out, x) in out.iter_mut().zip(buf.iter()) {
+                *out = x * 3;
+            }
+            out
+        })
+        .unwrap();
+        assert_eq!(out.read(), [3, 6, 9]);
+
+        device
+            .log()
+            .assert_golden(golden("gpu_recorder_exec_on_cpu.log"));
+    }
+
+    #[test]
+    fn test_recorder_log_clear_keeps_ids() {
+        let device = GpuRecorder::<Base>::new();
+        let _a = Buffer::<u8, _>::new(&device, 2);
+        device.log().clear();
+
+        let _b = Buffer::<u8, _>::new(&device, 3);
+        assert_eq!(
+            *device.log().records(),
+            [Record::Alloc {
+                id: 1,
+                len: 3,
+                dtype: "u8"
+            }]
+        );
+    }
+
+    #[test]
+    fn test_recorder_golden_mismatch() {
+        let device = Recorder::<Base>::new();
+        let _buf = Buffer::<f32, _>::new(&device, 4);
+
+        let golden = std::fs::read_to_string(golden("recorder_ops.log")).unwrap();
+        assert!(device.log().check_golden(&golden).is_err());
+        assert!(device.log().check_golden("alloc #0 f32[4]\r\n").is_ok());
+    }
+
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_recorder_lazy_add_op() {
+        use crate::{Lazy, Run};
+
+        let device = Recorder::<Lazy<Base>>::new();
+        let buf: Buffer<f32, _> = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&buf, |x| x.sub(1.));
+
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [0., 1., 2.]);
+        assert!(device.log().records().contains(&Record::AddOp {
+            ids: vec![Some(1), Some(0)],
+            lazy: true
+        }));
+    }
+
+    #[cfg(feature = "fork")]
+    #[test]
+    fn test_recorder_fork_unified_mem() {
+        use crate::{Fork, UseGpuOrCpu};
+
+        let device = Recorder::<Fork<Base>>::new();
+        assert!(device.is_fork_enabled());
+
+        let mut buf: Buffer<f32, _> = device.buffer([1., 2., 3.]);
+        for _ in 0..3 {
+            let out = device.apply_fn(&buf, |x| x.neg());
+            assert_eq!(&**out, [-1., -2., -3.]);
+        }
+        device.clear(&mut buf);
+        assert_eq!(&**buf, [0.; 3]);
+
+        let device = GpuRecorder::<Fork<Base>>::new();
+        assert!(!device.is_fork_enabled());
+
+        let buf: Buffer<f32, _> = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&buf, |x| x.neg());
+        assert_eq!(out.read(), [-1., -2., -3.]);
+    }
+}
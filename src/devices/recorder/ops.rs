@@ -0,0 +1,200 @@
+use core::ops::{Range, RangeBounds};
+
+use crate::{
+    bounds_to_range, cpu_stack_ops::apply_fn_slice, AddOperation, ApplyFunction, Buffer, ClearBuf,
+    CopySlice, HasId, OnDropBuffer, Read, Resolve, Retrieve, Retriever, Shape, ToMarker, ToVal,
+    TwoWay, Unit, UseGpuOrCpu, WriteBuf,
+};
+
+use super::{GpuRecorder, Record, Recorder};
+
+macro_rules! impl_recorder_ops {
+    ($device:ident) => {
+        impl<Mods: OnDropBuffer, T: Unit + Clone, S: Shape> WriteBuf<T, S> for $device<Mods> {
+            #[inline]
+            fn write(&self, buf: &mut Buffer<T, Self, S>, data: &[T]) {
+                self.log().push(Record::Write {
+                    id: self.log().label(buf.id().id),
+                    len: data.len(),
+                });
+                buf.base_mut().as_mut_slice()[..data.len()].clone_from_slice(data)
+            }
+
+            #[inline]
+            fn write_buf(&self, dst: &mut Buffer<T, Self, S>, src: &Buffer<T, Self, S>) {
+                self.log().push(Record::WriteBuf {
+                    src: self.log().label(src.id().id),
+                    dst: self.log().label(dst.id().id),
+                    len: src.len(),
+                });
+                dst.base_mut().as_mut_slice()[..src.len()].clone_from_slice(src.base().as_slice())
+            }
+        }
+
+        impl<Mods: OnDropBuffer + UseGpuOrCpu, T: Unit + Default> ClearBuf<T> for $device<Mods> {
+            fn clear(&self, buf: &mut Buffer<T, Self>) {
+                self.log().push(Record::Clear {
+                    id: self.log().label(buf.id().id),
+                });
+
+                let host = buf.base_mut().as_mut_slice() as *mut [T];
+                let clear = || {
+                    for value in unsafe { &mut *host } {
+                        *value = T::default();
+                    }
+                };
+                self.use_cpu_or_gpu(
+                    (file!(), line!(), column!()).into(),
+                    &[],
+                    &[buf.len()],
+                    clear,
+                    clear,
+                );
+            }
+        }
+
+        impl<Mods: OnDropBuffer, T: Unit + Clone> CopySlice<T> for $device<Mods> {
+            fn copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+                &self,
+                source: &Buffer<T, Self>,
+                source_range: SR,
+                dest: &mut Buffer<T, Self>,
+                dest_range: DR,
+            ) {
+                let source_range = bounds_to_range(source_range, source.len());
+                let dest_range = bounds_to_range(dest_range, dest.len());
+
+                assert_eq!(
+                    source_range.end - source_range.start,
+                    dest_range.end - dest_range.start,
+                );
+
+                self.log().push(Record::CopySlice {
+                    src: self.log().label(source.id().id),
+                    src_range: source_range.clone(),
+                    dst: self.log().label(dest.id().id),
+                    dst_range: dest_range.clone(),
+                });
+
+                dest.base_mut().as_mut_slice()[dest_range]
+                    .clone_from_slice(&source.base().as_slice()[source_range]);
+            }
+
+            fn copy_slice_all<I: IntoIterator<Item = (Range<usize>, Range<usize>)>>(
+                &self,
+                source: &Buffer<T, Self>,
+                dest: &mut Buffer<T, Self>,
+                ranges: I,
+            ) {
+                for (source_range, dest_range) in ranges {
+                    self.copy_slice_to(source, source_range, dest, dest_range);
+                }
+            }
+        }
+
+        impl<Mods, T, S> ApplyFunction<T, S> for $device<Mods>
+        where
+            Mods: Retrieve<Self, T, S> + AddOperation + UseGpuOrCpu + 'static,
+            T: Unit + Copy + Default + ToVal + 'static,
+            S: Shape,
+        {
+            fn apply_fn<F>(
+                &self,
+                buf: &Buffer<T, Self, S>,
+                f: impl Fn(Resolve<T>) -> F + Copy + 'static,
+            ) -> Buffer<T, Self, S>
+            where
+                F: TwoWay<T> + 'static,
+            {
+                let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+                let marker = f("x".to_marker());
+                self.log().push(Record::ApplyFn {
+                    src: self.log().label(buf.id().id),
+                    out: self.log().label(out.id().id),
+                    cl_source: marker.to_cl_source(),
+                    wgsl_source: marker.to_wgsl_source(),
+                });
+
+                self.add_op((&mut out, buf), move |(out, buf)| {
+                    let x = buf.base().as_slice();
+                    let host_out = out.base_mut().as_mut_slice() as *mut [T];
+                    let apply = || apply_fn_slice(x, unsafe { &mut *host_out }, f);
+                    out.device().use_cpu_or_gpu(
+                        (file!(), line!(), column!()).into(),
+                        &[x.len()],
+                        &[x.len()],
+                        apply,
+                        apply,
+                    );
+                    Ok(())
+                })
+                .unwrap();
+
+                out
+            }
+        }
+    };
+}
+
+impl_recorder_ops!(Recorder);
+impl_recorder_ops!(GpuRecorder);
+
+impl<Mods: OnDropBuffer, T: Unit, S: Shape> Read<T, S> for Recorder<Mods> {
+    type Read<'a>
+        = &'a [T]
+    where
+        T: 'a,
+        Self: 'a,
+        S: 'a;
+
+    #[inline]
+    fn read<'a>(&self, buf: &'a Self::Base<T, S>) -> Self::Read<'a>
+    where
+        Self: 'a,
+    {
+        self.log().push(Record::Read {
+            id: self.log().label(buf.id().id),
+            len: buf.len(),
+        });
+        buf
+    }
+
+    #[inline]
+    fn read_to_vec(&self, buf: &Self::Base<T, S>) -> Vec<T>
+    where
+        T: Default + Clone,
+    {
+        Read::<T, S>::read(self, buf).to_vec()
+    }
+}
+
+impl<Mods: OnDropBuffer, T: Unit + Clone, S: Shape> Read<T, S> for GpuRecorder<Mods> {
+    type Read<'a>
+        = Vec<T>
+    where
+        T: 'a,
+        Self: 'a,
+        S: 'a;
+
+    #[inline]
+    fn read<'a>(&self, buf: &'a Self::Base<T, S>) -> Self::Read<'a>
+    where
+        Self: 'a,
+    {
+        let host = buf.as_slice();
+        self.log().push(Record::Read {
+            id: self.log().label(buf.id().id),
+            len: host.len(),
+        });
+        host.to_vec()
+    }
+
+    #[inline]
+    fn read_to_vec(&self, buf: &Self::Base<T, S>) -> Vec<T>
+    where
+        T: Default + Clone,
+    {
+        Read::<T, S>::read(self, buf)
+    }
+}
@@ -0,0 +1,186 @@
+use core::{
+    cell::{Cell, Ref, RefCell},
+    fmt::{self, Display},
+    ops::Range,
+};
+use std::{collections::HashMap, path::Path};
+
+/// A single device call logged by a [`Recorder`](super::Recorder) or [`GpuRecorder`](super::GpuRecorder).
+/// Buffers are referred to by labels (`#0`, `#1`, ...) in order of their first appearance, which are stable across runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Record {
+    Alloc {
+        id: u64,
+        len: usize,
+        dtype: &'static str,
+    },
+    Read {
+        id: u64,
+        len: usize,
+    },
+    Write {
+        id: u64,
+        len: usize,
+    },
+    WriteBuf {
+        src: u64,
+        dst: u64,
+        len: usize,
+    },
+    Clear {
+        id: u64,
+    },
+    CopySlice {
+        src: u64,
+        src_range: Range<usize>,
+        dst: u64,
+        dst_range: Range<usize>,
+    },
+    ApplyFn {
+        src: u64,
+        out: u64,
+        cl_source: String,
+        wgsl_source: String,
+    },
+    /// An operation was passed to [`AddOperation::add_op`](crate::AddOperation::add_op).
+    /// `None` is used for arguments without an id.
+    AddOp {
+        ids: Vec<Option<u64>>,
+        lazy: bool,
+    },
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Record::Alloc { id, len, dtype } => write!(f, "alloc #{id} {dtype}[{len}]"),
+            Record::Read { id, len } => write!(f, "read #{id} len={len}"),
+            Record::Write { id, len } => write!(f, "write #{id} len={len}"),
+            Record::WriteBuf { src, dst, len } => write!(f, "write_buf #{src} -> #{dst} len={len}"),
+            Record::Clear { id } => write!(f, "clear #{id}"),
+            Record::CopySlice {
+                src,
+                src_range,
+                dst,
+                dst_range,
+            } => write!(
+                f,
+                "copy_slice #{src}[{src_range:?}] -> #{dst}[{dst_range:?}]"
+            ),
+            Record::ApplyFn {
+                src,
+                out,
+                cl_source,
+                wgsl_source,
+            } => write!(
+                f,
+                "apply_fn #{src} -> #{out} cl=`{cl_source}` wgsl=`{wgsl_source}`"
+            ),
+            Record::AddOp { ids, lazy } => {
+                write!(f, "add_op")?;
+                for id in ids {
+                    match id {
+                        Some(id) => write!(f, " #{id}")?,
+                        None => write!(f, " _")?,
+                    }
+                }
+                if *lazy {
+                    write!(f, " (lazy)")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The log of a recording device. Its [`Display`] output contains one [`Record`] per line.
+#[derive(Debug, Default)]
+pub struct RecordLog {
+    records: RefCell<Vec<Record>>,
+    labels: RefCell<HashMap<u64, u64>>,
+    next_label: Cell<u64>,
+}
+
+impl RecordLog {
+    #[inline]
+    pub fn push(&self, record: Record) {
+        self.records.borrow_mut().push(record)
+    }
+
+    #[inline]
+    pub fn records(&self) -> Ref<'_, Vec<Record>> {
+        self.records.borrow()
+    }
+
+    /// Removes all records. Labels of buffers are not reset.
+    #[inline]
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear()
+    }
+
+    /// Assigns a new label to the buffer [`Id`](crate::Id) `id`, e.g. after an allocation reused a freed address.
+    pub(crate) fn register(&self, id: u64) -> u64 {
+        let label = self.next_label.get();
+        self.next_label.set(label + 1);
+        self.labels.borrow_mut().insert(id, label);
+        label
+    }
+
+    /// Returns the label of the buffer [`Id`](crate::Id) `id`.
+    /// Buffers that were not allocated by the device yet (e.g. by the [`Lazy`](crate::Lazy) module) receive a new label.
+    pub(crate) fn label(&self, id: u64) -> u64 {
+        let label = self.labels.borrow().get(&id).copied();
+        label.unwrap_or_else(|| self.register(id))
+    }
+
+    /// Compares the log against the golden file at `path`.
+    /// If the environment variable `CUSTOS_UPDATE_GOLDEN` is set, the file is (re)written instead.
+    ///
+    /// # Panics
+    /// If the file cannot be read or written, or the log differs from the file.
+    #[track_caller]
+    pub fn assert_golden(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+
+        if std::env::var_os("CUSTOS_UPDATE_GOLDEN").is_some() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).unwrap();
+            }
+            std::fs::write(path, self.to_string()).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(path).unwrap_or_else(|err| {
+            panic!(
+                "Cannot read golden file {}: {err}. Set CUSTOS_UPDATE_GOLDEN=1 to create it.",
+                path.display()
+            )
+        });
+
+        if let Err(actual) = self.check_golden(&expected) {
+            panic!(
+                "Recorded log differs from golden file {}.\n--- expected\n{expected}\n--- actual\n{actual}",
+                path.display(),
+            );
+        }
+    }
+
+    /// Returns the log as error if it differs from `expected`. Line endings are ignored.
+    pub(crate) fn check_golden(&self, expected: &str) -> Result<(), String> {
+        let actual = self.to_string();
+        if expected.lines().map(str::trim_end).eq(actual.lines()) {
+            Ok(())
+        } else {
+            Err(actual)
+        }
+    }
+}
+
+impl Display for RecordLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for record in self.records.borrow().iter() {
+            writeln!(f, "{record}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{cpu::CPUPtr, Base, Buffer, Device, HasId, Shape, WrappedData};
+
+use super::{GpuRecorderPtr, Record, RecordLog};
+
+macro_rules! impl_recorder_device {
+    ($device:ident, $ptr:ident, $unified:literal) => {
+        $crate::impl_device_traits!($device);
+        $crate::pass_down_use_gpu_or_cpu!($device);
+        $crate::pass_down_exec_now!($device);
+
+        impl<Mods: $crate::OnDropBuffer> $crate::Device for $device<Mods> {
+            type Base<T: $crate::Unit, S: $crate::Shape> = $ptr<T>;
+            type Data<T: $crate::Unit, S: $crate::Shape> = Self::Wrap<T, Self::Base<T, S>>;
+
+            type Error = core::convert::Infallible;
+
+            #[inline]
+            fn base_to_data<T: $crate::Unit, S: $crate::Shape>(
+                &self,
+                base: Self::Base<T, S>,
+            ) -> Self::Data<T, S> {
+                self.wrap_in_base(base)
+            }
+
+            #[inline]
+            fn wrap_to_data<T: $crate::Unit, S: $crate::Shape>(
+                &self,
+                wrap: Self::Wrap<T, Self::Base<T, S>>,
+            ) -> Self::Data<T, S> {
+                wrap
+            }
+
+            #[inline]
+            fn data_as_wrap<T: $crate::Unit, S: $crate::Shape>(
+                data: &Self::Data<T, S>,
+            ) -> &Self::Wrap<T, Self::Base<T, S>> {
+                data
+            }
+
+            #[inline]
+            fn data_as_wrap_mut<T: $crate::Unit, S: $crate::Shape>(
+                data: &mut Self::Data<T, S>,
+            ) -> &mut Self::Wrap<T, Self::Base<T, S>> {
+                data
+            }
+        }
+
+        impl<Mods> $crate::HasModules for $device<Mods> {
+            type Mods = Mods;
+
+            #[inline]
+            fn modules(&self) -> &Mods {
+                &self.modules
+            }
+        }
+
+        unsafe impl<Mods: $crate::OnDropBuffer> $crate::IsShapeIndep for $device<Mods> {}
+
+        impl<SimpleMods> $device<SimpleMods> {
+            #[inline]
+            pub fn new<'a, NewMods>() -> $device<NewMods>
+            where
+                Self: 'a,
+                SimpleMods: $crate::Module<'a, $device, Module = NewMods>,
+                NewMods: $crate::Setup<$device<NewMods>>,
+            {
+                let mut device = $device {
+                    modules: SimpleMods::new(),
+                    log: RecordLog::default(),
+                };
+                NewMods::setup(&mut device).unwrap();
+                device
+            }
+        }
+
+        impl<Mods> $device<Mods> {
+            /// Returns the log of all recorded device calls.
+            #[inline]
+            pub fn log(&self) -> &RecordLog {
+                &self.log
+            }
+        }
+
+        impl<T: $crate::Unit, Mods: $crate::OnDropBuffer> $crate::Alloc<T> for $device<Mods> {
+            fn alloc<S: $crate::Shape>(
+                &self,
+                mut len: usize,
+                flag: $crate::flag::AllocFlag,
+            ) -> $crate::Result<Self::Base<T, S>> {
+                if len == 0 {
+                    return Err($crate::DeviceError::ZeroLengthBuffer.into());
+                }
+
+                if S::LEN > len {
+                    len = S::LEN
+                }
+
+                let ptr = CPUPtr::new_initialized(len, flag);
+                self.log.push(Record::Alloc {
+                    id: self.log.register(ptr.ptr as u64),
+                    len,
+                    dtype: core::any::type_name::<T>(),
+                });
+                Ok(ptr.into())
+            }
+
+            fn alloc_from_slice<S: $crate::Shape>(
+                &self,
+                data: &[T],
+            ) -> $crate::Result<Self::Base<T, S>>
+            where
+                T: Clone,
+            {
+                if data.is_empty() {
+                    return Err($crate::DeviceError::ZeroLengthBuffer.into());
+                }
+                if !(S::LEN == data.len() || S::LEN == 0) {
+                    return Err($crate::DeviceError::ShapeLengthMismatch.into());
+                }
+
+                let mut ptr = self.alloc::<S>(data.len(), $crate::flag::AllocFlag::None)?;
+                ptr.as_mut_slice().clone_from_slice(data);
+                self.log.push(Record::Write {
+                    id: self.log.label(ptr.id().id),
+                    len: data.len(),
+                });
+                Ok(ptr)
+            }
+        }
+
+        impl<Mods: $crate::SetOpHint<T>, T> $crate::SetOpHint<T> for $device<Mods> {
+            #[inline]
+            fn set_op_hint(&self, op_hint: $crate::op_hint::OpHint<T>) {
+                self.modules.set_op_hint(op_hint)
+            }
+        }
+
+        impl<Mods: $crate::AddOperation> $crate::AddOperation for $device<Mods> {
+            fn add_op<Args: $crate::Parents<N> + $crate::AnyOp, const N: usize>(
+                &self,
+                args: Args,
+                op: impl for<'b> Fn(Args::Replicated<'b>) -> $crate::Result<()> + 'static,
+            ) -> $crate::Result<()> {
+                self.log.push(Record::AddOp {
+                    ids: args
+                        .maybe_ids()
+                        .iter()
+                        .map(|id| id.map(|id| self.log.label(id.id)))
+                        .collect(),
+                    lazy: self.is_lazy_enabled(),
+                });
+                self.modules.add_op(args, op)
+            }
+
+            unsafe fn add_parallel_op<Args: $crate::Parents<N> + $crate::AnyOp, const N: usize>(
+                &self,
+                args: Args,
+                op: impl for<'b> Fn(Args::Replicated<'b>) -> $crate::Result<()> + Send + Sync + 'static,
+            ) -> $crate::Result<()> {
+                self.log.push(Record::AddOp {
+                    ids: args
+                        .maybe_ids()
+                        .iter()
+                        .map(|id| id.map(|id| self.log.label(id.id)))
+                        .collect(),
+                    lazy: self.is_lazy_enabled(),
+                });
+                self.modules.add_parallel_op(args, op)
+            }
+
+            #[inline]
+            fn ops_count(&self) -> usize {
+                self.modules.ops_count()
+            }
+
+            #[inline]
+            fn set_lazy_enabled(&self, enabled: bool) {
+                self.modules.set_lazy_enabled(enabled)
+            }
+
+            #[inline]
+            fn is_lazy_enabled(&self) -> bool {
+                self.modules.is_lazy_enabled()
+            }
+        }
+
+        impl<Mods: $crate::RunModule<Self>> $crate::Run for $device<Mods> {
+            #[inline]
+            fn run(&self) -> $crate::Result<()> {
+                self.modules.run(self)
+            }
+        }
+
+        #[cfg(feature = "lazy")]
+        impl<Mods> $crate::LazySetup for $device<Mods> {}
+
+        #[cfg(feature = "lazy")]
+        impl<Mods> $crate::LazyRun for $device<Mods> {}
+
+        #[cfg(feature = "lazy")]
+        impl<Mods: $crate::OnDropBuffer + 'static> $crate::RunCaptured for $device<Mods> {}
+
+        #[cfg(feature = "fork")]
+        impl<Mods> $crate::ForkSetup for $device<Mods> {
+            #[inline]
+            fn has_unified_mem(&self) -> bool {
+                $unified
+            }
+        }
+    };
+}
+
+/// A device that records every allocation, transfer and operation in a [`RecordLog`].
+/// The data lives in host memory and [`Buffer`](crate::Buffer)s dereference to slices, like on a GPU with unified memory.
+/// Therefore, the [`Fork`](crate::Fork) module is enabled on this device.
+///
+/// # Example
+/// ```
+/// use custos::{ApplyFunction, Base, Buffer, Combiner, Device, Recorder};
+///
+/// let device = Recorder::<Base>::new();
+/// let buf: Buffer<f32, _> = device.buffer([1., 2., 3.]);
+/// let out = device.apply_fn(&buf, |x| x.mul(2.));
+/// assert_eq!(out.read(), [2., 4., 6.]);
+///
+/// assert_eq!(
+///     device.log().to_string(),
+///     "alloc #0 f32[3]\nwrite #0 len=3\nalloc #1 f32[3]\n\
+///      apply_fn #0 -> #1 cl=`(x * 2.0)` wgsl=`(x * f32(2.0))`\nadd_op #1 #0\nread #1 len=3\n"
+/// );
+/// ```
+pub struct Recorder<Mods = Base> {
+    pub modules: Mods,
+    log: RecordLog,
+}
+
+impl_recorder_device!(Recorder, CPUPtr, true);
+
+/// Like a [`Recorder`], but imitates a GPU without unified memory:
+/// [`Buffer`](crate::Buffer)s do not dereference to host slices and the [`Fork`](crate::Fork) module is disabled.
+/// Host code has to go through [`Read`](crate::Read) and [`WriteBuf`](crate::WriteBuf),
+/// e.g. by using the functions of [`exec_on_cpu`](crate::exec_on_cpu).
+pub struct GpuRecorder<Mods = Base> {
+    pub modules: Mods,
+    log: RecordLog,
+}
+
+impl_recorder_device!(GpuRecorder, GpuRecorderPtr, false);
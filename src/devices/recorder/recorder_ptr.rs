@@ -0,0 +1,70 @@
+use crate::{cpu::CPUPtr, flag::AllocFlag, HasId, Id, PtrType, ShallowCopy, WrappedCopy};
+
+/// The pointer used for [`GpuRecorder`](super::GpuRecorder) [`Buffer`](crate::Buffer)s.
+/// The data lives in host memory, but unlike a [`CPUPtr`], it does not dereference to a slice,
+/// like the pointer of a GPU without unified memory.
+#[derive(Debug)]
+pub struct GpuRecorderPtr<T> {
+    pub(crate) ptr: CPUPtr<T>,
+}
+
+impl<T> GpuRecorderPtr<T> {
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[T] {
+        self.ptr.as_slice()
+    }
+
+    #[inline]
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [T] {
+        self.ptr.as_mut_slice()
+    }
+}
+
+impl<T> From<CPUPtr<T>> for GpuRecorderPtr<T> {
+    #[inline]
+    fn from(ptr: CPUPtr<T>) -> Self {
+        GpuRecorderPtr { ptr }
+    }
+}
+
+impl<T> HasId for GpuRecorderPtr<T> {
+    #[inline]
+    fn id(&self) -> Id {
+        self.ptr.id()
+    }
+}
+
+impl<T> PtrType for GpuRecorderPtr<T> {
+    #[inline]
+    fn size(&self) -> usize {
+        self.ptr.len
+    }
+
+    #[inline]
+    fn flag(&self) -> AllocFlag {
+        self.ptr.flag
+    }
+
+    #[inline]
+    unsafe fn set_flag(&mut self, flag: AllocFlag) {
+        self.ptr.flag = flag
+    }
+}
+
+impl<T> ShallowCopy for GpuRecorderPtr<T> {
+    #[inline]
+    unsafe fn shallow(&self) -> Self {
+        GpuRecorderPtr {
+            ptr: self.ptr.shallow(),
+        }
+    }
+}
+
+impl<T> WrappedCopy for GpuRecorderPtr<T> {
+    type Base = Self;
+
+    #[inline]
+    fn wrapped_copy(&self, to_wrap: Self::Base) -> Self {
+        to_wrap
+    }
+}
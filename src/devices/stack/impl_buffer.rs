@@ -1,29 +1,33 @@
 use super::stack_device::Stack;
-use crate::{Buffer, IsConstDim, StackArray, Unit};
+use crate::{Buffer, IsConstDim, OnDropBuffer, OnNewBuffer, StackArray, Unit};
 
-impl<'a, T: Unit + Copy + Default, S: IsConstDim, const N: usize> From<(&'a Stack, [T; N])>
-    for Buffer<'a, T, Stack, S>
+impl<'a, T, Mods, S, const N: usize> From<(&'a Stack<Mods>, [T; N])>
+    for Buffer<'a, T, Stack<Mods>, S>
+where
+    T: Unit + Copy + Default,
+    Mods: OnDropBuffer + Default,
+    Stack<Mods>: OnNewBuffer<'a, T, Stack<Mods>, S>,
+    S: IsConstDim,
 {
-    fn from((dev, array): (&'a Stack, [T; N])) -> Self {
+    fn from((dev, array): (&'a Stack<Mods>, [T; N])) -> Self {
         let mut data = StackArray::new();
         data.copy_from_slice(&array);
-        Buffer {
-            data,
-            device: Some(dev),
-        }
+        Buffer::from_new_alloc(dev, data)
     }
 }
 
-impl<'a, T: Unit + Copy + Default, S: IsConstDim, const N: usize> From<(&'a Stack, &[T; N])>
-    for Buffer<'a, T, Stack, S>
+impl<'a, T, Mods, S, const N: usize> From<(&'a Stack<Mods>, &[T; N])>
+    for Buffer<'a, T, Stack<Mods>, S>
+where
+    T: Unit + Copy + Default,
+    Mods: OnDropBuffer + Default,
+    Stack<Mods>: OnNewBuffer<'a, T, Stack<Mods>, S>,
+    S: IsConstDim,
 {
-    fn from((dev, array): (&'a Stack, &[T; N])) -> Self {
+    fn from((dev, array): (&'a Stack<Mods>, &[T; N])) -> Self {
         let mut data = StackArray::new();
         data.copy_from_slice(array);
-        Buffer {
-            data,
-            device: Some(dev),
-        }
+        Buffer::from_new_alloc(dev, data)
     }
 }
 
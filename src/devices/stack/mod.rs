@@ -3,18 +3,23 @@
 mod impl_buffer;
 mod stack_device;
 
-use core::ops::{AddAssign, Deref, DerefMut};
+use core::ops::{AddAssign, Deref, DerefMut, Index, Range, RangeBounds};
 
 pub use stack_device::*;
 
+#[cfg(feature = "std")]
+use crate::op_hint::unary;
 use crate::{
-    cpu_stack_ops::clear_slice, ApplyFunction, Buffer, ClearBuf, Device, Eval, MayToCLSource,
-    OnDropBuffer, Resolve, Retrieve, Retriever, Shape, ToVal, UnaryGrad, Unit, ZeroGrad,
+    bounds_to_range,
+    cpu_stack_ops::{apply_fn_slice, clear_slice, scale_slice, squared_norm_slice},
+    AddOperation, ApplyFunction, Buffer, ClearBuf, CopySlice, Device, Eval, MayToCLSource,
+    OnDropBuffer, Resolve, Retrieve, Retriever, SetOpHint, Shape, ToVal, TwoWay, UnaryGrad, Unit,
+    ZeroGrad,
 };
 
-// #[impl_stack]
-impl<T, D, S> ClearBuf<T, S, D> for Stack
+impl<Mods, T, D, S> ClearBuf<T, S, D> for Stack<Mods>
 where
+    Mods: OnDropBuffer + Default,
     T: Unit + Default,
     D: Device,
     D::Base<T, S>: DerefMut<Target = [T]>,
@@ -29,29 +34,95 @@ where
 impl<Mods, T> ZeroGrad<T> for Stack<Mods>
 where
     T: Unit + Default,
-    Mods: OnDropBuffer,
+    Mods: OnDropBuffer + Default,
 {
     #[inline]
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>) {
         clear_slice(data)
     }
+
+    #[inline]
+    fn grad_squared_norm<S: Shape>(&self, data: &Self::Base<T, S>) -> crate::Result<f64>
+    where
+        T: 'static,
+    {
+        squared_norm_slice(data).ok_or_else(|| crate::DeviceError::GradOpUnsupported.into())
+    }
+
+    #[inline]
+    fn scale_grad<S: Shape>(&self, data: &mut Self::Base<T, S>, factor: f64) -> crate::Result<()>
+    where
+        T: 'static,
+    {
+        scale_slice(data, factor).ok_or_else(|| crate::DeviceError::GradOpUnsupported.into())
+    }
+}
+
+impl<Mods, T, D, S> CopySlice<T, D, S> for Stack<Mods>
+where
+    [T]: Index<Range<usize>, Output = [T]>,
+    Mods: OnDropBuffer + Default,
+    T: Unit + Copy + Default,
+    D: Device,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+        &self,
+        source: &Buffer<T, D, S>,
+        source_range: SR,
+        dest: &mut Buffer<T, Self, S>,
+        dest_range: DR,
+    ) {
+        let source_range = bounds_to_range(source_range, source.len());
+        let dest_range = bounds_to_range(dest_range, dest.len());
+
+        assert_eq!(
+            source_range.end - source_range.start,
+            dest_range.end - dest_range.start,
+        );
+
+        dest[dest_range].copy_from_slice(&source[source_range]);
+    }
+
+    fn copy_slice_all<I: IntoIterator<Item = (Range<usize>, Range<usize>)>>(
+        &self,
+        source: &Buffer<T, D, S>,
+        dest: &mut Buffer<T, Self, S>,
+        ranges: I,
+    ) {
+        for (source_range, dest_range) in ranges {
+            self.copy_slice_to(source, source_range, dest, dest_range);
+        }
+    }
 }
 
 impl<Mods, T, D, S> ApplyFunction<T, S, D> for Stack<Mods>
 where
-    Mods: Retrieve<Self, T, S>,
+    Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + Default + 'static,
     T: Unit + Copy + Default + ToVal + 'static,
-    D: Device,
+    D: Device + 'static,
     D::Base<T, S>: Deref<Target = [T]>,
     S: Shape,
 {
-    fn apply_fn<F>(&self, buf: &Buffer<T, D, S>, f: impl Fn(Resolve<T>) -> F) -> Buffer<T, Self, S>
+    fn apply_fn<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
     where
-        F: Eval<T> + MayToCLSource,
+        F: TwoWay<T> + 'static,
     {
         let mut out = self.retrieve(buf.len(), buf).unwrap();
 
-        crate::cpu_stack_ops::apply_fn_slice(buf, &mut out, f);
+        self.add_op((&mut out, buf), move |(out, buf)| {
+            apply_fn_slice(buf, out, f);
+            Ok(())
+        })
+        .unwrap();
+
+        #[cfg(feature = "std")]
+        self.set_op_hint(unary(f));
 
         out
     }
@@ -59,10 +130,10 @@ where
 
 impl<Mods, T, D, S> UnaryGrad<T, S, D> for Stack<Mods>
 where
-    Mods: OnDropBuffer,
-    T: Unit + AddAssign + Copy + core::ops::Mul<Output = T>,
+    Mods: AddOperation + OnDropBuffer + Default,
+    T: Unit + AddAssign + Copy + core::ops::Mul<Output = T> + 'static,
     S: Shape,
-    D: Device,
+    D: Device + 'static,
     D::Base<T, S>: Deref<Target = [T]> + DerefMut,
 {
     #[inline]
@@ -71,11 +142,15 @@ where
         lhs: &Buffer<T, D, S>,
         lhs_grad: &mut Buffer<T, D, S>,
         out: &Buffer<T, D, S>,
-        lhs_grad_fn: impl Fn(Resolve<T>) -> F,
+        lhs_grad_fn: impl Fn(Resolve<T>) -> F + Copy + 'static,
     ) where
         F: Eval<T> + MayToCLSource,
     {
-        crate::cpu_stack_ops::add_unary_grad(lhs, out, lhs_grad, lhs_grad_fn)
+        self.add_op::<_, 3>((lhs, lhs_grad, out), move |(lhs, lhs_grad, out)| {
+            crate::cpu_stack_ops::add_unary_grad(lhs, out, lhs_grad, lhs_grad_fn);
+            Ok(())
+        })
+        .unwrap();
     }
 }
 
@@ -1,16 +1,20 @@
 use core::convert::Infallible;
 
 use crate::{
-    flag::AllocFlag, impl_buffer_hook_traits, impl_retriever, impl_wrapped_data,
-    pass_down_add_operation, pass_down_cursor, pass_down_grad_fn, pass_down_tape_actions,
-    pass_down_use_gpu_or_cpu, shape::Shape, Alloc, Base, Buffer, CloneBuf, Device, DeviceError,
-    DevicelessAble, OnDropBuffer, Read, StackArray, Unit, WrappedData, WriteBuf,
+    flag::AllocFlag, impl_buffer_hook_traits, impl_wrapped_data, pass_down_add_operation,
+    pass_down_cached_buffers, pass_down_cursor, pass_down_exec_now, pass_down_grad_fn,
+    pass_down_tape_actions, pass_down_use_gpu_or_cpu, shape::Shape, Alloc, Base, Buffer, CloneBuf,
+    Device, DeviceError, DevicelessAble, HasModules, OnDropBuffer, OnNewBuffer, Parents, PtrType,
+    Read, ReplaceBuf, Retrieve, Retriever, StackArray, Unit, WrappedData, WriteBuf,
 };
 
 /// A device that allocates memory on the stack.
+///
+/// A stack device with modules is created via [`Device::new`] or [`Default::default`].
+/// Stack arrays are copied by value, hence modules that keep copies of buffers, e.g. [`Autograd`](crate::Autograd) or [`Lazy`](crate::Lazy), require [`IsShapeIndep`](crate::IsShapeIndep) devices and are not available.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct Stack<Mods = Base> {
-    modules: Mods,
+    pub modules: Mods,
 }
 
 impl Stack {
@@ -20,25 +24,82 @@ impl Stack {
 }
 
 impl_buffer_hook_traits!(Stack);
-impl_retriever!(Stack, Copy + Default);
 impl_wrapped_data!(Stack);
 pass_down_cursor!(Stack);
 pass_down_grad_fn!(Stack);
 pass_down_tape_actions!(Stack);
+crate::pass_down_tangent_actions!(Stack);
+crate::pass_down_thread_pool_actions!(Stack);
 pass_down_use_gpu_or_cpu!(Stack);
 #[cfg(feature = "graph")]
 crate::pass_down_optimize_mem_graph!(Stack);
 pass_down_add_operation!(Stack);
+pass_down_cached_buffers!(Stack);
+pass_down_exec_now!(Stack);
+
+impl<T, Mods, S> Retriever<T, S> for Stack<Mods>
+where
+    T: Unit + Copy + Default,
+    Mods: Retrieve<Self, T, S> + Default,
+    S: Shape,
+{
+    #[inline]
+    fn retrieve<const NUM_PARENTS: usize>(
+        &self,
+        len: usize,
+        parents: impl Parents<NUM_PARENTS>,
+    ) -> crate::Result<Buffer<T, Self, S>> {
+        let data = unsafe { self.modules.retrieve::<NUM_PARENTS>(self, len, &parents)? };
+        let mut buf = Buffer {
+            data,
+            device: Some(self),
+        };
+        self.modules.on_retrieve_finish(&parents, &mut buf);
+        Ok(buf)
+    }
+}
+
+impl<T, S, Mods> ReplaceBuf<T, Self, S> for Stack<Mods>
+where
+    T: Unit,
+    S: Shape,
+    Mods: ReplaceBuf<T, Self, S> + Default,
+{
+    #[inline]
+    fn replace_buf<'a, 'c>(
+        &'c self,
+        buffer: &'c Buffer<'a, T, Self, S>,
+    ) -> &'c Buffer<'a, T, Self, S> {
+        self.modules.replace_buf(buffer)
+    }
+}
+
+impl<Mods> HasModules for Stack<Mods> {
+    type Mods = Mods;
+
+    #[inline]
+    fn modules(&self) -> &Mods {
+        &self.modules
+    }
+}
+
+impl<Mods: crate::RunModule<Self>> crate::Run for Stack<Mods> {
+    #[inline]
+    fn run(&self) -> crate::Result<()> {
+        self.modules.run(self)
+    }
+}
 
 impl<'a, T: Unit + Copy + Default, S: Shape> DevicelessAble<'a, T, S> for Stack {}
 
-impl<Mods: OnDropBuffer> Device for Stack<Mods> {
+impl<Mods: OnDropBuffer + Default> Device for Stack<Mods> {
     type Data<U: Unit, S: Shape> = Self::Wrap<U, Self::Base<U, S>>;
     type Base<T: Unit, S: Shape> = StackArray<S, T>;
     type Error = Infallible;
 
+    #[inline]
     fn new() -> Result<Self, Infallible> {
-        todo!()
+        Ok(Stack::default())
     }
 
     #[inline]
@@ -69,10 +130,12 @@ impl<Mods: OnDropBuffer> Device for Stack<Mods> {
     }
 }
 
-impl<Mods: OnDropBuffer, T: Unit + Copy + Default> Alloc<T> for Stack<Mods> {
+impl<Mods: OnDropBuffer + Default, T: Unit + Copy + Default> Alloc<T> for Stack<Mods> {
     #[inline]
-    fn alloc<S: Shape>(&self, _len: usize, _flag: AllocFlag) -> crate::Result<StackArray<S, T>> {
-        Ok(StackArray::new())
+    fn alloc<S: Shape>(&self, _len: usize, flag: AllocFlag) -> crate::Result<StackArray<S, T>> {
+        let mut array = StackArray::new();
+        unsafe { array.set_flag(flag) };
+        Ok(array)
     }
 
     #[inline]
@@ -98,20 +161,23 @@ impl<Mods: OnDropBuffer, T: Unit + Copy + Default> Alloc<T> for Stack<Mods> {
     }
 }
 
-impl<T: Unit + Copy, S: Shape> Read<T, S> for Stack
+impl<Mods, T, S> Read<T, S> for Stack<Mods>
 where
+    Mods: OnDropBuffer + Default,
+    T: Unit + Copy,
+    S: Shape,
     S::ARR<T>: Copy,
 {
     type Read<'a> = S::ARR<T>
     where
         T: 'a,
-        Stack: 'a,
+        Self: 'a,
         S: 'a;
 
     #[inline]
     fn read<'a>(&self, buf: &'a Self::Base<T, S>) -> Self::Read<'a>
     where
-        Stack: 'a,
+        Self: 'a,
     {
         buf.array
     }
@@ -126,20 +192,26 @@ where
     }
 }
 
-impl<'a, T: Unit, S: Shape> CloneBuf<'a, T, S> for Stack
+impl<'a, Mods, T, S> CloneBuf<'a, T, S> for Stack<Mods>
 where
-    <Stack as Device>::Data<T, S>: Copy,
+    Mods: OnDropBuffer + OnNewBuffer<'a, T, Self, S> + Default,
+    T: Unit + Copy + Default,
+    S: Shape,
 {
     #[inline]
     fn clone_buf(&'a self, buf: &Buffer<'a, T, Self, S>) -> Buffer<'a, T, Self, S> {
-        Buffer {
-            data: buf.data,
-            device: Some(self),
-        }
+        let mut cloned = Buffer::new(self, buf.len());
+        cloned.copy_from_slice(buf);
+        cloned
     }
 }
 
-impl<T: Unit + Copy, S: Shape> WriteBuf<T, S> for Stack {
+impl<Mods, T, S> WriteBuf<T, S> for Stack<Mods>
+where
+    Mods: OnDropBuffer + Default,
+    T: Unit + Copy,
+    S: Shape,
+{
     #[inline]
     fn write(&self, buf: &mut Buffer<T, Self, S>, data: &[T]) {
         buf.copy_from_slice(data)
@@ -170,4 +242,32 @@ mod tests {
 
         assert_eq!(buf.read_to_vec(), [3., 2., 1., 4., 7., 1.]);
     }
+
+    #[test]
+    fn test_stack_device_new() {
+        use crate::{Buffer, Device, Dim1, Stack};
+
+        let dev = <Stack as Device>::new().unwrap();
+        let buf = Buffer::<i32, Stack, Dim1<3>>::from((&dev, [1, 2, 3]));
+        assert_eq!(buf.read(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_stack_copy_slice_clone_clear_write() {
+        use crate::{Buffer, ClearBuf, CloneBuf, CopySlice, Dim1, Stack, WriteBuf};
+
+        let dev = Stack::new();
+        let src = Buffer::<i32, Stack, Dim1<4>>::from((&dev, [1, 2, 3, 4]));
+        let mut dst = Buffer::<i32, Stack, Dim1<4>>::from((&dev, [0; 4]));
+
+        dev.copy_slice_to(&src, 1..3, &mut dst, 2..);
+        assert_eq!(dst.read(), [0, 0, 2, 3]);
+
+        let mut cloned = dev.clone_buf(&dst);
+        dev.write(&mut dst, &[5, 6, 7, 8]);
+        assert_eq!(cloned.read(), [0, 0, 2, 3]);
+
+        dev.clear(&mut cloned);
+        assert_eq!(cloned.read(), [0; 4]);
+    }
 }
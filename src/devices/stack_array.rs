@@ -1,12 +1,31 @@
-use core::ops::{Deref, DerefMut};
+use core::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use crate::{shape::Shape, HasId, HostPtr, PtrType, ShallowCopy, WrappedCopy};
+use crate::{flag::AllocFlag, shape::Shape, HasId, HostPtr, PtrType, ShallowCopy, UniqueId, WrappedCopy};
+
+/// Pointer based ids, ids of lazily allocated buffers and detached ids do not reach this range.
+const STACK_ID_OFFSET: UniqueId = 1 << 62;
+
+static STACK_IDS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new id for a [`StackArray`].
+#[inline]
+fn next_stack_id() -> UniqueId {
+    STACK_ID_OFFSET + STACK_IDS.fetch_add(1, Ordering::Relaxed) as UniqueId
+}
 
 /// A possibly multi-dimensional array allocated on the stack.
 /// It uses `S:`[`Shape`] to get the type of the array.
+///
+/// The array moves together with its [`Buffer`](crate::Buffer), hence the id is not derived from its address.
+/// Copies share the id of the original array.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct StackArray<S: Shape, T> {
     pub(crate) array: S::ARR<T>,
+    id: UniqueId,
+    flag: AllocFlag,
 }
 
 impl<S: Shape, T: Default + Copy> StackArray<S, T> {
@@ -19,7 +38,11 @@ impl<S: Shape, T: Default + Copy> StackArray<S, T> {
                 "The size (N) of a stack allocated buffer must be greater than 0."
             )
         };
-        StackArray { array: S::new() }
+        StackArray {
+            array: S::new(),
+            id: next_stack_id(),
+            flag: AllocFlag::None,
+        }
     }
 
     /// Returns a reference to the possibly multi-dimensional array.
@@ -39,7 +62,7 @@ impl<T, S: Shape> HasId for StackArray<S, T> {
     #[inline]
     fn id(&self) -> crate::Id {
         crate::Id {
-            id: self.as_ptr() as u64,
+            id: self.id,
             len: self.len(),
         }
     }
@@ -62,7 +85,11 @@ impl<S: Shape, T> StackArray<S, T> {
             )
         };
 
-        StackArray { array }
+        StackArray {
+            array,
+            id: next_stack_id(),
+            flag: AllocFlag::None,
+        }
     }
 }
 
@@ -117,12 +144,14 @@ impl<S: Shape, T> PtrType for StackArray<S, T> {
     }
 
     #[inline]
-    fn flag(&self) -> crate::flag::AllocFlag {
-        crate::flag::AllocFlag::None
+    fn flag(&self) -> AllocFlag {
+        self.flag
     }
 
     #[inline]
-    unsafe fn set_flag(&mut self, _flag: crate::flag::AllocFlag) {}
+    unsafe fn set_flag(&mut self, flag: AllocFlag) {
+        self.flag = flag
+    }
 }
 
 impl<S: Shape, T> HostPtr<T> for StackArray<S, T> {
@@ -153,7 +182,11 @@ where
 {
     #[inline]
     unsafe fn shallow(&self) -> Self {
-        StackArray { array: self.array }
+        StackArray {
+            array: self.array,
+            id: self.id,
+            flag: AllocFlag::Wrapper,
+        }
     }
 }
 
@@ -0,0 +1,127 @@
+use core::str::FromStr;
+
+use crate::DeviceError;
+
+/// Selects the backend of an [`Untyped`](super::Untyped) device.
+///
+/// A descriptor can be parsed from strings like `cpu`, `cuda:1`, `opencl` or `vulkan:0` (`cl` and `wgsl` are accepted as well).
+/// Without an index, the index is read from the environment variable of the backend
+/// (`CUSTOS_CU_DEVICE_IDX`, `CUSTOS_CL_DEVICE_IDX` or `CUSTOS_WGSL_DEVICE_IDX`) and defaults to 0.
+///
+/// # Example
+/// ```
+/// use custos::untyped::DeviceDescriptor;
+///
+/// assert_eq!("opencl:1".parse::<DeviceDescriptor>().unwrap(), DeviceDescriptor::OpenCL(1));
+/// assert_eq!("cpu".parse::<DeviceDescriptor>().unwrap(), DeviceDescriptor::Cpu);
+/// assert!("tpu".parse::<DeviceDescriptor>().is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceDescriptor {
+    Cpu,
+    Cuda(usize),
+    OpenCL(usize),
+    /// A Vulkan device, which executes WGSL shaders.
+    Vulkan(usize),
+}
+
+impl DeviceDescriptor {
+    /// The environment variable read by [`DeviceDescriptor::from_env`].
+    pub const ENV_VAR: &'static str = "CUSTOS_UNTYPED_DEVICE";
+
+    /// Parses the environment variable `CUSTOS_UNTYPED_DEVICE`. Returns `None` if it is not set.
+    pub fn from_env() -> crate::Result<Option<DeviceDescriptor>> {
+        match std::env::var(Self::ENV_VAR) {
+            Ok(desc) => desc.parse().map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Returns the descriptors of all enabled accelerators in order of preference, followed by the CPU.
+    /// [`Untyped::new`](crate::Device::new) uses the first one if `CUSTOS_UNTYPED_DEVICE` is not set.
+    pub fn candidates() -> crate::Result<Vec<DeviceDescriptor>> {
+        Ok(vec![
+            #[cfg(feature = "cuda")]
+            DeviceDescriptor::Cuda(env_idx("CUSTOS_CU_DEVICE_IDX")?),
+            #[cfg(feature = "opencl")]
+            DeviceDescriptor::OpenCL(env_idx("CUSTOS_CL_DEVICE_IDX")?),
+            #[cfg(feature = "vulkan")]
+            DeviceDescriptor::Vulkan(env_idx("CUSTOS_WGSL_DEVICE_IDX")?),
+            DeviceDescriptor::Cpu,
+        ])
+    }
+}
+
+fn env_idx(var: &str) -> crate::Result<usize> {
+    match std::env::var(var) {
+        Ok(idx) => idx
+            .parse()
+            .map_err(|_| DeviceError::InvalidDeviceDescriptor.into()),
+        Err(_) => Ok(0),
+    }
+}
+
+impl FromStr for DeviceDescriptor {
+    type Err = crate::Error;
+
+    fn from_str(desc: &str) -> crate::Result<Self> {
+        let desc = desc.trim().to_ascii_lowercase();
+        let (backend, idx) = match desc.split_once(':') {
+            Some((backend, idx)) => {
+                let idx = idx
+                    .trim()
+                    .parse()
+                    .map_err(|_| DeviceError::InvalidDeviceDescriptor)?;
+                (backend.trim(), Some(idx))
+            }
+            None => (desc.as_str(), None),
+        };
+
+        let idx = |var| idx.map_or_else(|| env_idx(var), Ok);
+
+        Ok(match backend {
+            "cpu" => DeviceDescriptor::Cpu,
+            "cuda" => DeviceDescriptor::Cuda(idx("CUSTOS_CU_DEVICE_IDX")?),
+            "opencl" | "cl" => DeviceDescriptor::OpenCL(idx("CUSTOS_CL_DEVICE_IDX")?),
+            "vulkan" | "wgsl" => DeviceDescriptor::Vulkan(idx("CUSTOS_WGSL_DEVICE_IDX")?),
+            _ => return Err(DeviceError::InvalidDeviceDescriptor.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DeviceError, ErrorKind};
+
+    use super::DeviceDescriptor;
+
+    #[test]
+    fn test_parse_device_descriptor() {
+        assert_eq!(
+            "CUDA:2".parse::<DeviceDescriptor>().unwrap(),
+            DeviceDescriptor::Cuda(2)
+        );
+        assert_eq!(
+            " wgsl : 1 ".parse::<DeviceDescriptor>().unwrap(),
+            DeviceDescriptor::Vulkan(1)
+        );
+        assert_eq!(
+            "cl:0".parse::<DeviceDescriptor>().unwrap(),
+            DeviceDescriptor::OpenCL(0)
+        );
+
+        for invalid in ["", "gpu", "opencl:x", "cuda:-1"] {
+            let err = invalid.parse::<DeviceDescriptor>().unwrap_err();
+            assert_eq!(
+                err.kind::<DeviceError>(),
+                Some(&DeviceError::InvalidDeviceDescriptor)
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_descriptor_candidates_end_with_cpu() {
+        let candidates = DeviceDescriptor::candidates().unwrap();
+        assert_eq!(candidates.last(), Some(&DeviceDescriptor::Cpu));
+    }
+}
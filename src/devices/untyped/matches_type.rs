@@ -1,7 +1,8 @@
 use crate::Unit;
 
 pub trait MatchesType {
-    fn matches_storage_type<T: AsType>(&self) -> Result<(), String>;
+    /// Returns [`DeviceError::UntypedTypeMismatch`](crate::DeviceError::UntypedTypeMismatch) if the storage does not contain elements of type `T`.
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()>;
 }
 
 pub trait AsDeviceType {
@@ -12,6 +13,8 @@ pub trait AsDeviceType {
 pub enum DeviceType {
     CPU,
     CUDA,
+    OpenCL,
+    Vulkan,
 }
 
 impl<Mods> AsDeviceType for crate::CPU<Mods> {
@@ -23,44 +26,60 @@ impl<Mods> AsDeviceType for crate::CUDA<Mods> {
     const DEVICE_TYPE: DeviceType = DeviceType::CUDA;
 }
 
-pub trait AsType: Unit {
-    const TYPE: Type;
+#[cfg(feature = "opencl")]
+impl<Mods> AsDeviceType for crate::OpenCL<Mods> {
+    const DEVICE_TYPE: DeviceType = DeviceType::OpenCL;
 }
 
-impl AsType for u8 {
-    const TYPE: Type = Type::U8;
+#[cfg(feature = "vulkan")]
+impl<Mods> AsDeviceType for crate::Vulkan<Mods> {
+    const DEVICE_TYPE: DeviceType = DeviceType::Vulkan;
 }
 
-impl AsType for u32 {
-    const TYPE: Type = Type::U32;
+pub trait AsType: Unit {
+    const TYPE: Type;
 }
 
-impl AsType for i64 {
-    const TYPE: Type = Type::I64;
+macro_rules! impl_as_type {
+    ($($t:ty => $variant:ident),*) => {
+        $(
+            impl AsType for $t {
+                const TYPE: Type = Type::$variant;
+            }
+        )*
+    };
 }
 
-#[cfg(feature = "half")]
-impl AsType for half::bf16 {
-    const TYPE: Type = Type::BF16;
+impl_as_type! {
+    bool => Bool,
+    u8 => U8,
+    u16 => U16,
+    u32 => U32,
+    u64 => U64,
+    i8 => I8,
+    i16 => I16,
+    i32 => I32,
+    i64 => I64,
+    f32 => F32,
+    f64 => F64
 }
 
 #[cfg(feature = "half")]
-impl AsType for half::f16 {
-    const TYPE: Type = Type::F16;
-}
-
-impl AsType for f32 {
-    const TYPE: Type = Type::F32;
-}
-
-impl AsType for f64 {
-    const TYPE: Type = Type::F64;
+impl_as_type! {
+    half::bf16 => BF16,
+    half::f16 => F16
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Type {
+    Bool,
     U8,
+    U16,
     U32,
+    U64,
+    I8,
+    I16,
+    I32,
     I64,
     #[cfg(feature = "half")]
     BF16,
@@ -69,3 +88,16 @@ pub enum Type {
     F32,
     F64,
 }
+
+impl Type {
+    /// Returns `true` for floating point types.
+    #[inline]
+    pub fn is_float(&self) -> bool {
+        match self {
+            #[cfg(feature = "half")]
+            Type::BF16 | Type::F16 => true,
+            Type::F32 | Type::F64 => true,
+            _ => false,
+        }
+    }
+}
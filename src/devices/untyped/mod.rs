@@ -1,6 +1,10 @@
+mod descriptor;
 pub mod storages;
 mod untyped_device;
 
+pub use descriptor::*;
+pub use untyped_device::{Untyped, UntypedDevice};
+
 #[cfg(not(feature = "cuda"))]
 mod dummy_cuda;
 
@@ -13,7 +17,7 @@ pub use matches_type::*;
 
 use crate::{Buffer, Unit};
 
-use self::untyped_device::Untyped;
+use self::storages::UntypedData;
 
 impl<'a, T: Unit, S: crate::Shape> Buffer<'a, T, Untyped, S> {
     #[inline]
@@ -83,6 +87,32 @@ impl<'a, T: Unit, S: crate::Shape> Buffer<'a, T, Untyped, S> {
     {
         self.as_typed::<OT, ()>().map(|buf| buf.read())
     }
+
+    /// Returns a new buffer with the elements converted to the data type `ty`. See [`UntypedData::cast`].
+    pub fn cast(&self, ty: Type) -> crate::Result<Buffer<'a, (), Untyped, ()>> {
+        let data = match &self.data {
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL(_) => {
+                let host = self.data.to_host(self.device())?.cast(ty);
+                UntypedData::from_host(&host, self.device())?
+            }
+            data => data.cast(ty)?,
+        };
+        Ok(Buffer {
+            data,
+            device: self.device,
+        })
+    }
+
+    /// Copies the buffer to another untyped device, e.g. from a CUDA to an OpenCL device.
+    /// The data is transferred via host memory and keeps its data type.
+    pub fn to_device<'b>(&self, device: &'b Untyped) -> crate::Result<Buffer<'b, T, Untyped, S>> {
+        let host = self.data.to_host(self.device())?;
+        Ok(Buffer {
+            data: UntypedData::from_host(&host, device)?,
+            device: Some(device),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -109,6 +139,20 @@ mod tests {
                 }
                 _ => panic!(),
             },
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL(cl) => match cl {
+                super::storages::OpenCLStorage::F32(_) => {
+                    assert_eq!(buf.read_typed::<f32>().unwrap(), [1., 2., 3., 4.,])
+                }
+                _ => panic!(),
+            },
+            #[cfg(feature = "vulkan")]
+            UntypedData::Vulkan(vk) => match vk {
+                super::storages::VulkanStorage::F32(data) => {
+                    assert_eq!(data.read_staged_to_vec(), [1., 2., 3., 4.,])
+                }
+                _ => panic!(),
+            },
         }
     }
 
@@ -136,6 +180,60 @@ mod tests {
         assert!(typed_buf.is_none())
     }
 
+    #[test]
+    fn test_cast_untyped_buf() {
+        let device = Untyped::new().unwrap();
+        let buf = device.buffer([1.5f32, -2., 3., 300.]);
+        let casted = buf.cast(super::Type::I8).unwrap();
+        assert_eq!(casted.data.dtype(), super::Type::I8);
+        assert_eq!(casted.read_typed::<i8>().unwrap(), [1, -2, 3, 127]);
+        assert!(casted.read_typed::<f32>().is_none());
+    }
+
+    #[test]
+    fn test_untyped_to_device() {
+        use super::DeviceDescriptor;
+
+        let device = Untyped::new().unwrap();
+        let cpu = Untyped::from_descriptor(DeviceDescriptor::Cpu).unwrap();
+
+        let buf = device.buffer([1u16, 2, 3]);
+        let on_cpu = buf.to_device(&cpu).unwrap();
+        assert!(matches!(on_cpu.data, UntypedData::CPU(_)));
+        assert_eq!(on_cpu.read(), [1, 2, 3]);
+
+        let back = on_cpu.to_device(&device).unwrap();
+        assert_eq!(back.read(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_untyped_from_unavailable_descriptor() {
+        use super::DeviceDescriptor;
+        use crate::{DeviceError, ErrorKind};
+
+        #[cfg(not(feature = "opencl"))]
+        {
+            let err = Untyped::from_descriptor(DeviceDescriptor::OpenCL(0))
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.kind::<DeviceError>(),
+                Some(&DeviceError::UntypedDeviceUnavailable)
+            );
+        }
+
+        #[cfg(not(feature = "vulkan"))]
+        {
+            let err = Untyped::from_descriptor(DeviceDescriptor::Vulkan(0))
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.kind::<DeviceError>(),
+                Some(&DeviceError::UntypedDeviceUnavailable)
+            );
+        }
+    }
+
     #[test]
     fn test_add_type_info_to_untyped_ref() {
         let device = Untyped::new().unwrap();
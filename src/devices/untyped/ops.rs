@@ -1,6 +1,9 @@
+use core::ops::{Range, RangeBounds};
+
 use crate::{
-    cpu_stack_ops::apply_fn_slice, untyped::untyped_device::UntypedDevice, ApplyFunction,
-    CDatatype, Read, Retriever, Shape, CPU,
+    bounds_to_range, cpu_stack_ops::apply_fn_slice, untyped::untyped_device::UntypedDevice,
+    ApplyFunction, Buffer, CDatatype, CopySlice, DeviceError, Read, Retriever, Shape, WriteBuf,
+    CPU,
 };
 
 use super::{untyped_device::Untyped, AsType};
@@ -37,6 +40,16 @@ impl<T: 'static + AsType + Default + Clone, S: Shape> Read<T, S> for Untyped {
                     .unwrap()
                     .read()
             }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => {
+                let buf = buf.convert_to_typed::<T, crate::OpenCL, S>().unwrap();
+                Read::<T, S>::read_to_vec(cl, buf)
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(_vk) => buf
+                .convert_to_typed::<T, crate::Vulkan, S>()
+                .unwrap()
+                .read_staged_to_vec(),
         }
     }
 }
@@ -71,21 +84,248 @@ where
                 #[cfg(not(feature = "cuda"))]
                 unimplemented!()
             }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => {
+                let x = buf.convert_to_typed::<T, crate::OpenCL, S>().unwrap();
+                let out = out.convert_to_typed_mut::<T, crate::OpenCL, S>().unwrap();
+                crate::opencl::try_cl_apply_fn_mut(cl, x, out, f).unwrap();
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(vk) => {
+                let x = buf.convert_to_typed::<T, crate::Vulkan, S>().unwrap();
+                let out = out.convert_to_typed_mut::<T, crate::Vulkan, S>().unwrap();
+                crate::vulkan::try_vk_apply_fn_mut(vk, x, out, f).unwrap();
+            }
         }
         out
     }
 }
 
+impl<T: AsType + Clone, S: Shape> WriteBuf<T, S> for Untyped {
+    fn write(&self, buf: &mut Buffer<T, Self, S>, data: &[T]) {
+        match &self.device {
+            UntypedDevice::Cpu(_cpu) => {
+                let buf = buf.data.convert_to_typed_mut::<T, CPU, S>().unwrap();
+                buf[..data.len()].clone_from_slice(data)
+            }
+            UntypedDevice::Cuda(_cuda) => {
+                #[cfg(feature = "cuda")]
+                {
+                    let buf = buf
+                        .data
+                        .convert_to_typed_mut::<T, crate::CUDA, S>()
+                        .unwrap();
+                    crate::cuda::api::cu_write(buf.ptr, data).unwrap();
+                }
+                #[cfg(not(feature = "cuda"))]
+                unimplemented!()
+            }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => {
+                let buf = buf
+                    .data
+                    .convert_to_typed_mut::<T, crate::OpenCL, S>()
+                    .unwrap();
+                let event =
+                    unsafe { cl.device.enqueue_write_buffer(buf.ptr, data, false) }.unwrap();
+                event.wait().unwrap();
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(_vk) => buf
+                .data
+                .convert_to_typed_mut::<T, crate::Vulkan, S>()
+                .unwrap()
+                .write_staged(data),
+        }
+    }
+
+    #[inline]
+    fn write_buf(&self, dst: &mut Buffer<T, Self, S>, src: &Buffer<T, Self, S>) {
+        self.try_write_buf(dst, src).unwrap()
+    }
+}
+
+impl Untyped {
+    /// Copies `src` to the start of `dst` on the selected backend.
+    /// Fails if `src` is longer than `dst`.
+    pub fn try_write_buf<T: AsType + Clone, S: Shape>(
+        &self,
+        dst: &mut Buffer<T, Self, S>,
+        src: &Buffer<T, Self, S>,
+    ) -> crate::Result<()> {
+        if src.len() > dst.len() {
+            return Err(DeviceError::WriteLengthMismatch.into());
+        }
+
+        match &self.device {
+            UntypedDevice::Cpu(_cpu) => {
+                let src = src.data.convert_to_typed::<T, CPU, S>()?;
+                let dst = dst.data.convert_to_typed_mut::<T, CPU, S>()?;
+                dst[..src.len()].clone_from_slice(src)
+            }
+            UntypedDevice::Cuda(_cuda) => {
+                #[cfg(feature = "cuda")]
+                {
+                    let src = src.data.convert_to_typed::<T, crate::CUDA, S>()?;
+                    let dst = dst.data.convert_to_typed_mut::<T, crate::CUDA, S>()?;
+                    unsafe {
+                        crate::cuda::api::cuMemcpy(
+                            dst.ptr,
+                            src.ptr,
+                            src.len * core::mem::size_of::<T>(),
+                        )
+                    }
+                    .to_result()?;
+                }
+                #[cfg(not(feature = "cuda"))]
+                unimplemented!()
+            }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => {
+                let src = src.data.convert_to_typed::<T, crate::OpenCL, S>()?;
+                let dst = dst.data.convert_to_typed_mut::<T, crate::OpenCL, S>()?;
+                let event = unsafe {
+                    crate::opencl::api::enqueue_full_copy_buffer::<T>(
+                        cl.queue(),
+                        src.ptr,
+                        dst.ptr,
+                        src.len(),
+                        None,
+                    )
+                }?;
+                event.wait()?;
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(_vk) => {
+                let src = src.data.convert_to_typed::<T, crate::Vulkan, S>()?;
+                let dst = dst.data.convert_to_typed_mut::<T, crate::Vulkan, S>()?;
+                dst.copy_from(src, 0, 0, src.len())
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsType + Clone> CopySlice<T> for Untyped {
+    fn copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+        &self,
+        source: &Buffer<T, Self>,
+        source_range: SR,
+        dest: &mut Buffer<T, Self>,
+        dest_range: DR,
+    ) {
+        let source_range = bounds_to_range(source_range, source.len());
+        let dest_range = bounds_to_range(dest_range, dest.len());
+
+        let len = source_range.end - source_range.start;
+        assert_eq!(len, dest_range.end - dest_range.start);
+
+        match &self.device {
+            UntypedDevice::Cpu(_cpu) => {
+                let source = source.data.convert_to_typed::<T, CPU, ()>().unwrap();
+                let dest = dest.data.convert_to_typed_mut::<T, CPU, ()>().unwrap();
+                dest[dest_range].clone_from_slice(&source[source_range]);
+            }
+            UntypedDevice::Cuda(_cuda) => {
+                #[cfg(feature = "cuda")]
+                {
+                    let source = source
+                        .data
+                        .convert_to_typed::<T, crate::CUDA, ()>()
+                        .unwrap();
+                    let dest = dest
+                        .data
+                        .convert_to_typed_mut::<T, crate::CUDA, ()>()
+                        .unwrap();
+                    let size = core::mem::size_of::<T>();
+                    unsafe {
+                        crate::cuda::api::cuMemcpy(
+                            dest.ptr + (dest_range.start * size) as u64,
+                            source.ptr + (source_range.start * size) as u64,
+                            len * size,
+                        );
+                    }
+                }
+                #[cfg(not(feature = "cuda"))]
+                unimplemented!()
+            }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => {
+                let source = source
+                    .data
+                    .convert_to_typed::<T, crate::OpenCL, ()>()
+                    .unwrap();
+                let dest = dest
+                    .data
+                    .convert_to_typed_mut::<T, crate::OpenCL, ()>()
+                    .unwrap();
+                let event = unsafe {
+                    crate::opencl::api::enqueue_copy_buffer::<T>(
+                        cl.queue(),
+                        source.ptr,
+                        dest.ptr,
+                        source_range.start,
+                        dest_range.start,
+                        len,
+                        Some(&cl.device.event_wait_list.borrow()),
+                    )
+                }
+                .unwrap();
+                event.wait().unwrap();
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(_vk) => {
+                let source = source
+                    .data
+                    .convert_to_typed::<T, crate::Vulkan, ()>()
+                    .unwrap();
+                let dest = dest
+                    .data
+                    .convert_to_typed_mut::<T, crate::Vulkan, ()>()
+                    .unwrap();
+                dest.copy_from(source, source_range.start, dest_range.start, len);
+            }
+        }
+    }
+
+    fn copy_slice_all<I: IntoIterator<Item = (Range<usize>, Range<usize>)>>(
+        &self,
+        source: &Buffer<T, Self>,
+        dest: &mut Buffer<T, Self>,
+        ranges: I,
+    ) {
+        for (source_range, dest_range) in ranges {
+            self.copy_slice_to(source, source_range, dest, dest_range);
+        }
+    }
+}
+
+/// Applies `$cpu_op` or `$cuda_op` to the storages of two untyped buffers and evaluates to a `crate::Result` of the output buffer.
+/// Fails with [`DeviceError::UntypedTypeMismatch`](crate::DeviceError::UntypedTypeMismatch) if the data types of the buffers differ
+/// and with [`DeviceError::UntypedDeviceMismatch`](crate::DeviceError::UntypedDeviceMismatch) if they live on different backends.
 #[macro_export]
 macro_rules! untyped_binary_op {
-    ($device:ident, $lhs:ident, $rhs:ident, $cpu_op:expr, $cuda_op:expr) => {{
+    ($device:ident, $lhs:ident, $rhs:ident, $cpu_op:expr, $cuda_op:expr) => {'op: {
         let out = match (&$lhs.data, &$rhs.data) {
             (UntypedData::CPU(lhs), UntypedData::CPU(rhs)) => {
                 let storage = match (lhs, rhs) {
                     (CpuStorage::U8(lhs), CpuStorage::U8(rhs)) => CpuStorage::U8($cpu_op(lhs, rhs)),
+                    (CpuStorage::U16(lhs), CpuStorage::U16(rhs)) => {
+                        CpuStorage::U16($cpu_op(lhs, rhs))
+                    }
                     (CpuStorage::U32(lhs), CpuStorage::U32(rhs)) => {
                         CpuStorage::U32($cpu_op(lhs, rhs))
                     }
+                    (CpuStorage::U64(lhs), CpuStorage::U64(rhs)) => {
+                        CpuStorage::U64($cpu_op(lhs, rhs))
+                    }
+                    (CpuStorage::I8(lhs), CpuStorage::I8(rhs)) => CpuStorage::I8($cpu_op(lhs, rhs)),
+                    (CpuStorage::I16(lhs), CpuStorage::I16(rhs)) => {
+                        CpuStorage::I16($cpu_op(lhs, rhs))
+                    }
+                    (CpuStorage::I32(lhs), CpuStorage::I32(rhs)) => {
+                        CpuStorage::I32($cpu_op(lhs, rhs))
+                    }
                     (CpuStorage::I64(lhs), CpuStorage::I64(rhs)) => {
                         CpuStorage::I64($cpu_op(lhs, rhs))
                     }
@@ -103,22 +343,37 @@ macro_rules! untyped_binary_op {
                     (CpuStorage::F64(lhs), CpuStorage::F64(rhs)) => {
                         CpuStorage::F64($cpu_op(lhs, rhs))
                     }
-                    _ => unimplemented!(),
+                    _ => break 'op Err($crate::DeviceError::UntypedTypeMismatch.into()),
                 };
                 UntypedData::CPU(storage)
             }
             (UntypedData::CUDA(lhs), UntypedData::CUDA(rhs)) => {
                 let UntypedDevice::Cuda(dev) = &$device.device else {
-                    panic!()
+                    break 'op Err($crate::DeviceError::UntypedDeviceMismatch.into());
                 };
                 #[cfg(feature = "cuda")]
                 let storage = match (lhs, rhs) {
                     (CudaStorage::U8(lhs), CudaStorage::U8(rhs)) => {
                         CudaStorage::U8($cuda_op(dev, lhs, rhs))
                     }
+                    (CudaStorage::U16(lhs), CudaStorage::U16(rhs)) => {
+                        CudaStorage::U16($cuda_op(dev, lhs, rhs))
+                    }
                     (CudaStorage::U32(lhs), CudaStorage::U32(rhs)) => {
                         CudaStorage::U32($cuda_op(dev, lhs, rhs))
                     }
+                    (CudaStorage::U64(lhs), CudaStorage::U64(rhs)) => {
+                        CudaStorage::U64($cuda_op(dev, lhs, rhs))
+                    }
+                    (CudaStorage::I8(lhs), CudaStorage::I8(rhs)) => {
+                        CudaStorage::I8($cuda_op(dev, lhs, rhs))
+                    }
+                    (CudaStorage::I16(lhs), CudaStorage::I16(rhs)) => {
+                        CudaStorage::I16($cuda_op(dev, lhs, rhs))
+                    }
+                    (CudaStorage::I32(lhs), CudaStorage::I32(rhs)) => {
+                        CudaStorage::I32($cuda_op(dev, lhs, rhs))
+                    }
                     (CudaStorage::I64(lhs), CudaStorage::I64(rhs)) => {
                         CudaStorage::I64($cuda_op(dev, lhs, rhs))
                     }
@@ -137,7 +392,7 @@ macro_rules! untyped_binary_op {
                     (CudaStorage::F64(lhs), CudaStorage::F64(rhs)) => {
                         CudaStorage::F64($cuda_op(dev, lhs, rhs))
                     }
-                    _ => unimplemented!(),
+                    _ => break 'op Err($crate::DeviceError::UntypedTypeMismatch.into()),
                 };
 
                 #[cfg(feature = "cuda")]
@@ -145,15 +400,15 @@ macro_rules! untyped_binary_op {
                     UntypedData::CUDA(storage)
                 }
                 #[cfg(not(feature = "cuda"))]
-                unimplemented!()
+                break 'op Err($crate::DeviceError::UntypedDeviceUnavailable.into());
             }
-            _ => unimplemented!(),
+            _ => break 'op Err($crate::DeviceError::UntypedDeviceMismatch.into()),
         };
 
-        return $crate::Buffer {
+        Ok($crate::Buffer {
             data: out,
             device: Some($device),
-        };
+        })
     }};
 }
 
@@ -166,7 +421,8 @@ mod tests {
             storages::{CpuStorage, CudaStorage, UntypedData},
             untyped_device::{Untyped, UntypedDevice},
         },
-        ApplyFunction, Buffer, Combiner, Device, Shape, Unit,
+        ApplyFunction, Buffer, Combiner, CopySlice, Device, DeviceError, ErrorKind, Shape, Unit,
+        WriteBuf,
     };
 
     #[test]
@@ -177,6 +433,60 @@ mod tests {
         roughly_eq_slices(&out.read(), &[2., 3., 4., 5.]);
     }
 
+    #[test]
+    fn test_apply_fn_untyped_ints() {
+        let device = Untyped::new().unwrap();
+        let buf = device.buffer([1i8, -2, 3]);
+        let out = device.apply_fn(&buf, |x| x.mul(2));
+        assert_eq!(out.read(), [2, -4, 6]);
+
+        let buf = device.buffer([1u16, 2, 3]);
+        let out = device.apply_fn(&buf, |x| x.add(1));
+        assert_eq!(out.read(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_read_untyped_bool() {
+        let device = Untyped::new().unwrap();
+        let buf = device.buffer([true, false, true]);
+        assert_eq!(buf.read(), [true, false, true]);
+    }
+
+    #[test]
+    fn test_write_untyped() {
+        let device = Untyped::new().unwrap();
+        let mut buf = Buffer::<u64, _>::new(&device, 4);
+        device.write(&mut buf, &[1, 2, 3, 4]);
+        assert_eq!(buf.read(), [1, 2, 3, 4]);
+
+        let mut dst = Buffer::<u64, _>::new(&device, 4);
+        device.write_buf(&mut dst, &buf);
+        assert_eq!(dst.read(), [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_write_buf_untyped_lengths() {
+        let device = Untyped::new().unwrap();
+        let short = device.buffer([1f32, 2.]);
+        let mut long = device.buffer([0f32; 4]);
+
+        device.try_write_buf(&mut long, &short).unwrap();
+        assert_eq!(long.read(), [1., 2., 0., 0.]);
+
+        let mut short = device.buffer([0f32; 2]);
+        let err = device.try_write_buf(&mut short, &long).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::WriteLengthMismatch));
+        assert_eq!(short.read(), [0., 0.]);
+    }
+
+    #[test]
+    fn test_copy_slice_untyped() {
+        let device = Untyped::new().unwrap();
+        let buf = device.buffer([1i16, 2, 3, 4, 5]);
+        let slice = device.copy_slice(&buf, 1..4);
+        assert_eq!(slice.read(), [2, 3, 4]);
+    }
+
     fn alloc_and_add_slice<T: Copy + std::ops::Add<Output = T>>(lhs: &[T], rhs: &[T]) -> CPUPtr<T> {
         let mut out = unsafe { CPUPtr::<T>::new(lhs.len(), crate::flag::AllocFlag::None) };
         add_ew_slice(lhs, rhs, &mut out);
@@ -215,9 +525,19 @@ mod tests {
         out
     }
 
+    impl Untyped {
+        fn try_add<T: Unit, S: Shape>(
+            &self,
+            lhs: &Buffer<T, Self, S>,
+            rhs: &Buffer<T, Self, S>,
+        ) -> crate::Result<Buffer<T, Self, S>> {
+            untyped_binary_op!(self, lhs, rhs, alloc_and_add_slice, alloc_and_add_cu)
+        }
+    }
+
     impl<T: Unit, S: Shape> AddEw<T, Self, S> for Untyped {
         fn add(&self, lhs: &Buffer<T, Self, S>, rhs: &Buffer<T, Self, S>) -> Buffer<T, Self, S> {
-            untyped_binary_op!(self, lhs, rhs, alloc_and_add_slice, alloc_and_add_cu)
+            self.try_add(lhs, rhs).unwrap()
         }
     }
 
@@ -239,4 +559,28 @@ mod tests {
         let out = device.add(&lhs, &rhs);
         roughly_eq_slices(&[2., 4., 6., 8.], &out.read_typed::<f32>().unwrap())
     }
+
+    #[test]
+    fn test_untyped_ew_add_i32() {
+        let device = Untyped::new().unwrap();
+        let lhs = device.buffer([1i32, -2, 3]).to_untyped();
+        let rhs = device.buffer([1i32, 2, 3]).to_untyped();
+
+        let out = device.add(&lhs, &rhs);
+        assert_eq!(out.read_typed::<i32>().unwrap(), [2, 0, 6]);
+    }
+
+    #[test]
+    fn test_untyped_ew_add_mismatching_types() {
+        let device = Untyped::new().unwrap();
+        let lhs = device.buffer([1i32, -2, 3]).to_untyped();
+        let rhs = device.buffer([1u32, 2, 3]).to_untyped();
+        let Err(err) = device.try_add(&lhs, &rhs) else {
+            panic!("adding buffers of different data types must fail");
+        };
+        assert_eq!(
+            crate::ErrorKind::kind::<crate::DeviceError>(&err),
+            Some(&crate::DeviceError::UntypedTypeMismatch)
+        );
+    }
 }
@@ -1,130 +1,380 @@
-mod cpu_storage;
 use core::mem::transmute;
 
-pub use cpu_storage::*;
+use crate::{cpu::CPUPtr, untyped::DeviceType, Alloc, Device, DeviceError, HasId, PtrType, Shape};
 
-mod cuda_storage;
-pub use cuda_storage::*;
+use super::{untyped_device::Untyped, AsDeviceType, AsType, MatchesType, Type};
+
+/// Evaluates `$op` for every variant of the storage enum `$storage`.
+/// The typed pointer is bound to `$ptr` and its element type is available as `$elem`.
+macro_rules! match_storage {
+    ($value:expr, $storage:ident, $ptr:ident: $elem:ident => $op:expr) => {
+        match $value {
+            $storage::Bool($ptr) => {
+                #[allow(dead_code)]
+                type $elem = bool;
+                $op
+            }
+            $storage::U8($ptr) => {
+                #[allow(dead_code)]
+                type $elem = u8;
+                $op
+            }
+            $storage::U16($ptr) => {
+                #[allow(dead_code)]
+                type $elem = u16;
+                $op
+            }
+            $storage::U32($ptr) => {
+                #[allow(dead_code)]
+                type $elem = u32;
+                $op
+            }
+            $storage::U64($ptr) => {
+                #[allow(dead_code)]
+                type $elem = u64;
+                $op
+            }
+            $storage::I8($ptr) => {
+                #[allow(dead_code)]
+                type $elem = i8;
+                $op
+            }
+            $storage::I16($ptr) => {
+                #[allow(dead_code)]
+                type $elem = i16;
+                $op
+            }
+            $storage::I32($ptr) => {
+                #[allow(dead_code)]
+                type $elem = i32;
+                $op
+            }
+            $storage::I64($ptr) => {
+                #[allow(dead_code)]
+                type $elem = i64;
+                $op
+            }
+            #[cfg(feature = "half")]
+            $storage::BF16($ptr) => {
+                #[allow(dead_code)]
+                type $elem = half::bf16;
+                $op
+            }
+            #[cfg(feature = "half")]
+            $storage::F16($ptr) => {
+                #[allow(dead_code)]
+                type $elem = half::f16;
+                $op
+            }
+            $storage::F32($ptr) => {
+                #[allow(dead_code)]
+                type $elem = f32;
+                $op
+            }
+            $storage::F64($ptr) => {
+                #[allow(dead_code)]
+                type $elem = f64;
+                $op
+            }
+        }
+    };
+}
+
+/// Evaluates `$op` with `$elem` being the element type described by the [`Type`] `$ty`.
+macro_rules! match_type {
+    ($ty:expr, $elem:ident => $op:expr) => {
+        match $ty {
+            Type::Bool => {
+                type $elem = bool;
+                $op
+            }
+            Type::U8 => {
+                type $elem = u8;
+                $op
+            }
+            Type::U16 => {
+                type $elem = u16;
+                $op
+            }
+            Type::U32 => {
+                type $elem = u32;
+                $op
+            }
+            Type::U64 => {
+                type $elem = u64;
+                $op
+            }
+            Type::I8 => {
+                type $elem = i8;
+                $op
+            }
+            Type::I16 => {
+                type $elem = i16;
+                $op
+            }
+            Type::I32 => {
+                type $elem = i32;
+                $op
+            }
+            Type::I64 => {
+                type $elem = i64;
+                $op
+            }
+            #[cfg(feature = "half")]
+            Type::BF16 => {
+                type $elem = half::bf16;
+                $op
+            }
+            #[cfg(feature = "half")]
+            Type::F16 => {
+                type $elem = half::f16;
+                $op
+            }
+            Type::F32 => {
+                type $elem = f32;
+                $op
+            }
+            Type::F64 => {
+                type $elem = f64;
+                $op
+            }
+        }
+    };
+}
+
+/// Wraps the pointer `$data` of type `$ptr<$t>` into the matching variant of `$storage`.
+macro_rules! storage_from_typed {
+    ($storage:ident, $ptr:ident<$t:ty>, $data:expr) => {
+        match <$t as AsType>::TYPE {
+            Type::Bool => {
+                $storage::Bool(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<bool>>($data) })
+            }
+            Type::U8 => $storage::U8(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<u8>>($data) }),
+            Type::U16 => {
+                $storage::U16(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<u16>>($data) })
+            }
+            Type::U32 => {
+                $storage::U32(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<u32>>($data) })
+            }
+            Type::U64 => {
+                $storage::U64(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<u64>>($data) })
+            }
+            Type::I8 => $storage::I8(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<i8>>($data) }),
+            Type::I16 => {
+                $storage::I16(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<i16>>($data) })
+            }
+            Type::I32 => {
+                $storage::I32(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<i32>>($data) })
+            }
+            Type::I64 => {
+                $storage::I64(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<i64>>($data) })
+            }
+            #[cfg(feature = "half")]
+            Type::BF16 => {
+                $storage::BF16(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<half::bf16>>($data) })
+            }
+            #[cfg(feature = "half")]
+            Type::F16 => {
+                $storage::F16(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<half::f16>>($data) })
+            }
+            Type::F32 => {
+                $storage::F32(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<f32>>($data) })
+            }
+            Type::F64 => {
+                $storage::F64(unsafe { core::mem::transmute::<$ptr<$t>, $ptr<f64>>($data) })
+            }
+        }
+    };
+}
 
-use crate::{untyped::DeviceType, Device, HasId, PtrType, Shape};
+mod cast;
+mod cpu_storage;
+mod cuda_storage;
+#[cfg(feature = "opencl")]
+mod opencl_storage;
+#[cfg(feature = "vulkan")]
+mod vulkan_storage;
 
-use super::{AsDeviceType, AsType, MatchesType};
+pub use cast::*;
+pub use cpu_storage::*;
+pub use cuda_storage::*;
+#[cfg(feature = "opencl")]
+pub use opencl_storage::*;
+#[cfg(feature = "vulkan")]
+pub use vulkan_storage::*;
 
 pub enum UntypedData {
     CPU(CpuStorage),
     CUDA(CudaStorage),
+    #[cfg(feature = "opencl")]
+    OpenCL(OpenCLStorage),
+    #[cfg(feature = "vulkan")]
+    Vulkan(VulkanStorage),
+}
+
+/// Evaluates `$op` with `$storage` bound to the storage of any variant of [`UntypedData`].
+macro_rules! match_data {
+    ($data:expr, $storage:ident => $op:expr) => {
+        match $data {
+            UntypedData::CPU($storage) => $op,
+            UntypedData::CUDA($storage) => $op,
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL($storage) => $op,
+            #[cfg(feature = "vulkan")]
+            UntypedData::Vulkan($storage) => $op,
+        }
+    };
 }
 
 impl PtrType for UntypedData {
     #[inline]
     fn size(&self) -> usize {
-        match self {
-            UntypedData::CPU(cpu) => cpu.size(),
-            UntypedData::CUDA(cuda) => cuda.size(),
-        }
+        match_data!(self, storage => storage.size())
     }
 
     #[inline]
     fn flag(&self) -> crate::flag::AllocFlag {
-        match self {
-            UntypedData::CPU(cpu) => cpu.flag(),
-            UntypedData::CUDA(cuda) => cuda.flag(),
-        }
+        match_data!(self, storage => storage.flag())
     }
 
     #[inline]
     unsafe fn set_flag(&mut self, flag: crate::flag::AllocFlag) {
-        match self {
-            UntypedData::CPU(cpu) => cpu.set_flag(flag),
-            UntypedData::CUDA(cuda) => cuda.set_flag(flag),
-        }
+        match_data!(self, storage => storage.set_flag(flag))
     }
 }
 
 impl HasId for UntypedData {
     #[inline]
     fn id(&self) -> crate::Id {
-        match self {
-            UntypedData::CPU(cpu) => cpu.id(),
-            UntypedData::CUDA(cuda) => cuda.id(),
-        }
+        match_data!(self, storage => storage.id())
     }
 }
 
 impl MatchesType for UntypedData {
     #[inline]
-    fn matches_storage_type<T: super::AsType>(&self) -> Result<(), String> {
-        match self {
-            UntypedData::CPU(cpu) => cpu.matches_storage_type::<T>(),
-            UntypedData::CUDA(cuda) => cuda.matches_storage_type::<T>(),
-        }
+    fn matches_storage_type<T: super::AsType>(&self) -> crate::Result<()> {
+        match_data!(self, storage => storage.matches_storage_type::<T>())
     }
 }
 
-macro_rules! convert_to_typed {
-    ($self:ident) => {
-        match $self {
-            UntypedData::CPU(cpu) => {
-                if D::DEVICE_TYPE != DeviceType::CPU {
-                    return None;
-                }
-                Some(match cpu {
-                    CpuStorage::U8(data) => unsafe { transmute(data) },
-                    CpuStorage::U32(data) => unsafe { transmute(data) },
-                    CpuStorage::I64(data) => unsafe { transmute(data) },
-                    #[cfg(feature = "half")]
-                    CpuStorage::BF16(data) => unsafe { transmute(data) },
-                    #[cfg(feature = "half")]
-                    CpuStorage::F16(data) => unsafe { transmute(data) },
-                    CpuStorage::F32(data) => unsafe { transmute(data) },
-                    CpuStorage::F64(data) => unsafe { transmute(data) },
-                })
-            }
-            UntypedData::CUDA(cuda) => {
-                if D::DEVICE_TYPE != DeviceType::CUDA {
-                    return None;
-                }
-                #[cfg(feature = "cuda")]
-                {
-                    Some(match cuda {
-                        CudaStorage::U8(data) => unsafe { transmute(data) },
-                        CudaStorage::U32(data) => unsafe { transmute(data) },
-                        CudaStorage::I64(data) => unsafe { transmute(data) },
-                        #[cfg(feature = "half")]
-                        CudaStorage::BF16(data) => unsafe { transmute(data) },
-                        #[cfg(feature = "half")]
-                        CudaStorage::F16(data) => unsafe { transmute(data) },
-                        CudaStorage::F32(data) => unsafe { transmute(data) },
-                        CudaStorage::F64(data) => unsafe { transmute(data) },
-                    })
-                }
-
-                #[cfg(not(feature = "cuda"))]
-                None
-            }
-        }
-    };
-}
-
 impl UntypedData {
+    /// Returns the data type of the elements.
+    #[inline]
+    pub fn dtype(&self) -> Type {
+        match_data!(self, storage => storage.dtype())
+    }
+
+    /// Returns the typed base of device `D`.
+    /// Fails with [`DeviceError::UntypedTypeMismatch`] or [`DeviceError::UntypedDeviceMismatch`] if `T` or `D` do not match the storage.
     pub fn convert_to_typed<T: AsType, D: AsDeviceType + Device, S: Shape>(
         &self,
-    ) -> Option<&D::Base<T, S>> {
-        self.matches_storage_type::<T>().ok()?;
-        convert_to_typed!(self)
+    ) -> crate::Result<&D::Base<T, S>> {
+        self.matches_storage_type::<T>()?;
+        match self {
+            UntypedData::CPU(cpu) if D::DEVICE_TYPE == DeviceType::CPU => {
+                Ok(match_storage!(cpu, CpuStorage, data: E => unsafe {
+                    transmute::<&CPUPtr<E>, &D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "cuda")]
+            UntypedData::CUDA(cuda) if D::DEVICE_TYPE == DeviceType::CUDA => {
+                Ok(match_storage!(cuda, CudaStorage, data: E => unsafe {
+                    transmute::<&crate::cuda::CUDAPtr<E>, &D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL(cl) if D::DEVICE_TYPE == DeviceType::OpenCL => {
+                Ok(match_storage!(cl, OpenCLStorage, data: E => unsafe {
+                    transmute::<&crate::opencl::CLPtr<E>, &D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedData::Vulkan(vk) if D::DEVICE_TYPE == DeviceType::Vulkan => {
+                Ok(match_storage!(vk, VulkanStorage, data: E => unsafe {
+                    transmute::<&crate::vulkan::VkArray<E>, &D::Base<T, S>>(data)
+                }))
+            }
+            _ => Err(DeviceError::UntypedDeviceMismatch.into()),
+        }
     }
+
     // add "checked" to name maybe, then add unsafe variants..
     pub fn convert_to_typed_mut<T: AsType, D: AsDeviceType + Device, S: Shape>(
         &mut self,
-    ) -> Option<&mut D::Base<T, S>> {
-        self.matches_storage_type::<T>().ok()?;
-        convert_to_typed!(self)
+    ) -> crate::Result<&mut D::Base<T, S>> {
+        self.matches_storage_type::<T>()?;
+        match self {
+            UntypedData::CPU(cpu) if D::DEVICE_TYPE == DeviceType::CPU => {
+                Ok(match_storage!(cpu, CpuStorage, data: E => unsafe {
+                    transmute::<&mut CPUPtr<E>, &mut D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "cuda")]
+            UntypedData::CUDA(cuda) if D::DEVICE_TYPE == DeviceType::CUDA => {
+                Ok(match_storage!(cuda, CudaStorage, data: E => unsafe {
+                    transmute::<&mut crate::cuda::CUDAPtr<E>, &mut D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL(cl) if D::DEVICE_TYPE == DeviceType::OpenCL => {
+                Ok(match_storage!(cl, OpenCLStorage, data: E => unsafe {
+                    transmute::<&mut crate::opencl::CLPtr<E>, &mut D::Base<T, S>>(data)
+                }))
+            }
+            #[cfg(feature = "vulkan")]
+            UntypedData::Vulkan(vk) if D::DEVICE_TYPE == DeviceType::Vulkan => {
+                Ok(match_storage!(vk, VulkanStorage, data: E => unsafe {
+                    transmute::<&mut crate::vulkan::VkArray<E>, &mut D::Base<T, S>>(data)
+                }))
+            }
+            _ => Err(DeviceError::UntypedDeviceMismatch.into()),
+        }
+    }
+
+    /// Converts the elements to the data type `ty` like an `as` cast, e.g. `1.7f32` becomes `1u8`.
+    /// Non-zero values become `true`, `true` becomes `1`.
+    /// The storage stays on its device.
+    ///
+    /// OpenCL storages can only be read with their device. Use [`Buffer::cast`](crate::Buffer::cast) for them,
+    /// this function returns [`DeviceError::UntypedDeviceMismatch`].
+    pub fn cast(&self, ty: Type) -> crate::Result<UntypedData> {
+        Ok(match self {
+            UntypedData::CPU(cpu) => UntypedData::CPU(cpu.cast(ty)),
+            UntypedData::CUDA(cuda) => UntypedData::CUDA(cuda.cast(ty)?),
+            #[cfg(feature = "opencl")]
+            UntypedData::OpenCL(_) => return Err(DeviceError::UntypedDeviceMismatch.into()),
+            #[cfg(feature = "vulkan")]
+            UntypedData::Vulkan(vk) => UntypedData::Vulkan(vk.cast(ty)?),
+        })
+    }
+
+    /// Copies the elements into host memory.
+    /// `device` must be the device the storage was allocated with.
+    pub fn to_host(&self, device: &Untyped) -> crate::Result<CpuStorage> {
+        match (self, &device.device) {
+            (UntypedData::CPU(cpu), _) => Ok(cpu.to_host()),
+            (UntypedData::CUDA(cuda), _) => cuda.to_host(),
+            #[cfg(feature = "opencl")]
+            (UntypedData::OpenCL(cl), super::untyped_device::UntypedDevice::OpenCL(device)) => {
+                Ok(cl.to_host(device))
+            }
+            #[cfg(feature = "vulkan")]
+            (UntypedData::Vulkan(vk), _) => Ok(vk.to_host()),
+            #[allow(unreachable_patterns)]
+            _ => Err(DeviceError::UntypedDeviceMismatch.into()),
+        }
+    }
+
+    /// Allocates a copy of the host storage `host` on `device`.
+    #[inline]
+    pub fn from_host(host: &CpuStorage, device: &Untyped) -> crate::Result<UntypedData> {
+        match_storage!(host, CpuStorage, ptr: E => Alloc::<E>::alloc_from_slice::<()>(device, ptr))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{cpu::CPUPtr, CPU};
+    use crate::{cpu::CPUPtr, untyped::Type, DeviceError, ErrorKind, CPU};
 
     use super::{CpuStorage, UntypedData};
 
@@ -138,25 +388,56 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn test_convert_untyped_to_typed_checked_mismatching_data_types() {
         let data = UntypedData::CPU(CpuStorage::U32(CPUPtr::new_initialized(
             10,
             crate::flag::AllocFlag::None,
         )));
-        data.convert_to_typed::<f32, CPU, ()>().unwrap();
+        let err = data.convert_to_typed::<f32, CPU, ()>().err().unwrap();
+        assert_eq!(
+            err.kind::<DeviceError>(),
+            Some(&DeviceError::UntypedTypeMismatch)
+        );
     }
 
     #[test]
-    #[should_panic]
     fn test_convert_untyped_to_typed_checked_mismatching_device_types() {
         use crate::untyped::untyped_device::Cuda;
 
-        let data = UntypedData::CPU(CpuStorage::U32(CPUPtr::new_initialized(
+        let mut data = UntypedData::CPU(CpuStorage::U32(CPUPtr::new_initialized(
             10,
             crate::flag::AllocFlag::None,
         )));
-        data.convert_to_typed::<u32, Cuda<crate::Base>, ()>()
+        let err = data
+            .convert_to_typed_mut::<u32, Cuda<crate::Base>, ()>()
+            .err()
             .unwrap();
+        assert_eq!(
+            err.kind::<DeviceError>(),
+            Some(&DeviceError::UntypedDeviceMismatch)
+        );
+    }
+
+    #[test]
+    fn test_cast_untyped_data() {
+        let data = UntypedData::CPU(CpuStorage::from(CPUPtr::from_vec(vec![0i16, -3, 7])));
+        let casted = data.cast(Type::Bool).unwrap();
+        assert_eq!(casted.dtype(), Type::Bool);
+        assert_eq!(
+            casted
+                .convert_to_typed::<bool, CPU, ()>()
+                .unwrap()
+                .as_slice(),
+            [false, true, true]
+        );
+
+        let casted = data.cast(Type::F64).unwrap();
+        assert_eq!(
+            casted
+                .convert_to_typed::<f64, CPU, ()>()
+                .unwrap()
+                .as_slice(),
+            [0., -3., 7.]
+        );
     }
 }
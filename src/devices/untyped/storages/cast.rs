@@ -0,0 +1,131 @@
+use crate::untyped::AsType;
+
+/// Element types of untyped storages that can be converted into each other at runtime.
+/// Integers are converted via `i128`, everything involving floats via `f64`, which matches the behaviour of `as`.
+pub trait CastElem: AsType + Copy {
+    fn to_i128(self) -> i128;
+    fn to_f64(self) -> f64;
+    fn from_i128(value: i128) -> Self;
+    fn from_f64(value: f64) -> Self;
+}
+
+macro_rules! impl_cast_elem {
+    ($($t:ty),*) => {
+        $(
+            impl CastElem for $t {
+                #[inline]
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                #[inline]
+                fn from_i128(value: i128) -> Self {
+                    value as $t
+                }
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    value as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_cast_elem!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl CastElem for bool {
+    #[inline]
+    fn to_i128(self) -> i128 {
+        self as i128
+    }
+
+    #[inline]
+    fn to_f64(self) -> f64 {
+        self as u8 as f64
+    }
+
+    #[inline]
+    fn from_i128(value: i128) -> Self {
+        value != 0
+    }
+
+    #[inline]
+    fn from_f64(value: f64) -> Self {
+        value != 0.
+    }
+}
+
+#[cfg(feature = "half")]
+macro_rules! impl_cast_elem_half {
+    ($($t:ty),*) => {
+        $(
+            impl CastElem for $t {
+                #[inline]
+                fn to_i128(self) -> i128 {
+                    self.to_f64() as i128
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    <$t>::to_f64(self)
+                }
+
+                #[inline]
+                fn from_i128(value: i128) -> Self {
+                    <$t>::from_f64(value as f64)
+                }
+
+                #[inline]
+                fn from_f64(value: f64) -> Self {
+                    <$t>::from_f64(value)
+                }
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "half")]
+impl_cast_elem_half!(half::bf16, half::f16);
+
+/// Converts every element of `src` to `Dst`.
+pub fn cast_slice<Src: CastElem, Dst: CastElem>(src: &[Src]) -> Vec<Dst> {
+    if Src::TYPE.is_float() || Dst::TYPE.is_float() {
+        src.iter().map(|x| Dst::from_f64(x.to_f64())).collect()
+    } else {
+        src.iter().map(|x| Dst::from_i128(x.to_i128())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cast_slice;
+
+    #[test]
+    fn test_cast_slice() {
+        assert_eq!(cast_slice::<f32, u8>(&[1.7, -1., 300.]), [1, 0, 255]);
+        assert_eq!(cast_slice::<i32, i8>(&[-1, 129]), [-1, -127]);
+        assert_eq!(cast_slice::<u64, f64>(&[1 << 40]), [(1u64 << 40) as f64]);
+        assert_eq!(cast_slice::<i16, bool>(&[0, -2]), [false, true]);
+        assert_eq!(cast_slice::<bool, f32>(&[true, false]), [1., 0.]);
+        assert_eq!(cast_slice::<u64, i64>(&[u64::MAX]), [-1]);
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_cast_slice_half() {
+        assert_eq!(
+            cast_slice::<i32, half::f16>(&[3]),
+            [half::f16::from_f32(3.)]
+        );
+        assert_eq!(
+            cast_slice::<half::bf16, u16>(&[half::bf16::from_f32(2.5)]),
+            [2]
+        );
+    }
+}
@@ -1,13 +1,21 @@
 use crate::{
     cpu::CPUPtr,
     untyped::{AsType, Type},
-    HasId, PtrType,
+    DeviceError, HasId, PtrType,
 };
 
+use super::cast_slice;
+
 #[derive(Debug)]
 pub enum CpuStorage {
+    Bool(CPUPtr<bool>),
     U8(CPUPtr<u8>),
+    U16(CPUPtr<u16>),
     U32(CPUPtr<u32>),
+    U64(CPUPtr<u64>),
+    I8(CPUPtr<i8>),
+    I16(CPUPtr<i16>),
+    I32(CPUPtr<i32>),
     I64(CPUPtr<i64>),
     #[cfg(feature = "half")]
     BF16(CPUPtr<half::bf16>),
@@ -17,106 +25,71 @@ pub enum CpuStorage {
     F64(CPUPtr<f64>),
 }
 
+impl CpuStorage {
+    /// Returns the data type of the elements.
+    #[inline]
+    pub fn dtype(&self) -> Type {
+        match_storage!(self, CpuStorage, _ptr: E => E::TYPE)
+    }
+
+    /// Converts the elements to the data type `ty`. See [`UntypedData::cast`](super::UntypedData::cast).
+    pub fn cast(&self, ty: Type) -> CpuStorage {
+        match_storage!(self, CpuStorage, ptr: Src => {
+            match_type!(ty, Dst => CpuStorage::from(CPUPtr::from_vec(cast_slice::<Src, Dst>(ptr))))
+        })
+    }
+
+    /// Returns a copy of the storage.
+    #[inline]
+    pub fn to_host(&self) -> CpuStorage {
+        match_storage!(self, CpuStorage, ptr: _E => CpuStorage::from(CPUPtr::from_vec(ptr.to_vec())))
+    }
+}
+
 impl PtrType for CpuStorage {
     #[inline]
     fn size(&self) -> usize {
-        match self {
-            CpuStorage::U8(ptr) => ptr.size(),
-            CpuStorage::U32(ptr) => ptr.size(),
-            CpuStorage::I64(ptr) => ptr.size(),
-            #[cfg(feature = "half")]
-            CpuStorage::BF16(ptr) => ptr.size(),
-            #[cfg(feature = "half")]
-            CpuStorage::F16(ptr) => ptr.size(),
-            CpuStorage::F32(ptr) => ptr.size(),
-            CpuStorage::F64(ptr) => ptr.size(),
-        }
+        match_storage!(self, CpuStorage, ptr: _E => ptr.size())
     }
 
     #[inline]
     fn flag(&self) -> crate::flag::AllocFlag {
-        match self {
-            CpuStorage::U8(ptr) => ptr.flag(),
-            CpuStorage::U32(ptr) => ptr.flag(),
-            CpuStorage::I64(ptr) => ptr.flag(),
-            #[cfg(feature = "half")]
-            CpuStorage::BF16(ptr) => ptr.flag(),
-            #[cfg(feature = "half")]
-            CpuStorage::F16(ptr) => ptr.flag(),
-            CpuStorage::F32(ptr) => ptr.flag(),
-            CpuStorage::F64(ptr) => ptr.flag(),
-        }
+        match_storage!(self, CpuStorage, ptr: _E => ptr.flag())
     }
 
     #[inline]
     unsafe fn set_flag(&mut self, flag: crate::flag::AllocFlag) {
-        match self {
-            CpuStorage::U8(ptr) => ptr.set_flag(flag),
-            CpuStorage::U32(ptr) => ptr.set_flag(flag),
-            CpuStorage::I64(ptr) => ptr.set_flag(flag),
-            #[cfg(feature = "half")]
-            CpuStorage::BF16(ptr) => ptr.set_flag(flag),
-            #[cfg(feature = "half")]
-            CpuStorage::F16(ptr) => ptr.set_flag(flag),
-            CpuStorage::F32(ptr) => ptr.set_flag(flag),
-            CpuStorage::F64(ptr) => ptr.set_flag(flag),
-        }
+        match_storage!(self, CpuStorage, ptr: _E => ptr.set_flag(flag))
     }
 }
 
 impl HasId for CpuStorage {
     #[inline]
     fn id(&self) -> crate::Id {
-        match self {
-            CpuStorage::U8(ptr) => ptr.id(),
-            CpuStorage::U32(ptr) => ptr.id(),
-            CpuStorage::I64(ptr) => ptr.id(),
-            #[cfg(feature = "half")]
-            CpuStorage::BF16(ptr) => ptr.id(),
-            #[cfg(feature = "half")]
-            CpuStorage::F16(ptr) => ptr.id(),
-            CpuStorage::F32(ptr) => ptr.id(),
-            CpuStorage::F64(ptr) => ptr.id(),
-        }
+        match_storage!(self, CpuStorage, ptr: _E => ptr.id())
     }
 }
 
 impl crate::untyped::MatchesType for CpuStorage {
-    fn matches_storage_type<T: AsType>(&self) -> Result<(), String> {
-        match (T::TYPE, self) {
-            (Type::U8, CpuStorage::U8(_)) => Ok(()),
-            (Type::U32, CpuStorage::U32(_)) => Ok(()),
-            (Type::I64, CpuStorage::I64(_)) => Ok(()),
-            #[cfg(feature = "half")]
-            (Type::BF16, CpuStorage::BF16(_)) => Ok(()),
-            #[cfg(feature = "half")]
-            (Type::F16, CpuStorage::F16(_)) => Ok(()),
-            (Type::F32, CpuStorage::F32(_)) => Ok(()),
-            (Type::F64, CpuStorage::F64(_)) => Ok(()),
-            _ => Err("Storage type mismatch".into()),
+    #[inline]
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()> {
+        if self.dtype() != T::TYPE {
+            return Err(DeviceError::UntypedTypeMismatch.into());
         }
+        Ok(())
     }
 }
 
 impl<T: AsType> From<CPUPtr<T>> for CpuStorage {
+    #[inline]
     fn from(data: CPUPtr<T>) -> Self {
-        match T::TYPE {
-            Type::U8 => CpuStorage::U8(unsafe { std::mem::transmute(data) }),
-            Type::U32 => CpuStorage::U32(unsafe { std::mem::transmute(data) }),
-            Type::I64 => CpuStorage::I64(unsafe { std::mem::transmute(data) }),
-            #[cfg(feature = "half")]
-            Type::BF16 => CpuStorage::BF16(unsafe { std::mem::transmute(data) }),
-            #[cfg(feature = "half")]
-            Type::F16 => CpuStorage::F16(unsafe { std::mem::transmute(data) }),
-            Type::F32 => CpuStorage::F32(unsafe { std::mem::transmute(data) }),
-            Type::F64 => CpuStorage::F64(unsafe { std::mem::transmute(data) }),
-        }
+        storage_from_typed!(CpuStorage, CPUPtr<T>, data)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::cpu::CPUPtr;
+    use crate::{cpu::CPUPtr, untyped::Type};
 
     use super::CpuStorage;
 
@@ -129,4 +102,16 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn test_cpu_storage_dtypes() {
+        let storage = CpuStorage::from(CPUPtr::from_vec(vec![-1i8, 2]));
+        assert_eq!(storage.dtype(), Type::I8);
+
+        let storage = CpuStorage::from(CPUPtr::from_vec(vec![true, false]));
+        assert_eq!(storage.dtype(), Type::Bool);
+
+        let storage = CpuStorage::from(CPUPtr::from_vec(vec![1u64 << 40]));
+        assert_eq!(storage.dtype(), Type::U64);
+    }
 }
@@ -1,5 +1,5 @@
 #[cfg(feature = "cuda")]
-use crate::cuda::CUDAPtr;
+use crate::{cuda::CUDAPtr, DeviceError};
 use crate::{
     untyped::{AsType, MatchesType, Type},
     HasId, PtrType,
@@ -8,8 +8,14 @@ use crate::{
 #[cfg(feature = "cuda")]
 #[derive(Debug)]
 pub enum CudaStorage {
+    Bool(CUDAPtr<bool>),
     U8(CUDAPtr<u8>),
+    U16(CUDAPtr<u16>),
     U32(CUDAPtr<u32>),
+    U64(CUDAPtr<u64>),
+    I8(CUDAPtr<i8>),
+    I16(CUDAPtr<i16>),
+    I32(CUDAPtr<i32>),
     I64(CUDAPtr<i64>),
     #[cfg(feature = "half")]
     BF16(CUDAPtr<half::bf16>),
@@ -19,51 +25,51 @@ pub enum CudaStorage {
     F64(CUDAPtr<f64>),
 }
 
+#[cfg(feature = "cuda")]
+impl CudaStorage {
+    /// Returns the data type of the elements.
+    #[inline]
+    pub fn dtype(&self) -> Type {
+        match_storage!(self, CudaStorage, _ptr: E => E::TYPE)
+    }
+
+    /// Converts the elements to the data type `ty`. See [`UntypedData::cast`](super::UntypedData::cast).
+    /// The conversion happens on the host.
+    pub fn cast(&self, ty: Type) -> crate::Result<CudaStorage> {
+        match_storage!(self, CudaStorage, ptr: Src => {
+            match_type!(ty, Dst => {
+                let host = super::cast_slice::<Src, Dst>(&ptr.read());
+                let out = CUDAPtr::<Dst>::new(host.len(), crate::flag::AllocFlag::None)?;
+                crate::cuda::api::cu_write(out.ptr, &host)?;
+                Ok(CudaStorage::from(out))
+            })
+        })
+    }
+
+    /// Copies the elements into host memory.
+    #[inline]
+    pub fn to_host(&self) -> crate::Result<super::CpuStorage> {
+        Ok(match_storage!(self, CudaStorage, ptr: _E => {
+            super::CpuStorage::from(crate::cpu::CPUPtr::from_vec(ptr.read()))
+        }))
+    }
+}
+
 #[cfg(feature = "cuda")]
 impl PtrType for CudaStorage {
     #[inline]
     fn size(&self) -> usize {
-        match self {
-            CudaStorage::U8(ptr) => ptr.size(),
-            CudaStorage::U32(ptr) => ptr.size(),
-            CudaStorage::I64(ptr) => ptr.size(),
-            #[cfg(feature = "half")]
-            CudaStorage::BF16(ptr) => ptr.size(),
-            #[cfg(feature = "half")]
-            CudaStorage::F16(ptr) => ptr.size(),
-            CudaStorage::F32(ptr) => ptr.size(),
-            CudaStorage::F64(ptr) => ptr.size(),
-        }
+        match_storage!(self, CudaStorage, ptr: _E => ptr.size())
     }
 
     #[inline]
     fn flag(&self) -> crate::flag::AllocFlag {
-        match self {
-            CudaStorage::U8(ptr) => ptr.flag(),
-            CudaStorage::U32(ptr) => ptr.flag(),
-            CudaStorage::I64(ptr) => ptr.flag(),
-            #[cfg(feature = "half")]
-            CudaStorage::BF16(ptr) => ptr.flag(),
-            #[cfg(feature = "half")]
-            CudaStorage::F16(ptr) => ptr.flag(),
-            CudaStorage::F32(ptr) => ptr.flag(),
-            CudaStorage::F64(ptr) => ptr.flag(),
-        }
+        match_storage!(self, CudaStorage, ptr: _E => ptr.flag())
     }
 
     #[inline]
     unsafe fn set_flag(&mut self, flag: crate::flag::AllocFlag) {
-        match self {
-            CudaStorage::U8(ptr) => ptr.set_flag(flag),
-            CudaStorage::U32(ptr) => ptr.set_flag(flag),
-            CudaStorage::I64(ptr) => ptr.set_flag(flag),
-            #[cfg(feature = "half")]
-            CudaStorage::BF16(ptr) => ptr.set_flag(flag),
-            #[cfg(feature = "half")]
-            CudaStorage::F16(ptr) => ptr.set_flag(flag),
-            CudaStorage::F32(ptr) => ptr.set_flag(flag),
-            CudaStorage::F64(ptr) => ptr.set_flag(flag),
-        }
+        match_storage!(self, CudaStorage, ptr: _E => ptr.set_flag(flag))
     }
 }
 
@@ -71,83 +77,72 @@ impl PtrType for CudaStorage {
 impl HasId for CudaStorage {
     #[inline]
     fn id(&self) -> crate::Id {
-        match self {
-            CudaStorage::U8(ptr) => ptr.id(),
-            CudaStorage::U32(ptr) => ptr.id(),
-            CudaStorage::I64(ptr) => ptr.id(),
-            #[cfg(feature = "half")]
-            CudaStorage::BF16(ptr) => ptr.id(),
-            #[cfg(feature = "half")]
-            CudaStorage::F16(ptr) => ptr.id(),
-            CudaStorage::F32(ptr) => ptr.id(),
-            CudaStorage::F64(ptr) => ptr.id(),
-        }
+        match_storage!(self, CudaStorage, ptr: _E => ptr.id())
     }
 }
 
 #[cfg(feature = "cuda")]
 impl MatchesType for CudaStorage {
-    fn matches_storage_type<T: AsType>(&self) -> Result<(), String> {
-        match (T::TYPE, self) {
-            (Type::U8, CudaStorage::U8(_)) => Ok(()),
-            (Type::U32, CudaStorage::U32(_)) => Ok(()),
-            (Type::I64, CudaStorage::I64(_)) => Ok(()),
-            #[cfg(feature = "half")]
-            (Type::BF16, CudaStorage::BF16(_)) => Ok(()),
-            #[cfg(feature = "half")]
-            (Type::F16, CudaStorage::F16(_)) => Ok(()),
-            (Type::F32, CudaStorage::F32(_)) => Ok(()),
-            (Type::F64, CudaStorage::F64(_)) => Ok(()),
-            _ => Err("Storage type mismatch".into()),
+    #[inline]
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()> {
+        if self.dtype() != T::TYPE {
+            return Err(DeviceError::UntypedTypeMismatch.into());
         }
+        Ok(())
     }
 }
 
 #[cfg(feature = "cuda")]
 impl<T: AsType> From<CUDAPtr<T>> for CudaStorage {
+    #[inline]
     fn from(data: CUDAPtr<T>) -> Self {
-        match T::TYPE {
-            Type::U8 => CudaStorage::U8(unsafe { std::mem::transmute(data) }),
-            Type::U32 => CudaStorage::U32(unsafe { std::mem::transmute(data) }),
-            Type::I64 => CudaStorage::I64(unsafe { std::mem::transmute(data) }),
-            #[cfg(feature = "half")]
-            Type::BF16 => CudaStorage::BF16(unsafe { std::mem::transmute(data) }),
-            #[cfg(feature = "half")]
-            Type::F16 => CudaStorage::F16(unsafe { std::mem::transmute(data) }),
-            Type::F32 => CudaStorage::F32(unsafe { std::mem::transmute(data) }),
-            Type::F64 => CudaStorage::F64(unsafe { std::mem::transmute(data) }),
-        }
+        storage_from_typed!(CudaStorage, CUDAPtr<T>, data)
     }
 }
 
 #[cfg(not(feature = "cuda"))]
 pub enum CudaStorage {}
 
+#[cfg(not(feature = "cuda"))]
+impl CudaStorage {
+    pub fn dtype(&self) -> Type {
+        match *self {}
+    }
+
+    pub fn cast(&self, _ty: Type) -> crate::Result<CudaStorage> {
+        match *self {}
+    }
+
+    pub fn to_host(&self) -> crate::Result<super::CpuStorage> {
+        match *self {}
+    }
+}
+
 #[cfg(not(feature = "cuda"))]
 impl PtrType for CudaStorage {
     fn size(&self) -> usize {
-        unimplemented!()
+        match *self {}
     }
 
     fn flag(&self) -> crate::flag::AllocFlag {
-        unimplemented!()
+        match *self {}
     }
 
     unsafe fn set_flag(&mut self, _flag: crate::flag::AllocFlag) {
-        unimplemented!()
+        match *self {}
     }
 }
 
 #[cfg(not(feature = "cuda"))]
 impl HasId for CudaStorage {
     fn id(&self) -> crate::Id {
-        unimplemented!()
+        match *self {}
     }
 }
 
 #[cfg(not(feature = "cuda"))]
 impl MatchesType for CudaStorage {
-    fn matches_storage_type<T: AsType>(&self) -> Result<(), String> {
-        unimplemented!()
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()> {
+        match *self {}
     }
 }
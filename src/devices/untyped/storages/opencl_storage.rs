@@ -0,0 +1,84 @@
+use crate::{
+    cpu::CPUPtr,
+    opencl::CLPtr,
+    untyped::{AsType, MatchesType, Type},
+    DeviceError, HasId, OpenCL, PtrType, Read,
+};
+
+/// The storage of [`Untyped`](crate::untyped::Untyped) buffers allocated with an [`OpenCL`](crate::OpenCL) device.
+/// Unlike the other storages, reading an OpenCL storage requires its device.
+#[derive(Debug)]
+pub enum OpenCLStorage {
+    Bool(CLPtr<bool>),
+    U8(CLPtr<u8>),
+    U16(CLPtr<u16>),
+    U32(CLPtr<u32>),
+    U64(CLPtr<u64>),
+    I8(CLPtr<i8>),
+    I16(CLPtr<i16>),
+    I32(CLPtr<i32>),
+    I64(CLPtr<i64>),
+    #[cfg(feature = "half")]
+    BF16(CLPtr<half::bf16>),
+    #[cfg(feature = "half")]
+    F16(CLPtr<half::f16>),
+    F32(CLPtr<f32>),
+    F64(CLPtr<f64>),
+}
+
+impl OpenCLStorage {
+    /// Returns the data type of the elements.
+    #[inline]
+    pub fn dtype(&self) -> Type {
+        match_storage!(self, OpenCLStorage, _ptr: E => E::TYPE)
+    }
+
+    /// Copies the elements into host memory. `device` must be the device the storage was allocated with.
+    #[inline]
+    pub fn to_host(&self, device: &OpenCL) -> super::CpuStorage {
+        match_storage!(self, OpenCLStorage, ptr: E => {
+            super::CpuStorage::from(CPUPtr::from_vec(Read::<E, ()>::read_to_vec(device, ptr)))
+        })
+    }
+}
+
+impl PtrType for OpenCLStorage {
+    #[inline]
+    fn size(&self) -> usize {
+        match_storage!(self, OpenCLStorage, ptr: _E => ptr.size())
+    }
+
+    #[inline]
+    fn flag(&self) -> crate::flag::AllocFlag {
+        match_storage!(self, OpenCLStorage, ptr: _E => ptr.flag())
+    }
+
+    #[inline]
+    unsafe fn set_flag(&mut self, flag: crate::flag::AllocFlag) {
+        match_storage!(self, OpenCLStorage, ptr: _E => ptr.set_flag(flag))
+    }
+}
+
+impl HasId for OpenCLStorage {
+    #[inline]
+    fn id(&self) -> crate::Id {
+        match_storage!(self, OpenCLStorage, ptr: _E => ptr.id())
+    }
+}
+
+impl MatchesType for OpenCLStorage {
+    #[inline]
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()> {
+        if self.dtype() != T::TYPE {
+            return Err(DeviceError::UntypedTypeMismatch.into());
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsType> From<CLPtr<T>> for OpenCLStorage {
+    #[inline]
+    fn from(data: CLPtr<T>) -> Self {
+        storage_from_typed!(OpenCLStorage, CLPtr<T>, data)
+    }
+}
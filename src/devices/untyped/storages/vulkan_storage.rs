@@ -0,0 +1,103 @@
+use ash::vk::BufferUsageFlags;
+
+use crate::{
+    flag::AllocFlag,
+    untyped::{AsType, MatchesType, Type},
+    vulkan::VkArray,
+    DeviceError, HasId, PtrType,
+};
+
+/// The storage of [`Untyped`](crate::untyped::Untyped) buffers allocated with a [`Vulkan`](crate::Vulkan) device.
+pub enum VulkanStorage {
+    Bool(VkArray<bool>),
+    U8(VkArray<u8>),
+    U16(VkArray<u16>),
+    U32(VkArray<u32>),
+    U64(VkArray<u64>),
+    I8(VkArray<i8>),
+    I16(VkArray<i16>),
+    I32(VkArray<i32>),
+    I64(VkArray<i64>),
+    #[cfg(feature = "half")]
+    BF16(VkArray<half::bf16>),
+    #[cfg(feature = "half")]
+    F16(VkArray<half::f16>),
+    F32(VkArray<f32>),
+    F64(VkArray<f64>),
+}
+
+impl VulkanStorage {
+    /// Returns the data type of the elements.
+    #[inline]
+    pub fn dtype(&self) -> Type {
+        match_storage!(self, VulkanStorage, _ptr: E => E::TYPE)
+    }
+
+    /// Converts the elements to the data type `ty`. See [`UntypedData::cast`](super::UntypedData::cast).
+    /// The conversion happens on the host.
+    pub fn cast(&self, ty: Type) -> crate::Result<VulkanStorage> {
+        match_storage!(self, VulkanStorage, ptr: Src => {
+            match_type!(ty, Dst => {
+                let host = super::cast_slice::<Src, Dst>(&ptr.read_staged_to_vec());
+                let out = VkArray::from_slice(
+                    ptr.context.clone(),
+                    &host,
+                    BufferUsageFlags::STORAGE_BUFFER
+                        | BufferUsageFlags::TRANSFER_SRC
+                        | BufferUsageFlags::TRANSFER_DST,
+                    AllocFlag::None,
+                )?;
+                Ok(VulkanStorage::from(out))
+            })
+        })
+    }
+
+    /// Copies the elements into host memory.
+    #[inline]
+    pub fn to_host(&self) -> super::CpuStorage {
+        match_storage!(self, VulkanStorage, ptr: _E => {
+            super::CpuStorage::from(crate::cpu::CPUPtr::from_vec(ptr.read_staged_to_vec()))
+        })
+    }
+}
+
+impl PtrType for VulkanStorage {
+    #[inline]
+    fn size(&self) -> usize {
+        match_storage!(self, VulkanStorage, ptr: _E => ptr.size())
+    }
+
+    #[inline]
+    fn flag(&self) -> AllocFlag {
+        match_storage!(self, VulkanStorage, ptr: _E => ptr.flag())
+    }
+
+    #[inline]
+    unsafe fn set_flag(&mut self, flag: AllocFlag) {
+        match_storage!(self, VulkanStorage, ptr: _E => ptr.set_flag(flag))
+    }
+}
+
+impl HasId for VulkanStorage {
+    #[inline]
+    fn id(&self) -> crate::Id {
+        match_storage!(self, VulkanStorage, ptr: _E => ptr.id())
+    }
+}
+
+impl MatchesType for VulkanStorage {
+    #[inline]
+    fn matches_storage_type<T: AsType>(&self) -> crate::Result<()> {
+        if self.dtype() != T::TYPE {
+            return Err(DeviceError::UntypedTypeMismatch.into());
+        }
+        Ok(())
+    }
+}
+
+impl<T: AsType> From<VkArray<T>> for VulkanStorage {
+    #[inline]
+    fn from(data: VkArray<T>) -> Self {
+        storage_from_typed!(VulkanStorage, VkArray<T>, data)
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
-    Alloc, Base, Buffer, Device, HasId, HasModules, IsShapeIndep, OnDropBuffer, OnNewBuffer,
-    PtrType, Retriever, Shape, Unit, WrappedData, CPU,
+    Alloc, Base, Buffer, Device, DeviceError, HasId, HasModules, IsShapeIndep, OnDropBuffer,
+    OnNewBuffer, PtrType, Retriever, Shape, Unit, WrappedData, CPU,
 };
 
 use super::{
     storages::{CpuStorage, CudaStorage, UntypedData},
-    AsType,
+    AsType, DeviceDescriptor,
 };
 
 #[cfg(feature = "cuda")]
@@ -17,12 +17,41 @@ pub type Cuda<Mods> = super::CUDA<Mods>;
 pub enum UntypedDevice {
     Cpu(CPU<Base>),
     Cuda(Cuda<Base>),
+    #[cfg(feature = "opencl")]
+    OpenCL(crate::OpenCL<Base>),
+    #[cfg(feature = "vulkan")]
+    Vulkan(crate::Vulkan<Base>),
 }
 
+/// A device whose buffers carry their data type at runtime.
+/// The same code can run on whichever backend is available; see [`DeviceDescriptor`] for the selection.
 pub struct Untyped {
     pub device: UntypedDevice,
 }
 
+impl Untyped {
+    /// Creates an untyped device using the backend described by `desc`.
+    /// Fails with [`DeviceError::UntypedDeviceUnavailable`] if the feature of the backend is disabled.
+    pub fn from_descriptor(desc: DeviceDescriptor) -> crate::Result<Untyped> {
+        let device = match desc {
+            DeviceDescriptor::Cpu => UntypedDevice::Cpu(CPU::based()),
+            #[cfg(feature = "cuda")]
+            DeviceDescriptor::Cuda(idx) => UntypedDevice::Cuda(crate::CUDA::<Base>::new(idx)?),
+            #[cfg(feature = "opencl")]
+            DeviceDescriptor::OpenCL(idx) => {
+                UntypedDevice::OpenCL(crate::OpenCL::<Base>::new(idx)?)
+            }
+            #[cfg(feature = "vulkan")]
+            DeviceDescriptor::Vulkan(idx) => {
+                UntypedDevice::Vulkan(crate::Vulkan::<Base>::new(idx)?)
+            }
+            #[allow(unreachable_patterns)]
+            _ => return Err(DeviceError::UntypedDeviceUnavailable.into()),
+        };
+        Ok(Untyped { device })
+    }
+}
+
 impl Device for Untyped {
     type Base<T: Unit, S: crate::Shape> = UntypedData;
     type Data<T: Unit, S: crate::Shape> = UntypedData;
@@ -55,14 +84,19 @@ impl Device for Untyped {
         data
     }
 
-    #[inline]
+    /// Uses the backend given by the environment variable `CUSTOS_UNTYPED_DEVICE`, e.g. `opencl:1`.
+    /// Otherwise, the first entry of [`DeviceDescriptor::candidates`] is used, i.e. the preferred enabled accelerator or the CPU if no accelerator feature is enabled.
+    ///
+    /// Errors while creating the selected backend are returned; there is no silent fallback to the CPU.
     fn new() -> Result<Self, Self::Error> {
-        Ok(Untyped {
-            #[cfg(not(feature = "cuda"))]
-            device: UntypedDevice::Cpu(CPU::based()),
-            #[cfg(feature = "cuda")]
-            device: UntypedDevice::Cuda(crate::CUDA::<Base>::new(crate::cuda::chosen_cu_idx())?),
-        })
+        let desc = match DeviceDescriptor::from_env()? {
+            Some(desc) => desc,
+            None => DeviceDescriptor::candidates()?
+                .into_iter()
+                .next()
+                .ok_or(DeviceError::UntypedDeviceUnavailable)?,
+        };
+        Untyped::from_descriptor(desc)
     }
 }
 
@@ -75,6 +109,10 @@ impl HasModules for Untyped {
         match &self.device {
             UntypedDevice::Cpu(cpu) => &cpu.modules,
             UntypedDevice::Cuda(cuda) => &cuda.modules,
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => &cl.modules,
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(vk) => &vk.modules,
         }
     }
 }
@@ -126,6 +164,14 @@ impl<T: AsType> Alloc<T> for Untyped {
                 #[cfg(not(feature = "cuda"))]
                 unimplemented!()
             }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => UntypedData::OpenCL(super::storages::OpenCLStorage::from(
+                Alloc::<T>::alloc::<S>(cl, len, flag)?,
+            )),
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(vk) => UntypedData::Vulkan(super::storages::VulkanStorage::from(
+                Alloc::<T>::alloc::<S>(vk, len, flag)?,
+            )),
         })
     }
 
@@ -148,6 +194,14 @@ impl<T: AsType> Alloc<T> for Untyped {
                 #[cfg(not(feature = "cuda"))]
                 unimplemented!()
             }
+            #[cfg(feature = "opencl")]
+            UntypedDevice::OpenCL(cl) => UntypedData::OpenCL(super::storages::OpenCLStorage::from(
+                Alloc::<T>::alloc_from_slice::<S>(cl, data)?,
+            )),
+            #[cfg(feature = "vulkan")]
+            UntypedDevice::Vulkan(vk) => UntypedData::Vulkan(super::storages::VulkanStorage::from(
+                Alloc::<T>::alloc_from_slice::<S>(vk, data)?,
+            )),
         })
     }
 }
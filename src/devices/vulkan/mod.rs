@@ -10,6 +10,7 @@ mod vk_array;
 mod vulkan_device;
 
 pub use context::*;
+pub use ops::*;
 pub use shader::*;
 pub use vk_array::*;
 pub use vulkan_device::*;
@@ -1,10 +1,14 @@
 use core::fmt::Debug;
 
 use crate::{
-    cpu_stack_ops::clear_slice, pass_down_add_operation, pass_down_exec_now, prelude::Number,
-    AddOperation, ApplyFunction, Buffer, CDatatype, ClearBuf, OnDropBuffer, Read, Resolve,
-    Retrieve, Retriever, Shape, ToCLSource, ToMarker, ToWgslSource, UnaryGrad, Unit, UseGpuOrCpu,
-    Vulkan, WriteBuf, ZeroGrad,
+    cpu_stack_ops::clear_slice,
+    gemm::{check_gemm_lengths, gemm_out_len},
+    pass_down_add_operation, pass_down_exec_now,
+    prelude::Number,
+    wgsl::{gemm_workgroups, wgsl_dtype, wgsl_enable_directives, wgsl_gemm_src},
+    AddOperation, ApplyFunction, Buffer, CDatatype, ClearBuf, Gemm, GemmGrad, MayTangentActions,
+    OnDropBuffer, Read, Resolve, Retrieve, Retriever, Shape, ToCLSource, ToMarker, ToWgslSource,
+    UnaryGrad, Unit, UseGpuOrCpu, Vulkan, WriteBuf, ZeroGrad,
 };
 
 use super::{VkArray, VkDevice};
@@ -20,6 +24,7 @@ impl<Mods: OnDropBuffer + UseGpuOrCpu, T: CDatatype + Default + Debug> ClearBuf<
         let cpu_buf = unsafe { &mut *(buf as *mut Buffer<T, Vulkan<Mods>>) };
         self.use_cpu_or_gpu(
             (file!(), line!(), column!()).into(),
+            &[],
             &[buf.len()],
             || clear_slice(cpu_buf),
             || try_vk_clear(self, buf).unwrap(),
@@ -27,19 +32,20 @@ impl<Mods: OnDropBuffer + UseGpuOrCpu, T: CDatatype + Default + Debug> ClearBuf<
     }
 }
 
-impl<Mods: OnDropBuffer, T: Unit + Default + Debug> ZeroGrad<T> for Vulkan<Mods> {
+impl<Mods: OnDropBuffer, T: Unit + Default + Debug + 'static> ZeroGrad<T> for Vulkan<Mods> {
     #[inline]
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>) {
         try_vk_clear(self, data).unwrap()
     }
 }
 
-pub fn try_vk_clear<T: Default + Debug>(
+pub fn try_vk_clear<T: Default + Debug + 'static>(
     device: &VkDevice,
     buf: &mut VkArray<T>,
 ) -> crate::Result<()> {
     let src = format!(
-        "@group(0)
+        "{enable}
+        @group(0)
         @binding(0)
         var<storage, read_write> buf: array<{dtype}>;
         
@@ -52,7 +58,8 @@ pub fn try_vk_clear<T: Default + Debug>(
             buf[global_id.x] = {default:?}; 
         }}
     ",
-        dtype = std::any::type_name::<T>(),
+        enable = wgsl_enable_directives::<T>(),
+        dtype = wgsl_dtype::<T>(),
         default = T::default(),
     );
 
@@ -107,6 +114,7 @@ impl<Mods, T, S> ApplyFunction<T, S> for Vulkan<Mods>
 where
     T: Number,
     Mods: AddOperation + Retrieve<Self, T, S> + UseGpuOrCpu + 'static,
+    Self: MayTangentActions,
     S: Shape,
 {
     #[inline]
@@ -116,15 +124,44 @@ where
         f: impl Fn(Resolve<T>) -> F + Copy,
     ) -> Buffer<T, Self, S>
     where
-        F: crate::Eval<T> + crate::MayToCLSource + crate::MayToWgslSource,
+        F: crate::TwoWay<T>,
     {
         let mut out = self.retrieve(buf.len(), buf).unwrap();
 
+        #[cfg(feature = "forward")]
+        if let Some(mut tangents) = crate::TangentActions::tangents_mut(self) {
+            if let Some((tangent, out_tangent)) = tangents.tangent_pair(buf, &out) {
+                let cpu_out = unsafe { &mut *(&mut out as *mut Buffer<T, Vulkan<Mods>, _>) };
+                let cpu_out_tangent =
+                    unsafe { &mut *(out_tangent as *mut Buffer<T, Vulkan<Mods>, _>) };
+                self.use_cpu_or_gpu(
+                    (file!(), line!(), column!()).into(),
+                    &[buf.len(), tangent.len()],
+                    &[out.len(), out_tangent.len()],
+                    || {
+                        crate::devices::cpu_stack_ops::apply_fn_tangent_slice(
+                            buf,
+                            tangent,
+                            cpu_out,
+                            cpu_out_tangent,
+                            f,
+                        )
+                    },
+                    || {
+                        try_vk_apply_fn_tangent_mut(self, buf, tangent, &mut out, out_tangent, f)
+                            .unwrap()
+                    },
+                );
+                return out;
+            }
+        }
+
         // self.add_op(&mut out, move |out| {
         let cpu_out = unsafe { &mut *(&mut out as *mut Buffer<T, Vulkan<Mods>, _>) };
         self.use_cpu_or_gpu(
             (file!(), line!(), column!()).into(),
             &[buf.len()],
+            &[out.len()],
             || crate::devices::cpu_stack_ops::apply_fn_slice(buf, cpu_out, f),
             || try_vk_apply_fn_mut(self, &buf, &mut out, f).unwrap(),
         );
@@ -143,11 +180,12 @@ pub fn try_vk_apply_fn_mut<T, F>(
     f: impl Fn(Resolve<T>) -> F,
 ) -> crate::Result<()>
 where
-    T: Number,
+    T: Unit + Default + 'static,
     F: ToWgslSource,
 {
     let src = format!(
         "
+        {enable}
         @group(0)
         @binding(0)
         var<storage, read_write> x: array<{dtype}>;
@@ -166,12 +204,69 @@ where
         }}
 
     ",
-        dtype = std::any::type_name::<T>(),
+        enable = wgsl_enable_directives::<T>(),
+        dtype = wgsl_dtype::<T>(),
         op = f("x[global_id.x]".to_marker()).to_wgsl_source()
     );
     device.launch_shader(src, [(32 + x.len as u32) / 32, 1, 1], &[x, out])
 }
 
+#[cfg(feature = "forward")]
+pub fn try_vk_apply_fn_tangent_mut<T, F>(
+    device: &VkDevice,
+    x: &VkArray<T>,
+    x_tangent: &VkArray<T>,
+    out: &mut VkArray<T>,
+    out_tangent: &mut VkArray<T>,
+    f: impl Fn(Resolve<T>) -> F,
+) -> crate::Result<()>
+where
+    T: Number,
+    F: crate::ToWgslTangentSource,
+{
+    let op = f("x[global_id.x]".to_marker());
+    let src = format!(
+        "
+        {enable}
+        @group(0)
+        @binding(0)
+        var<storage, read_write> x: array<{dtype}>;
+
+        @group(0)
+        @binding(1)
+        var<storage, read_write> tangent: array<{dtype}>;
+
+        @group(0)
+        @binding(2)
+        var<storage, read_write> out: array<{dtype}>;
+
+        @group(0)
+        @binding(3)
+        var<storage, read_write> out_tangent: array<{dtype}>;
+
+        @compute
+        @workgroup_size(32)
+        fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+            if global_id.x >= arrayLength(&out) {{
+                return;
+            }}
+            out[global_id.x] = {op};
+            out_tangent[global_id.x] = {tangent_op};
+        }}
+
+    ",
+        enable = wgsl_enable_directives::<T>(),
+        dtype = wgsl_dtype::<T>(),
+        op = op.to_wgsl_source(),
+        tangent_op = op.to_wgsl_tangent_source("tangent[global_id.x]"),
+    );
+    device.launch_shader(
+        src,
+        [(32 + x.len as u32) / 32, 1, 1],
+        &[x, x_tangent, out, out_tangent],
+    )
+}
+
 impl<T, S, Mods> UnaryGrad<T, S> for Vulkan<Mods>
 where
     T: CDatatype + Number,
@@ -208,6 +303,7 @@ where
 {
     let src = format!(
         "
+        {enable}
         @group(0)
         @binding(0)
         var<storage, read_write> lhs: array<{dtype}>;
@@ -230,7 +326,8 @@ where
         }}
 
     ",
-        dtype = std::any::type_name::<T>(),
+        enable = wgsl_enable_directives::<T>(),
+        dtype = wgsl_dtype::<T>(),
         op = lhs_grad_fn("lhs[global_id.x]".to_marker()).to_cl_source()
     );
     device.launch_shader(
@@ -240,6 +337,89 @@ where
     )
 }
 
+impl<Mods, T> Gemm<T> for Vulkan<Mods>
+where
+    T: Number,
+    Mods: AddOperation + Retrieve<Self, T> + 'static,
+{
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs: &Buffer<T, Self>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            lhs.device().launch_shader(
+                wgsl_gemm_src::<T>(m, k, n, false, false, false),
+                gemm_workgroups(m, n),
+                &[lhs, rhs, out],
+            )
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<Mods, T> GemmGrad<T> for Vulkan<Mods>
+where
+    T: Number,
+    Mods: OnDropBuffer + AddOperation + 'static,
+{
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, Self>,
+        lhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                rhs.device().launch_shader(
+                    wgsl_gemm_src::<T>(m, n, k, false, true, true),
+                    gemm_workgroups(m, k),
+                    &[out_grad, rhs, lhs_grad],
+                )
+            },
+        )
+        .unwrap();
+    }
+
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                lhs.device().launch_shader(
+                    wgsl_gemm_src::<T>(k, m, n, true, false, true),
+                    gemm_workgroups(k, n),
+                    &[lhs, out_grad, rhs_grad],
+                )
+            },
+        )
+        .unwrap();
+    }
+}
+
 impl<Mods: OnDropBuffer, T: Unit + Clone, S: Shape> WriteBuf<T, S> for Vulkan<Mods> {
     #[inline]
     fn write(&self, buf: &mut Buffer<T, Self, S>, data: &[T]) {
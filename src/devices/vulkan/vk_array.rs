@@ -140,10 +140,23 @@ impl<T> VkArray<T> {
         self.write_buf(&src_buffer)
     }
 
+    #[inline]
     pub fn write_buf(&self, src_buf: &VkArray<T>)
     where
         T: Clone,
     {
+        self.copy_from(src_buf, 0, 0, self.len())
+    }
+
+    /// Copies `len` elements of `src_buf`, starting at `src_offset`, to this array, starting at `dst_offset`.
+    /// Offsets and length are given in elements.
+    pub fn copy_from(
+        &self,
+        src_buf: &VkArray<T>,
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    ) {
         let ctx = &self.context;
         let device = &ctx.device;
         let command_buffer = ctx.command_buffer;
@@ -156,9 +169,9 @@ impl<T> VkArray<T> {
         unsafe { device.begin_command_buffer(command_buffer, &command_buffer_begin_info) }.unwrap();
 
         let buffer_copy_region = vk::BufferCopy::default()
-            .dst_offset(0)
-            .src_offset(0)
-            .size((self.len() * size_of::<T>()) as u64);
+            .dst_offset((dst_offset * size_of::<T>()) as u64)
+            .src_offset((src_offset * size_of::<T>()) as u64)
+            .size((len * size_of::<T>()) as u64);
 
         unsafe {
             device.cmd_copy_buffer(
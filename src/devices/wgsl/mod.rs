@@ -4,6 +4,7 @@ mod spirv;
 mod wgsl_device;
 
 pub use launch_shader::*;
+pub use ops::{gemm_workgroups, wgsl_gemm_src};
 pub use spirv::*;
 
 pub trait WgslDevice: Sized {
@@ -25,3 +26,41 @@ impl WgslNumber for i32 {}
 impl WgslNumber for u32 {}
 #[cfg(feature = "half")]
 impl WgslNumber for half::f16 {}
+
+/// Returns `true` if `T` is `f16`, which requires the `enable f16;` directive in WGSL.
+#[inline]
+fn is_f16<T: 'static>() -> bool {
+    #[cfg(feature = "half")]
+    if core::any::TypeId::of::<T>() == core::any::TypeId::of::<half::f16>() {
+        return true;
+    }
+    false
+}
+
+/// Returns the WGSL name of the scalar type `T`.
+#[inline]
+pub fn wgsl_dtype<T: 'static>() -> &'static str {
+    if is_f16::<T>() {
+        return "f16";
+    }
+    core::any::type_name::<T>()
+}
+
+/// Returns the WGSL type used to accumulate values of type `T`.
+/// `f16` values are accumulated in `f32`.
+#[inline]
+pub fn wgsl_acc_dtype<T: 'static>() -> &'static str {
+    if is_f16::<T>() {
+        return "f32";
+    }
+    wgsl_dtype::<T>()
+}
+
+/// Returns the enable directives a shader operating on `T` has to start with, e.g. `enable f16;` for `f16`.
+#[inline]
+pub fn wgsl_enable_directives<T: 'static>() -> &'static str {
+    if is_f16::<T>() {
+        return "enable f16;\n";
+    }
+    ""
+}
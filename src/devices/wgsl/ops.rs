@@ -1,9 +1,14 @@
 use crate::{
-    op_hint::unary, AddOperation, Alloc, ApplyFunction, OnDropBuffer, Read, Retrieve, Retriever,
-    SetOpHint, Shape, ToMarker, Unit,
+    gemm::{check_gemm_lengths, gemm_index_src, gemm_out_len, GEMM_TILE},
+    op_hint::{binary, unary},
+    AddOperation, Alloc, ApplyBinaryFunction, ApplyFunction, Buffer, Gemm, GemmGrad, Number,
+    OnDropBuffer, Read, Retrieve, Retriever, SetOpHint, Shape, ToMarker, Unit,
 };
 
-use super::{wgsl_device::Wgsl, AsShaderArg, WgslShaderLaunch};
+use super::{
+    wgsl_acc_dtype, wgsl_device::Wgsl, wgsl_dtype, wgsl_enable_directives, AsShaderArg,
+    WgslShaderLaunch,
+};
 
 impl<T: Unit, S: Shape, D: Read<T, S>, Mods: OnDropBuffer + 'static> Read<T, S> for Wgsl<D, Mods> {
     type Read<'a> = D::Read<'a>
@@ -50,6 +55,7 @@ where
         self.add_op((&mut out, buf), move |(out, buf)| {
             let src = format!(
                 "
+                {enable}
                 @group(0)
                 @binding(0)
                 var<storage, read_write> x: array<{dtype}>;
@@ -68,7 +74,8 @@ where
                 }}
 
             ",
-                dtype = std::any::type_name::<T>(),
+                enable = wgsl_enable_directives::<T>(),
+                dtype = wgsl_dtype::<T>(),
                 op = f("x[global_id.x]".to_marker()).to_wgsl_source()
             );
 
@@ -85,6 +92,520 @@ where
     }
 }
 
+impl<D, Mods, T, S> ApplyBinaryFunction<T, S, Self> for Wgsl<D, Mods>
+where
+    T: Unit + Default + 'static,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, S>: AsShaderArg<D>,
+    Mods: SetOpHint<T> + Retrieve<Self, T, S> + AddOperation + 'static,
+    S: Shape,
+{
+    fn apply_binary_fn<F>(
+        &self,
+        lhs: &crate::Buffer<T, Self, S>,
+        rhs: &crate::Buffer<T, Self, S>,
+        f: impl Fn(crate::Resolve<T>, crate::Resolve<T>) -> F + Copy + 'static,
+    ) -> crate::Buffer<T, Self, S>
+    where
+        F: crate::TwoWay<T> + 'static,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            let src = format!(
+                "
+                {enable}
+                @group(0)
+                @binding(0)
+                var<storage, read_write> lhs: array<{dtype}>;
+
+                @group(0)
+                @binding(1)
+                var<storage, read_write> rhs: array<{dtype}>;
+
+                @group(0)
+                @binding(2)
+                var<storage, read_write> out: array<{dtype}>;
+                
+                @compute
+                @workgroup_size(32)
+                fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+                    if global_id.x >= arrayLength(&out) {{
+                        return;    
+                    }}
+                    out[global_id.x] = {op};
+                }}
+
+            ",
+                enable = wgsl_enable_directives::<T>(),
+                dtype = wgsl_dtype::<T>(),
+                op = f(
+                    "lhs[global_id.x]".to_marker(),
+                    "rhs[global_id.x]".to_marker()
+                )
+                .to_wgsl_source()
+            );
+
+            out.device().launch_shader(
+                src,
+                [(32 + lhs.len() as u32) / 32, 1, 1],
+                &[lhs.arg(), rhs.arg(), out.arg_mut()],
+            )
+        })
+        .unwrap();
+        self.modules.set_op_hint(binary(f));
+
+        out
+    }
+}
+
+#[cfg(feature = "half")]
+impl<D, Mods, T, S> crate::ApplyFunctionF32<T, S, Self> for Wgsl<D, Mods>
+where
+    T: crate::HalfFloat + super::WgslNumber,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, S>: AsShaderArg<D>,
+    Mods: Retrieve<Self, T, S> + AddOperation + 'static,
+    S: Shape,
+{
+    fn apply_fn_f32<F>(
+        &self,
+        buf: &crate::Buffer<T, Self, S>,
+        f: impl Fn(crate::Resolve<f32>) -> F + Copy + 'static,
+    ) -> crate::Buffer<T, Self, S>
+    where
+        F: crate::TwoWay<f32> + 'static,
+    {
+        let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+        self.add_op((&mut out, buf), move |(out, buf)| {
+            let src = format!(
+                "
+                {enable}
+                @group(0)
+                @binding(0)
+                var<storage, read_write> x: array<{dtype}>;
+
+                @group(0)
+                @binding(1)
+                var<storage, read_write> out: array<{dtype}>;
+
+                @compute
+                @workgroup_size(32)
+                fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+                    if global_id.x >= arrayLength(&out) {{
+                        return;
+                    }}
+                    out[global_id.x] = {dtype}({op});
+                }}
+            ",
+                enable = wgsl_enable_directives::<T>(),
+                dtype = wgsl_dtype::<T>(),
+                op = f("f32(x[global_id.x])".to_marker()).to_wgsl_source()
+            );
+
+            out.device().launch_shader(
+                src,
+                [(32 + buf.len() as u32) / 32, 1, 1],
+                &[buf.arg(), out.arg_mut()],
+            )
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+#[cfg(feature = "half")]
+impl<D, Mods, T, S> crate::ApplyBinaryFunctionF32<T, S, Self> for Wgsl<D, Mods>
+where
+    T: crate::HalfFloat + super::WgslNumber,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, S>: AsShaderArg<D>,
+    Mods: Retrieve<Self, T, S> + AddOperation + 'static,
+    S: Shape,
+{
+    fn apply_binary_fn_f32<F>(
+        &self,
+        lhs: &crate::Buffer<T, Self, S>,
+        rhs: &crate::Buffer<T, Self, S>,
+        f: impl Fn(crate::Resolve<f32>, crate::Resolve<f32>) -> F + Copy + 'static,
+    ) -> crate::Buffer<T, Self, S>
+    where
+        F: crate::TwoWay<f32> + 'static,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            let src = format!(
+                "
+                {enable}
+                @group(0)
+                @binding(0)
+                var<storage, read_write> lhs: array<{dtype}>;
+
+                @group(0)
+                @binding(1)
+                var<storage, read_write> rhs: array<{dtype}>;
+
+                @group(0)
+                @binding(2)
+                var<storage, read_write> out: array<{dtype}>;
+
+                @compute
+                @workgroup_size(32)
+                fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+                    if global_id.x >= arrayLength(&out) {{
+                        return;
+                    }}
+                    out[global_id.x] = {dtype}({op});
+                }}
+            ",
+                enable = wgsl_enable_directives::<T>(),
+                dtype = wgsl_dtype::<T>(),
+                op = f(
+                    "f32(lhs[global_id.x])".to_marker(),
+                    "f32(rhs[global_id.x])".to_marker()
+                )
+                .to_wgsl_source()
+            );
+
+            out.device().launch_shader(
+                src,
+                [(32 + lhs.len() as u32) / 32, 1, 1],
+                &[lhs.arg(), rhs.arg(), out.arg_mut()],
+            )
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<D, Mods, T> Gemm<T, Self> for Wgsl<D, Mods>
+where
+    T: Number,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, ()>: AsShaderArg<D>,
+    Mods: Retrieve<Self, T> + AddOperation + 'static,
+{
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs: &Buffer<T, Self>,
+    ) -> Buffer<T, Self> {
+        let len = gemm_out_len(m, k, n, lhs.len(), rhs.len()).unwrap();
+        let mut out = self.retrieve(len, (lhs, rhs)).unwrap();
+
+        self.add_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+            out.device().launch_shader(
+                wgsl_gemm_src::<T>(m, k, n, false, false, false),
+                gemm_workgroups(m, n),
+                &[lhs.arg(), rhs.arg(), out.arg_mut()],
+            )
+        })
+        .unwrap();
+
+        out
+    }
+}
+
+impl<D, Mods, T> GemmGrad<T, Self> for Wgsl<D, Mods>
+where
+    T: Number,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, ()>: AsShaderArg<D>,
+    Mods: AddOperation + OnDropBuffer + 'static,
+{
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, Self>,
+        lhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs_grad.len(), rhs.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (rhs, lhs_grad, out_grad),
+            move |(rhs, lhs_grad, out_grad)| {
+                rhs.device().launch_shader(
+                    wgsl_gemm_src::<T>(m, n, k, false, true, true),
+                    gemm_workgroups(m, k),
+                    &[out_grad.arg(), rhs.arg(), lhs_grad.arg_mut()],
+                )
+            },
+        )
+        .unwrap();
+    }
+
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, Self>,
+        rhs_grad: &mut Buffer<T, Self>,
+        out_grad: &Buffer<T, Self>,
+    ) {
+        check_gemm_lengths(m, k, n, lhs.len(), rhs_grad.len(), out_grad.len()).unwrap();
+
+        self.add_op(
+            (lhs, rhs_grad, out_grad),
+            move |(lhs, rhs_grad, out_grad)| {
+                lhs.device().launch_shader(
+                    wgsl_gemm_src::<T>(k, m, n, true, false, true),
+                    gemm_workgroups(k, n),
+                    &[lhs.arg(), out_grad.arg(), rhs_grad.arg_mut()],
+                )
+            },
+        )
+        .unwrap();
+    }
+}
+
+/// Returns the number of workgroups of a [`wgsl_gemm_src`] shader computing a `m x n` matrix.
+#[inline]
+pub fn gemm_workgroups(m: usize, n: usize) -> [u32; 3] {
+    [
+        n.div_ceil(GEMM_TILE) as u32,
+        m.div_ceil(GEMM_TILE) as u32,
+        1,
+    ]
+}
+
+/// Generates a tiled WGSL shader computing `out = op(lhs) * op(rhs)`, where `op(lhs)` is `m x k` and `op(rhs)` is `k x n`.
+/// If `lhs_trans` (`rhs_trans`) is `true`, `lhs` (`rhs`) is stored transposed.
+/// If `accumulate` is `true`, the product is added to `out`.
+///
+/// The shader binds `lhs`, `rhs` and `out` in this order and is launched with [`gemm_workgroups`].
+pub fn wgsl_gemm_src<T: 'static>(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs_trans: bool,
+    rhs_trans: bool,
+    accumulate: bool,
+) -> String {
+    format!(
+        "
+        {enable}
+        @group(0)
+        @binding(0)
+        var<storage, read_write> lhs: array<{dtype}>;
+
+        @group(0)
+        @binding(1)
+        var<storage, read_write> rhs: array<{dtype}>;
+
+        @group(0)
+        @binding(2)
+        var<storage, read_write> out: array<{dtype}>;
+
+        const TILE: u32 = {GEMM_TILE}u;
+        const m: u32 = {m}u;
+        const k: u32 = {k}u;
+        const n: u32 = {n}u;
+
+        var<workgroup> lhs_tile: array<array<{dtype}, {GEMM_TILE}>, {GEMM_TILE}>;
+        var<workgroup> rhs_tile: array<array<{dtype}, {GEMM_TILE}>, {GEMM_TILE}>;
+
+        @compute
+        @workgroup_size({GEMM_TILE}, {GEMM_TILE})
+        fn main(
+            @builtin(local_invocation_id) local_id: vec3<u32>,
+            @builtin(workgroup_id) group_id: vec3<u32>
+        ) {{
+            let local_row = local_id.y;
+            let local_col = local_id.x;
+            let row = group_id.y * TILE + local_row;
+            let col = group_id.x * TILE + local_col;
+
+            var acc = {acc_dtype}(0);
+            for (var tile = 0u; tile < k; tile += TILE) {{
+                let lhs_col = tile + local_col;
+                let rhs_row = tile + local_row;
+
+                lhs_tile[local_row][local_col] = {dtype}(0);
+                if row < m && lhs_col < k {{
+                    lhs_tile[local_row][local_col] = lhs[{lhs_idx}];
+                }}
+                rhs_tile[local_row][local_col] = {dtype}(0);
+                if rhs_row < k && col < n {{
+                    rhs_tile[local_row][local_col] = rhs[{rhs_idx}];
+                }}
+                workgroupBarrier();
+
+                for (var idx = 0u; idx < TILE; idx++) {{
+                    acc += {acc_dtype}(lhs_tile[local_row][idx]) * {acc_dtype}(rhs_tile[idx][local_col]);
+                }}
+                workgroupBarrier();
+            }}
+
+            if row < m && col < n {{
+                out[row * n + col] = {dtype}({prev}acc);
+            }}
+        }}
+    ",
+        enable = wgsl_enable_directives::<T>(),
+        dtype = wgsl_dtype::<T>(),
+        acc_dtype = wgsl_acc_dtype::<T>(),
+        lhs_idx = gemm_index_src("row", "lhs_col", "m", "k", lhs_trans),
+        rhs_idx = gemm_index_src("rhs_row", "col", "k", "n", rhs_trans),
+        prev = if accumulate {
+            format!("{}(out[row * n + col]) + ", wgsl_acc_dtype::<T>())
+        } else {
+            String::new()
+        },
+    )
+}
+
+#[cfg(feature = "vulkan")]
+impl<Mods: OnDropBuffer + 'static> crate::ElementWiseFusing for Wgsl<crate::Vulkan, Mods> {
+    #[cfg(feature = "lazy")]
+    fn fused_ew<T: crate::CDatatype + crate::Numeric>(
+        &self,
+        fused: &crate::FusedElementWise<T>,
+        inputs: &[&crate::Buffer<'_, T, Self, ()>],
+        outputs: &mut [&mut crate::Buffer<'_, T, Self, ()>],
+    ) -> crate::Result<()> {
+        let Some(len) = outputs.first().map(|out| out.len()) else {
+            return Ok(());
+        };
+
+        let dtype = wgsl_dtype::<T>();
+        let bindings = (0..fused.inputs())
+            .map(|idx| format!("in{idx}"))
+            .chain((0..fused.outputs().len()).map(|idx| format!("out{idx}")))
+            .enumerate()
+            .map(|(binding, name)| {
+                format!(
+                    "
+                    @group(0)
+                    @binding({binding})
+                    var<storage, read_write> {name}: array<{dtype}>;
+                    "
+                )
+            })
+            .collect::<String>();
+
+        let fused_operations = fused.to_wgsl_src(
+            |idx| format!("in{idx}[global_id.x]"),
+            |idx| format!("out{idx}[global_id.x]"),
+        );
+
+        let src = format!(
+            "
+            {enable}
+            {bindings}
+
+            @compute
+            @workgroup_size(32)
+            fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+                if global_id.x >= arrayLength(&out0) {{
+                    return;    
+                }}
+                {fused_operations}
+            }}
+        ",
+            enable = wgsl_enable_directives::<T>()
+        );
+
+        let args = inputs
+            .iter()
+            .map(|buf| buf.arg())
+            .chain(outputs.iter_mut().map(|buf| &*buf.arg_mut()))
+            .collect::<Vec<_>>();
+
+        self.launch_shader(src, [(32 + len as u32) / 32, 1, 1], &args)
+    }
+}
+
+#[cfg(feature = "autograd")]
+impl<D, Mods, T, S> crate::optim::ApplyUpdate<T, S> for Wgsl<D, Mods>
+where
+    T: Unit + Copy + Default + 'static,
+    D: WgslShaderLaunch + Alloc<T> + 'static,
+    D::Base<T, S>: AsShaderArg<D>,
+    D::Base<T, ()>: AsShaderArg<D>,
+    Mods: OnDropBuffer + 'static,
+    S: Shape,
+{
+    fn apply_update(
+        &self,
+        rule: &crate::optim::UpdateRule<T>,
+        param: &mut crate::Buffer<T, Self, S>,
+        grad: &crate::Buffer<T, Self, S>,
+        states: &mut [crate::Buffer<T, Self, S>],
+        hyper: &[T],
+    ) -> crate::Result<()> {
+        let dtype = wgsl_dtype::<T>();
+        let bindings = ["param", "grad"]
+            .into_iter()
+            .map(String::from)
+            .chain((0..states.len()).map(|idx| format!("state{idx}")))
+            .chain(["hp".into()])
+            .enumerate()
+            .map(|(binding, name)| {
+                format!(
+                    "
+                    @group(0)
+                    @binding({binding})
+                    var<storage, read_write> {name}: array<{dtype}>;
+                    "
+                )
+            })
+            .collect::<String>();
+
+        let load_states = (0..states.len())
+            .map(|idx| format!("var s{idx} = state{idx}[global_id.x];\n"))
+            .collect::<String>();
+        let store_states = (0..states.len())
+            .map(|idx| format!("state{idx}[global_id.x] = s{idx};\n"))
+            .collect::<String>();
+
+        let src = format!(
+            "
+            {enable}
+            {bindings}
+
+            @compute
+            @workgroup_size(32)
+            fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {{
+                if global_id.x >= arrayLength(&param) {{
+                    return;
+                }}
+                var p = param[global_id.x];
+                let g = grad[global_id.x];
+                {load_states}
+                {update}
+                param[global_id.x] = p;
+                {store_states}
+            }}
+        ",
+            enable = wgsl_enable_directives::<T>(),
+            update = rule.to_wgsl_src()
+        );
+
+        // hyperparameters are passed in a storage buffer, which must not be empty
+        let mut hyper_vals = [T::default(); crate::optim::MAX_HYPER];
+        hyper_vals[..rule.hyper()].copy_from_slice(&hyper[..rule.hyper()]);
+        let hyper = self.backend.alloc_from_slice::<()>(&hyper_vals)?;
+
+        let len = param.len();
+        let mut args = vec![&*param.arg_mut(), grad.arg()];
+        args.extend(states.iter_mut().map(|state| &*state.arg_mut()));
+        args.push(hyper.arg());
+
+        self.launch_shader(src, [(32 + len as u32) / 32, 1, 1], &args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{wgsl::wgsl_device::Wgsl, ApplyFunction, Combiner, Device, Vulkan};
@@ -97,4 +618,24 @@ mod tests {
         let out = dev.apply_fn(&x, |x| x.add(5));
         assert_eq!(out.read_to_vec(), [6, 7, 8])
     }
+
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_wgsl_apply_update() {
+        use crate::optim::{ApplyUpdate, UpdateRule};
+
+        let dev = Wgsl::<Vulkan>::new(0).unwrap();
+        let mut param = dev.buffer([1f32, 2., 3.]);
+        let grad = dev.buffer([1f32, 1., 2.]);
+        let mut states = [dev.buffer([0f32; 3])];
+
+        let rule = UpdateRule::new(1, 2)
+            .state(0, |v| v.hyper[1].mul(v.states[0]).add(v.grad))
+            .param(|v| v.param.sub(v.hyper[0].mul(v.states[0])));
+        dev.apply_update(&rule, &mut param, &grad, &mut states, &[0.5, 0.9])
+            .unwrap();
+
+        assert_eq!(states[0].read_to_vec(), [1., 1., 2.]);
+        assert_eq!(param.read_to_vec(), [0.5, 1.5, 2.]);
+    }
 }
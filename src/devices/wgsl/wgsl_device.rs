@@ -171,12 +171,12 @@ impl<D: Device + Alloc<T>, T: Unit, Mods: Retrieve<Self, T, S>, S: Shape> Retrie
         len: usize,
         parents: impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Buffer<T, Self, S>> {
-        let data = unsafe { self.modules.retrieve::<NUM_PARENTS>(self, len, parents) }?;
-        let buf = Buffer {
+        let data = unsafe { self.modules.retrieve::<NUM_PARENTS>(self, len, &parents) }?;
+        let mut buf = Buffer {
             data,
             device: Some(self),
         };
-        self.modules.on_retrieve_finish(&buf);
+        self.modules.on_retrieve_finish(&parents, &mut buf);
         Ok(buf)
     }
 }
@@ -37,7 +37,7 @@ pub enum DeviceError {
     MissingCacheTraces,
     /// This graph can't be optimized. This indicates a bug in custos.
     GraphOptimization, // probably a bug
-    /// An address was not supplied for a Network device.
+    /// An address was not supplied for a Remote device.
     MissingAddress,
     /// Cannot create WGPU device instance.
     WGPUDeviceReturn,
@@ -53,6 +53,42 @@ pub enum DeviceError {
     ZeroLengthBuffer,
     /// Given generic shape length does not match with e.g. slice length
     ShapeLengthMismatch,
+    /// The buffer was not used while capturing the graph or has a different type.
+    InvalidCaptureSlot,
+    /// The parameters do not match the parameters the optimizer was created with.
+    OptimizerParamMismatch,
+    /// A gradient function that does not record its own gradient functions was called by `backward_create_graph`.
+    SecondOrderGradUnavailable,
+    /// The device does not support this operation on gradients of this data type.
+    GradOpUnsupported,
+    /// The fork profile was recorded with another custos version.
+    ForkProfileVersionMismatch,
+    /// The fork profile was recorded on another device.
+    ForkProfileDeviceMismatch,
+    /// The peer of a Remote device is not a custos server.
+    RemoteHandshake,
+    /// The custos server speaks another protocol version.
+    RemoteVersionMismatch,
+    /// The custos server sent a response that could not be decoded.
+    RemoteInvalidResponse,
+    /// The requested data type does not match the data type of the untyped storage.
+    UntypedTypeMismatch,
+    /// The requested device does not match the device of the untyped storage.
+    UntypedDeviceMismatch,
+    /// The backend requested for the Untyped device is not enabled.
+    UntypedDeviceUnavailable,
+    /// The device descriptor of the Untyped device could not be parsed.
+    InvalidDeviceDescriptor,
+    /// The arena of an Arena device has not enough memory left for the allocation.
+    ArenaOutOfMemory,
+    /// The lengths of the buffers passed to a BLAS routine do not match the given dimensions.
+    BlasLengthMismatch,
+    /// The lengths of the matrices passed to gemm do not match the given dimensions.
+    GemmLengthMismatch,
+    /// The source buffer of a write is longer than the destination buffer.
+    WriteLengthMismatch,
+    /// OpenCL kernels cannot operate on the data type, e.g. `bf16`.
+    CLDatatypeUnsupported,
 }
 
 impl core::error::Error for crate::DeviceError {}
@@ -69,7 +105,7 @@ impl DeviceError {
             DeviceError::GraphOptimization => {
                 "This graph can't be optimized. This indicates a bug in custos."
             }
-            DeviceError::MissingAddress => "An address was not supplied for a Network device.",
+            DeviceError::MissingAddress => "An address was not supplied for a Remote device. Set CUSTOS_REMOTE_ADDR.",
             DeviceError::WGPUDeviceReturn => "Cannot create WGPU device instance.",
             DeviceError::CPUDeviceNotAvailable => {
                 "The 'cpu' feature is disabled. Hence this CPU can't be created."
@@ -79,7 +115,25 @@ impl DeviceError {
             DeviceError::LocationAlreadyInUse => "Location is already in use.",
             DeviceError::UnaryFusingUnsupported => "Unary fusing is not supported for this module configuration.",
             DeviceError::ZeroLengthBuffer => "Zero length buffers are not supported",
-            DeviceError::ShapeLengthMismatch => "Given generic shape length does not match with e.g. slice length"
+            DeviceError::ShapeLengthMismatch => "Given generic shape length does not match with e.g. slice length",
+            DeviceError::InvalidCaptureSlot => "The buffer was not used while capturing the graph or has a different type.",
+            DeviceError::OptimizerParamMismatch => "The parameters do not match the parameters the optimizer was created with.",
+            DeviceError::SecondOrderGradUnavailable => "An operation without a second derivative was used to compute higher-order gradients. Use e.g. unary_ew_second_order instead of unary_ew or add_second_order_grad_fn instead of add_grad_fn.",
+            DeviceError::GradOpUnsupported => "The device does not support this operation on gradients of this data type (e.g. computing the norm of integer gradients).",
+            DeviceError::ForkProfileVersionMismatch => "The fork profile was recorded with another custos version. Kernels and timings may have changed.",
+            DeviceError::ForkProfileDeviceMismatch => "The fork profile was recorded on another device.",
+            DeviceError::RemoteHandshake => "The peer of the Remote device is not a custos server.",
+            DeviceError::RemoteVersionMismatch => "The custos server speaks another protocol version. Update the client or the server.",
+            DeviceError::RemoteInvalidResponse => "The custos server sent a response that could not be decoded.",
+            DeviceError::UntypedTypeMismatch => "The requested data type does not match the data type of the untyped storage. Use `cast` to convert the storage.",
+            DeviceError::UntypedDeviceMismatch => "The requested device does not match the device of the untyped storage.",
+            DeviceError::UntypedDeviceUnavailable => "The backend requested for the Untyped device is not enabled. Enable its feature, e.g. `opencl` or `vulkan`.",
+            DeviceError::InvalidDeviceDescriptor => "Invalid untyped device descriptor. Expected `cpu`, `cuda`, `opencl` or `vulkan`, optionally followed by `:<idx>`.",
+            DeviceError::ArenaOutOfMemory => "The arena of the Arena device has not enough memory left for the allocation. Provide a larger arena or reset the cursor.",
+            DeviceError::BlasLengthMismatch => "The lengths of the buffers passed to a BLAS routine do not match the given dimensions.",
+            DeviceError::GemmLengthMismatch => "The lengths of the matrices passed to gemm do not match the given dimensions m x k and k x n.",
+            DeviceError::WriteLengthMismatch => "The source buffer of the write is longer than the destination buffer.",
+            DeviceError::CLDatatypeUnsupported => "The OpenCL device does not support this data type. bf16 has no OpenCL counterpart, use f16 or f32 instead.",
         }
     }
 }
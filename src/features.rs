@@ -17,13 +17,13 @@ use super::{Alloc, Buffer, Device, OnDropBuffer};
 
 pub trait Feature: OnDropBuffer {}
 
-// is a cached module is placed before Autograd results a problem
-// -> the retrieved buffer is not added to the no grads pool of the autograd module
-// let device = CPU::<Cached<Autograd<Base>>>::new();
-//
-// how to fix this:
-// add retrieved buffer to no grads pool at the end of the chain (at device level (Retriever trait))
-// => "generator", "actor"
+/// Retrieves the data of a [`Buffer`] in two phases, as modules can be stacked in any order.
+///
+/// `retrieve` ("generator") creates the data. Modules that allocate memory themselves (e.g. `Cached` or `Lazy`) do not pass this call down to their inner modules.
+///
+/// `on_retrieve_finish` ("actor") is called with the retrieved [`Buffer`] afterwards.
+/// Every module must pass this call down.
+/// Hence, bookkeeping of retrieved buffers (e.g. registering them in `Autograd`) belongs here, as it then does not depend on the position of the module in the stack.
 pub trait Retrieve<D, T: Unit, S: Shape = ()>: OnDropBuffer {
     // "generator"
     #[track_caller]
@@ -31,7 +31,7 @@ pub trait Retrieve<D, T: Unit, S: Shape = ()>: OnDropBuffer {
         &self,
         device: &D,
         len: usize,
-        parents: impl Parents<NUM_PARENTS>,
+        parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         S: Shape,
@@ -39,8 +39,11 @@ pub trait Retrieve<D, T: Unit, S: Shape = ()>: OnDropBuffer {
 
     // "actor"
     #[inline]
-    fn on_retrieve_finish(&self, _retrieved_buf: &Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        _parents: &impl Parents<NUM_PARENTS>,
+        _retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
     }
@@ -150,6 +153,26 @@ pub trait GradActions {
     unsafe fn gradients_mut(&self) -> Option<&mut crate::Gradients> {
         None
     }
+
+    /// Scales all gradients so that their global L2 norm is at most `max_norm`. Returns the norm before clipping.
+    /// See [`Gradients::clip_grad_norm`](crate::Gradients::clip_grad_norm).
+    #[inline]
+    fn clip_grad_norm(&self, max_norm: f64) -> crate::Result<f64> {
+        match unsafe { self.gradients_mut() } {
+            Some(gradients) => gradients.clip_grad_norm(max_norm),
+            None => Ok(0.),
+        }
+    }
+
+    /// Accumulates the gradients of `steps` backward passes before optimizers update the parameters.
+    /// See [`Gradients::set_accumulation_steps`](crate::Gradients::set_accumulation_steps).
+    #[inline]
+    fn set_grad_accumulation_steps(&self, steps: usize) {
+        if let Some(gradients) = unsafe { self.gradients_mut() } {
+            gradients.set_accumulation_steps(steps);
+        }
+    }
+
     unsafe fn grad<'a, T: 'static, D: Device + Alloc<T> + ZeroGrad<T> + 'static, S: Shape>(
         &self,
         device: &'a D,
@@ -183,6 +206,17 @@ pub trait AddGradFn {
         self.add_grad_fn(args, grad_fn)
     }
 
+    /// Like [`AddGradFn::add_grad_fn`], but `op` records the gradient functions of its own computations if [`Gradients::create_graph`](crate::Gradients::create_graph) is set.
+    /// Gradient functions added via [`AddGradFn::add_grad_fn`] return [`DeviceError::SecondOrderGradUnavailable`](crate::DeviceError::SecondOrderGradUnavailable) during [`Buffer::backward_create_graph`](crate::Buffer::backward_create_graph) instead.
+    #[inline]
+    fn add_second_order_grad_fn<Args: Parents<N> + AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
+    ) {
+        self.add_grad_fn(args, op)
+    }
+
     fn backward(&mut self) {}
 
     #[inline]
@@ -226,6 +260,55 @@ pub trait AddGradFn {
         op();
         self.set_grad_enabled(enabled_before);
     }
+
+    /// Starts a checkpointed region. Prefer [`AddGradFn::checkpoint`].
+    #[inline]
+    fn begin_checkpoint(&self) {}
+
+    /// Ends the checkpointed region started by [`AddGradFn::begin_checkpoint`].
+    #[inline]
+    fn end_checkpoint(&self) {}
+
+    /// Gradient checkpointing: Intermediate buffers computed by `op` do not have to stay alive until the backward pass.
+    /// The region does not drop them itself. If they are dropped, e.g. as locals of `op`, they are recomputed during the backward pass by replaying the recorded forward operations of `op`.
+    /// This trades compute for memory.
+    ///
+    /// Only buffers that are deallocated when they are dropped are recomputed (e.g. not those of a `Cached` module).
+    /// The inputs of the region must not be modified before the backward pass.
+    /// The region is ended even if `op` panics.
+    /// # Example
+    #[cfg_attr(all(feature = "cpu", feature = "autograd"), doc = "```")]
+    #[cfg_attr(not(all(feature = "cpu", feature = "autograd")), doc = "```ignore")]
+    /// use custos::{AddGradFn, Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([1., 2., 3.]).require_grad();
+    ///
+    /// let out = device.checkpoint(|| {
+    ///     // `squared` is dropped at the end of the closure
+    ///     let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+    ///     device.unary_ew(&squared, |x| x.mul(3.), |_| 3.)
+    /// });
+    ///
+    /// out.backward().unwrap();
+    /// assert_eq!(x.grad().read(), [6., 12., 18.]);
+    /// ```
+    #[inline]
+    fn checkpoint<R>(&self, op: impl FnOnce() -> R) -> R {
+        /// Ends the region on drop, i.e. also if `op` panics.
+        struct EndCheckpoint<'a, D: AddGradFn + ?Sized>(&'a D);
+
+        impl<D: AddGradFn + ?Sized> Drop for EndCheckpoint<'_, D> {
+            #[inline]
+            fn drop(&mut self) {
+                self.0.end_checkpoint();
+            }
+        }
+
+        self.begin_checkpoint();
+        let _end = EndCheckpoint(self);
+        op()
+    }
 }
 
 #[macro_export]
@@ -270,6 +353,15 @@ macro_rules! pass_down_grad_fn {
                 self.modules.add_grad_fn(args, op);
             }
 
+            #[inline]
+            fn add_second_order_grad_fn<Args: $crate::Parents<N> + $crate::AnyOp, const N: usize>(
+                &self,
+                args: Args,
+                op: impl for<'b> Fn(Args::Replicated<'b>) -> $crate::Result<()> + 'static,
+            ) {
+                self.modules.add_second_order_grad_fn(args, op);
+            }
+
             #[inline]
             fn backward(&mut self) {
                 self.modules.backward()
@@ -279,6 +371,21 @@ macro_rules! pass_down_grad_fn {
             fn set_grad_enabled(&self, enabled: bool) {
                 self.modules.set_grad_enabled(enabled)
             }
+
+            #[inline]
+            fn is_grad_enabled(&self) -> bool {
+                self.modules.is_grad_enabled()
+            }
+
+            #[inline]
+            fn begin_checkpoint(&self) {
+                self.modules.begin_checkpoint()
+            }
+
+            #[inline]
+            fn end_checkpoint(&self) {
+                self.modules.end_checkpoint()
+            }
         }
     };
     ($to_impl:ident) => {
@@ -328,6 +435,69 @@ macro_rules! pass_down_tape_actions {
     };
 }
 
+/// Provides access to the tangents of the [`Forward`](crate::Forward) module.
+#[cfg(feature = "forward")]
+pub trait TangentActions {
+    // "generator" - do not forget to pass down
+    #[inline]
+    fn tangents(&self) -> Option<core::cell::Ref<'_, crate::Tangents>> {
+        None
+    }
+
+    // "generator" - do not forget to pass down
+    #[inline]
+    fn tangents_mut(&self) -> Option<RefMut<'_, crate::Tangents>> {
+        None
+    }
+}
+
+#[macro_export]
+macro_rules! pass_down_tangent_actions {
+    ($to_impl:ident, $($generics:tt),*) => {
+        #[cfg(feature = "forward")]
+        impl<'dev, Mods: $crate::TangentActions> $crate::TangentActions for $to_impl<$($generics),*> {
+            #[inline]
+            fn tangents(&self) -> Option<core::cell::Ref<'_, $crate::Tangents>> {
+                self.modules.tangents()
+            }
+
+            #[inline]
+            fn tangents_mut(&self) -> Option<core::cell::RefMut<'_, $crate::Tangents>> {
+                self.modules.tangents_mut()
+            }
+        }
+    };
+    ($to_impl:ident) => {
+        $crate::pass_down_tangent_actions!($to_impl, Mods);
+    };
+}
+
+/// Provides access to the thread pool of the [`Parallel`](crate::Parallel) module.
+#[cfg(feature = "parallel")]
+pub trait ThreadPoolActions {
+    // "generator" - do not forget to pass down
+    #[inline]
+    fn thread_pool(&self) -> Option<&crate::ThreadPool> {
+        None
+    }
+}
+
+#[macro_export]
+macro_rules! pass_down_thread_pool_actions {
+    ($to_impl:ident, $($generics:tt),*) => {
+        #[cfg(feature = "parallel")]
+        impl<'dev, Mods: $crate::ThreadPoolActions> $crate::ThreadPoolActions for $to_impl<$($generics),*> {
+            #[inline]
+            fn thread_pool(&self) -> Option<&$crate::ThreadPool> {
+                self.modules.thread_pool()
+            }
+        }
+    };
+    ($to_impl:ident) => {
+        $crate::pass_down_thread_pool_actions!($to_impl, Mods);
+    };
+}
+
 pub trait OpArgs {
     fn as_ids(&self) -> [UniqueId; 2];
 }
@@ -387,6 +557,20 @@ pub trait AddOperation {
         op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
     ) -> crate::Result<()>;
 
+    /// Like [`AddOperation::add_op`], but a `Lazy` module may execute `op` concurrently with other operations (see `Lazy::set_exec_threads`).
+    ///
+    /// # Safety
+    /// `op` may be called on a different thread. Apart from the data of its buffers, it must not access the device of the buffers or any other state that is not [`Sync`].
+    /// The element types of the buffers must be [`Send`] and [`Sync`].
+    #[inline]
+    unsafe fn add_parallel_op<Args: Parents<N> + AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        self.add_op(args, op)
+    }
+
     fn ops_count(&self) -> usize;
     fn set_lazy_enabled(&self, enabled: bool);
     #[inline]
@@ -449,6 +633,15 @@ macro_rules! pass_down_add_operation {
                 self.modules.add_op(args, op)
             }
 
+            #[inline]
+            unsafe fn add_parallel_op<Args: $crate::Parents<N> + $crate::AnyOp, const N: usize>(
+                &self,
+                args: Args,
+                op: impl for<'a> Fn(Args::Replicated<'a>) -> $crate::Result<()> + Send + Sync + 'static,
+            ) -> $crate::Result<()> {
+                self.modules.add_parallel_op(args, op)
+            }
+
             #[inline]
             fn ops_count(&self) -> usize {
                 self.modules.ops_count()
@@ -573,11 +766,12 @@ macro_rules! pass_down_use_gpu_or_cpu {
                 &self,
                 location: $crate::HashLocation<'static>,
                 input_lengths: &[usize],
+                output_lengths: &[usize],
                 cpu_op: impl FnMut(),
                 gpu_op: impl FnMut(),
             ) -> $crate::GpuOrCpuInfo {
                 self.modules
-                    .use_cpu_or_gpu(location, input_lengths, cpu_op, gpu_op)
+                    .use_cpu_or_gpu(location, input_lengths, output_lengths, cpu_op, gpu_op)
             }
 
             #[inline]
@@ -599,10 +793,12 @@ macro_rules! pass_down_use_gpu_or_cpu {
 pass_down_use_gpu_or_cpu!(Autograd, 'dev, Mods);
 
 pub trait UseGpuOrCpu {
+    /// Executes `cpu_op` or `gpu_op`. The lengths of the input and output buffers are the size of the operation.
     fn use_cpu_or_gpu(
         &self,
         location: crate::HashLocation<'static>,
         input_lengths: &[usize],
+        output_lengths: &[usize],
         cpu_op: impl FnMut(),
         gpu_op: impl FnMut(),
     ) -> GpuOrCpuInfo;
@@ -635,6 +831,9 @@ pub trait Optimize {
         device: &D,
         graph_translator: Option<&crate::modules::GraphTranslator>,
     ) -> crate::Result<()>;
+
+    /// Fuses consecutive element-wise operations (unary and binary) into a single operation.
+    fn ew_fusing<D: crate::ElementWiseFusing + 'static>(&self, device: &D) -> crate::Result<()>;
 }
 
 #[macro_export]
@@ -655,6 +854,12 @@ macro_rules! pass_down_optimize_mem_graph {
             ) -> $crate::Result<()> {
                 self.modules.unary_fusing(device, graph_translator)
             }
+            fn ew_fusing<D: $crate::ElementWiseFusing + 'static>(
+                &self,
+                device: &D,
+            ) -> $crate::Result<()> {
+                self.modules.ew_fusing(device)
+            }
         }
     };
     ($to_impl:ident) => {
@@ -14,6 +14,12 @@ pub enum AllocFlag {
     /// Similiar to `None`, but the resulting [`Buffer`](crate::Buffer) is borrowed and not owned.
     BorrowedCache,
     Lazy,
+    /// The buffer only holds the id of an allocation that is owned by a module, e.g. a lazily allocated buffer of the `Lazy` module.
+    /// Nothing is deallocated when such a buffer goes out of scope, but the modules are notified.
+    Id,
+    /// The buffer shares the memory of another buffer under an id of its own, e.g. a detached alias (see [`Buffer::detach`](crate::Buffer::detach)).
+    /// Nothing is deallocated when such a buffer goes out of scope, but the modules are notified.
+    Alias,
 }
 
 impl PartialEq for AllocFlag {
@@ -30,4 +36,10 @@ impl AllocFlag {
             AllocFlag::None | AllocFlag::BorrowedCache | AllocFlag::Lazy
         )
     }
+
+    /// Returns `true` if the modules are notified (see [`OnDropBuffer`](crate::OnDropBuffer)) when a [`Buffer`](crate::Buffer) with this flag goes out of scope.
+    #[inline]
+    pub fn notify_on_drop(&self) -> bool {
+        matches!(self, AllocFlag::None | AllocFlag::Id | AllocFlag::Alias)
+    }
 }
@@ -0,0 +1,419 @@
+use crate::{
+    AddGradFn, AddOperation, Alloc, Buffer, Device, DeviceError, HasId, MayGradActions, Unit,
+    ZeroGrad,
+};
+
+/// Multiplies two row-major matrices.
+pub trait Gemm<T: Unit, D: Device = Self>: Device {
+    /// Multiplies the `m x k` matrix `lhs` with the `k x n` matrix `rhs` and returns the `m x n` product.
+    /// All matrices are stored in row-major order.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Base, Device, Gemm, CPU};
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let lhs = device.buffer([1, 2, 3, 4, 5, 6]);
+    /// let rhs = device.buffer([7, 8, 9, 10, 11, 12]);
+    ///
+    /// let out = device.gemm(2, 3, 2, &lhs, &rhs);
+    /// assert_eq!(out.read(), [58, 64, 139, 154]);
+    /// ```
+    fn gemm(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs: &Buffer<T, D>,
+    ) -> Buffer<T, Self>;
+}
+
+/// Writes the gradients of a matrix multiplication (with chainrule) to the gradient buffers of its inputs.
+/// `m`, `k` and `n` are the dimensions of the forward [`Gemm::gemm`] call.
+pub trait GemmGrad<T: Unit, D: Device = Self>: Device {
+    /// Adds `out_grad * rhs^T` to the `m x k` gradient of `lhs`.
+    fn add_gemm_lhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        rhs: &Buffer<T, D>,
+        lhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    );
+
+    /// Adds `lhs^T * out_grad` to the `k x n` gradient of `rhs`.
+    fn add_gemm_rhs_grad(
+        &self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<T, D>,
+        rhs_grad: &mut Buffer<T, D>,
+        out_grad: &Buffer<T, D>,
+    );
+}
+
+/// Multiplies two row-major matrices.
+/// If the `autograd` feature is enabled, the gradient function is also recorded.
+pub trait MatMulMayGrad<T: Unit, D: Device = Self>: Device {
+    /// Multiplies the `m x k` matrix `lhs` with the `k x n` matrix `rhs` and returns the `m x n` product.
+    /// If the `autograd` feature is enabled, the gradient function is also recorded.
+    /// # Example
+    #[cfg_attr(all(feature = "autograd", feature = "cpu"), doc = "```")]
+    #[cfg_attr(not(all(feature = "autograd", feature = "cpu")), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Device, MatMulMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let lhs = device.buffer([1., 2., 3., 4.]).require_grad();
+    /// let rhs = device.buffer([5., 6.]).require_grad();
+    ///
+    /// let out = device.matmul(2, 2, 1, &lhs, &rhs);
+    /// assert_eq!(out.read(), [17., 39.]);
+    ///
+    /// out.backward().unwrap();
+    /// assert_eq!(lhs.grad().read(), [5., 6., 5., 6.]);
+    /// assert_eq!(rhs.grad().read(), [4., 6.]);
+    /// ```
+    fn matmul<'a>(
+        &'a self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<'a, T, D>,
+        rhs: &Buffer<'a, T, D>,
+    ) -> Buffer<'a, T, Self>;
+}
+
+impl<T, D> MatMulMayGrad<T, D> for D
+where
+    T: Unit + 'static,
+    D: AddGradFn + Gemm<T, D> + GemmGrad<T, D> + AddOperation + MayGradActions,
+    D: Alloc<T> + ZeroGrad<T> + 'static,
+{
+    fn matmul<'a>(
+        &'a self,
+        m: usize,
+        k: usize,
+        n: usize,
+        lhs: &Buffer<'a, T, D>,
+        rhs: &Buffer<'a, T, D>,
+    ) -> Buffer<'a, T, Self> {
+        let out = self.gemm(m, k, n, lhs, rhs);
+
+        self.add_grad_fn((lhs, rhs, &out), move |(lhs, rhs, out)| {
+            let device = lhs.device();
+
+            // lazy execution is already disabled during backward pass
+            device.eagerly(|| unsafe {
+                if lhs.requires_grad() {
+                    device.add_gemm_lhs_grad(m, k, n, rhs, lhs.grad_mut_unbound(), out.grad());
+                }
+                if rhs.requires_grad() {
+                    device.add_gemm_rhs_grad(m, k, n, lhs, rhs.grad_mut_unbound(), out.grad());
+                }
+            });
+            Ok(())
+        });
+
+        out
+    }
+}
+
+/// Returns the length `m * n` of the product of the `m x k` matrix `lhs` and the `k x n` matrix `rhs`.
+/// Returns [`DeviceError::GemmLengthMismatch`] if `lhs` or `rhs` has another length or if a length overflows.
+pub(crate) fn gemm_out_len(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: usize,
+    rhs: usize,
+) -> crate::Result<usize> {
+    match (m.checked_mul(k), k.checked_mul(n), m.checked_mul(n)) {
+        (Some(lhs_len), Some(rhs_len), Some(out_len)) if lhs_len == lhs && rhs_len == rhs => {
+            Ok(out_len)
+        }
+        _ => Err(DeviceError::GemmLengthMismatch.into()),
+    }
+}
+
+/// Like [`gemm_out_len`], but also returns [`DeviceError::GemmLengthMismatch`] if `out` does not hold `m * n` elements.
+pub(crate) fn check_gemm_lengths(
+    m: usize,
+    k: usize,
+    n: usize,
+    lhs: usize,
+    rhs: usize,
+    out: usize,
+) -> crate::Result<()> {
+    if gemm_out_len(m, k, n, lhs, rhs)? != out {
+        return Err(DeviceError::GemmLengthMismatch.into());
+    }
+    Ok(())
+}
+
+/// Side length of the square tiles of the generated gemm kernels.
+#[cfg(any(feature = "opencl", feature = "cuda", feature = "wgsl"))]
+pub(crate) const GEMM_TILE: usize = 16;
+
+/// Returns the source of the index of the element at (`row`, `col`) of a row-major `rows x cols` matrix.
+/// If `trans` is `true`, the matrix is stored transposed, i.e. as a row-major `cols x rows` matrix.
+#[cfg(any(feature = "opencl", feature = "cuda", feature = "wgsl"))]
+pub(crate) fn gemm_index_src(row: &str, col: &str, rows: &str, cols: &str, trans: bool) -> String {
+    if trans {
+        format!("{col} * {rows} + {row}")
+    } else {
+        format!("{row} * {cols} + {col}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    fn naive_gemm(m: usize, k: usize, n: usize, lhs: &[i64], rhs: &[i64]) -> Vec<i64> {
+        let mut out = vec![0; m * n];
+        for row in 0..m {
+            for col in 0..n {
+                for idx in 0..k {
+                    out[row * n + col] += lhs[row * k + idx] * rhs[idx * n + col];
+                }
+            }
+        }
+        out
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_gemm_cpu_blocked_int() {
+        use crate::{Base, Device, Gemm, CPU};
+
+        // larger than a single block in every dimension
+        let (m, k, n) = (70, 300, 520);
+        let lhs = (0..m * k).map(|x| (x % 7) as i64 - 3).collect::<Vec<_>>();
+        let rhs = (0..k * n).map(|x| (x % 5) as i64 - 2).collect::<Vec<_>>();
+
+        let device = CPU::<Base>::new();
+        let out = device.gemm(
+            m,
+            k,
+            n,
+            &device.buffer(lhs.as_slice()),
+            &device.buffer(rhs.as_slice()),
+        );
+        assert_eq!(out.read(), naive_gemm(m, k, n, &lhs, &rhs));
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_gemm_cpu_float() {
+        use crate::{Base, Device, Gemm, CPU};
+
+        let device = CPU::<Base>::new();
+        let lhs = device.buffer([1f32, 2., 3., 4., 5., 6.]);
+        let rhs = device.buffer([1f32, 0., 0., 1., 1., 1.]);
+
+        let out = device.gemm(2, 3, 2, &lhs, &rhs);
+        assert_eq!(out.read(), [4., 5., 10., 11.]);
+    }
+
+    #[test]
+    fn test_gemm_lengths() {
+        use super::{check_gemm_lengths, gemm_out_len};
+        use crate::{DeviceError, ErrorKind};
+
+        assert_eq!(gemm_out_len(2, 3, 4, 6, 12).unwrap(), 8);
+        assert!(check_gemm_lengths(2, 3, 4, 6, 12, 8).is_ok());
+
+        let err = gemm_out_len(2, 3, 4, 6, 11).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::GemmLengthMismatch));
+
+        let err = check_gemm_lengths(2, 3, 4, 6, 12, 6).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::GemmLengthMismatch));
+
+        // m * n overflows, although lhs and rhs are empty
+        let err = gemm_out_len(usize::MAX, 0, 2, 0, 0).unwrap_err();
+        assert_eq!(err.kind(), Some(&DeviceError::GemmLengthMismatch));
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    #[should_panic]
+    fn test_gemm_cpu_length_mismatch() {
+        use crate::{Base, Device, Gemm, CPU};
+
+        let device = CPU::<Base>::new();
+        let lhs = device.buffer([1f32, 2., 3., 4., 5., 6.]);
+        let rhs = device.buffer([1f32, 0., 0., 1.]);
+
+        device.gemm(2, 3, 2, &lhs, &rhs);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    #[should_panic]
+    fn test_gemm_slice_length_mismatch() {
+        use crate::cpu_stack_ops::gemm_slice;
+
+        let mut out = [0f32; 3];
+        gemm_slice(2, 1, 2, &[1., 2.], false, &[3., 4.], false, &mut out);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_gemm_cpu_half_f32_acc() {
+        use crate::{Base, Device, Gemm, CPU};
+        use half::{bf16, f16};
+
+        let device = CPU::<Base>::new();
+
+        // accumulating in f16 (bf16) would get stuck at 2048 (256)
+        let ones = device.buffer(vec![f16::ONE; 4096]);
+        let out = device.gemm(1, 4096, 1, &ones, &ones);
+        assert_eq!(out.read(), [f16::from_f32(4096.)]);
+
+        let lhs = device.buffer(vec![bf16::ONE; 1024]);
+        let rhs = device.buffer(vec![bf16::ONE; 512]);
+        let out = device.gemm(2, 512, 1, &lhs, &rhs);
+        assert_eq!(out.read(), [bf16::from_f32(512.); 2]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_gemm_grad_cpu() {
+        use crate::{Base, Device, GemmGrad, CPU};
+
+        let device = CPU::<Base>::new();
+        let lhs = device.buffer([1, 2, 3, 4, 5, 6]);
+        let rhs = device.buffer([1, 2, 3, 4, 5, 6]);
+        let out_grad = device.buffer([1, 1, 1, 1]);
+
+        let mut lhs_grad = device.buffer([1; 6]);
+        device.add_gemm_lhs_grad(2, 3, 2, &rhs, &mut lhs_grad, &out_grad);
+        assert_eq!(lhs_grad.read(), [4, 8, 12, 4, 8, 12]);
+
+        let mut rhs_grad = device.buffer([0; 6]);
+        device.add_gemm_rhs_grad(2, 3, 2, &lhs, &mut rhs_grad, &out_grad);
+        assert_eq!(rhs_grad.read(), [5, 5, 7, 7, 9, 9]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_matmul_backward_cpu() {
+        use crate::{Autograd, Base, Device, MatMulMayGrad, CPU};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let lhs = device.buffer([1., 2., 3., 4., 5., 6.]).require_grad();
+        let rhs = device.buffer([1., 2., 3., 4., 5., 6.]).require_grad();
+
+        let out = device.matmul(2, 3, 2, &lhs, &rhs);
+        assert_eq!(out.read(), [22., 28., 49., 64.]);
+
+        out.backward().unwrap();
+        assert_eq!(lhs.grad().read(), [3., 7., 11., 3., 7., 11.]);
+        assert_eq!(rhs.grad().read(), [5., 5., 7., 7., 9., 9.]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_gemm_cpu_lazy() {
+        use crate::{Base, Device, Gemm, Lazy, Run, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let lhs = device.buffer([1, 2, 3, 4]);
+        let rhs = device.buffer([1, 0, 0, 1]);
+
+        let out = device.gemm(2, 2, 2, &lhs, &rhs);
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [1, 2, 3, 4]);
+    }
+
+    #[cfg(feature = "wgsl")]
+    #[test]
+    fn test_wgsl_gemm_src_validates() {
+        use crate::wgsl::{wgsl_gemm_src, Spirv};
+
+        for (lhs_trans, rhs_trans, accumulate) in [
+            (false, false, false),
+            (false, true, true),
+            (true, false, true),
+        ] {
+            let src = wgsl_gemm_src::<f32>(17, 33, 18, lhs_trans, rhs_trans, accumulate);
+            Spirv::from_wgsl(src).unwrap();
+        }
+        Spirv::from_wgsl(wgsl_gemm_src::<i32>(2, 3, 2, false, false, false)).unwrap();
+    }
+
+    #[cfg(feature = "wgsl")]
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_wgsl_gemm_src_f16() {
+        use crate::wgsl::wgsl_gemm_src;
+
+        let src = wgsl_gemm_src::<half::f16>(2, 3, 2, false, false, true);
+        assert!(src.trim_start().starts_with("enable f16;"));
+        assert!(src.contains("array<f16>"));
+        assert!(src.contains("var acc = f32(0);"));
+        assert!(src.contains("out[row * n + col] = f16(f32(out[row * n + col]) + acc);"));
+    }
+
+    #[cfg(feature = "vulkan")]
+    #[test]
+    fn test_gemm_vk() {
+        use crate::{Base, Device, Gemm, Vulkan};
+
+        let (m, k, n) = (17, 33, 18);
+        let lhs = (0..m * k).map(|x| (x % 7) as i64 - 3).collect::<Vec<_>>();
+        let rhs = (0..k * n).map(|x| (x % 5) as i64 - 2).collect::<Vec<_>>();
+        let cast = |values: &[i64]| values.iter().map(|x| *x as i32).collect::<Vec<_>>();
+
+        let device = Vulkan::<Base>::new(0).unwrap();
+        let out = device.gemm(
+            m,
+            k,
+            n,
+            &device.buffer(cast(&lhs).as_slice()),
+            &device.buffer(cast(&rhs).as_slice()),
+        );
+        assert_eq!(out.read_to_vec(), cast(&naive_gemm(m, k, n, &lhs, &rhs)));
+    }
+
+    #[cfg(feature = "opencl")]
+    #[test]
+    fn test_gemm_cl() {
+        use crate::{Base, Device, Gemm, GemmGrad, OpenCL};
+
+        let device = OpenCL::<Base>::new(0).unwrap();
+        let lhs = device.buffer([1, 2, 3, 4, 5, 6]);
+        let rhs = device.buffer([1, 2, 3, 4, 5, 6]);
+
+        let out = device.gemm(2, 3, 2, &lhs, &rhs);
+        assert_eq!(out.read(), [22, 28, 49, 64]);
+
+        let out_grad = device.buffer([1, 1, 1, 1]);
+        let mut lhs_grad = device.buffer([0; 6]);
+        device.add_gemm_lhs_grad(2, 3, 2, &rhs, &mut lhs_grad, &out_grad);
+        assert_eq!(lhs_grad.read(), [3, 7, 11, 3, 7, 11]);
+    }
+
+    #[cfg(feature = "cuda")]
+    #[test]
+    fn test_gemm_cu() {
+        use crate::{Base, Device, Gemm, CUDA};
+
+        let device = CUDA::<Base>::new(0).unwrap();
+        let lhs = device.buffer([1f32, 2., 3., 4., 5., 6.]);
+        let rhs = device.buffer([1f32, 2., 3., 4., 5., 6.]);
+        assert_eq!(
+            device.gemm(2, 3, 2, &lhs, &rhs).read(),
+            [22., 28., 49., 64.]
+        );
+
+        let lhs = device.buffer([1, 2, 3, 4, 5, 6]);
+        let rhs = device.buffer([1, 2, 3, 4, 5, 6]);
+        assert_eq!(device.gemm(2, 3, 2, &lhs, &rhs).read(), [22, 28, 49, 64]);
+    }
+}
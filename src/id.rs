@@ -1,6 +1,6 @@
 use core::ops::{Deref, DerefMut};
 
-use crate::{Buffer, Device, Shape, Unit};
+use crate::{Buffer, Device, Shape, UniqueId, Unit};
 
 pub trait HasId {
     const HAS_NO_ID: bool = false;
@@ -20,6 +20,17 @@ pub trait HasId {
     // TODO: maybe move this to another trait -> `RequiresGrad`, probably needs to be added as trait bound for D::Data/Base
     #[inline]
     fn set_requires_grad(&mut self, _requires_grad: bool) {}
+
+    /// Gives a detached alias (see [`Buffer::detach`]) an id of its own.
+    /// Data that is not wrapped by the [`Autograd`](crate::Autograd) module keeps its id.
+    #[inline]
+    fn set_detached_id(&mut self, _id: UniqueId) {}
+
+    /// Returns the id of the buffer whose memory is shared, if this is a detached alias (see [`Buffer::detach`]).
+    #[inline]
+    fn alias_of(&self) -> Option<UniqueId> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
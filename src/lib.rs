@@ -74,11 +74,25 @@ pub use devices::opencl::OpenCL;
 #[cfg(feature = "stack")]
 pub use devices::stack::Stack;
 
+#[cfg(feature = "arena")]
+pub use devices::arena::Arena;
+
 #[cfg(feature = "nnapi")]
 pub use devices::nnapi::{AsOperandCode, NnapiDevice};
 #[cfg(feature = "vulkan")]
 pub use devices::vulkan::Vulkan;
 
+#[cfg(feature = "network")]
+pub use devices::network::Remote;
+
+#[cfg(feature = "recorder")]
+pub use devices::recorder::{GpuRecorder, Recorder};
+
+pub use binary::*;
+pub use blas::*;
+pub use gemm::*;
+#[cfg(feature = "half")]
+pub use mixed_precision::*;
 pub use unary::*;
 
 #[cfg(feature = "std")]
@@ -99,11 +113,16 @@ pub mod features;
 pub mod flag;
 // mod graph;
 mod any_op;
+mod binary;
+mod blas;
 #[cfg(feature = "std")]
 mod boxed_shallow_copy;
+mod gemm;
 pub mod hooks;
 mod id;
 mod layer_management;
+#[cfg(feature = "half")]
+mod mixed_precision;
 pub mod modules;
 mod op_hint;
 mod op_traits;
@@ -135,6 +154,9 @@ pub use dummy_cpu::*;
 #[cfg(feature = "static-api")]
 pub mod static_api;
 
+#[cfg(feature = "autograd")]
+pub mod optim;
+
 pub mod number;
 pub use op_traits::*;
 pub use shape::*;
@@ -221,6 +243,18 @@ pub trait MayGradActions {}
 #[cfg(not(feature = "autograd"))]
 impl<D> MayGradActions for D {}
 
+/// If the `forward` feature is enabled, then this will be implemented for all types that implement [`TangentActions`].
+/// On the other hand, if the `forward` feature is disabled, no tangents are propagated.
+#[cfg(feature = "forward")]
+pub trait MayTangentActions: TangentActions {}
+#[cfg(feature = "forward")]
+impl<D: crate::TangentActions> MayTangentActions for D {}
+
+#[cfg(not(feature = "forward"))]
+pub trait MayTangentActions {}
+#[cfg(not(feature = "forward"))]
+impl<D> MayTangentActions for D {}
+
 /// If the OpenCL device selected by the environment variable `CUSTOS_CL_DEVICE_IDX` supports unified memory, then this will be `true`.
 /// In your case, this is `false`.
 #[cfg(not(unified_cl))]
@@ -260,6 +294,9 @@ pub mod prelude {
     #[cfg(feature = "stack")]
     pub use crate::stack::Stack;
 
+    #[cfg(feature = "arena")]
+    pub use crate::arena::Arena;
+
     #[cfg(feature = "nnapi")]
     pub use crate::nnapi::NnapiDevice;
 
@@ -268,6 +305,12 @@ pub mod prelude {
 
     #[cfg(feature = "vulkan")]
     pub use crate::Vulkan;
+
+    #[cfg(feature = "network")]
+    pub use crate::Remote;
+
+    #[cfg(feature = "recorder")]
+    pub use crate::{GpuRecorder, Recorder};
 }
 
 #[cfg(test)]
@@ -0,0 +1,99 @@
+use crate::{Buffer, Device, Float, Resolve, Shape, TwoWay};
+
+/// A half precision floating point type (`f16` or `bf16`).
+/// Mixed precision kernels load values of this type as `f32`, compute in `f32` and store the result as `Self`.
+pub trait HalfFloat: Float {
+    /// Converts `self` to `f32` (lossless).
+    fn to_f32(self) -> f32;
+    /// Converts a `f32` value to `Self`, rounding to the nearest representable value.
+    fn from_f32(value: f32) -> Self;
+}
+
+macro_rules! impl_half_float {
+    ($($t:ty),*) => {
+        $(
+            impl HalfFloat for $t {
+                #[inline]
+                fn to_f32(self) -> f32 {
+                    <$t>::to_f32(self)
+                }
+
+                #[inline]
+                fn from_f32(value: f32) -> Self {
+                    <$t>::from_f32(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_half_float!(half::f16, half::bf16);
+
+/// Applies a function, which is evaluated in `f32`, to a half precision buffer and returns a new buffer.
+/// Every element is loaded as `f32` and the result is stored as `T`.
+pub trait ApplyFunctionF32<T: HalfFloat, S: Shape = (), D: Device = Self>: Device {
+    /// Applies a function, which is evaluated in `f32`, to a half precision buffer and returns a new buffer.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{ApplyFunctionF32, Base, Buffer, Combiner, CPU};
+    /// use half::f16;
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let x = Buffer::from((&device, [f16::from_f32(1.), f16::from_f32(2.)]));
+    ///
+    /// let out = device.apply_fn_f32(&x, |x| x.mul(3.).add(1.));
+    /// assert_eq!(&**out, &[f16::from_f32(4.), f16::from_f32(7.)]);
+    /// ```
+    fn apply_fn_f32<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32> + 'static;
+}
+
+/// Applies a binary function, which is evaluated in `f32`, to two half precision buffers and returns a new buffer.
+/// Every element is loaded as `f32` and the result is stored as `T`.
+pub trait ApplyBinaryFunctionF32<T: HalfFloat, S: Shape = (), D: Device = Self>: Device {
+    /// Applies a binary function, which is evaluated in `f32`, to two half precision buffers and returns a new buffer.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{ApplyBinaryFunctionF32, Base, Buffer, Combiner, CPU};
+    /// use half::bf16;
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let lhs = Buffer::from((&device, [bf16::from_f32(1.), bf16::from_f32(2.)]));
+    /// let rhs = Buffer::from((&device, [bf16::from_f32(3.), bf16::from_f32(4.)]));
+    ///
+    /// let out = device.apply_binary_fn_f32(&lhs, &rhs, |lhs, rhs| lhs.mul(rhs).sub(1.));
+    /// assert_eq!(&**out, &[bf16::from_f32(2.), bf16::from_f32(7.)]);
+    /// ```
+    fn apply_binary_fn_f32<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<f32>, Resolve<f32>) -> F + Copy + 'static,
+    ) -> Buffer<T, Self, S>
+    where
+        F: TwoWay<f32> + 'static;
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_apply_fn_f32_cpu_precision() {
+        use crate::{ApplyFunctionF32, Base, Buffer, Combiner, CPU};
+        use half::f16;
+
+        let device = CPU::<Base>::new();
+        let x = Buffer::from((&device, [f16::from_f32(2048.)]));
+
+        // 2049 is not representable as f16, in f16 arithmetic both additions would round back to 2048
+        let out = device.apply_fn_f32(&x, |x| x.add(1.).add(1.));
+        assert_eq!(out.read(), [f16::from_f32(2050.)]);
+    }
+}
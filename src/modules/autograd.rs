@@ -1,7 +1,11 @@
+mod checkpoint;
+mod gradcheck;
 mod gradients;
 mod tape;
 mod wrapper;
 
+pub use checkpoint::*;
+pub use gradcheck::*;
 pub use gradients::*;
 pub use tape::*;
 
@@ -9,21 +13,23 @@ use core::{
     cell::{Cell, UnsafeCell},
     marker::PhantomData,
 };
+use std::rc::Rc;
 
 use crate::{
-    impl_remove_layer, pass_down_add_operation, pass_down_cached_buffers, pass_down_cursor,
+    impl_remove_layer, op_hint::OpHint, pass_down_cached_buffers, pass_down_cursor,
     pass_down_exec_now_module, pass_down_replace_buf_module, register_buf_copyable,
-    unregister_buf_copyable, AddGradFn, AddLayer, Alloc, Buffer, Device, GradActions, HasId,
-    HasModules, IsShapeIndep, Module, OnDropBuffer, OnNewBuffer, Parents, Retrieve, RunModule,
-    Setup, ShallowCopy, Shape, TapeActions, Unit,
+    unregister_buf_copyable, AddGradFn, AddLayer, AddOperation, Alloc, AnyOp, Buffer, Device,
+    GradActions, HasId, HasModules, IsShapeIndep, Module, OnDropBuffer, OnNewBuffer, Parents,
+    Retrieve, RunModule, SetOpHint, Setup, ShallowCopy, Shape, TapeActions, Unit,
 };
 
+pub(crate) use self::wrapper::next_reserved_id;
 use self::wrapper::ReqGradWrapper;
 
 pub trait HasAutograd {}
 impl<'a, Mods> HasAutograd for Autograd<'a, Mods> {}
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Autograd<'dev, Mods> {
     pub modules: Mods,
     /// Caches gradients for each [`Buffer`]'s id ([`Ident`]).
@@ -50,6 +56,19 @@ impl<'a, Mods: Module<'a, D>, D: Device + 'a> Module<'a, D> for Autograd<'a, Mod
     }
 }
 
+impl<Mods: Default> Default for Autograd<'_, Mods> {
+    #[inline]
+    fn default() -> Self {
+        Autograd {
+            modules: Mods::default(),
+            grads: Default::default(),
+            tape: Default::default(),
+            enabled: Cell::new(true),
+            pd: PhantomData,
+        }
+    }
+}
+
 impl<'dev, Mods> Autograd<'dev, Mods> {
     pub fn register_no_grad_buf<T, D, S>(&self, buf: &Buffer<T, D, S>)
     where
@@ -87,9 +106,11 @@ where
         // no_grads.cache.insert(*new_buf.id(), Box::new(buf));
 
         unsafe {
-            (*self.grads.get())
+            let grads = &mut *self.grads.get();
+            grads
                 .buf_requires_grad
-                .insert(*new_buf.id(), new_buf.requires_grad())
+                .insert(*new_buf.id(), new_buf.requires_grad());
+            grads.intermediate_ids.remove(&*new_buf.id());
         };
         self.register_no_grad_buf(new_buf);
 
@@ -101,9 +122,21 @@ where
 impl<'dev, Mods: OnDropBuffer> OnDropBuffer for Autograd<'dev, Mods> {
     #[inline]
     fn on_drop_buffer<T: Unit, D: Device, S: Shape>(&self, device: &D, buf: &Buffer<T, D, S>) {
-        unsafe { (*self.grads.get()).buf_requires_grad.remove(&*buf.id()) };
+        unsafe {
+            let grads = &mut *self.grads.get();
+            grads.buf_requires_grad.remove(&*buf.id());
+            grads.intermediate_ids.remove(&*buf.id());
+            if !grads.grad_hooks.is_empty() {
+                grads.remove_grad_hooks(*buf.id());
+            }
+        };
         unregister_buf_copyable(unsafe { &mut (*self.grads.get()).no_grads_pool }, buf.id());
 
+        let tape = unsafe { &mut *self.tape.get() };
+        if !tape.checkpoints.is_empty() {
+            tape.checkpoints.on_drop(*buf.id(), &mut tape.lazy_graph);
+        }
+
         // TODO
         // FIXME if an alloc flag None buffer goes out of scope and it has used it's gradient buffer before,
         // the gradient buffer will stay allocated
@@ -132,34 +165,49 @@ where
         &self,
         device: &D,
         len: usize,
-        parents: impl Parents<NUM_PARENTS>,
+        parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         D: Alloc<T>,
     {
-        let requires_grad = parents.requires_grads().iter().any(|&x| x);
-        let data = self.modules.retrieve(device, len, parents)?;
-        unsafe {
-            (*self.grads.get())
-                .buf_requires_grad
-                .insert(*data.id(), requires_grad)
-        };
-
         Ok(ReqGradWrapper {
-            requires_grad,
-            data,
+            // set in on_retrieve_finish, as this is not called if e.g. a Cached module is placed before Autograd
+            requires_grad: true,
+            detached_id: None,
+            owns_detached_id: false,
+            data: self.modules.retrieve(device, len, parents)?,
             _pd: core::marker::PhantomData,
         })
     }
 
     #[inline]
-    fn on_retrieve_finish(&self, retrieved_buf: &Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
+        let requires_grad = parents.requires_grads().iter().any(|&x| x);
+        retrieved_buf.set_requires_grad(requires_grad);
+
+        let id = retrieved_buf.id();
+        unsafe {
+            let grads = &mut *self.grads.get();
+            grads.buf_requires_grad.insert(*id, requires_grad);
+            if requires_grad {
+                grads.intermediate_ids.insert(*id);
+            }
+
+            let checkpoints = &mut (*self.tape.get()).checkpoints;
+            if checkpoints.is_recording() {
+                checkpoints.add_intermediate::<T, D, S>(id, requires_grad);
+            }
+        };
+
         self.register_no_grad_buf(retrieved_buf);
 
-        self.modules.on_retrieve_finish(retrieved_buf)
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
     }
 }
 
@@ -223,6 +271,26 @@ impl<'a, Mods: AddGradFn> AddGradFn for Autograd<'a, Mods> {
         &self,
         args: Args,
         op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
+    ) {
+        if !self.enabled.get() {
+            return;
+        }
+        // the gradient functions of `op` are not recorded
+        let create_graph = unsafe { (*self.grads.get()).create_graph.clone() };
+        unsafe {
+            (*self.tape.get()).add_grad_fn(args, move |args| {
+                if create_graph.get() {
+                    return Err(crate::DeviceError::SecondOrderGradUnavailable.into());
+                }
+                op(args)
+            })
+        }
+    }
+
+    fn add_second_order_grad_fn<Args: Parents<N> + crate::AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
     ) {
         if !self.enabled.get() {
             return;
@@ -239,6 +307,72 @@ impl<'a, Mods: AddGradFn> AddGradFn for Autograd<'a, Mods> {
     fn is_grad_enabled(&self) -> bool {
         self.enabled.get()
     }
+
+    #[inline]
+    fn begin_checkpoint(&self) {
+        let tape = unsafe { &mut *self.tape.get() };
+        tape.checkpoints.begin(tape.lazy_graph.ops_count());
+    }
+
+    #[inline]
+    fn end_checkpoint(&self) {
+        let tape = unsafe { &mut *self.tape.get() };
+        tape.checkpoints.end(tape.lazy_graph.ops_count());
+    }
+}
+
+impl<'dev, T, Mods: SetOpHint<T>> SetOpHint<T> for Autograd<'dev, Mods> {
+    #[inline]
+    fn set_op_hint(&self, op_hint: OpHint<T>) {
+        self.modules.set_op_hint(op_hint)
+    }
+}
+
+impl<'dev, Mods: AddOperation> AddOperation for Autograd<'dev, Mods> {
+    fn add_op<Args: Parents<N> + AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'a> Fn(Args::Replicated<'a>) -> crate::Result<()> + 'static,
+    ) -> crate::Result<()> {
+        let checkpoints = unsafe { &mut (*self.tape.get()).checkpoints };
+        if !checkpoints.is_recording() {
+            return self.modules.add_op(args, op);
+        }
+
+        // the operation is replayed if its intermediates are rematerialized
+        let op = Rc::new(op);
+        let recorded_op = op.clone();
+        checkpoints.add_forward(&args, move |args| recorded_op(args));
+        self.modules.add_op(args, move |args| op(args))
+    }
+
+    unsafe fn add_parallel_op<Args: Parents<N> + AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'a> Fn(Args::Replicated<'a>) -> crate::Result<()> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        let checkpoints = unsafe { &(*self.tape.get()).checkpoints };
+        // recorded operations are shared with the checkpoint
+        if checkpoints.is_recording() {
+            return self.add_op(args, op);
+        }
+        self.modules.add_parallel_op(args, op)
+    }
+
+    #[inline]
+    fn ops_count(&self) -> usize {
+        self.modules.ops_count()
+    }
+
+    #[inline]
+    fn set_lazy_enabled(&self, enabled: bool) {
+        self.modules.set_lazy_enabled(enabled)
+    }
+
+    #[inline]
+    fn is_lazy_enabled(&self) -> bool {
+        self.modules.is_lazy_enabled()
+    }
 }
 
 impl<'a, Mods: RunModule<D>, D> RunModule<D> for Autograd<'a, Mods> {
@@ -266,10 +400,11 @@ impl<'a, NewMods, SD> AddLayer<NewMods, SD> for Autograd<'a, ()> {
 }
 
 pass_down_cursor!(Autograd, 'dev, Mods);
-pass_down_add_operation!(Autograd, 'dev, Mods);
 pass_down_exec_now_module!(Autograd, 'dev, Mods);
 pass_down_cached_buffers!(Autograd, 'dev, Mods);
 pass_down_replace_buf_module!(Autograd, 'dev, Mods);
+crate::pass_down_tangent_actions!(Autograd, 'dev, Mods);
+crate::pass_down_thread_pool_actions!(Autograd, 'dev, Mods);
 
 impl<'a, Mods> HasModules for Autograd<'a, Mods> {
     type Mods = Mods;
@@ -365,14 +500,10 @@ mod tests {
 
     #[test]
     fn test_cached_before_autograd() {
-        // is a cached module is placed before Autograd results a problem
-        // -> the retrieved buffer is not added to the no grads pool of the autograd module
+        // the retrieved buffer is added to the no grads pool of the autograd module,
+        // even though the cached module is placed before Autograd
         let device = CPU::<Cached<Autograd<Base>>>::new();
 
-        // how to fix this:
-        // add retrieved buffer to no grads pool at the end of the chain (at device level (Retriever trait))
-        // => "generator", "actor"
-
         let _lhs = Buffer::<f32, _>::new(&device, 10);
 
         for _ in device.range(0..100) {
@@ -530,4 +661,267 @@ mod tests {
         let out: Buffer<i32, _> = device.retrieve(rhs.len(), (&no_grad, &rhs)).unwrap();
         assert!(!out.requires_grad());
     }
+
+    #[test]
+    fn test_backward_create_graph_gradient_penalty() {
+        use crate::UnaryElementWiseMayGrad;
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        // y = x^3
+        let y = device.unary_ew_second_order(
+            &x,
+            |x| x.mul(x).mul(x),
+            |x| x.mul(x).mul(3.),
+            |x| x.mul(6.),
+        );
+        y.backward_create_graph().unwrap();
+        assert_eq!(x.grad().read(), [3., 12., 27.]);
+
+        // penalty = (3x^2)^2 -> d penalty / dx = 36x^3
+        let penalty = device.unary_ew(x.grad(), |x| x.mul(x), |x| x.mul(2.));
+        penalty.backward().unwrap();
+        assert_eq!(x.grad().read(), [36., 288., 972.]);
+    }
+
+    #[test]
+    fn test_backward_create_graph_chain() {
+        use crate::UnaryElementWiseMayGrad;
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([0.3, -1.2, 2.5]).require_grad();
+
+        // out = sin(x)^2 -> d out / dx = sin(2x), d^2 out / dx^2 = 2cos(2x)
+        let y = device.unary_ew_second_order(&x, |x| x.sin(), |x| x.cos(), |x| x.sin().neg());
+        let out =
+            device.unary_ew_second_order(&y, |x| x.mul(x), |x| x.mul(2.), |x| x.mul(0.).add(2.));
+        out.backward_create_graph().unwrap();
+
+        for (grad, x) in x.grad().read().iter().zip([0.3f64, -1.2, 2.5]) {
+            assert!((grad - (2. * x).sin()).abs() < 1e-9);
+        }
+
+        x.grad().backward().unwrap();
+        for (grad, x) in x.grad().read().iter().zip([0.3f64, -1.2, 2.5]) {
+            assert!((grad - 2. * (2. * x).cos()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_backward_create_graph_without_second_order() {
+        use crate::UnaryElementWiseMayGrad;
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let y = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        assert!(y.backward_create_graph().is_err());
+    }
+
+    #[test]
+    fn test_backward_create_graph_custom_grad_fn() {
+        use crate::{AddGradFn, DeviceError, ErrorKind};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let y = device.buffer([2., 4., 6.]);
+        device.add_grad_fn((&x, &y), |(x, y)| {
+            for (x_grad, y_grad) in unsafe { x.grad_mut_unbound() }
+                .iter_mut()
+                .zip(y.grad().iter())
+            {
+                *x_grad += 2. * y_grad;
+            }
+            Ok(())
+        });
+
+        let err = y.backward_create_graph().unwrap_err();
+        assert_eq!(
+            err.kind::<DeviceError>(),
+            Some(&DeviceError::SecondOrderGradUnavailable)
+        );
+    }
+
+    #[test]
+    fn test_backward_create_graph_third_order() {
+        use crate::{DeviceError, ErrorKind, UnaryElementWiseMayGrad};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let y = device.unary_ew_second_order(
+            &x,
+            |x| x.mul(x).mul(x),
+            |x| x.mul(x).mul(3.),
+            |x| x.mul(6.),
+        );
+        y.backward_create_graph().unwrap();
+
+        // the gradient functions of the second derivative are not differentiable again
+        let err = x.grad().backward_create_graph().unwrap_err();
+        assert_eq!(
+            err.kind::<DeviceError>(),
+            Some(&DeviceError::SecondOrderGradUnavailable)
+        );
+    }
+
+    #[test]
+    fn test_detach_shares_memory() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let mut detached = x.detach();
+        assert_ne!(detached.id(), x.id());
+        assert!(!detached.requires_grad());
+
+        detached.write(&[4., 5., 6.]);
+        assert_eq!(x.read(), [4., 5., 6.]);
+    }
+
+    #[test]
+    fn test_detach_cuts_gradient() {
+        use crate::UnaryElementWiseMayGrad;
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+        let detached = x.detach();
+
+        // replicated arguments keep the requires grad state of the alias
+        device.add_grad_fn((&detached, &x), |(detached, x)| {
+            assert!(!detached.requires_grad());
+            assert!(x.requires_grad());
+            Ok(())
+        });
+
+        let out = device.unary_ew(&detached, |x| x.mul(x), |x| x.mul(2.));
+        assert!(!out.requires_grad());
+        out.backward().unwrap();
+        assert_eq!(x.grad().read(), [0.; 3]);
+
+        let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        out.backward().unwrap();
+        assert_eq!(x.grad().read(), [2., 4., 6.]);
+    }
+
+    #[test]
+    fn test_detach_drop_unregisters_alias() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let id = {
+            let detached = x.detach();
+            let no_grads_pool = unsafe { &(*device.modules.grads.get()).no_grads_pool };
+            assert_eq!(no_grads_pool.len(), 2);
+            detached.id()
+        };
+
+        let grads = unsafe { &*device.modules.grads.get() };
+        assert!(grads.no_grads_pool.get(&id).is_none());
+        assert!(!grads.buf_requires_grad.contains_key(&*id));
+        assert_eq!(grads.no_grads_pool.len(), 1);
+
+        // the memory is still owned by x
+        assert_eq!(x.read(), [1., 2., 3.]);
+    }
+
+    #[test]
+    fn test_detach_lazy() {
+        use crate::{Run, UnaryElementWiseMayGrad};
+
+        let device = CPU::<Autograd<Lazy<Base>>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let detached = x.detach();
+        let out = device.unary_ew(&detached, |x| x.mul(x), |x| x.mul(2.));
+        let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [1., 4., 9.]);
+
+        // lazily allocated buffers are detached after running the graph
+        let squared = squared.replace().detach();
+        let out = device.unary_ew(&squared, |x| x.mul(3.), |_| 3.);
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [3., 12., 27.]);
+
+        out.replace().backward().unwrap();
+        assert_eq!(x.grad().read(), [0.; 3]);
+    }
+
+    #[test]
+    fn test_detach_cached() {
+        use crate::UnaryElementWiseMayGrad;
+
+        let device = CPU::<Autograd<Cached<Base>>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        for _ in device.range(0..3) {
+            let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+            let detached = squared.detach();
+            let out = device.unary_ew(&detached, |x| x.mul(3.), |_| 3.);
+            assert_eq!(out.read(), [3., 12., 27.]);
+
+            out.backward().unwrap();
+            assert_eq!(x.grad().read(), [0.; 3]);
+        }
+
+        // detached aliases are unregistered when they are dropped
+        let no_grads_pool = unsafe { &(*device.modules.grads.get()).no_grads_pool };
+        assert_eq!(no_grads_pool.len(), 3);
+    }
+
+    #[test]
+    fn test_stop_gradient() {
+        use crate::{StopGradient, UnaryElementWiseMayGrad};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        let stopped = device.stop_gradient(&squared).unwrap();
+        assert_ne!(stopped.id(), squared.id());
+        assert!(!stopped.requires_grad());
+
+        let out = device.unary_ew(&stopped, |x| x.mul(3.), |_| 3.);
+        assert_eq!(out.read(), [3., 12., 27.]);
+        out.backward().unwrap();
+        assert_eq!(x.grad().read(), [0.; 3]);
+    }
+
+    #[test]
+    fn test_stop_gradient_lazy() {
+        use crate::{Run, StopGradient, UnaryElementWiseMayGrad};
+
+        let device = CPU::<Autograd<Lazy<Base>>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        let stopped = device.stop_gradient(&squared).unwrap();
+        let out = device.unary_ew(&stopped, |x| x.mul(3.), |_| 3.);
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [3., 12., 27.]);
+
+        out.replace().backward().unwrap();
+        assert_eq!(x.grad().read(), [0.; 3]);
+    }
+
+    #[test]
+    fn test_stop_gradient_cached() {
+        use crate::{StopGradient, UnaryElementWiseMayGrad};
+
+        let device = CPU::<Autograd<Cached<Base>>>::new();
+        let mut x = device.buffer([1., 2., 3.]).require_grad();
+
+        for value in device.range(1..4) {
+            x.write(&[value as f64; 3]);
+            let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+            let stopped = device.stop_gradient(&squared).unwrap();
+            assert_eq!(stopped.read(), [(value * value) as f64; 3]);
+
+            let out = device.unary_ew(&stopped, |x| x.mul(3.), |_| 3.);
+            out.backward().unwrap();
+            assert_eq!(x.grad().read(), [0.; 3]);
+        }
+    }
 }
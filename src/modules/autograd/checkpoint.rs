@@ -0,0 +1,420 @@
+use core::{any::Any, hash::BuildHasherDefault, ops::Range};
+use std::collections::HashMap;
+
+use crate::{
+    flag::AllocFlag, op_hint::OpHint, Alloc, AnyOp, BoxedShallowCopy, Buffer, Buffers, Device,
+    GradActions, HasId, Id, LazyGraph, NoHasher, Operation, Parents, ShallowCopy, Shape, UniqueId,
+    Unit,
+};
+
+/// Allocates an intermediate [`Buffer`] of a checkpointed region again.
+/// Returns the id of the new allocation and the type erased [`Buffer`].
+type RematerializeFn =
+    fn(&dyn Any, usize, bool) -> crate::Result<(UniqueId, Box<dyn BoxedShallowCopy>)>;
+
+struct Intermediate {
+    id: Id,
+    requires_grad: bool,
+    dropped: bool,
+    rematerialize: RematerializeFn,
+}
+
+/// A region of forward operations whose dropped intermediate buffers are recomputed during the backward pass.
+/// See [`AddGradFn::checkpoint`](crate::AddGradFn::checkpoint).
+#[derive(Default)]
+pub struct Checkpoint {
+    /// The forward operations that were executed in the region.
+    pub forward: LazyGraph<Box<dyn BoxedShallowCopy>>,
+    /// The indices of the gradient functions (on the [`Tape`](crate::Tape)) that were recorded in the region.
+    pub grad_fns: Range<usize>,
+    intermediates: Vec<Intermediate>,
+    /// The keys of the rematerialized buffers and the ids of their allocations.
+    rematerialized: Option<Vec<(UniqueId, UniqueId)>>,
+}
+
+/// Keeps track of the checkpointed regions of a [`Tape`](crate::Tape).
+///
+/// If an intermediate buffer of a region is dropped, the operations referring to it are renamed to a unique id that cannot collide with the id of another allocation.
+/// Before the first gradient function using such an id runs, the region is rematerialized:
+/// The dropped intermediates are allocated again and the forward operations writing to them are replayed.
+/// After the first gradient function of the region ran, the rematerialized buffers are deallocated.
+#[derive(Default)]
+pub struct Checkpoints {
+    pub regions: Vec<Checkpoint>,
+    /// Nested regions are part of the outermost region.
+    depth: usize,
+    /// Maps the ids of intermediates that are still alive to their region.
+    live: HashMap<UniqueId, usize, BuildHasherDefault<NoHasher>>,
+    /// Maps the renamed ids of dropped intermediates to their region.
+    dropped: HashMap<UniqueId, usize, BuildHasherDefault<NoHasher>>,
+}
+
+impl Checkpoints {
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.depth > 0
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Starts a region. `grad_fn_count` is the amount of gradient functions on the tape.
+    pub fn begin(&mut self, grad_fn_count: usize) {
+        self.depth += 1;
+        if self.depth == 1 {
+            self.regions.push(Checkpoint {
+                grad_fns: grad_fn_count..grad_fn_count,
+                ..Default::default()
+            });
+        }
+    }
+
+    /// Ends the current region. `grad_fn_count` is the amount of gradient functions on the tape.
+    pub fn end(&mut self, grad_fn_count: usize) {
+        if self.depth == 0 {
+            return;
+        }
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Some(region) = self.regions.last_mut() {
+                region.grad_fns.end = grad_fn_count;
+            }
+        }
+    }
+
+    /// Records a forward operation of the current region.
+    pub fn add_forward<Args: Parents<N> + AnyOp, const N: usize>(
+        &mut self,
+        args: &Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
+    ) {
+        let Some(region) = self.regions.last_mut() else {
+            return;
+        };
+        region.forward.operations.push(Operation {
+            arg_ids: args
+                .maybe_ids()
+                .into_iter()
+                .map(|id| id.expect("every parent must have an id"))
+                .collect(),
+            mutable_args: Args::MUTABLE_ARGS.to_vec(),
+            op: Args::replication_fn::<Box<dyn BoxedShallowCopy>>(op),
+            op_hint: OpHint::None,
+            parallel_safe: false,
+        });
+    }
+
+    /// Records a buffer that was retrieved in the current region.
+    pub fn add_intermediate<T, D, S>(&mut self, id: Id, requires_grad: bool)
+    where
+        T: Unit + 'static,
+        D: Alloc<T> + 'static,
+        D::Data<T, S>: ShallowCopy,
+        S: Shape,
+    {
+        let region_idx = self.regions.len() - 1;
+        self.regions[region_idx].intermediates.push(Intermediate {
+            id,
+            requires_grad,
+            dropped: false,
+            rematerialize: rematerialize::<T, D, S>,
+        });
+        self.live.insert(*id, region_idx);
+    }
+
+    /// Renames the dropped intermediate with the `id` in all operations that refer to it.
+    /// `grad_fns` are the gradient functions of the [`Tape`](crate::Tape).
+    pub fn on_drop(&mut self, id: UniqueId, grad_fns: &mut LazyGraph<Box<dyn BoxedShallowCopy>>) {
+        let Some(region_idx) = self.live.remove(&id) else {
+            return;
+        };
+
+        let renamed_id = crate::next_reserved_id();
+
+        let rename = |ids: &mut [Id]| {
+            for arg_id in ids.iter_mut().filter(|arg_id| arg_id.id == id) {
+                arg_id.id = renamed_id;
+            }
+        };
+
+        let start = self.regions[region_idx].grad_fns.start;
+        for op in grad_fns.operations.iter_mut().skip(start) {
+            rename(&mut op.arg_ids);
+        }
+        for region in &mut self.regions[region_idx..] {
+            for op in &mut region.forward.operations {
+                rename(&mut op.arg_ids);
+            }
+        }
+
+        let region = &mut self.regions[region_idx];
+        for intermediate in region
+            .intermediates
+            .iter_mut()
+            .filter(|intermediate| !intermediate.dropped && intermediate.id.id == id)
+        {
+            intermediate.id.id = renamed_id;
+            intermediate.dropped = true;
+        }
+        self.dropped.insert(renamed_id, region_idx);
+    }
+
+    /// Rematerializes the regions of all dropped intermediates in `ids`.
+    pub fn rematerialize_for<D: Device + GradActions + 'static>(
+        &mut self,
+        ids: &[Id],
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+    ) -> crate::Result<()> {
+        for id in ids {
+            if let Some(&region_idx) = self.dropped.get(&id.id) {
+                self.rematerialize(region_idx, device, buffers)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn rematerialize<D: Device + GradActions + 'static>(
+        &mut self,
+        region_idx: usize,
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+    ) -> crate::Result<()> {
+        if self.regions[region_idx].rematerialized.is_some() {
+            return Ok(());
+        }
+
+        // the forward operations may use dropped intermediates of previous regions
+        let previous_regions = self.regions[region_idx]
+            .forward
+            .operations
+            .iter()
+            .flat_map(|op| &op.arg_ids)
+            .filter_map(|id| self.dropped.get(&id.id).copied())
+            .filter(|&idx| idx != region_idx)
+            .collect::<Vec<_>>();
+        for idx in previous_regions {
+            self.rematerialize(idx, device, buffers)?;
+        }
+
+        let region = &mut self.regions[region_idx];
+        let mut rematerialized = Vec::new();
+        for intermediate in region.intermediates.iter().filter(|i| i.dropped) {
+            let (alloc_id, buf) = (intermediate.rematerialize)(
+                device,
+                intermediate.id.len,
+                intermediate.requires_grad,
+            )?;
+
+            // a gradient of a previous allocation with the same id must not be accumulated
+            remove_grad(device, alloc_id);
+            buffers.insert(intermediate.id.id, buf);
+            rematerialized.push((intermediate.id.id, alloc_id));
+        }
+        region.rematerialized = Some(rematerialized);
+
+        // only operations writing to dropped intermediates are replayed, e.g. in-place operations on inputs are not
+        for op in &region.forward.operations {
+            let writes_dropped = op
+                .written_ids()
+                .any(|id| self.dropped.get(&id.id) == Some(&region_idx));
+            if writes_dropped {
+                op.call(buffers, device)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Deallocates the rematerialized buffers of all regions whose first gradient function has the index `grad_fn_idx`.
+    pub fn free_from<D: GradActions>(
+        &mut self,
+        grad_fn_idx: usize,
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+    ) {
+        for region in &mut self.regions {
+            if region.grad_fns.start == grad_fn_idx {
+                free_region(region, device, buffers);
+            }
+        }
+    }
+
+    /// Deallocates all rematerialized buffers.
+    pub fn free_all<D: GradActions>(
+        &mut self,
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+    ) {
+        for region in &mut self.regions {
+            free_region(region, device, buffers);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.live.clear();
+        self.dropped.clear();
+    }
+}
+
+fn free_region<D: GradActions>(
+    region: &mut Checkpoint,
+    device: &D,
+    buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+) {
+    for (key, alloc_id) in region.rematerialized.take().unwrap_or_default() {
+        buffers.remove(&key);
+        remove_grad(device, alloc_id);
+    }
+}
+
+#[inline]
+fn remove_grad<D: GradActions>(device: &D, id: UniqueId) {
+    if let Some(gradients) = unsafe { device.gradients_mut() } {
+        gradients.remove_grad(id);
+    }
+}
+
+fn rematerialize<T, D, S>(
+    device: &dyn Any,
+    len: usize,
+    requires_grad: bool,
+) -> crate::Result<(UniqueId, Box<dyn BoxedShallowCopy>)>
+where
+    T: Unit + 'static,
+    D: Alloc<T> + 'static,
+    D::Data<T, S>: ShallowCopy,
+    S: Shape,
+{
+    let device = device.downcast_ref::<D>().unwrap();
+
+    // AllocFlag::Lazy prevents calling on_drop_buffer, the buffer is deallocated when it is removed from the buffers
+    let base = device.alloc::<S>(len, AllocFlag::Lazy)?;
+    let mut buffer = Buffer {
+        data: device.base_to_data(base),
+        device: Some(device),
+    };
+    buffer.set_requires_grad(requires_grad);
+
+    let id = *buffer.id();
+    let buffer: Buffer<'static, T, D, S> = unsafe { core::mem::transmute(buffer) };
+    Ok((id, Box::new(buffer)))
+}
+
+#[cfg(test)]
+#[cfg(feature = "cpu")]
+mod tests {
+    use crate::{AddGradFn, Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+
+    fn roughly_eq(lhs: &[f64], rhs: &[f64]) {
+        for (lhs, rhs) in lhs.iter().zip(rhs) {
+            assert!((lhs - rhs).abs() < 1e-9, "{lhs} != {rhs}");
+        }
+    }
+
+    #[test]
+    fn test_checkpoint_recomputes_dropped_intermediates() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([0.5, 1., 2.]).require_grad();
+
+        let out = device.checkpoint(|| {
+            let y = device.unary_ew(
+                &device.unary_ew(&x, |x| x.sin(), |x| x.cos()),
+                |x| x.exp(),
+                |x| x.exp(),
+            );
+            device.unary_ew(&y, |x| x.mul(x), |x| x.mul(2.))
+        });
+
+        let no_grads_pool = unsafe { &(*device.modules.grads.get()).no_grads_pool };
+        assert_eq!(no_grads_pool.len(), 2);
+
+        let checkpoints = unsafe { &(*device.modules.tape.get()).checkpoints };
+        assert_eq!(checkpoints.regions.len(), 1);
+        assert_eq!(checkpoints.regions[0].forward.ops_count(), 3);
+        assert_eq!(checkpoints.regions[0].grad_fns, 0..3);
+
+        out.backward().unwrap();
+
+        // d/dx exp(sin(x))^2 = 2 exp(2 sin(x)) cos(x)
+        let expected = [0.5f64, 1., 2.].map(|x| 2. * (2. * x.sin()).exp() * x.cos());
+        roughly_eq(&x.grad().read(), &expected);
+
+        // the rematerialized intermediates are deallocated after the backward pass
+        let no_grads_pool = unsafe { &(*device.modules.grads.get()).no_grads_pool };
+        assert_eq!(no_grads_pool.len(), 2);
+    }
+
+    #[test]
+    fn test_dropped_intermediate_without_checkpoint() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([0.5, 1., 2.]).require_grad();
+
+        let out = device.unary_ew(
+            &device.unary_ew(&x, |x| x.sin(), |x| x.cos()),
+            |x| x.exp(),
+            |x| x.exp(),
+        );
+        assert!(out.backward().is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_intermediate_used_after_region() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let (y, _out) = device.checkpoint(|| {
+            let y = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+            let out = device.unary_ew(&y, |x| x.add(1.), |_| 1.);
+            (y, out)
+        });
+        let z = device.unary_ew(&y, |x| x.mul(3.), |_| 3.);
+        drop(y);
+
+        z.backward().unwrap();
+        assert_eq!(x.grad().read(), [6., 12., 18.]);
+    }
+
+    #[test]
+    fn test_checkpoint_nested_and_multiple_regions() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let y = device.checkpoint(|| {
+            let squared = device.checkpoint(|| device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.)));
+            device.unary_ew(&squared, |x| x.mul(2.), |_| 2.)
+        });
+        let out = device.checkpoint(|| {
+            let cubed = device.unary_ew(&y, |x| x.mul(x).mul(x), |x| x.mul(x).mul(3.));
+            device.unary_ew(&cubed, |x| x.add(1.), |_| 1.)
+        });
+
+        let checkpoints = unsafe { &(*device.modules.tape.get()).checkpoints };
+        assert_eq!(checkpoints.regions.len(), 2);
+
+        out.backward().unwrap();
+
+        // out = (2x^2)^3 + 1 -> d out / dx = 48x^5
+        assert_eq!(x.grad().read(), [48., 1536., 11664.]);
+    }
+
+    #[test]
+    fn test_checkpoint_ends_region_on_panic() {
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2., 3.]).require_grad();
+
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            device.checkpoint(|| {
+                let _y = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+                panic!("checkpointed op failed");
+            })
+        }));
+        assert!(res.is_err());
+
+        let checkpoints = unsafe { &(*device.modules.tape.get()).checkpoints };
+        assert!(!checkpoints.is_recording());
+        assert_eq!(checkpoints.regions[0].grad_fns, 0..1);
+    }
+}
@@ -0,0 +1,267 @@
+use core::fmt;
+
+use crate::{
+    AddGradFn, AddOperation, Alloc, Buffer, CachedBuffers, Float, GradActions, Read, Shape,
+    TapeActions, WriteBuf, ZeroGrad,
+};
+
+use super::HasAutograd;
+
+/// The step size and tolerances used by [`gradcheck`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradCheckConfig {
+    /// The perturbation used for the central differences.
+    pub eps: f64,
+    pub atol: f64,
+    pub rtol: f64,
+    /// How many of the worst elements are kept in the [`GradCheckReport`].
+    pub max_reported: usize,
+}
+
+impl Default for GradCheckConfig {
+    #[inline]
+    fn default() -> Self {
+        GradCheckConfig {
+            eps: 1e-6,
+            atol: 1e-5,
+            rtol: 1e-3,
+            max_reported: 5,
+        }
+    }
+}
+
+impl GradCheckConfig {
+    /// Suitable for `f32` buffers.
+    #[inline]
+    pub fn f32() -> Self {
+        GradCheckConfig {
+            eps: 1e-3,
+            atol: 1e-2,
+            rtol: 1e-2,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn with_eps(mut self, eps: f64) -> Self {
+        self.eps = eps;
+        self
+    }
+
+    #[inline]
+    pub fn with_tolerances(mut self, atol: f64, rtol: f64) -> Self {
+        self.atol = atol;
+        self.rtol = rtol;
+        self
+    }
+
+    #[inline]
+    pub fn with_max_reported(mut self, max_reported: usize) -> Self {
+        self.max_reported = max_reported;
+        self
+    }
+}
+
+/// The comparison of the analytical and numerical gradient of one input element.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradCheckElement {
+    /// The index of the input buffer.
+    pub input: usize,
+    /// The index of the element in the input buffer.
+    pub idx: usize,
+    /// The gradient computed by the backward pass.
+    pub analytical: f64,
+    /// The gradient computed via central differences.
+    pub numerical: f64,
+}
+
+impl GradCheckElement {
+    #[inline]
+    pub fn abs_err(&self) -> f64 {
+        (self.analytical - self.numerical).abs()
+    }
+
+    #[inline]
+    pub fn rel_err(&self) -> f64 {
+        let scale = self.analytical.abs().max(self.numerical.abs());
+        if scale == 0. {
+            return 0.;
+        }
+        self.abs_err() / scale
+    }
+
+    /// Returns `true` if `|analytical - numerical| <= atol + rtol * |numerical|`.
+    #[inline]
+    pub fn is_close(&self, config: &GradCheckConfig) -> bool {
+        self.abs_err() <= config.atol + config.rtol * self.numerical.abs()
+    }
+}
+
+/// The result of [`gradcheck`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradCheckReport {
+    pub config: GradCheckConfig,
+    /// The number of checked elements.
+    pub checked: usize,
+    /// The number of elements exceeding the tolerances.
+    pub failed: usize,
+    /// The worst elements (by absolute error) in descending order.
+    pub worst: Vec<GradCheckElement>,
+}
+
+impl GradCheckReport {
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.failed == 0
+    }
+
+    #[inline]
+    pub fn max_abs_err(&self) -> f64 {
+        self.worst.first().map_or(0., GradCheckElement::abs_err)
+    }
+}
+
+impl fmt::Display for GradCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "gradcheck: {} of {} elements exceed atol={}, rtol={}",
+            self.failed, self.checked, self.config.atol, self.config.rtol
+        )?;
+        for element in &self.worst {
+            writeln!(
+                f,
+                "  input {}[{}]: analytical={}, numerical={}, abs_err={:e}, rel_err={:e}",
+                element.input,
+                element.idx,
+                element.analytical,
+                element.numerical,
+                element.abs_err(),
+                element.rel_err(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the gradients computed by the backward pass of `f` with central differences.
+///
+/// The objective is a weighted sum of the elements of `f`'s output (with fixed, distinct weights, so that mixed up output elements are detected).
+/// The gradients of the `inputs` are zeroed before the backward pass, the values of the `inputs` are restored afterwards.
+/// `f` is called once with gradient tracking and twice per input element without.
+/// Hence, it should not record operations that are not differentiated, and the [`Tape`](crate::Tape) should not contain other gradient functions.
+/// # Example
+#[cfg_attr(feature = "cpu", doc = "```")]
+#[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+/// use custos::{gradcheck, Autograd, Base, Combiner, Device, GradCheckConfig, UnaryElementWiseMayGrad, CPU};
+///
+/// let device = CPU::<Autograd<Base>>::new();
+/// let mut x = device.buffer([1., 2., 3.]).require_grad();
+///
+/// let report = gradcheck(&device, &mut [&mut x], |inputs| {
+///     device.unary_ew(inputs[0], |x| x.mul(x), |x| x.mul(2.))
+/// }, GradCheckConfig::default()).unwrap();
+/// assert!(report.passed(), "{report}");
+///
+/// // wrong derivative
+/// let report = gradcheck(&device, &mut [&mut x], |inputs| {
+///     device.unary_ew(inputs[0], |x| x.mul(x), |x| x.mul(3.))
+/// }, GradCheckConfig::default()).unwrap();
+/// assert_eq!(report.failed, 3);
+/// assert_eq!(report.worst[0].idx, 2);
+/// ```
+pub fn gradcheck<'a, 'b, T, D, S, OS>(
+    device: &'a D,
+    inputs: &mut [&mut Buffer<'a, T, D, S>],
+    f: impl Fn(&[&Buffer<'a, T, D, S>]) -> Buffer<'a, T, D, OS>,
+    config: GradCheckConfig,
+) -> crate::Result<GradCheckReport>
+where
+    T: Float + 'static,
+    D: HasAutograd
+        + TapeActions<'b>
+        + GradActions
+        + AddGradFn
+        + CachedBuffers
+        + ZeroGrad<T>
+        + Read<T, S>
+        + Read<T, OS>
+        + WriteBuf<T, S, D>
+        + WriteBuf<T, OS, D>
+        + Alloc<T>
+        + AddOperation
+        + 'static,
+    S: Shape,
+    OS: Shape,
+{
+    for input in inputs.iter_mut() {
+        let grad = input.grad_mut();
+        device.zero_grad(grad.base_mut());
+    }
+
+    let out = f(&as_refs(inputs));
+    let weights = (0..out.len())
+        .map(|idx| 1. + (idx % 7) as f64 / 7.)
+        .collect::<Vec<_>>();
+    let seed = weights.iter().map(|w| T::from_f64(*w)).collect::<Vec<_>>();
+    out.backward_with(&seed)?;
+    drop(out);
+
+    let objective = |inputs: &[&mut Buffer<'a, T, D, S>]| {
+        let mut value = 0.;
+        device.no_grad_ctx(|| {
+            let out = f(&as_refs(inputs));
+            value = out
+                .read_to_vec()
+                .iter()
+                .zip(&weights)
+                .map(|(x, w)| x.as_f64() * w)
+                .sum();
+        });
+        value
+    };
+
+    let mut elements = Vec::new();
+    for input_idx in 0..inputs.len() {
+        let analytical = inputs[input_idx].grad().read_to_vec();
+        let values = inputs[input_idx].read_to_vec();
+        let mut perturbed = values.clone();
+
+        for (idx, analytical) in analytical.iter().enumerate() {
+            perturbed[idx] = T::from_f64(values[idx].as_f64() + config.eps);
+            inputs[input_idx].write(&perturbed);
+            let plus = objective(inputs);
+
+            perturbed[idx] = T::from_f64(values[idx].as_f64() - config.eps);
+            inputs[input_idx].write(&perturbed);
+            let minus = objective(inputs);
+
+            perturbed[idx] = values[idx];
+            elements.push(GradCheckElement {
+                input: input_idx,
+                idx,
+                analytical: analytical.as_f64(),
+                numerical: (plus - minus) / (2. * config.eps),
+            });
+        }
+        inputs[input_idx].write(&values);
+    }
+
+    let failed = elements.iter().filter(|e| !e.is_close(&config)).count();
+    elements.sort_by(|a, b| b.abs_err().total_cmp(&a.abs_err()));
+    elements.truncate(config.max_reported);
+
+    Ok(GradCheckReport {
+        config,
+        checked: inputs.iter().map(|input| input.len()).sum(),
+        failed,
+        worst: elements,
+    })
+}
+
+#[inline]
+fn as_refs<'a, 'b, T, D: crate::Device, S: Shape>(
+    inputs: &'b [&mut Buffer<'a, T, D, S>],
+) -> Vec<&'b Buffer<'a, T, D, S>> {
+    inputs.iter().map(|input| &**input).collect()
+}
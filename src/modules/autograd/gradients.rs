@@ -1,9 +1,12 @@
-use core::{any::Any, hash::BuildHasherDefault};
-use std::collections::HashMap;
+use core::{any::Any, cell::Cell, hash::BuildHasherDefault};
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use crate::{
-    Alloc, BorrowCache, BoxedShallowCopy, Buffer, Buffers, CachingError, Device, HasId, Id,
-    NoHasher, Shape, UniqueId, Unit, ZeroGrad,
+    register_buf_copyable, Alloc, BorrowCache, BoxedShallowCopy, Buffer, Buffers, CachingError,
+    Device, HasId, Id, IsShapeIndep, NoHasher, ShallowCopy, Shape, UniqueId, Unit, ZeroGrad,
 };
 
 const INVALID_ID: &str = "A matching Buffer does not exist.";
@@ -16,6 +19,33 @@ pub struct Gradients {
     pub no_grads_pool: Buffers<Box<dyn BoxedShallowCopy>>,
     pub zero_grad_cbs: Vec<(Id, fn(&mut dyn Any))>,
     pub buf_requires_grad: HashMap<UniqueId, bool, BuildHasherDefault<NoHasher>>,
+    /// Is set while [`Tape::backward_create_graph`](crate::Tape::backward_create_graph) executes the gradient functions.
+    /// Gradient functions added via [`AddGradFn::add_second_order_grad_fn`](crate::AddGradFn::add_second_order_grad_fn) then record the gradient functions of their own computations.
+    /// The flag is shared with the recorded gradient functions.
+    pub create_graph: Rc<Cell<bool>>,
+    /// The gradients registered by [`Gradients::register_grads`] as (id of the buffer, id of the gradient).
+    pub(crate) graph_grads: Vec<(UniqueId, UniqueId)>,
+    /// Gradients that were replaced by [`Gradients::retain_graph_grads`] as (id of the gradient, gradient).
+    /// The recorded gradient functions may still use them.
+    pub(crate) retained_grads: Vec<(UniqueId, Box<dyn Any>)>,
+    /// The ids of buffers computed by operations (retrieved with parents requiring a gradient).
+    /// Their gradients are not taken into account by [`Gradients::grad_norm`] and [`Gradients::scale_grads`].
+    pub(crate) intermediate_ids: HashSet<UniqueId, BuildHasherDefault<NoHasher>>,
+    /// Type-erased operations for every gradient, registered together with the `zero_grad_cbs`.
+    pub(crate) grad_cbs: Vec<(Id, GradCallbacks)>,
+    /// Hooks that are called with the gradient of a buffer after the gradient was computed.
+    pub(crate) grad_hooks: Vec<(UniqueId, GradHook)>,
+    accumulation_steps: usize,
+    accumulated: usize,
+}
+
+pub(crate) type GradHook = Box<dyn FnMut(&mut dyn Any)>;
+
+/// Operations on a gradient that do not require knowing its concrete [`Buffer`] type.
+#[derive(Clone, Copy)]
+pub(crate) struct GradCallbacks {
+    squared_norm: fn(&dyn Any) -> crate::Result<f64>,
+    scale: fn(&mut dyn Any, f64) -> crate::Result<()>,
 }
 
 impl core::fmt::Debug for Gradients {
@@ -27,6 +57,7 @@ impl core::fmt::Debug for Gradients {
 }
 
 impl Gradients {
+    /// Zeroes all gradients and resets the count of accumulated backward passes.
     pub fn zero_grad(&mut self) {
         for (id, cb) in &self.zero_grad_cbs {
             let grad_buf = self.grads_pool.cache.get_mut(id).unwrap();
@@ -37,6 +68,7 @@ impl Gradients {
                 cb(&mut **grad_buf);
             }
         }
+        self.accumulated = 0;
         // self.grads_pool.cache.clear();
     }
 
@@ -52,6 +84,171 @@ impl Gradients {
 
             grad_buf.device().zero_grad(grad_buf);
         }));
+        self.grad_cbs.push((
+            *id,
+            GradCallbacks {
+                squared_norm: |grad_buf| {
+                    let grad_buf = grad_buf.downcast_ref::<Buffer<T, D, S>>().unwrap();
+                    grad_buf.device().grad_squared_norm(grad_buf)
+                },
+                scale: |grad_buf, factor| {
+                    let grad_buf = grad_buf.downcast_mut::<Buffer<T, D, S>>().unwrap();
+                    grad_buf.device().scale_grad(grad_buf, factor)
+                },
+            },
+        ));
+    }
+
+    /// Calls `f` with the gradient of every leaf buffer (e.g. parameters) that requires a gradient.
+    fn for_each_grad(
+        &mut self,
+        mut f: impl FnMut(&mut dyn Any, &GradCallbacks) -> crate::Result<()>,
+    ) -> crate::Result<()> {
+        for (id, cbs) in &self.grad_cbs {
+            if self.intermediate_ids.contains(id) {
+                continue;
+            }
+            let Some(grad_buf) = self.grads_pool.cache.get_mut(id) else {
+                continue;
+            };
+            if *self.buf_requires_grad.get(id).unwrap_or(&true) {
+                f(&mut **grad_buf, cbs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the L2 norm of the gradients of all leaf buffers (e.g. parameters), as if they were concatenated into a single vector.
+    /// Gradients of buffers computed by operations are skipped.
+    /// The squared norms are computed on the devices of the gradients.
+    pub fn grad_norm(&mut self) -> crate::Result<f64> {
+        let mut squared_norm = 0.;
+        self.for_each_grad(|grad_buf, cbs| {
+            squared_norm += (cbs.squared_norm)(grad_buf)?;
+            Ok(())
+        })?;
+        Ok(squared_norm.sqrt())
+    }
+
+    /// Multiplies the gradients of all leaf buffers by `factor`.
+    pub fn scale_grads(&mut self, factor: f64) -> crate::Result<()> {
+        self.for_each_grad(|grad_buf, cbs| (cbs.scale)(grad_buf, factor))
+    }
+
+    /// Scales the gradients of all leaf buffers so that their global L2 norm (see [`Gradients::grad_norm`]) is at most `max_norm`.
+    /// Returns the norm before clipping.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Combiner, Device, GradActions, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([6., 8.]).require_grad();
+    ///
+    /// let out = device.unary_ew(&x, |x| x.mul(x).div(2.), |x| x);
+    /// out.backward().unwrap();
+    ///
+    /// // the gradient of `out` is not clipped
+    /// let norm = device.clip_grad_norm(5.).unwrap();
+    /// assert_eq!(norm, 10.);
+    /// assert_eq!(x.grad().read(), [3., 4.]);
+    /// ```
+    pub fn clip_grad_norm(&mut self, max_norm: f64) -> crate::Result<f64> {
+        let norm = self.grad_norm()?;
+        if norm > max_norm {
+            self.scale_grads(max_norm / norm)?;
+        }
+        Ok(norm)
+    }
+
+    /// Sets the amount of backward passes whose gradients are accumulated before an update.
+    /// Optimizers skip steps until the gradients of `steps` backward passes were accumulated, then update with their mean.
+    #[inline]
+    pub fn set_accumulation_steps(&mut self, steps: usize) {
+        self.accumulation_steps = steps;
+    }
+
+    #[inline]
+    pub fn accumulation_steps(&self) -> usize {
+        self.accumulation_steps.max(1)
+    }
+
+    /// Returns the amount of backward passes since the gradients were zeroed.
+    #[inline]
+    pub fn accumulated(&self) -> usize {
+        self.accumulated
+    }
+
+    /// Returns `true` if the configured amount of backward passes was accumulated.
+    /// Always `true` if gradients are not accumulated over several backward passes.
+    #[inline]
+    pub fn is_accumulation_complete(&self) -> bool {
+        self.accumulation_steps() == 1 || self.accumulated >= self.accumulation_steps()
+    }
+
+    /// Is called after every backward pass.
+    #[inline]
+    pub(crate) fn count_backward(&mut self) {
+        self.accumulated += 1;
+    }
+
+    /// Turns the accumulated gradients into their mean.
+    pub fn average_accumulated_grads(&mut self) -> crate::Result<()> {
+        if self.accumulated <= 1 {
+            return Ok(());
+        }
+        self.scale_grads(1. / self.accumulated as f64)
+    }
+
+    /// Adds a hook that is called with the gradient of the buffer with the `id` once it was computed during a backward pass,
+    /// i.e. after the last gradient function that uses the buffer.
+    /// The hook is removed if the buffer is dropped.
+    pub fn add_grad_hook<T, D, S>(
+        &mut self,
+        id: Id,
+        mut hook: impl for<'b> FnMut(&mut Buffer<'b, T, D, S>) + 'static,
+    ) where
+        T: Unit + 'static,
+        D: Device + 'static,
+        S: Shape,
+    {
+        self.grad_hooks.push((
+            *id,
+            Box::new(move |grad_buf| {
+                if let Some(grad_buf) = grad_buf.downcast_mut::<Buffer<T, D, S>>() {
+                    hook(grad_buf)
+                }
+            }),
+        ));
+    }
+
+    /// Removes all hooks of the buffer with the `id`.
+    #[inline]
+    pub fn remove_grad_hooks(&mut self, id: UniqueId) {
+        self.grad_hooks.retain(|(hook_id, _)| *hook_id != id);
+    }
+
+    /// Calls the hooks of the buffer with the `id` if its gradient exists.
+    pub(crate) fn run_grad_hooks(&mut self, id: UniqueId) {
+        let Some(grad_buf) = self.grads_pool.cache.get_mut(&id) else {
+            return;
+        };
+        for (_, hook) in self
+            .grad_hooks
+            .iter_mut()
+            .filter(|(hook_id, _)| *hook_id == id)
+        {
+            hook(&mut **grad_buf);
+        }
+    }
+
+    /// Removes (and deallocates) the gradient of the buffer with the `id`.
+    pub fn remove_grad(&mut self, id: UniqueId) {
+        if self.grads_pool.cache.remove(&id).is_none() {
+            return;
+        }
+        self.zero_grad_cbs.retain(|(grad_id, _)| **grad_id != id);
+        self.grad_cbs.retain(|(grad_id, _)| **grad_id != id);
     }
 
     /// May get a reference to a gradient [`Buffer`].
@@ -135,6 +332,55 @@ impl Gradients {
         self.get_ref(buf.device(), buf.id())
     }
 
+    /// Registers all gradient buffers of type `Buffer<T, D, S>` in `buffers` (or the `no_grads_pool` if `None`).
+    /// Afterwards, gradients can be used as arguments of operations.
+    ///
+    /// The next backward pass computes new gradients for these buffers (see [`Gradients::retain_graph_grads`]).
+    pub fn register_grads<T, D, S>(
+        &mut self,
+        buffers: Option<&mut Buffers<Box<dyn BoxedShallowCopy>>>,
+    ) where
+        T: Unit + 'static,
+        D: Device + IsShapeIndep + 'static,
+        D::Data<T, S>: ShallowCopy,
+        S: Shape,
+    {
+        let buffers = buffers.unwrap_or(&mut self.no_grads_pool);
+
+        for (id, grad) in &self.grads_pool.cache {
+            let Some(grad) = grad.downcast_ref::<Buffer<T, D, S>>() else {
+                continue;
+            };
+            self.graph_grads.push((*id, *grad.id()));
+            if buffers.contains_key(&grad.id()) {
+                continue;
+            }
+            unsafe { register_buf_copyable(buffers, grad) };
+        }
+    }
+
+    /// Replaces the gradients registered by [`Gradients::register_grads`] with new (zeroed) gradients.
+    /// The replaced gradients stay allocated until the next call, as gradient functions recorded by [`Tape::backward_create_graph`](crate::Tape::backward_create_graph) use them.
+    pub fn retain_graph_grads(&mut self, buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>) {
+        // the tape does not contain gradient functions using these gradients anymore
+        for (grad_id, _) in self.retained_grads.drain(..) {
+            buffers.remove(&grad_id);
+            self.no_grads_pool.remove(&grad_id);
+        }
+
+        for (id, grad_id) in self.graph_grads.drain(..) {
+            if let Some(grad) = self.grads_pool.cache.remove(&id) {
+                self.retained_grads.push((grad_id, grad));
+            }
+        }
+
+        let grads_pool = &self.grads_pool;
+        self.zero_grad_cbs
+            .retain(|(id, _)| grads_pool.cache.contains_key(id));
+        self.grad_cbs
+            .retain(|(id, _)| grads_pool.cache.contains_key(id));
+    }
+
     #[inline]
     pub fn get_buf_from_no_grad_pool<'a, T, S, D>(&self, id: Id) -> &Buffer<'a, T, D, S>
     where
@@ -180,4 +426,113 @@ mod tests {
         let grad = grads.get_ref::<i32, (), _>(&dev, lhs.id());
         assert_eq!(grad.as_slice(), &[0; 4]);
     }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_clip_grad_norm_mixed_types() {
+        use crate::{Base, Device, Gradients, HasId, CPU};
+
+        let dev = CPU::<Base>::new();
+        let mut grads = Gradients::default();
+
+        let lhs = dev.buffer([1f32, 2.]);
+        let rhs = dev.buffer([1f64, 2., 3.]);
+        grads.get_mut::<f32, (), _>(&dev, lhs.id()).write(&[3., 0.]);
+        grads
+            .get_mut::<f64, (), _>(&dev, rhs.id())
+            .write(&[0., 4., 12.]);
+
+        assert_eq!(grads.grad_norm().unwrap(), 13.);
+
+        // below max_norm
+        assert_eq!(grads.clip_grad_norm(20.).unwrap(), 13.);
+        assert_eq!(
+            grads.get_ref::<f64, (), _>(&dev, rhs.id()).as_slice(),
+            [0., 4., 12.]
+        );
+
+        assert_eq!(grads.clip_grad_norm(6.5).unwrap(), 13.);
+        assert_eq!(
+            grads.get_ref::<f32, (), _>(&dev, lhs.id()).as_slice(),
+            [1.5, 0.]
+        );
+        assert_eq!(
+            grads.get_ref::<f64, (), _>(&dev, rhs.id()).as_slice(),
+            [0., 2., 6.]
+        );
+        assert_eq!(grads.grad_norm().unwrap(), 6.5);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_grad_norm_of_integers_unsupported() {
+        use crate::{Base, Device, DeviceError, Gradients, HasId, CPU};
+
+        let dev = CPU::<Base>::new();
+        let mut grads = Gradients::default();
+
+        let lhs = dev.buffer([1, 2, 3, 4]);
+        grads.get_mut::<i32, (), _>(&dev, lhs.id());
+
+        let err = grads.grad_norm().unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::GradOpUnsupported)
+        );
+    }
+
+    #[cfg(feature = "stack")]
+    #[test]
+    fn test_scale_grads_stack() {
+        use crate::{Buffer, Dim1, Gradients, HasId, Stack};
+
+        let dev = Stack::new();
+        let mut grads = Gradients::default();
+
+        let lhs = Buffer::<f32, Stack, Dim1<3>>::from((&dev, &[1., 2., 3.]));
+        grads
+            .get_mut::<f32, Dim1<3>, _>(&dev, lhs.id())
+            .copy_from_slice(&[2., 4., 6.]);
+
+        grads.scale_grads(0.5).unwrap();
+        assert_eq!(
+            grads.get_ref::<f32, Dim1<3>, _>(&dev, lhs.id()).to_vec(),
+            [1., 2., 3.]
+        );
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_grad_hooks_run_once_grad_is_computed() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::{Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let x = device.buffer([1., 2.]).require_grad();
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+
+        // y = 2x, out = y^2
+        let y = device.unary_ew(&x, |x| x.mul(2.), |x| x.mul(0.).add(2.));
+        let out = device.unary_ew(&y, |x| x.mul(x), |x| x.mul(2.));
+
+        let x_calls = calls.clone();
+        x.register_grad_hook(move |grad| x_calls.borrow_mut().push(("x", grad.to_vec())));
+        let y_calls = calls.clone();
+        y.register_grad_hook(move |grad| y_calls.borrow_mut().push(("y", grad.to_vec())));
+
+        out.backward().unwrap();
+        calls.borrow_mut().sort_by_key(|(name, _)| *name);
+        assert_eq!(*calls.borrow(), [("x", vec![8., 16.]), ("y", vec![4., 8.])]);
+
+        // hooks are removed when the buffer is dropped
+        drop(out);
+        drop(y);
+        calls.borrow_mut().clear();
+
+        let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        out.backward().unwrap();
+        assert_eq!(*calls.borrow(), [("x", vec![10., 20.])]);
+    }
 }
@@ -2,10 +2,10 @@ use core::marker::PhantomData;
 
 use crate::{
     AddOperation, Alloc, AnyOp, BoxedShallowCopy, Buffer, Buffers, Device, GradActions, LazyGraph,
-    Parents, Shape, Unit, WriteBuf, ZeroGrad,
+    Parents, Shape, UniqueId, Unit, WriteBuf, ZeroGrad,
 };
 
-use super::Gradients;
+use super::{Checkpoints, Gradients};
 
 pub type GradFn = Box<dyn Fn(&mut Gradients)>;
 
@@ -13,6 +13,8 @@ pub type GradFn = Box<dyn Fn(&mut Gradients)>;
 #[derive(Default)]
 pub struct Tape<'a> {
     pub lazy_graph: LazyGraph<Box<dyn BoxedShallowCopy>>,
+    /// The checkpointed regions, whose intermediates are recomputed during the backward pass.
+    pub checkpoints: Checkpoints,
     pd: PhantomData<&'a ()>,
 }
 
@@ -26,21 +28,66 @@ impl<'t> Tape<'t> {
         self.lazy_graph.add_operation(args, op);
     }
 
+    /// Exports the recorded gradient functions to the Graphviz DOT format.
+    /// See [`LazyGraph::to_dot`].
+    #[inline]
+    pub fn to_dot(&self) -> String {
+        self.lazy_graph.to_dot()
+    }
+
+    /// Exports the recorded gradient functions as JSON.
+    /// See [`LazyGraph::to_json`].
+    #[cfg(feature = "json")]
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        self.lazy_graph.to_json()
+    }
+
     /// Calls all gradient functions in reverse order.
-    pub fn backward<D: Device + 'static>(
+    /// The gradient hooks of a buffer (see [`Gradients::add_grad_hook`]) are called after the last gradient function using the buffer.
+    pub fn backward<D: Device + GradActions + 'static>(
         &mut self,
         device: &D,
         buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
         lazy_enabled: bool,
     ) -> crate::Result<()> {
-        for val in self.lazy_graph.iter_with(device, buffers).rev() {
-            val?;
-        }
+        let mut checkpoints = core::mem::take(&mut self.checkpoints);
+        let res = call_grad_fns(&mut self.lazy_graph, &mut checkpoints, device, buffers);
+        self.checkpoints = checkpoints;
+        res?;
+
         if !lazy_enabled {
             self.lazy_graph.clear();
+            self.checkpoints.clear();
         }
         Ok(())
     }
+
+    /// Calls all gradient functions in reverse order, while the gradient functions record the gradient functions of their own computations.
+    /// The recorded gradient functions are appended to the tape, hence `backward` can be called on gradients (e.g. Hessian-vector products or gradient penalties).
+    pub fn backward_create_graph<D: Device + GradActions + 'static>(
+        &mut self,
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+    ) -> crate::Result<()> {
+        // gradient functions recorded during the backward pass are added to the new graph
+        let mut grad_fns = core::mem::take(&mut self.lazy_graph);
+        let mut checkpoints = core::mem::take(&mut self.checkpoints);
+
+        set_create_graph(device, true);
+        let res = call_grad_fns(&mut grad_fns, &mut checkpoints, device, buffers);
+        set_create_graph(device, false);
+
+        // the indices of the original gradient functions do not change
+        self.checkpoints = checkpoints;
+
+        // the original gradient functions propagate the gradients of the next backward pass to the inputs
+        grad_fns.operations.append(&mut self.lazy_graph.operations);
+        self.lazy_graph = grad_fns;
+        res
+    }
+
     pub fn seed_grad_for_buf<'a, T, D, S>(&self, buf: &Buffer<'a, T, D, S>, seed: &[T])
     where
         T: Unit + 'static,
@@ -54,6 +101,7 @@ impl<'t> Tape<'t> {
         buf.device().write(out, seed);
     }
 
+    #[inline]
     pub fn backward_seeded_with_buffers<'a, T, D, S: Shape>(
         &mut self,
         buf: &Buffer<'a, T, D, S>,
@@ -64,27 +112,85 @@ impl<'t> Tape<'t> {
         T: Unit + 'static,
         D: Alloc<T> + ZeroGrad<T> + WriteBuf<T, S, D> + GradActions + AddOperation + 'static,
     {
+        self.backward_seeded(buf, seed, buffers, false)
+    }
+
+    fn backward_seeded<'a, T, D, S: Shape>(
+        &mut self,
+        buf: &Buffer<'a, T, D, S>,
+        seed: &[T],
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+        create_graph: bool,
+    ) -> crate::Result<()>
+    where
+        T: Unit + 'static,
+        D: Alloc<T> + ZeroGrad<T> + WriteBuf<T, S, D> + GradActions + AddOperation + 'static,
+    {
+        // gradients of a previous create_graph backward pass are inputs now
+        if let Some(gradients) = unsafe { buf.device().gradients_mut() } {
+            gradients.retain_graph_grads(buffers);
+        }
         self.seed_grad_for_buf(buf, seed);
 
         let is_lazy_enabled = buf.device().is_lazy_enabled();
         let mut res = Ok(());
-        buf.device()
-            .eagerly(|| res = self.backward(buf.device(), buffers, is_lazy_enabled));
-        res
+        buf.device().eagerly(|| {
+            res = if create_graph {
+                self.backward_create_graph(buf.device(), buffers)
+            } else {
+                self.backward(buf.device(), buffers, is_lazy_enabled)
+            }
+        });
+        res?;
+
+        if let Some(gradients) = unsafe { buf.device().gradients_mut() } {
+            gradients.count_backward();
+        }
+        Ok(())
     }
 
+    #[inline]
     pub fn backward_seeded_maybe_with_buffers<'a, T, D, S: Shape>(
         &mut self,
         buf: &Buffer<'a, T, D, S>,
         seed: &[T],
         buffers: Option<&mut Buffers<Box<dyn BoxedShallowCopy>>>,
     ) -> crate::Result<()>
+    where
+        T: Unit + 'static,
+        D: Alloc<T> + ZeroGrad<T> + WriteBuf<T, S, D> + GradActions + AddOperation + 'static,
+    {
+        self.backward_seeded_maybe_with_buffers_impl(buf, seed, buffers, false)
+    }
+
+    /// Like [`Tape::backward_seeded_maybe_with_buffers`], but uses [`Tape::backward_create_graph`] to execute the gradient functions.
+    #[inline]
+    pub fn backward_seeded_create_graph<'a, T, D, S: Shape>(
+        &mut self,
+        buf: &Buffer<'a, T, D, S>,
+        seed: &[T],
+        buffers: Option<&mut Buffers<Box<dyn BoxedShallowCopy>>>,
+    ) -> crate::Result<()>
+    where
+        T: Unit + 'static,
+        D: Alloc<T> + ZeroGrad<T> + WriteBuf<T, S, D> + GradActions + AddOperation + 'static,
+    {
+        self.backward_seeded_maybe_with_buffers_impl(buf, seed, buffers, true)
+    }
+
+    fn backward_seeded_maybe_with_buffers_impl<'a, T, D, S: Shape>(
+        &mut self,
+        buf: &Buffer<'a, T, D, S>,
+        seed: &[T],
+        buffers: Option<&mut Buffers<Box<dyn BoxedShallowCopy>>>,
+        create_graph: bool,
+    ) -> crate::Result<()>
     where
         T: Unit + 'static,
         D: Alloc<T> + ZeroGrad<T> + WriteBuf<T, S, D> + GradActions + AddOperation + 'static,
     {
         match buffers {
-            Some(buffers) => self.backward_seeded_with_buffers(buf, seed, buffers),
+            Some(buffers) => self.backward_seeded(buf, seed, buffers, create_graph),
             None => {
                 let mut no_grads = {
                     // unique mutable access to gradients
@@ -97,13 +203,96 @@ impl<'t> Tape<'t> {
                 };
 
                 // unique mutable access required for "buf.grad()"s in grad functions
-                self.backward_seeded_with_buffers(buf, seed, &mut no_grads)?;
+                let res = self.backward_seeded(buf, seed, &mut no_grads, create_graph);
 
                 let gradients = unsafe { buf.device().gradients_mut() }.unwrap();
                 let no_grads_src = &mut gradients.no_grads_pool;
                 *no_grads_src = no_grads;
-                Ok(())
+                res
             }
         }
     }
 }
+
+/// Calls the gradient functions in reverse order and the gradient hooks once the gradient of a hooked buffer is computed.
+/// Checkpointed regions are rematerialized before their gradient functions are called.
+fn call_grad_fns<D: Device + GradActions + 'static>(
+    grad_fns: &mut LazyGraph<Box<dyn BoxedShallowCopy>>,
+    checkpoints: &mut Checkpoints,
+    device: &D,
+    buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+) -> crate::Result<()> {
+    let res = call_grad_fns_with_hooks(grad_fns, checkpoints, device, buffers);
+    // also deallocates the rematerialized buffers if a gradient function failed
+    checkpoints.free_all(device, buffers);
+    res
+}
+
+fn call_grad_fns_with_hooks<D: Device + GradActions + 'static>(
+    grad_fns: &mut LazyGraph<Box<dyn BoxedShallowCopy>>,
+    checkpoints: &mut Checkpoints,
+    device: &D,
+    buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+) -> crate::Result<()> {
+    let mut hooked_ids = unsafe { device.gradients() }
+        .map(|gradients| {
+            gradients
+                .grad_hooks
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    if hooked_ids.is_empty() && checkpoints.is_empty() {
+        for res in grad_fns.iter_with(device, buffers).rev() {
+            res?;
+        }
+        return Ok(());
+    }
+    hooked_ids.sort_unstable();
+    hooked_ids.dedup();
+
+    // as the gradient functions are called in reverse order,
+    // the first gradient function using a buffer is the last one contributing to its gradient
+    let mut computed_after = vec![Vec::new(); grad_fns.ops_count()];
+    let mut unused = Vec::new();
+    for id in hooked_ids {
+        let first_use = grad_fns
+            .operations
+            .iter()
+            .position(|op| op.arg_ids.iter().any(|arg_id| **arg_id == id));
+        match first_use {
+            Some(idx) => computed_after[idx].push(id),
+            None => unused.push(id),
+        }
+    }
+
+    for (idx, op) in grad_fns.operations.iter().enumerate().rev() {
+        checkpoints.rematerialize_for(&op.arg_ids, device, buffers)?;
+        op.call(buffers, device)?;
+        checkpoints.free_from(idx, device, buffers);
+
+        for id in &computed_after[idx] {
+            run_grad_hooks(device, *id);
+        }
+    }
+    for id in unused {
+        run_grad_hooks(device, id);
+    }
+    Ok(())
+}
+
+#[inline]
+fn run_grad_hooks<D: GradActions>(device: &D, id: UniqueId) {
+    if let Some(gradients) = unsafe { device.gradients_mut() } {
+        gradients.run_grad_hooks(id);
+    }
+}
+
+#[inline]
+fn set_create_graph<D: GradActions>(device: &D, create_graph: bool) {
+    if let Some(gradients) = unsafe { device.gradients_mut() } {
+        gradients.create_graph.set(create_graph);
+    }
+}
@@ -1,10 +1,29 @@
-use core::marker::PhantomData;
+use core::{
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use crate::{flag::AllocFlag, Autograd, HasId, PtrType, ShallowCopy, WrappedCopy, WrappedData};
+use crate::{
+    flag::AllocFlag, Autograd, HasId, PtrType, ShallowCopy, UniqueId, WrappedCopy, WrappedData,
+};
+
+/// Pointer based ids, ids of lazily allocated buffers and stack ids do not reach this range.
+static RESERVED_IDS: AtomicU64 = AtomicU64::new(1 << 63);
+
+/// Returns a new id that does not collide with the id of an allocation,
+/// e.g. for a detached alias (see [`Buffer::detach`](crate::Buffer::detach)) or a dropped intermediate of a checkpoint.
+#[inline]
+pub(crate) fn next_reserved_id() -> UniqueId {
+    RESERVED_IDS.fetch_add(1, Ordering::Relaxed)
+}
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ReqGradWrapper<Data, T> {
     pub requires_grad: bool,
+    /// Replaces the id of `data` if this wraps a detached alias (see [`Buffer::detach`](crate::Buffer::detach)).
+    pub detached_id: Option<UniqueId>,
+    /// Only the alias returned by [`Buffer::detach`](crate::Buffer::detach) notifies the modules when it is dropped, not its shallow copies.
+    pub(crate) owns_detached_id: bool,
     pub data: Data,
     pub _pd: PhantomData<T>,
 }
@@ -20,6 +39,8 @@ impl<'dev, Mods: WrappedData> WrappedData for Autograd<'dev, Mods> {
         ReqGradWrapper {
             // by default: true -> if lazy layer is (accidentally) put before autograd, all gradients will be computed instead of none.. subject to change
             requires_grad: true,
+            detached_id: None,
+            owns_detached_id: false,
             data: self.modules.wrap_in_base(base),
             _pd: PhantomData,
         }
@@ -43,7 +64,14 @@ impl<'dev, Mods: WrappedData> WrappedData for Autograd<'dev, Mods> {
 impl<Data: HasId, T> HasId for ReqGradWrapper<Data, T> {
     #[inline]
     fn id(&self) -> crate::Id {
-        self.data.id()
+        let id = self.data.id();
+        match self.detached_id {
+            Some(detached_id) => crate::Id {
+                id: detached_id,
+                len: id.len,
+            },
+            None => id,
+        }
     }
 
     #[inline]
@@ -55,6 +83,17 @@ impl<Data: HasId, T> HasId for ReqGradWrapper<Data, T> {
     fn set_requires_grad(&mut self, requires_grad: bool) {
         self.requires_grad = requires_grad;
     }
+
+    #[inline]
+    fn set_detached_id(&mut self, id: UniqueId) {
+        self.detached_id = Some(id);
+        self.owns_detached_id = true;
+    }
+
+    #[inline]
+    fn alias_of(&self) -> Option<UniqueId> {
+        self.detached_id.map(|_| self.data.id().id)
+    }
 }
 
 impl<Data: PtrType, T> PtrType for ReqGradWrapper<Data, T> {
@@ -65,6 +104,9 @@ impl<Data: PtrType, T> PtrType for ReqGradWrapper<Data, T> {
 
     #[inline]
     fn flag(&self) -> AllocFlag {
+        if self.owns_detached_id {
+            return AllocFlag::Alias;
+        }
         self.data.flag()
     }
 
@@ -84,6 +126,8 @@ where
     fn wrapped_copy(&self, to_wrap: Self::Base) -> Self {
         Self {
             requires_grad: self.requires_grad,
+            detached_id: None,
+            owns_detached_id: false,
             data: self.data.wrapped_copy(to_wrap),
             _pd: PhantomData,
         }
@@ -97,6 +141,8 @@ where
     unsafe fn shallow(&self) -> Self {
         ReqGradWrapper {
             requires_grad: self.requires_grad,
+            detached_id: self.detached_id,
+            owns_detached_id: false,
             data: self.data.shallow(),
             _pd: PhantomData,
         }
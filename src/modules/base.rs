@@ -84,7 +84,7 @@ impl<D, T: Unit, S: Shape> Retrieve<D, T, S> for Base {
         &self,
         device: &D,
         len: usize,
-        _parents: impl Parents<NUM_PARENTS>,
+        _parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         D: Alloc<T>,
@@ -109,6 +109,7 @@ impl crate::UseGpuOrCpu for Base {
         &self,
         _location: HashLocation,
         _input_lengths: &[usize],
+        _output_lengths: &[usize],
         _cpu_op: impl FnMut(),
         mut gpu_op: impl FnMut(),
     ) -> crate::GpuOrCpuInfo {
@@ -147,6 +148,11 @@ impl crate::Optimize for Base {
     ) -> crate::Result<()> {
         Ok(())
     }
+
+    #[inline]
+    fn ew_fusing<D: 'static>(&self, _device: &D) -> crate::Result<()> {
+        Ok(())
+    }
 }
 
 impl AddGradFn for Base {
@@ -193,6 +199,12 @@ impl crate::GradActions for Base {
 #[cfg(feature = "autograd")]
 impl<'a> crate::TapeActions<'a> for Base {}
 
+#[cfg(feature = "forward")]
+impl crate::TangentActions for Base {}
+
+#[cfg(feature = "parallel")]
+impl crate::ThreadPoolActions for Base {}
+
 impl CachedBuffers for Base {}
 impl<T: Unit, D: Device, S: Shape> ReplaceBuf<T, D, S> for Base {
     #[inline]
@@ -85,6 +85,15 @@ impl<CacheType, SD: Device, Mods: AddOperation> AddOperation for CachedModule<Mo
         self.modules.add_op(args, op)
     }
 
+    #[inline]
+    unsafe fn add_parallel_op<Args: Parents<N> + crate::AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        self.modules.add_parallel_op(args, op)
+    }
+
     #[inline]
     fn ops_count(&self) -> usize {
         self.modules.ops_count()
@@ -163,7 +172,7 @@ where
         &self,
         device: &D,
         len: usize,
-        _parents: impl Parents<NUM_PARENTS>,
+        _parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         D: Alloc<T>,
@@ -179,11 +188,14 @@ where
     }
 
     #[inline]
-    fn on_retrieve_finish(&self, retrieved_buf: &Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
-        self.modules.on_retrieve_finish(retrieved_buf)
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
     }
 }
 
@@ -220,6 +232,31 @@ impl<'dev, CacheType, Mods: crate::TapeActions<'dev>, SD: Device> crate::TapeAct
     }
 }
 
+#[cfg(feature = "forward")]
+impl<CacheType, Mods: crate::TangentActions, SD: Device> crate::TangentActions
+    for CachedModule<Mods, SD, CacheType>
+{
+    #[inline]
+    fn tangents(&self) -> Option<core::cell::Ref<'_, crate::Tangents>> {
+        self.modules.tangents()
+    }
+
+    #[inline]
+    fn tangents_mut(&self) -> Option<core::cell::RefMut<'_, crate::Tangents>> {
+        self.modules.tangents_mut()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<CacheType, Mods: crate::ThreadPoolActions, SD: Device> crate::ThreadPoolActions
+    for CachedModule<Mods, SD, CacheType>
+{
+    #[inline]
+    fn thread_pool(&self) -> Option<&crate::ThreadPool> {
+        self.modules.thread_pool()
+    }
+}
+
 #[cfg(feature = "autograd")]
 impl<CacheType, Mods: crate::GradActions, SD: Device> crate::GradActions
     for CachedModule<Mods, SD, CacheType>
@@ -292,10 +329,34 @@ impl<CacheType, Mods: AddGradFn, D: Device> AddGradFn for CachedModule<Mods, D,
         self.modules.add_grad_fn(args, op)
     }
 
+    #[inline]
+    fn add_second_order_grad_fn<Args: Parents<N> + crate::AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
+    ) {
+        self.modules.add_second_order_grad_fn(args, op)
+    }
+
     #[inline]
     fn set_grad_enabled(&self, enabled: bool) {
         self.modules.set_grad_enabled(enabled)
     }
+
+    #[inline]
+    fn is_grad_enabled(&self) -> bool {
+        self.modules.is_grad_enabled()
+    }
+
+    #[inline]
+    fn begin_checkpoint(&self) {
+        self.modules.begin_checkpoint()
+    }
+
+    #[inline]
+    fn end_checkpoint(&self) {
+        self.modules.end_checkpoint()
+    }
 }
 
 impl<CacheType, Mods: crate::UseGpuOrCpu, D: Device> crate::UseGpuOrCpu
@@ -306,11 +367,12 @@ impl<CacheType, Mods: crate::UseGpuOrCpu, D: Device> crate::UseGpuOrCpu
         &self,
         location: crate::HashLocation<'static>,
         input_lengths: &[usize],
+        output_lengths: &[usize],
         cpu_op: impl FnMut(),
         gpu_op: impl FnMut(),
     ) -> crate::GpuOrCpuInfo {
         self.modules
-            .use_cpu_or_gpu(location, input_lengths, cpu_op, gpu_op)
+            .use_cpu_or_gpu(location, input_lengths, output_lengths, cpu_op, gpu_op)
     }
 
     #[inline]
@@ -378,6 +440,11 @@ impl<Mods: Optimize, SD: Device> Optimize for CachedModule<Mods, SD, FastCache>
     ) -> crate::Result<()> {
         Err(DeviceError::UnaryFusingUnsupported.into())
     }
+
+    #[inline]
+    fn ew_fusing<D: crate::ElementWiseFusing + 'static>(&self, device: &D) -> crate::Result<()> {
+        self.modules.ew_fusing(device)
+    }
 }
 
 impl<CacheType, Mods: OnDropBuffer, D: Device> CachedBuffers for CachedModule<Mods, D, CacheType> {
@@ -467,7 +534,7 @@ mod tests {
         let _x = {
             let device = CPU::<Cached<Base>>::new();
             // let buf: Buffer<f32, _> = device.retrieve(10, ());
-            unsafe { Retrieve::<_, f32, ()>::retrieve(&device.modules, &device, 10, ()) }
+            unsafe { Retrieve::<_, f32, ()>::retrieve(&device.modules, &device, 10, &()) }
         };
     }
 
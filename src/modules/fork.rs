@@ -1,19 +1,27 @@
 use crate::{
     impl_remove_layer, pass_down_add_operation, pass_down_exec_now, pass_down_replace_buf_module,
-    pass_down_tape_actions, AddLayer, Alloc, Buffer, Device, HasId, HasModules, IsShapeIndep,
-    Module, OnDropBuffer, OnNewBuffer, Parents, PtrType, Retrieve, RunModule, Setup, Shape, Unit,
-    UseGpuOrCpu, WrappedData, VERSION,
+    pass_down_tape_actions, AddLayer, Alloc, Buffer, Device, HasId, HasModules, HashLocation,
+    IsShapeIndep, LocationHasher, Module, OnDropBuffer, OnNewBuffer, Parents, PtrType, Retrieve,
+    RunModule, Setup, Shape, Unit, UseGpuOrCpu, WrappedData, VERSION,
 };
-use core::cell::{Cell, RefCell};
+use core::{
+    cell::{Cell, RefCell},
+    hash::BuildHasherDefault,
+};
+use std::collections::HashMap;
 
 mod analyzation;
 mod fork_data;
 mod fork_macro;
 #[cfg(feature = "serde")]
 mod impl_serde;
+mod policy;
+mod profile;
 mod use_gpu_or_cpu;
 
 pub use analyzation::Analyzation;
+pub use policy::*;
+pub use profile::*;
 pub use use_gpu_or_cpu::*;
 
 use self::fork_data::ForkData;
@@ -25,6 +33,33 @@ pub struct Fork<Mods> {
     pub version: &'static str,
     pub gpu_or_cpu: RefCell<ForkData>, // should use Location of operation in file file!(), ...
     pub enabled: Cell<bool>,
+    /// Decides on which device an operation is executed. Defaults to the [`RegressionPolicy`].
+    #[cfg_attr(feature = "serde", serde(skip, default = "default_policy"))]
+    pub policy: RefCell<Box<dyn ForkPolicy>>,
+    /// Distinguishes operations at the same location in a [`ForkProfile`] (see [`Fork::set_tag`]).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tags: RefCell<HashMap<HashLocation<'static>, String, BuildHasherDefault<LocationHasher>>>,
+    /// The timings of a loaded [`ForkProfile`] that were not used by an operation yet.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) loaded_profile: RefCell<Option<ForkProfile>>,
+}
+
+#[inline]
+fn default_policy() -> RefCell<Box<dyn ForkPolicy>> {
+    RefCell::new(Box::<RegressionPolicy>::default())
+}
+
+impl<Mods> Fork<Mods> {
+    /// Replaces the [`ForkPolicy`]. The timings measured so far are passed to the new policy.
+    pub fn set_policy(&self, policy: impl ForkPolicy + 'static) {
+        let mut policy: Box<dyn ForkPolicy> = Box::new(policy);
+        for (location, analyzations) in self.gpu_or_cpu.borrow().iter() {
+            for analyzation in analyzations {
+                policy.record(*location, analyzation);
+            }
+        }
+        *self.policy.borrow_mut() = policy;
+    }
 }
 
 impl<Mods: WrappedData> WrappedData for Fork<Mods> {
@@ -56,6 +91,9 @@ impl<'a, Mods: Module<'a, D>, D: Device + 'a> Module<'a, D> for Fork<Mods> {
             version: VERSION.unwrap(),
             gpu_or_cpu: Default::default(),
             enabled: Cell::new(true),
+            policy: default_policy(),
+            tags: Default::default(),
+            loaded_profile: Default::default(),
         }
     }
 }
@@ -112,7 +150,7 @@ impl<T: Unit + 'static, Mods: Retrieve<D, T, S>, D: IsShapeIndep + 'static, S: S
         &self,
         device: &D,
         len: usize,
-        parents: impl Parents<NUM_PARENTS>,
+        parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         S: Shape,
@@ -122,16 +160,21 @@ impl<T: Unit + 'static, Mods: Retrieve<D, T, S>, D: IsShapeIndep + 'static, S: S
     }
 
     #[inline]
-    fn on_retrieve_finish(&self, retrieved_buf: &Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
         // pass down
-        self.modules.on_retrieve_finish(retrieved_buf)
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
     }
 }
 
 pass_down_tape_actions!(Fork);
+crate::pass_down_tangent_actions!(Fork);
+crate::pass_down_thread_pool_actions!(Fork);
 
 impl<Mods: RunModule<D>, D> RunModule<D> for Fork<Mods> {
     #[inline]
@@ -153,6 +196,9 @@ impl<NewMods, SD> AddLayer<NewMods, SD> for Fork<()> {
             version: VERSION.unwrap(),
             gpu_or_cpu: Default::default(),
             enabled: Cell::new(true),
+            policy: default_policy(),
+            tags: Default::default(),
+            loaded_profile: Default::default(),
         }
     }
 }
@@ -185,6 +231,7 @@ mod tests {
     ) -> GpuOrCpuInfo {
         fork.use_cpu_or_gpu(
             (file!(), line!(), column!()).into(),
+            &[],
             &[cpu_buf.len()],
             || {
                 cpu_buf.clear();
@@ -424,9 +471,13 @@ mod tests {
 
         for _ in 0..100 {
             let _out = device.apply_fn(&buf, |x| x.add(3));
+        }
+
+        {
             let gpu_or_cpu = device.modules.gpu_or_cpu.borrow();
             let (_, operations) = gpu_or_cpu.iter().next().unwrap();
-            assert_eq!(operations.len(), 2);
+            // 1 warmup run, 3 measurements, re-exploration at the 64th decision
+            assert_eq!(operations.len(), 4);
             let analyzations = operations.iter().cloned().collect::<Vec<Analyzation>>();
             assert_eq!(&analyzations[0].input_lengths, &[6]);
         }
@@ -38,10 +38,14 @@ impl DerefMut for ForkData {
 
 #[cfg(feature = "serde")]
 mod serde {
-    use std::collections::HashMap;
+    use std::{
+        collections::{BTreeSet, HashMap},
+        string::String,
+        sync::Mutex,
+    };
 
     use serde::{
-        de::{MapAccess, Visitor},
+        de::{Error, MapAccess, Visitor},
         ser::SerializeMap,
         Deserialize, Serialize,
     };
@@ -64,9 +68,38 @@ mod serde {
         }
     }
 
+    /// Returns a `'static` file name with the content of `file`.
+    /// Every distinct file name is leaked only once, loading timings again does not leak memory.
+    fn intern_file(file: &str) -> &'static str {
+        static FILES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+        let mut files = FILES.lock().unwrap();
+        if let Some(file) = files.get(file) {
+            return file;
+        }
+        let file: &'static str = Box::leak(file.into());
+        files.insert(file);
+        file
+    }
+
+    fn parse_location<E: Error>(key: &str) -> Result<HashLocation<'static>, E> {
+        // the file name may contain commas
+        let mut key_split = key.rsplitn(3, ',');
+        let (Some(col), Some(line), Some(file)) =
+            (key_split.next(), key_split.next(), key_split.next())
+        else {
+            return Err(E::custom(format_args!("invalid location: {key}")));
+        };
+        Ok(HashLocation {
+            file: intern_file(file),
+            line: line.parse().map_err(E::custom)?,
+            col: col.parse().map_err(E::custom)?,
+        })
+    }
+
     pub struct ForkDataVisitor;
 
-    impl Visitor<'static> for ForkDataVisitor {
+    impl<'de> Visitor<'de> for ForkDataVisitor {
         type Value = ForkData;
 
         fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
@@ -75,37 +108,26 @@ mod serde {
 
         fn visit_map<M>(self, mut access: M) -> Result<Self::Value, M::Error>
         where
-            M: MapAccess<'static>,
+            M: MapAccess<'de>,
         {
-            let mut data: HashMap<&str, _> = HashMap::with_capacity_and_hasher(
+            let mut data = HashMap::with_capacity_and_hasher(
                 access.size_hint().unwrap_or(0),
                 Default::default(),
             );
 
-            while let Some((key, value)) = access.next_entry()? {
-                data.insert(key, value);
+            while let Some((key, value)) = access.next_entry::<String, _>()? {
+                data.insert(parse_location(&key)?, value);
             }
 
-            let data = data
-                .into_iter()
-                .map(|(key, value)| {
-                    let mut key_split = key.split(',');
-                    let file = key_split.next().unwrap();
-                    let line = key_split.next().unwrap().parse().unwrap();
-                    let col = key_split.next().unwrap().parse().unwrap();
-                    (HashLocation { file, line, col }, value)
-                })
-                .collect();
-
             Ok(ForkData { data })
         }
     }
 
-    impl Deserialize<'static> for ForkData {
+    impl<'de> Deserialize<'de> for ForkData {
         #[inline]
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
-            D: serde::Deserializer<'static>,
+            D: serde::Deserializer<'de>,
         {
             deserializer.deserialize_map(ForkDataVisitor {})
         }
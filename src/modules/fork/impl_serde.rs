@@ -1,7 +1,6 @@
 use serde::{Deserialize, Deserializer};
 
-use super::Fork;
-use crate::Base;
+use super::{fork_data::ForkData, Fork};
 
 // impl Deserialize<'static> for Fork<Base> {
 //     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -12,24 +11,41 @@ use crate::Base;
 //     }
 // }
 
+/// The serialized fields of a [`Fork`] that are loaded again.
+#[derive(Deserialize)]
+struct DeFork {
+    gpu_or_cpu: ForkData,
+}
+
 impl<Mods> Fork<Mods> {
+    /// Adds the deserialized timings to the current timings and passes them to the [`ForkPolicy`](crate::ForkPolicy).
     #[inline]
-    pub fn load_from_deserializer<D: Deserializer<'static>>(
+    pub fn load_from_deserializer<'de, D: Deserializer<'de>>(
         &mut self,
         deserializer: D,
     ) -> Result<(), D::Error> {
-        let de_fork = Fork::<Base>::deserialize(deserializer)?;
-        self.gpu_or_cpu = de_fork.gpu_or_cpu;
+        let de_fork = DeFork::deserialize(deserializer)?;
+
+        let policy = self.policy.get_mut();
+        let gpu_or_cpu = self.gpu_or_cpu.get_mut();
+        for (location, analyzations) in de_fork.gpu_or_cpu.data {
+            for analyzation in &analyzations {
+                policy.record(location, analyzation);
+            }
+            gpu_or_cpu.entry(location).or_default().extend(analyzations);
+        }
         Ok(())
     }
 
     #[cfg(feature = "json")]
     #[inline]
     pub fn save_as_json(&self, path: impl AsRef<std::path::Path>) -> crate::Result<()> {
-        serde_json::to_writer(std::fs::File::open(path)?, self)?;
+        serde_json::to_writer(std::fs::File::create(path)?, self)?;
         Ok(())
     }
 
+    /// Adds the timings of a json file created by [`Fork::save_as_json`] to the current timings.
+    /// Use a [`ForkProfile`](crate::ForkProfile) to persist timings across builds.
     #[cfg(feature = "json")]
     #[inline]
     pub fn load_from_json_read(&mut self, reader: impl std::io::Read) -> serde_json::Result<()> {
@@ -67,9 +83,13 @@ mod tests {
 
         for _ in 0..100 {
             let _out = device.apply_fn(&buf, |x| x.add(3));
+        }
+
+        {
             let gpu_or_cpu = device.modules.gpu_or_cpu.borrow();
             let (_, operations) = gpu_or_cpu.iter().next().unwrap();
-            assert_eq!(operations.len(), 2);
+            // 1 warmup run, 3 measurements, re-exploration at the 64th decision
+            assert_eq!(operations.len(), 4);
             let analyzations = operations.iter().cloned().collect::<Vec<_>>();
             assert_eq!(&analyzations[0].input_lengths, &[6]);
         }
@@ -84,13 +104,45 @@ mod tests {
 
         device.modules.serialize(&mut serializer).unwrap();
 
-        let json = Box::leak(json.into_boxed_slice());
-
-        let mut de = serde_json::Deserializer::from_slice(json);
+        let mut de = serde_json::Deserializer::from_slice(&json);
 
         device.modules.load_from_deserializer(&mut de).unwrap();
         // let data_now = device.modules.gpu_or_cpu.borrow().clone();
         // println!("data_now: {data_now:?}");
         // assert_eq!(data_now, data_prev);
     }
+
+    #[cfg(feature = "json")]
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_fork_load_from_json_read_twice() {
+        use crate::{Base, Fork, HashLocation, Module, UseGpuOrCpu, CPU};
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        for _ in 0..4 {
+            fork.use_cpu_or_gpu(HashLocation::here(), &[100], &[100], || {}, || {});
+        }
+        let json = serde_json::to_vec(&fork).unwrap();
+
+        let mut loaded = <Fork<Base> as Module<CPU>>::new();
+        loaded.load_from_json_read(&json[..]).unwrap();
+        loaded.load_from_json_read(&json[..]).unwrap();
+
+        {
+            let gpu_or_cpu = loaded.gpu_or_cpu.borrow();
+            // the file names of both loads are the same interned string
+            assert_eq!(gpu_or_cpu.len(), 1);
+            let (location, analyzations) = gpu_or_cpu.iter().next().unwrap();
+            assert_eq!(location.file, file!());
+            assert_eq!(
+                analyzations.len(),
+                2 * fork.gpu_or_cpu.borrow().values().next().unwrap().len()
+            );
+        }
+
+        let err = loaded
+            .load_from_json_read(&br#"{"gpu_or_cpu":{"no location":[]}}"#[..])
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid location"));
+    }
 }
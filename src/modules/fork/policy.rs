@@ -0,0 +1,537 @@
+use core::hash::BuildHasherDefault;
+use std::collections::HashMap;
+
+use crate::{Analyzation, HashLocation, LocationHasher};
+
+/// How the [`Fork`](crate::Fork) module executes an operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForkDecision {
+    Cpu,
+    Gpu,
+    /// Executes only the GPU operation without timing it, e.g. to remove jit compilation overhead.
+    Warmup,
+    /// Executes and times both operations. The timings are passed to [`ForkPolicy::record`].
+    Measure,
+}
+
+/// Decides on which device an operation of the [`Fork`](crate::Fork) module is executed.
+/// The default policy is the [`RegressionPolicy`].
+pub trait ForkPolicy {
+    fn decide(
+        &mut self,
+        location: HashLocation<'static>,
+        input_lengths: &[usize],
+        output_lengths: &[usize],
+    ) -> ForkDecision;
+
+    /// Receives the timings of an operation after a [`ForkDecision::Measure`] or when saved timings are loaded.
+    fn record(&mut self, location: HashLocation<'static>, analyzation: &Analyzation);
+
+    /// Returns the predicted times the decisions for an operation are based on (see [`Fork::explain`](crate::Fork::explain)).
+    #[inline]
+    fn estimate(
+        &self,
+        _location: HashLocation<'static>,
+        _input_lengths: &[usize],
+        _output_lengths: &[usize],
+    ) -> Option<ForkEstimate> {
+        None
+    }
+}
+
+/// The predicted CPU and GPU times (in seconds) of an operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForkEstimate {
+    pub cpu_time: f64,
+    /// The standard error of the predicted CPU time.
+    pub cpu_err: f64,
+    pub gpu_time: f64,
+    /// The standard error of the predicted GPU time.
+    pub gpu_err: f64,
+    /// The predicted time of moving the inputs to and the outputs from the CPU (see [`RegressionPolicyConfig::transfer_secs_per_elem`]).
+    /// The CPU is used if `cpu_time + transfer_time` is smaller than `gpu_time`.
+    pub transfer_time: f64,
+    /// The number of measurements.
+    pub samples: usize,
+    /// The device used after the last confident comparison.
+    pub use_cpu: Option<bool>,
+}
+
+/// The configuration of the [`RegressionPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegressionPolicyConfig {
+    /// The number of untimed GPU executions per location before the first measurement.
+    pub warmup: usize,
+    /// The number of measurements per location before the regressions are used.
+    pub min_samples: usize,
+    /// Every `explore_every`-th decision measures both operations again. `0` disables re-exploration.
+    pub explore_every: usize,
+    /// The number of standard errors the predicted times must differ to switch the device.
+    pub confidence: f64,
+    /// The time (in seconds) of moving one element between the GPU and the CPU.
+    /// The measured CPU times do not contain transfers, e.g. of unified memory, which are therefore added to the predicted CPU time.
+    /// `0` for devices without transfers.
+    pub transfer_secs_per_elem: f64,
+}
+
+impl Default for RegressionPolicyConfig {
+    #[inline]
+    fn default() -> Self {
+        RegressionPolicyConfig {
+            warmup: 1,
+            min_samples: 3,
+            explore_every: 64,
+            confidence: 2.,
+            transfer_secs_per_elem: 0.,
+        }
+    }
+}
+
+/// An online least squares fit of `time = intercept + slope * size`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct OnlineRegression {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    sxx: f64,
+    sxy: f64,
+    syy: f64,
+}
+
+impl OnlineRegression {
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.n += 1.;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / self.n;
+        self.mean_y += dy / self.n;
+        self.sxx += dx * (x - self.mean_x);
+        self.sxy += dx * (y - self.mean_y);
+        self.syy += dy * (y - self.mean_y);
+    }
+
+    #[inline]
+    pub fn samples(&self) -> usize {
+        self.n as usize
+    }
+
+    #[inline]
+    pub fn slope(&self) -> f64 {
+        if self.sxx > 0. {
+            self.sxy / self.sxx
+        } else {
+            0.
+        }
+    }
+
+    #[inline]
+    pub fn intercept(&self) -> f64 {
+        self.mean_y - self.slope() * self.mean_x
+    }
+
+    /// Returns the predicted time at `x` and the standard error of the prediction.
+    pub fn predict(&self, x: f64) -> (f64, f64) {
+        let prediction = self.intercept() + self.slope() * x;
+        if self.n < 2. {
+            return (prediction, f64::INFINITY);
+        }
+
+        // a single size seen so far: the mean is fitted, one degree of freedom less
+        let (sse, dof, leverage) = if self.sxx > 0. {
+            let dx = x - self.mean_x;
+            (
+                self.syy - self.sxy * self.sxy / self.sxx,
+                self.n - 2.,
+                1. / self.n + dx * dx / self.sxx,
+            )
+        } else {
+            (self.syy, self.n - 1., 1. / self.n)
+        };
+
+        if dof <= 0. {
+            return (prediction, f64::INFINITY);
+        }
+        let variance = sse.max(0.) / dof;
+        (prediction, (variance * leverage).sqrt())
+    }
+}
+
+/// The state of the [`RegressionPolicy`] for one location.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LocationStats {
+    pub cpu: OnlineRegression,
+    pub gpu: OnlineRegression,
+    pub decisions: usize,
+    /// The device used after the last confident comparison.
+    pub use_cpu: Option<bool>,
+}
+
+impl LocationStats {
+    pub fn estimate(
+        &self,
+        input_lengths: &[usize],
+        output_lengths: &[usize],
+        transfer_secs_per_elem: f64,
+    ) -> ForkEstimate {
+        let size = op_size(input_lengths, output_lengths);
+        let (cpu_time, cpu_err) = self.cpu.predict(size);
+        let (gpu_time, gpu_err) = self.gpu.predict(size);
+        ForkEstimate {
+            cpu_time,
+            cpu_err,
+            gpu_time,
+            gpu_err,
+            transfer_time: transfer_secs_per_elem * size,
+            samples: self.cpu.samples().min(self.gpu.samples()),
+            use_cpu: self.use_cpu,
+        }
+    }
+}
+
+/// Fits the CPU and GPU time of every location against the size of the operation (the sum of the input and output lengths).
+/// The predicted CPU time includes the transfer of the inputs and outputs (see [`RegressionPolicyConfig::transfer_secs_per_elem`]).
+/// The device is switched only if the predicted times differ by more than [`RegressionPolicyConfig::confidence`] standard errors.
+#[derive(Debug, Default, Clone)]
+pub struct RegressionPolicy {
+    pub config: RegressionPolicyConfig,
+    pub stats: HashMap<HashLocation<'static>, LocationStats, BuildHasherDefault<LocationHasher>>,
+}
+
+impl RegressionPolicy {
+    #[inline]
+    pub fn new(config: RegressionPolicyConfig) -> Self {
+        RegressionPolicy {
+            config,
+            stats: Default::default(),
+        }
+    }
+}
+
+#[inline]
+fn op_size(input_lengths: &[usize], output_lengths: &[usize]) -> f64 {
+    (input_lengths.iter().sum::<usize>() + output_lengths.iter().sum::<usize>()) as f64
+}
+
+impl ForkPolicy for RegressionPolicy {
+    fn decide(
+        &mut self,
+        location: HashLocation<'static>,
+        input_lengths: &[usize],
+        output_lengths: &[usize],
+    ) -> ForkDecision {
+        let config = self.config;
+        let stats = self.stats.entry(location).or_default();
+        stats.decisions += 1;
+
+        if stats.decisions <= config.warmup {
+            return ForkDecision::Warmup;
+        }
+
+        let samples = stats.cpu.samples().min(stats.gpu.samples());
+        if samples < config.min_samples.max(1) {
+            return ForkDecision::Measure;
+        }
+
+        if config.explore_every > 0 && stats.decisions % config.explore_every == 0 {
+            return ForkDecision::Measure;
+        }
+
+        let estimate = stats.estimate(input_lengths, output_lengths, config.transfer_secs_per_elem);
+
+        let diff = estimate.gpu_time - (estimate.cpu_time + estimate.transfer_time);
+        let err = (estimate.cpu_err.powi(2) + estimate.gpu_err.powi(2)).sqrt();
+
+        if diff.abs() > config.confidence * err {
+            stats.use_cpu = Some(diff > 0.);
+        }
+
+        // without a confident comparison, the cheaper estimate is used until there is one
+        match stats.use_cpu.unwrap_or(diff > 0.) {
+            true => ForkDecision::Cpu,
+            false => ForkDecision::Gpu,
+        }
+    }
+
+    fn record(&mut self, location: HashLocation<'static>, analyzation: &Analyzation) {
+        let stats = self.stats.entry(location).or_default();
+        let size = op_size(&analyzation.input_lengths, &analyzation.output_lengths);
+        stats.cpu.push(size, analyzation.cpu_dur.as_secs_f64());
+        stats.gpu.push(size, analyzation.gpu_dur.as_secs_f64());
+    }
+
+    #[inline]
+    fn estimate(
+        &self,
+        location: HashLocation<'static>,
+        input_lengths: &[usize],
+        output_lengths: &[usize],
+    ) -> Option<ForkEstimate> {
+        Some(self.stats.get(&location)?.estimate(
+            input_lengths,
+            output_lengths,
+            self.config.transfer_secs_per_elem,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{Analyzation, HashLocation};
+
+    use super::{
+        ForkDecision, ForkPolicy, OnlineRegression, RegressionPolicy, RegressionPolicyConfig,
+    };
+
+    fn analyzation(size: usize, cpu_secs: f64, gpu_secs: f64) -> Analyzation {
+        Analyzation {
+            input_lengths: vec![size],
+            output_lengths: vec![],
+            gpu_dur: Duration::from_secs_f64(gpu_secs),
+            cpu_dur: Duration::from_secs_f64(cpu_secs),
+        }
+    }
+
+    // the cpu is faster for small sizes, the gpu has a launch overhead but scales better
+    fn cpu_secs(size: usize) -> f64 {
+        1e-9 * size as f64
+    }
+
+    fn gpu_secs(size: usize) -> f64 {
+        1e-5 + 1e-10 * size as f64
+    }
+
+    #[test]
+    fn test_online_regression_fits_line() {
+        let mut regression = OnlineRegression::default();
+        for x in [1., 2., 4., 8.] {
+            regression.push(x, 3. + 2. * x);
+        }
+        assert!((regression.slope() - 2.).abs() < 1e-12);
+        assert!((regression.intercept() - 3.).abs() < 1e-12);
+
+        let (prediction, err) = regression.predict(16.);
+        assert!((prediction - 35.).abs() < 1e-12);
+        assert!(err < 1e-6);
+    }
+
+    #[test]
+    fn test_online_regression_single_size() {
+        let mut regression = OnlineRegression::default();
+        regression.push(10., 1.);
+        assert_eq!(regression.predict(10.).1, f64::INFINITY);
+
+        regression.push(10., 3.);
+        let (prediction, err) = regression.predict(100.);
+        assert_eq!(prediction, 2.);
+        assert!(err.is_finite());
+    }
+
+    #[test]
+    fn test_regression_policy_warmup_and_measure() {
+        let location = HashLocation::here();
+        let mut policy = RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 2,
+            min_samples: 3,
+            explore_every: 0,
+            confidence: 2.,
+            ..Default::default()
+        });
+
+        let mut decisions = vec![];
+        for _ in 0..6 {
+            let decision = policy.decide(location, &[100], &[]);
+            if decision == ForkDecision::Measure {
+                policy.record(location, &analyzation(100, cpu_secs(100), gpu_secs(100)));
+            }
+            decisions.push(decision);
+        }
+
+        use ForkDecision::*;
+        assert_eq!(decisions, [Warmup, Warmup, Measure, Measure, Measure, Cpu]);
+    }
+
+    #[test]
+    fn test_regression_policy_extrapolates_sizes() {
+        let location = HashLocation::here();
+        let mut policy = RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 0,
+            explore_every: 0,
+            ..Default::default()
+        });
+
+        for size in [1_000, 2_000, 4_000, 8_000] {
+            policy.record(location, &analyzation(size, cpu_secs(size), gpu_secs(size)));
+        }
+
+        assert_eq!(policy.decide(location, &[1_000], &[]), ForkDecision::Cpu);
+        // never measured, but the gpu is predicted to be faster
+        assert_eq!(
+            policy.decide(location, &[1_000_000], &[]),
+            ForkDecision::Gpu
+        );
+    }
+
+    #[test]
+    fn test_regression_policy_output_lengths_and_transfer_cost() {
+        let location = HashLocation::here();
+        let mut policy = RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 0,
+            explore_every: 0,
+            ..Default::default()
+        });
+
+        for size in [1_000, 2_000, 4_000, 8_000] {
+            let analyzation = Analyzation {
+                output_lengths: vec![size],
+                ..analyzation(size, cpu_secs(2 * size), gpu_secs(2 * size))
+            };
+            policy.record(location, &analyzation);
+        }
+
+        // the size of an operation includes its outputs
+        let estimate = policy.estimate(location, &[2_000], &[2_000]).unwrap();
+        assert!((estimate.cpu_time - cpu_secs(4_000)).abs() < 1e-12);
+        assert_eq!(estimate.transfer_time, 0.);
+        assert_eq!(
+            policy.decide(location, &[2_000], &[2_000]),
+            ForkDecision::Cpu
+        );
+
+        // moving the inputs and outputs to the cpu takes longer than the gpu execution
+        policy.config.transfer_secs_per_elem = 1e-8;
+        let estimate = policy.estimate(location, &[2_000], &[2_000]).unwrap();
+        assert!((estimate.transfer_time - 4e-5).abs() < 1e-12);
+        assert_eq!(
+            policy.decide(location, &[2_000], &[2_000]),
+            ForkDecision::Gpu
+        );
+    }
+
+    #[test]
+    fn test_regression_policy_requires_confidence_to_switch() {
+        let location = HashLocation::here();
+        let mut policy = RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 0,
+            explore_every: 0,
+            ..Default::default()
+        });
+
+        // noisy measurements at similar speed
+        for (cpu, gpu) in [(1.0, 1.2), (1.3, 0.9), (0.9, 1.1), (1.2, 1.0)] {
+            policy.record(location, &analyzation(100, cpu, gpu));
+        }
+        // no confident comparison: the cheaper estimate (cpu 1.1 vs gpu 1.05) is used
+        assert_eq!(policy.decide(location, &[100], &[]), ForkDecision::Gpu);
+        assert_eq!(policy.stats[&location].use_cpu, None);
+
+        // the cpu becomes consistently faster
+        for _ in 0..20 {
+            policy.record(location, &analyzation(100, 0.5, 1.1));
+        }
+        assert_eq!(policy.decide(location, &[100], &[]), ForkDecision::Cpu);
+        assert_eq!(policy.stats[&location].use_cpu, Some(true));
+
+        // a single outlier does not switch back
+        policy.record(location, &analyzation(100, 5., 1.1));
+        assert_eq!(policy.decide(location, &[100], &[]), ForkDecision::Cpu);
+    }
+
+    #[test]
+    fn test_regression_policy_re_exploration() {
+        let location = HashLocation::here();
+        let mut policy = RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 0,
+            min_samples: 1,
+            explore_every: 4,
+            confidence: 2.,
+            ..Default::default()
+        });
+
+        let mut measurements = 0;
+        for _ in 0..16 {
+            if policy.decide(location, &[100], &[]) == ForkDecision::Measure {
+                measurements += 1;
+                policy.record(location, &analyzation(100, 1., 2.));
+            }
+        }
+        // the first decision and every 4th decision
+        assert_eq!(measurements, 5);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_fork_executes_policy_decisions() {
+        use core::cell::Cell;
+
+        use crate::{Base, Fork, Module, UseGpuOrCpu, CPU};
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        fork.set_policy(RegressionPolicy::new(RegressionPolicyConfig {
+            warmup: 1,
+            min_samples: 2,
+            explore_every: 0,
+            confidence: 2.,
+            ..Default::default()
+        }));
+
+        let cpu_runs = Cell::new(0);
+        let gpu_runs = Cell::new(0);
+        let location = HashLocation::here();
+
+        let mut infos = vec![];
+        for _ in 0..5 {
+            infos.push(fork.use_cpu_or_gpu(
+                location,
+                &[100],
+                &[100],
+                || cpu_runs.set(cpu_runs.get() + 1),
+                || gpu_runs.set(gpu_runs.get() + 1),
+            ));
+        }
+
+        // warmup: gpu only, 2 measurements: both, then one device per call
+        assert_eq!(cpu_runs.get() + gpu_runs.get(), 1 + 2 * 2 + 2);
+        assert_eq!(fork.gpu_or_cpu.borrow()[&location].len(), 2);
+        assert_eq!(infos.iter().filter(|info| info.is_result_cached).count(), 2);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_fork_set_policy_replays_measurements() {
+        use std::rc::Rc;
+
+        use core::cell::Cell;
+
+        use crate::{Base, Fork, Module, CPU};
+
+        struct CountRecords(Rc<Cell<usize>>);
+
+        impl ForkPolicy for CountRecords {
+            fn decide(
+                &mut self,
+                _: HashLocation<'static>,
+                _: &[usize],
+                _: &[usize],
+            ) -> ForkDecision {
+                ForkDecision::Measure
+            }
+
+            fn record(&mut self, _: HashLocation<'static>, _: &Analyzation) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        let location = HashLocation::here();
+        fork.gpu_or_cpu.borrow_mut().insert(
+            location,
+            [analyzation(10, 1., 2.), analyzation(20, 2., 2.)].into(),
+        );
+
+        let records = Rc::new(Cell::new(0));
+        fork.set_policy(CountRecords(records.clone()));
+        assert_eq!(records.get(), 2);
+    }
+}
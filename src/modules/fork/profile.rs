@@ -0,0 +1,520 @@
+use core::fmt::Display;
+
+use crate::{Analyzation, DeviceError, Fork, ForkEstimate, HashLocation, VERSION};
+
+/// Identifies an operation of the [`Fork`](crate::Fork) module across builds.
+/// Unlike [`HashLocation`], which compares file name pointers, the file name is compared by value.
+/// The tag distinguishes operations at the same location, e.g. of a generic function used with different types (see [`Fork::set_tag`](crate::Fork::set_tag)).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpIdentity {
+    pub file: String,
+    pub line: u32,
+    pub col: u32,
+    pub tag: Option<String>,
+}
+
+impl OpIdentity {
+    #[inline]
+    pub fn new(location: HashLocation, tag: Option<&str>) -> Self {
+        OpIdentity {
+            file: location.file.into(),
+            line: location.line,
+            col: location.col,
+            tag: tag.map(Into::into),
+        }
+    }
+
+    #[inline]
+    fn key(&self) -> (&str, u32, u32, Option<&str>) {
+        (&self.file, self.line, self.col, self.tag.as_deref())
+    }
+}
+
+impl Display for OpIdentity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)?;
+        if let Some(tag) = &self.tag {
+            write!(f, "#{tag}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The timings of one operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileEntry {
+    pub identity: OpIdentity,
+    pub analyzations: Vec<Analyzation>,
+}
+
+/// Persisted timings of the [`Fork`](crate::Fork) module.
+/// A profile belongs to a custos version and a device, e.g. the name of an OpenCL device.
+/// Profiles of the same version and device can be merged, e.g. profiles of several runs or machines with the same GPU.
+/// # Example
+#[cfg_attr(all(feature = "cpu", feature = "json"), doc = "```")]
+#[cfg_attr(not(all(feature = "cpu", feature = "json")), doc = "```ignore")]
+/// use custos::{Base, Fork, ForkProfile, Module, UseGpuOrCpu, CPU};
+///
+/// let fork = <Fork<Base> as Module<CPU>>::new();
+/// for _ in 0..4 {
+///     fork.use_cpu_or_gpu(custos::location!(), &[100], &[100], || {}, || {});
+/// }
+///
+/// let mut profile = fork.profile("gpu-0").unwrap();
+/// let path = std::env::temp_dir().join("custos_fork_profile_doc.json");
+/// profile.save_json(&path).unwrap();
+///
+/// // e.g. the profile of an earlier run
+/// profile.merge(ForkProfile::load_json(&path).unwrap()).unwrap();
+/// assert_eq!(profile.entries()[0].analyzations.len(), 6);
+///
+/// // the next run starts with the saved timings
+/// let fork = <Fork<Base> as Module<CPU>>::new();
+/// fork.load_profile(profile, "gpu-0").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForkProfile {
+    pub version: String,
+    pub device: String,
+    /// Sorted by [`OpIdentity`].
+    entries: Vec<ProfileEntry>,
+}
+
+impl ForkProfile {
+    /// Creates an empty profile for the current custos [`VERSION`].
+    #[inline]
+    pub fn new(device: impl Into<String>) -> Self {
+        ForkProfile {
+            version: VERSION.unwrap_or_default().into(),
+            device: device.into(),
+            entries: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn entries(&self) -> &[ProfileEntry] {
+        &self.entries
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    fn position(&self, key: (&str, u32, u32, Option<&str>)) -> Result<usize, usize> {
+        self.entries
+            .binary_search_by(|entry| entry.identity.key().cmp(&key))
+    }
+
+    /// Returns the timings of an operation.
+    #[inline]
+    pub fn get(&self, identity: &OpIdentity) -> Option<&[Analyzation]> {
+        let idx = self.position(identity.key()).ok()?;
+        Some(&self.entries[idx].analyzations)
+    }
+
+    /// Returns the entries of all tags at a location.
+    pub fn entries_at<'a>(
+        &'a self,
+        file: &'a str,
+        line: u32,
+        col: u32,
+    ) -> impl Iterator<Item = &'a ProfileEntry> {
+        let start = self
+            .position((file, line, col, None))
+            .unwrap_or_else(|idx| idx);
+        self.entries[start..].iter().take_while(move |entry| {
+            (
+                entry.identity.file.as_str(),
+                entry.identity.line,
+                entry.identity.col,
+            ) == (file, line, col)
+        })
+    }
+
+    /// Appends timings to an operation.
+    pub fn insert(
+        &mut self,
+        identity: OpIdentity,
+        analyzations: impl IntoIterator<Item = Analyzation>,
+    ) {
+        match self.position(identity.key()) {
+            Ok(idx) => self.entries[idx].analyzations.extend(analyzations),
+            Err(idx) => self.entries.insert(
+                idx,
+                ProfileEntry {
+                    identity,
+                    analyzations: analyzations.into_iter().collect(),
+                },
+            ),
+        }
+    }
+
+    /// Removes the timings of an operation without allocating an [`OpIdentity`].
+    pub(crate) fn take(
+        &mut self,
+        location: HashLocation,
+        tag: Option<&str>,
+    ) -> Option<Vec<Analyzation>> {
+        let idx = self
+            .position((location.file, location.line, location.col, tag))
+            .ok()?;
+        Some(self.entries.remove(idx).analyzations)
+    }
+
+    /// Returns an error if the profiles belong to different custos versions or devices.
+    pub fn check_compatible(&self, other: &ForkProfile) -> crate::Result<()> {
+        if self.version != other.version {
+            return Err(DeviceError::ForkProfileVersionMismatch.into());
+        }
+        if self.device != other.device {
+            return Err(DeviceError::ForkProfileDeviceMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Appends the timings of `other` to the timings of this profile.
+    pub fn merge(&mut self, other: ForkProfile) -> crate::Result<()> {
+        self.check_compatible(&other)?;
+        for entry in other.entries {
+            self.insert(entry.identity, entry.analyzations);
+        }
+        Ok(())
+    }
+}
+
+impl<Mods> Fork<Mods> {
+    /// Tags the operation at `location`. The tag is part of the [`OpIdentity`] in a [`ForkProfile`].
+    #[inline]
+    pub fn set_tag(&self, location: HashLocation<'static>, tag: impl Into<String>) {
+        self.tags.borrow_mut().insert(location, tag.into());
+    }
+
+    #[inline]
+    pub fn identity(&self, location: HashLocation<'static>) -> OpIdentity {
+        let tags = self.tags.borrow();
+        OpIdentity::new(location, tags.get(&location).map(String::as_str))
+    }
+
+    /// Returns the predicted times of the [`ForkPolicy`](crate::ForkPolicy) for an operation at `location`, e.g. to inspect why the CPU was used.
+    #[inline]
+    pub fn explain(
+        &self,
+        location: HashLocation<'static>,
+        input_lengths: &[usize],
+        output_lengths: &[usize],
+    ) -> Option<ForkEstimate> {
+        self.policy
+            .borrow()
+            .estimate(location, input_lengths, output_lengths)
+    }
+
+    /// Returns the measured timings, including the timings of a loaded profile.
+    /// Returns an error if a profile of another device was loaded.
+    pub fn profile(&self, device: impl Into<String>) -> crate::Result<ForkProfile> {
+        let mut profile = ForkProfile::new(device);
+        if let Some(loaded) = self.loaded_profile.borrow().as_ref() {
+            profile.merge(loaded.clone())?;
+        }
+
+        for (location, analyzations) in self.gpu_or_cpu.borrow().iter() {
+            profile.insert(
+                self.identity(*location),
+                analyzations.clone().into_sorted_vec(),
+            );
+        }
+        Ok(profile)
+    }
+
+    /// Passes the timings of a profile to the [`ForkPolicy`](crate::ForkPolicy).
+    /// The timings of operations that were not executed yet are used on their first execution.
+    /// Loading several profiles merges them.
+    /// Returns an error if the profile was recorded with another custos version or on another `device` than the current one.
+    pub fn load_profile(&self, mut profile: ForkProfile, device: &str) -> crate::Result<()> {
+        if profile.version != VERSION.unwrap_or_default() {
+            return Err(DeviceError::ForkProfileVersionMismatch.into());
+        }
+        if profile.device != device {
+            return Err(DeviceError::ForkProfileDeviceMismatch.into());
+        }
+
+        let locations = self.gpu_or_cpu.borrow().keys().copied().collect::<Vec<_>>();
+        for location in locations {
+            self.resolve_profile_entry(&mut profile, location);
+        }
+
+        let mut loaded = self.loaded_profile.borrow_mut();
+        match loaded.as_mut() {
+            Some(loaded) => loaded.merge(profile)?,
+            None => *loaded = Some(profile),
+        }
+        Ok(())
+    }
+
+    /// Moves the loaded timings of the operation at `location` to the [`ForkPolicy`](crate::ForkPolicy).
+    pub(crate) fn resolve_profile(&self, location: HashLocation<'static>) {
+        let mut loaded = self.loaded_profile.borrow_mut();
+        let Some(profile) = loaded.as_mut() else {
+            return;
+        };
+        if !profile.is_empty() {
+            self.resolve_profile_entry(profile, location);
+        }
+    }
+
+    fn resolve_profile_entry(&self, profile: &mut ForkProfile, location: HashLocation<'static>) {
+        let tags = self.tags.borrow();
+        let Some(analyzations) = profile.take(location, tags.get(&location).map(String::as_str))
+        else {
+            return;
+        };
+
+        let mut policy = self.policy.borrow_mut();
+        for analyzation in &analyzations {
+            policy.record(location, analyzation);
+        }
+        self.gpu_or_cpu
+            .borrow_mut()
+            .entry(location)
+            .or_default()
+            .extend(analyzations);
+    }
+}
+
+#[cfg(feature = "json")]
+impl ForkProfile {
+    pub fn save_json(&self, path: impl AsRef<std::path::Path>) -> crate::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn from_json_read(reader: impl std::io::Read) -> crate::Result<Self> {
+        let mut profile: ForkProfile = serde_json::from_reader(reader)?;
+        // the file could be edited by hand
+        profile
+            .entries
+            .sort_by(|lhs, rhs| lhs.identity.cmp(&rhs.identity));
+        Ok(profile)
+    }
+
+    /// Loads a profile saved with [`ForkProfile::save_json`].
+    /// Returns an error if the profile was saved by another custos version.
+    pub fn load_json(path: impl AsRef<std::path::Path>) -> crate::Result<Self> {
+        let profile = Self::from_json_read(std::io::BufReader::new(std::fs::File::open(path)?))?;
+        if profile.version != VERSION.unwrap_or_default() {
+            return Err(DeviceError::ForkProfileVersionMismatch.into());
+        }
+        Ok(profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use crate::{Analyzation, DeviceError, ForkProfile, HashLocation, OpIdentity};
+
+    fn analyzation(size: usize) -> Analyzation {
+        Analyzation {
+            input_lengths: vec![size],
+            output_lengths: vec![],
+            gpu_dur: Duration::from_micros(2),
+            cpu_dur: Duration::from_micros(1),
+        }
+    }
+
+    // a file name as it is stored after a rebuild: same content, another address
+    fn rebuilt(location: HashLocation<'static>) -> HashLocation<'static> {
+        let file = Box::leak(location.file.to_string().into_boxed_str());
+        HashLocation { file, ..location }
+    }
+
+    #[test]
+    fn test_op_identity_compares_file_by_value() {
+        let location = HashLocation::here();
+        let rebuilt = rebuilt(location);
+        assert_ne!(location, rebuilt);
+        assert_eq!(
+            OpIdentity::new(location, None),
+            OpIdentity::new(rebuilt, None)
+        );
+
+        let mut profile = ForkProfile::new("gpu");
+        profile.insert(OpIdentity::new(location, None), [analyzation(10)]);
+        assert!(profile.take(rebuilt, Some("f32")).is_none());
+        assert_eq!(profile.take(rebuilt, None).unwrap().len(), 1);
+        assert!(profile.is_empty());
+    }
+
+    #[test]
+    fn test_profile_query_and_merge() {
+        let location = HashLocation::here();
+        let other_location = HashLocation::here();
+
+        let mut profile = ForkProfile::new("gpu");
+        profile.insert(OpIdentity::new(location, Some("f32")), [analyzation(10)]);
+        profile.insert(OpIdentity::new(other_location, None), [analyzation(10)]);
+
+        let mut other = ForkProfile::new("gpu");
+        other.insert(OpIdentity::new(location, Some("f32")), [analyzation(20)]);
+        other.insert(OpIdentity::new(location, None), [analyzation(30)]);
+        profile.merge(other).unwrap();
+
+        let f32_timings = profile
+            .get(&OpIdentity::new(location, Some("f32")))
+            .unwrap();
+        assert_eq!(f32_timings, [analyzation(10), analyzation(20)]);
+
+        let at_location = profile
+            .entries_at(location.file, location.line, location.col)
+            .map(|entry| entry.identity.tag.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(at_location, [None, Some("f32")]);
+        assert_eq!(profile.entries().len(), 3);
+    }
+
+    #[test]
+    fn test_profile_merge_mismatch() {
+        let mut profile = ForkProfile::new("gpu");
+
+        let err = profile.merge(ForkProfile::new("other gpu")).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::ForkProfileDeviceMismatch)
+        );
+
+        let mut old = ForkProfile::new("gpu");
+        old.version = "0.0.1".into();
+        let err = profile.merge(old).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::ForkProfileVersionMismatch)
+        );
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_fork_profile_survives_rebuild() {
+        use core::cell::Cell;
+
+        use crate::{Base, Fork, Module, UseGpuOrCpu, CPU};
+
+        let location = HashLocation::here();
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        for _ in 0..4 {
+            fork.use_cpu_or_gpu(location, &[100], &[100], || {}, || {});
+        }
+        let profile = fork.profile("gpu").unwrap();
+        assert_eq!(profile.entries()[0].analyzations.len(), 3);
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        let err = fork.load_profile(profile.clone(), "other gpu").unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::ForkProfileDeviceMismatch)
+        );
+        fork.load_profile(profile, "gpu").unwrap();
+
+        let location = rebuilt(location);
+        assert!(fork.explain(location, &[100], &[100]).is_none());
+
+        let runs = Cell::new(0);
+        let mut infos = vec![];
+        for _ in 0..4 {
+            infos.push(fork.use_cpu_or_gpu(
+                location,
+                &[100],
+                &[100],
+                || runs.set(runs.get() + 1),
+                || runs.set(runs.get() + 1),
+            ));
+        }
+        // warmup, then the loaded timings are used without measuring again
+        assert_eq!(runs.get(), 4);
+        assert!(infos[1..].iter().all(|info| info.is_result_cached));
+        assert_eq!(fork.explain(location, &[100], &[100]).unwrap().samples, 3);
+
+        // the profile of the next run contains the loaded timings
+        assert_eq!(
+            fork.profile("gpu").unwrap().entries()[0].analyzations.len(),
+            3
+        );
+        assert!(fork.profile("other gpu").is_err());
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_fork_profile_tags() {
+        use crate::{Base, Fork, Module, UseGpuOrCpu, CPU};
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        let location = HashLocation::here();
+        let other_location = HashLocation {
+            line: location.line + 1,
+            ..location
+        };
+        fork.set_tag(location, "f32");
+
+        for _ in 0..2 {
+            fork.use_cpu_or_gpu(location, &[100], &[100], || {}, || {});
+            fork.use_cpu_or_gpu(other_location, &[100], &[100], || {}, || {});
+        }
+
+        let profile = fork.profile("gpu").unwrap();
+        let tags = profile
+            .entries()
+            .iter()
+            .map(|entry| (entry.identity.line, entry.identity.tag.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [(location.line, Some("f32")), (other_location.line, None)]
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_profile_json_roundtrip() {
+        let location = HashLocation::here();
+        let mut profile = ForkProfile::new("gpu");
+        profile.insert(OpIdentity::new(location, Some("f32")), [analyzation(10)]);
+        profile.insert(OpIdentity::new(location, None), [analyzation(20)]);
+
+        let path = std::env::temp_dir().join("custos_test_profile_json_roundtrip.json");
+        profile.save_json(&path).unwrap();
+        assert_eq!(ForkProfile::load_json(&path).unwrap(), profile);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(all(feature = "cpu", feature = "json"))]
+    #[test]
+    fn test_fork_save_as_json_creates_file() {
+        use crate::{Base, Fork, Module, UseGpuOrCpu, CPU};
+
+        let fork = <Fork<Base> as Module<CPU>>::new();
+        for _ in 0..3 {
+            fork.use_cpu_or_gpu(HashLocation::here(), &[100], &[100], || {}, || {});
+        }
+
+        let path = std::env::temp_dir().join("custos_test_fork_save_as_json.json");
+        let _ = std::fs::remove_file(&path);
+        fork.save_as_json(&path).unwrap();
+
+        // loading adds the saved timings to the current ones
+        let mut fork = fork;
+        fork.load_from_json(&path).unwrap();
+        let analyzations = fork
+            .gpu_or_cpu
+            .borrow()
+            .values()
+            .map(|a| a.len())
+            .sum::<usize>();
+        assert_eq!(analyzations, 2 + 2);
+        std::fs::remove_file(path).unwrap();
+    }
+}
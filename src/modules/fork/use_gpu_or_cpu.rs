@@ -1,8 +1,7 @@
-use core::{hash::BuildHasher, time::Duration};
-use std::collections::{BinaryHeap, HashMap};
+use core::time::Duration;
 use std::time::Instant;
 
-use crate::{Analyzation, Fork, GpuOrCpuInfo, HashLocation, UseGpuOrCpu};
+use crate::{Analyzation, Fork, ForkDecision, GpuOrCpuInfo, HashLocation, UseGpuOrCpu};
 
 pub fn should_use_cpu(
     cpu_op: &mut impl FnMut(),
@@ -21,39 +20,13 @@ pub fn should_use_cpu(
     (cpu_time < gpu_time, cpu_time, gpu_time)
 }
 
-pub fn init_binary_heap<S: BuildHasher>(
-    cpu_op: &mut impl FnMut(),
-    gpu_op: &mut impl FnMut(),
-    location: HashLocation<'static>,
-    input_lengths: Vec<usize>,
-    gpu_or_cpu: &mut HashMap<HashLocation, BinaryHeap<Analyzation>, S>,
-) -> GpuOrCpuInfo {
-    // removes jit compilation overhead
-    // FIXME: algorithm runs twice
-    gpu_op();
-    let (use_cpu, cpu_dur, gpu_dur) = should_use_cpu(cpu_op, gpu_op);
-    // println!("use_cpu: {use_cpu}, cpu_dur: {cpu_dur:?}, gpu_dur: {gpu_dur:?}");
-    gpu_or_cpu.insert(
-        location,
-        BinaryHeap::from([Analyzation {
-            input_lengths: input_lengths.to_vec(),
-            output_lengths: vec![],
-            gpu_dur,
-            cpu_dur,
-        }]),
-    );
-    GpuOrCpuInfo {
-        use_cpu,
-        is_result_cached: false,
-    }
-}
-
 impl<Mods> UseGpuOrCpu for Fork<Mods> {
     // FIXME: if the operation assigns to  &mut out, you will get chaos
     fn use_cpu_or_gpu(
         &self,
         location: HashLocation<'static>,
         input_lengths: &[usize],
+        output_lengths: &[usize],
         mut cpu_op: impl FnMut(),
         mut gpu_op: impl FnMut(),
     ) -> GpuOrCpuInfo {
@@ -65,60 +38,54 @@ impl<Mods> UseGpuOrCpu for Fork<Mods> {
             };
         }
 
-        let mut gpu_or_cpu = self.gpu_or_cpu.borrow_mut();
-
-        let Some(operations) = gpu_or_cpu.get_mut(&location) else {
-            return init_binary_heap(
-                &mut cpu_op,
-                &mut gpu_op,
-                location,
-                input_lengths.to_vec(),
-                &mut gpu_or_cpu,
-            );
-        };
-
-        let anals = operations.clone().into_sorted_vec();
-
-        let input_lengths_sum = input_lengths.iter().sum::<usize>();
+        self.resolve_profile(location);
 
-        for anals in anals.windows(2) {
-            let lhs = &anals[0];
-            let rhs = &anals[1];
+        // the policy is not borrowed while the operations are executed
+        let decision = self
+            .policy
+            .borrow_mut()
+            .decide(location, input_lengths, output_lengths);
 
-            let input_lengths_lhs = lhs.input_lengths.iter().sum::<usize>();
-            let input_lengths_rhs = rhs.input_lengths.iter().sum::<usize>();
-
-            if input_lengths_sum >= input_lengths_lhs && input_lengths_sum <= input_lengths_rhs {
-                let new_cpu_dur = lhs.cpu_dur.as_secs_f32()
-                    + 0.5 * (rhs.cpu_dur.as_secs_f32() - lhs.cpu_dur.as_secs_f32());
-
-                let new_gpu_dur = lhs.gpu_dur.as_secs_f32()
-                    + 0.5 * (rhs.gpu_dur.as_secs_f32() - lhs.gpu_dur.as_secs_f32());
+        let use_cpu = match decision {
+            ForkDecision::Cpu => true,
+            ForkDecision::Gpu => false,
+            ForkDecision::Warmup => {
+                // removes jit compilation overhead from the measurements
+                gpu_op();
+                return GpuOrCpuInfo {
+                    use_cpu: false,
+                    is_result_cached: false,
+                };
+            }
+            ForkDecision::Measure => {
+                let (use_cpu, cpu_dur, gpu_dur) = should_use_cpu(&mut cpu_op, &mut gpu_op);
+                let analyzation = Analyzation {
+                    input_lengths: input_lengths.to_vec(),
+                    output_lengths: output_lengths.to_vec(),
+                    gpu_dur,
+                    cpu_dur,
+                };
+                self.policy.borrow_mut().record(location, &analyzation);
+                self.gpu_or_cpu
+                    .borrow_mut()
+                    .entry(location)
+                    .or_default()
+                    .push(analyzation);
 
-                let use_cpu = new_cpu_dur < new_gpu_dur;
-                match use_cpu {
-                    true => cpu_op(),
-                    false => gpu_op(),
-                }
                 return GpuOrCpuInfo {
                     use_cpu,
-                    is_result_cached: true,
+                    is_result_cached: false,
                 };
             }
-        }
-
-        gpu_op();
-        let (use_cpu, cpu_dur, gpu_dur) = should_use_cpu(&mut cpu_op, &mut gpu_op);
-        operations.push(Analyzation {
-            input_lengths: input_lengths.to_vec(),
-            output_lengths: vec![],
-            gpu_dur,
-            cpu_dur,
-        });
+        };
 
+        match use_cpu {
+            true => cpu_op(),
+            false => gpu_op(),
+        }
         GpuOrCpuInfo {
             use_cpu,
-            is_result_cached: false,
+            is_result_cached: true,
         }
     }
 
@@ -0,0 +1,281 @@
+mod tangents;
+
+pub use tangents::*;
+
+use core::cell::{Ref, RefCell, RefMut};
+
+use crate::{
+    impl_remove_layer, impl_wrapped_data, pass_down_add_operation, pass_down_cached_buffers,
+    pass_down_cursor, pass_down_exec_now_module, pass_down_grad_fn, pass_down_replace_buf_module,
+    pass_down_tape_actions, pass_down_use_gpu_or_cpu, AddLayer, Alloc, Buffer, Device, HasId,
+    HasModules, Module, OnDropBuffer, OnNewBuffer, Parents, Retrieve, RunModule, Setup, Shape,
+    TangentActions, Unit,
+};
+
+/// Forward-mode automatic differentiation.
+/// Every [`Buffer`] can carry a tangent [`Buffer`] (see [`Buffer::set_tangent`]).
+/// [`ApplyFunction::apply_fn`](crate::ApplyFunction::apply_fn) (and therefore [`unary_ew`](crate::UnaryElementWiseMayGrad::unary_ew)) evaluates the value and the tangent of the output in one pass if the input has a tangent.
+/// On the CPU, the dual number version of the expression is evaluated via [`EvalTangent`](crate::EvalTangent).
+/// OpenCL, CUDA and Vulkan devices compile the tangent expression into the kernel via [`ToCLTangentSource`](crate::ToCLTangentSource) or [`ToWgslTangentSource`](crate::ToWgslTangentSource).
+///
+/// Seeding the tangent of the input with a vector `v` computes the Jacobian-vector product `J v`.
+/// For `n` inputs, `n` forward passes compute the full Jacobian, which is cheaper than reverse mode ([`Autograd`](crate::Autograd)) for few inputs and many outputs.
+/// # Example
+#[cfg_attr(feature = "cpu", doc = "```")]
+#[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+/// use custos::{ApplyFunction, Base, Combiner, Device, Forward, CPU};
+///
+/// let device = CPU::<Forward<Base>>::new();
+///
+/// let x = device.buffer([1., 2., 3.]);
+/// x.set_tangent(&[1., 1., 0.]);
+///
+/// // out = x^2 + 3x
+/// let out = device.apply_fn(&x, |x| x.mul(x).add(x.mul(3.)));
+/// assert_eq!(out.read(), [4., 10., 18.]);
+///
+/// // tangent = (2x + 3) * dx
+/// assert_eq!(out.tangent().read(), [5., 7., 0.]);
+/// ```
+#[derive(Debug, Default)]
+pub struct Forward<Mods> {
+    pub modules: Mods,
+    /// Caches the tangent of each [`Buffer`]'s id.
+    pub tangents: RefCell<Tangents>,
+}
+
+impl<'a, Mods: Module<'a, D>, D: Device + 'a> Module<'a, D> for Forward<Mods> {
+    type Module = Forward<Mods::Module>;
+
+    #[inline]
+    fn new() -> Self::Module {
+        Forward {
+            modules: Mods::new(),
+            tangents: Default::default(),
+        }
+    }
+}
+
+impl<Mods: Setup<D>, D> Setup<D> for Forward<Mods> {
+    #[inline]
+    fn setup(device: &mut D) -> crate::Result<()> {
+        Mods::setup(device)
+    }
+}
+
+impl_wrapped_data!(Forward);
+
+impl<Mods> TangentActions for Forward<Mods> {
+    #[inline]
+    fn tangents(&self) -> Option<Ref<'_, Tangents>> {
+        Some(self.tangents.borrow())
+    }
+
+    #[inline]
+    fn tangents_mut(&self) -> Option<RefMut<'_, Tangents>> {
+        Some(self.tangents.borrow_mut())
+    }
+}
+
+impl<'a, Mods: OnNewBuffer<'a, T, D, S>, T: Unit, D: Device, S: Shape> OnNewBuffer<'a, T, D, S>
+    for Forward<Mods>
+{
+    #[inline]
+    unsafe fn on_new_buffer(&self, device: &'a D, new_buf: &Buffer<'a, T, D, S>) {
+        // ids are reused after a buffer is dropped
+        self.tangents.borrow_mut().remove(*new_buf.id());
+        self.modules.on_new_buffer(device, new_buf)
+    }
+}
+
+impl<Mods: OnDropBuffer> OnDropBuffer for Forward<Mods> {
+    #[inline]
+    fn on_drop_buffer<T: Unit, D: Device, S: Shape>(&self, device: &D, buf: &Buffer<T, D, S>) {
+        self.tangents.borrow_mut().remove(*buf.id());
+        self.modules.on_drop_buffer(device, buf)
+    }
+}
+
+impl<T: Unit, Mods: Retrieve<D, T, S>, D, S: Shape> Retrieve<D, T, S> for Forward<Mods> {
+    #[inline]
+    unsafe fn retrieve<const NUM_PARENTS: usize>(
+        &self,
+        device: &D,
+        len: usize,
+        parents: &impl Parents<NUM_PARENTS>,
+    ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
+    where
+        D: Device + Alloc<T>,
+    {
+        self.modules.retrieve(device, len, parents)
+    }
+
+    #[inline]
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
+        D: Alloc<T>,
+    {
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
+    }
+}
+
+impl<Mods: RunModule<D>, D> RunModule<D> for Forward<Mods> {
+    #[inline]
+    fn run(&self, device: &D) -> crate::Result<()> {
+        self.modules.run(device)
+    }
+}
+
+pass_down_cursor!(Forward);
+pass_down_add_operation!(Forward);
+pass_down_exec_now_module!(Forward);
+pass_down_cached_buffers!(Forward);
+pass_down_replace_buf_module!(Forward);
+pass_down_use_gpu_or_cpu!(Forward);
+pass_down_grad_fn!(Forward);
+pass_down_tape_actions!(Forward);
+crate::pass_down_thread_pool_actions!(Forward);
+#[cfg(feature = "cached")]
+crate::pass_down_unified_mem_chain!(Forward);
+
+impl_remove_layer!(Forward);
+
+impl<NewMods, SD> AddLayer<NewMods, SD> for Forward<()> {
+    type Wrapped = crate::Forward<NewMods>;
+
+    #[inline]
+    fn wrap_layer(inner_mods: NewMods) -> Self::Wrapped {
+        Forward {
+            modules: inner_mods,
+            tangents: Default::default(),
+        }
+    }
+}
+
+impl<Mods> HasModules for Forward<Mods> {
+    type Mods = Mods;
+
+    #[inline]
+    fn modules(&self) -> &Self::Mods {
+        &self.modules
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "cpu")]
+mod tests {
+    use crate::{
+        ApplyFunction, Base, Buffer, Combiner, Device, Forward, HasId, TangentActions,
+        UnaryElementWiseMayGrad, CPU,
+    };
+
+    fn roughly_eq(lhs: &[f64], rhs: &[f64]) {
+        for (lhs, rhs) in lhs.iter().zip(rhs) {
+            assert!((lhs - rhs).abs() < 1e-9, "{lhs} != {rhs}");
+        }
+    }
+
+    #[test]
+    fn test_forward_chain_rule() {
+        let device = CPU::<Forward<Base>>::new();
+
+        let x = device.buffer([0.5, 1., 2.]);
+        x.set_tangent(&[1.; 3]);
+
+        let y = device.apply_fn(&x, |x| x.sin());
+        let out = device.apply_fn(&y, |y| y.mul(y).exp());
+
+        let expected = [0.5f64, 1., 2.].map(|x| (x.sin() * x.sin()).exp() * 2. * x.sin() * x.cos());
+        roughly_eq(&out.tangent().read(), &expected);
+        roughly_eq(&y.tangent().read(), &[0.5f64.cos(), 1f64.cos(), 2f64.cos()]);
+    }
+
+    #[test]
+    fn test_forward_without_tangent() {
+        let device = CPU::<Forward<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&x, |x| x.mul(2.));
+        assert_eq!(out.read(), [2., 4., 6.]);
+
+        assert!(out.try_tangent().is_none());
+        assert!(device.tangents().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_forward_jacobian_columns() {
+        let device = CPU::<Forward<Base>>::new();
+        let x: Buffer<f64, _> = device.buffer([1., 2.]);
+
+        // element-wise functions have diagonal jacobians
+        let mut jacobian = vec![];
+        for col in 0..x.len() {
+            let mut seed = vec![0.; x.len()];
+            seed[col] = 1.;
+            x.set_tangent(&seed);
+
+            let out = device.apply_fn(&x, |x| x.pow(3.));
+            jacobian.push(out.tangent().read().to_vec());
+        }
+        assert_eq!(jacobian, [[3., 0.], [0., 12.]]);
+    }
+
+    #[test]
+    fn test_forward_tangent_removed_on_drop() {
+        let device = CPU::<Forward<Base>>::new();
+        let id = {
+            let x = device.buffer([1., 2.]);
+            x.set_tangent(&[1., 1.]);
+            x.id()
+        };
+        assert!(!device.tangents().unwrap().contains(*id));
+    }
+
+    #[test]
+    fn test_forward_unary_ew() {
+        let device = CPU::<Forward<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]).with_tangent(&[2., 2., 2.]);
+        let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        assert_eq!(out.tangent().read(), [4., 8., 12.]);
+    }
+
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_forward_lazy() {
+        use crate::{Lazy, Run};
+
+        let device = CPU::<Forward<Lazy<Base>>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&x, |x| x.mul(x).mul(x));
+
+        // the tangent is picked up when the operation is executed
+        x.set_tangent(&[1., 0., 1.]);
+        device.run().unwrap();
+        // the tangent belongs to the allocated buffer, not to the lazy placeholder
+        let out = out.replace();
+        assert_eq!(out.read(), [1., 8., 27.]);
+        assert_eq!(out.tangent().read(), [3., 0., 27.]);
+    }
+
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_forward_with_autograd() {
+        use crate::Autograd;
+
+        let device = CPU::<Forward<Autograd<Base>>>::new();
+
+        let x = device.buffer([1., 2., 3.]).require_grad();
+        x.set_tangent(&[1., 1., 1.]);
+
+        let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        out.backward().unwrap();
+
+        // both modes agree for element-wise functions
+        assert_eq!(x.grad().read(), out.tangent().read());
+    }
+}
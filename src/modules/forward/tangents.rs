@@ -0,0 +1,145 @@
+use crate::{Alloc, BorrowCache, Buffer, Device, HasId, Id, Shape, UniqueId, Unit, ZeroGrad};
+
+/// The tangent of an input [`Buffer`] and the tangent of an output [`Buffer`] (see [`Tangents::tangent_pair`]).
+pub type TangentPair<'a, 'b, T, D, OD, S> = (&'a Buffer<'b, T, D, S>, &'a mut Buffer<'b, T, OD, S>);
+
+/// A cache for the tangents of the [`Forward`](crate::Forward) module.
+/// A tangent [`Buffer`] is stored under the id of the [`Buffer`] it belongs to.
+#[derive(Default)]
+pub struct Tangents {
+    pub(crate) tangents_pool: BorrowCache,
+}
+
+impl core::fmt::Debug for Tangents {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Tangents")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Tangents {
+    /// Returns `true` if a tangent is stored for the given id.
+    #[inline]
+    pub fn contains(&self, id: UniqueId) -> bool {
+        self.tangents_pool.cache.contains_key(&id)
+    }
+
+    /// Returns the number of stored tangents.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tangents_pool.cache.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.tangents_pool.cache.is_empty()
+    }
+
+    /// Returns a reference to a tangent [`Buffer`] if it exists.
+    #[inline]
+    pub fn may_get_ref<'a, T, S, D>(&self, _device: &'a D, id: Id) -> Option<&Buffer<'a, T, D, S>>
+    where
+        T: Unit + 'static,
+        S: Shape,
+        D: Device + 'static,
+    {
+        self.tangents_pool.cache.get(&id)?.downcast_ref()
+    }
+
+    /// Returns a mutable reference to a tangent [`Buffer`].
+    /// Allocates a zeroed tangent [`Buffer`] if it does not exist.
+    pub fn get_mut<'a, T, S, D>(&mut self, device: &'a D, id: Id) -> &mut Buffer<'a, T, D, S>
+    where
+        T: Unit + 'static,
+        S: Shape,
+        D: Alloc<T> + ZeroGrad<T> + 'static,
+    {
+        let mut new_buf = false;
+        self.tangents_pool
+            .add_buf_once::<T, D, S>(device, id, &mut new_buf);
+
+        let tangent = self.tangents_pool.get_buf_mut(device, id).unwrap();
+        if new_buf {
+            device.zero_grad(tangent.base_mut());
+        }
+        tangent
+    }
+
+    /// Returns a reference to a tangent [`Buffer`].
+    /// Allocates a zeroed tangent [`Buffer`] if it does not exist.
+    #[inline]
+    pub fn get_ref<'a, T, S, D>(&mut self, device: &'a D, id: Id) -> &Buffer<'a, T, D, S>
+    where
+        T: Unit + 'static,
+        S: Shape,
+        D: Alloc<T> + ZeroGrad<T> + 'static,
+    {
+        self.get_mut(device, id)
+    }
+
+    /// Returns the tangent of `x` and the (possibly newly allocated) tangent of `out`.
+    /// If `x` has no tangent, a stale tangent of `out` is removed and `None` is returned.
+    pub fn tangent_pair<'a, 'b, T, D, OD, S>(
+        &'a mut self,
+        x: &Buffer<'b, T, D, S>,
+        out: &Buffer<'b, T, OD, S>,
+    ) -> Option<TangentPair<'a, 'b, T, D, OD, S>>
+    where
+        T: Unit + 'static,
+        S: Shape,
+        D: Device + 'static,
+        OD: Alloc<T> + ZeroGrad<T> + 'static,
+    {
+        let Some(tangent) = self.may_get_ref::<T, S, D>(x.device(), x.id()) else {
+            self.remove(*out.id());
+            return None;
+        };
+        if x.id() == out.id() {
+            return None;
+        }
+
+        // The tangent is boxed, hence inserting the tangent of `out` does not move it.
+        let tangent = tangent as *const Buffer<'b, T, D, S>;
+        let out_tangent = self.get_mut(out.device(), out.id());
+        Some((unsafe { &*tangent }, out_tangent))
+    }
+
+    /// Removes the tangent stored for the given id.
+    #[inline]
+    pub fn remove(&mut self, id: UniqueId) {
+        self.tangents_pool.cache.remove(&id);
+    }
+
+    /// Removes all tangents.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.tangents_pool.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_tangent_pair_removes_stale_out_tangent() {
+        use crate::{Base, Device, HasId, Tangents, CPU};
+
+        let device = CPU::<Base>::new();
+        let x = device.buffer([1., 2., 3.]);
+        let out = device.buffer([0.; 3]);
+
+        let mut tangents = Tangents::default();
+        tangents.get_mut::<f64, (), _>(&device, out.id());
+        assert!(tangents.tangent_pair(&x, &out).is_none());
+        assert!(tangents.is_empty());
+
+        tangents
+            .get_mut::<f64, (), _>(&device, x.id())
+            .write(&[1., 0., 2.]);
+        let (tangent, out_tangent) = tangents.tangent_pair(&x, &out).unwrap();
+        assert_eq!(tangent.read(), [1., 0., 2.]);
+        assert_eq!(out_tangent.read(), [0.; 3]);
+        assert_eq!(tangents.len(), 2);
+    }
+}
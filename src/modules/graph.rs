@@ -96,6 +96,11 @@ impl<Mods: Optimize> Optimize for Graph<Mods> {
             }
         }
     }
+
+    #[inline]
+    fn ew_fusing<D: crate::ElementWiseFusing + 'static>(&self, device: &D) -> crate::Result<()> {
+        self.modules.ew_fusing(device)
+    }
 }
 
 impl<'a, Mods: OnNewBuffer<'a, T, D, S>, T: Unit, D: Device, S: Shape> OnNewBuffer<'a, T, D, S>
@@ -130,6 +135,8 @@ crate::pass_down_unified_mem_chain!(Graph);
 pass_down_use_gpu_or_cpu!(Graph);
 pass_down_replace_buf_module!(Graph);
 pass_down_grad_fn!(Graph);
+crate::pass_down_tangent_actions!(Graph);
+crate::pass_down_thread_pool_actions!(Graph);
 
 impl_remove_layer!(Graph);
 
@@ -166,44 +173,58 @@ where
         &self,
         device: &D,
         len: usize,
-        parents: impl Parents<NUM_PARENTS>,
+        parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         D: Alloc<T>,
     {
-        let ids = parents.ids();
-        let data = self.modules.retrieve(device, len, parents)?;
+        self.modules.retrieve(device, len, parents)
+    }
 
-        let mut contains_ids = self.contains_ids.borrow_mut();
+    #[inline]
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
+        D: Alloc<T>,
+    {
+        self.add_retrieved_node(parents, retrieved_buf);
+
+        // pass down
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
+    }
+}
 
+impl<Mods> Graph<Mods> {
+    fn add_retrieved_node<T: Unit, D: Device + Cursor, S: Shape, const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &Buffer<T, D, S>,
+    ) {
         // subtracting 1 because retrieving increments the cursor (cached and lazy modules)
-        let cursor = device.cursor() as UniqueId - 1;
+        // without such a module, retrieved buffers cannot be identified across runs
+        let Some(cursor) = (retrieved_buf.device().cursor() as UniqueId).checked_sub(1) else {
+            return;
+        };
+
+        let mut contains_ids = self.contains_ids.borrow_mut();
 
         if contains_ids.get(&cursor).is_some() {
-            return Ok(data);
+            return;
         }
         contains_ids.insert(cursor);
 
         let mut graph_trans = self.graph_trans.borrow_mut();
 
         let next_idx = graph_trans.next_idx;
-        graph_trans.buf_id_to_idx.insert(data.id().id, next_idx);
-        graph_trans.idx_to_buf_id.insert(next_idx, data.id().id);
+        graph_trans.buf_id_to_idx.insert(retrieved_buf.id().id, next_idx);
+        graph_trans.idx_to_buf_id.insert(next_idx, retrieved_buf.id().id);
 
         graph_trans.idx_to_cursor.insert(next_idx, cursor);
 
         // does a hash location check internally (again)
-        graph_trans.add_node(len, &ids);
-        Ok(data)
-    }
-
-    #[inline]
-    fn on_retrieve_finish(&self, retrieved_buf: &Buffer<T, D, S>)
-    where
-        D: Alloc<T>,
-    {
-        // pass down
-        self.modules.on_retrieve_finish(retrieved_buf)
+        graph_trans.add_node(retrieved_buf.len(), &parents.ids());
     }
 }
 
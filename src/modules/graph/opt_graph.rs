@@ -1,6 +1,8 @@
+mod export;
 mod optimize;
 
 use super::node::Node;
+pub use export::*;
 pub use optimize::*;
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -0,0 +1,171 @@
+use core::fmt::Write;
+use std::collections::HashMap;
+
+use super::OptGraph;
+
+const TRACE_COLORS: [&str; 8] = [
+    "lightblue",
+    "lightgreen",
+    "lightsalmon",
+    "khaki",
+    "plum",
+    "lightpink",
+    "aquamarine",
+    "wheat",
+];
+
+/// A readable description of a [`Node`](crate::Node) of an [`OptGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeInfo {
+    /// The index of the node.
+    pub idx: usize,
+    /// The indices of the nodes that are dependencies of this node.
+    pub deps: Vec<usize>,
+    /// The amount of elements a corresponding [`Buffer`](crate::Buffer) has.
+    pub len: usize,
+    /// The index of the [`CacheTrace`](crate::CacheTrace) this node is part of, if any.
+    pub cache_trace: Option<usize>,
+}
+
+impl OptGraph {
+    /// Maps every node index that is part of a [`CacheTrace`](crate::CacheTrace) to the index of its trace.
+    fn trace_idx_per_node(&self) -> HashMap<usize, usize> {
+        let mut trace_idxs = HashMap::new();
+        for (trace_idx, trace) in self.cache_traces().into_iter().enumerate() {
+            trace_idxs.insert(trace.cache_idx, trace_idx);
+            for idx in trace.use_cache_idxs {
+                trace_idxs.insert(idx, trace_idx);
+            }
+        }
+        trace_idxs
+    }
+
+    /// Returns a readable description of every node, including the cache trace it belongs to.
+    pub fn node_infos(&self) -> Vec<NodeInfo> {
+        let trace_idxs = self.trace_idx_per_node();
+        self.nodes
+            .iter()
+            .map(|node| NodeInfo {
+                idx: node.idx,
+                deps: node.deps.clone(),
+                len: node.len,
+                cache_trace: trace_idxs.get(&node.idx).copied(),
+            })
+            .collect()
+    }
+
+    /// Exports the graph to the Graphviz DOT format.
+    /// Nodes sharing the same cache trace are filled with the same color.
+    /// # Example
+    /// ```
+    /// use custos::OptGraph;
+    ///
+    /// let mut graph = OptGraph::default();
+    /// let a = graph.add_leaf(10);
+    /// let b = graph.add_node(10, vec![a, a]);
+    /// let _c = graph.add_node(10, vec![b, b]);
+    ///
+    /// let dot = graph.to_dot();
+    /// assert!(dot.contains("node1 -> node2;"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph OptGraph {\n");
+
+        for info in self.node_infos() {
+            let shape = if self.nodes[info.idx].is_leaf() {
+                "ellipse"
+            } else {
+                "box"
+            };
+            match info.cache_trace {
+                Some(trace_idx) => writeln!(
+                    dot,
+                    "    node{idx} [shape={shape}, style=filled, fillcolor={color}, label=\"{idx}\\nlen: {len}\\ntrace: {trace_idx}\"];",
+                    idx = info.idx,
+                    len = info.len,
+                    color = TRACE_COLORS[trace_idx % TRACE_COLORS.len()],
+                ),
+                None => writeln!(
+                    dot,
+                    "    node{idx} [shape={shape}, label=\"{idx}\\nlen: {len}\"];",
+                    idx = info.idx,
+                    len = info.len,
+                ),
+            }
+            .unwrap();
+
+            let mut deps = info.deps.clone();
+            deps.dedup();
+            for dep in deps.into_iter().filter(|dep| *dep != info.idx) {
+                writeln!(dot, "    node{dep} -> node{};", info.idx).unwrap();
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the graph as a JSON array of [`NodeInfo`]s.
+    #[cfg(feature = "json")]
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.node_infos())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::OptGraph;
+
+    #[test]
+    fn test_opt_graph_node_infos() {
+        let mut graph = OptGraph::default();
+        let a = graph.add_leaf(10);
+        let b = graph.add_leaf(10);
+        let c = graph.add_node(10, vec![a, b]);
+        let d = graph.add_node(10, vec![c, c]);
+        let _e = graph.add_node(10, vec![d, b]);
+
+        let infos = graph.node_infos();
+        assert_eq!(infos[a].cache_trace, None);
+        assert_eq!(infos[b].cache_trace, None);
+        assert_eq!(infos[2].cache_trace, Some(0));
+        assert_eq!(infos[3].cache_trace, Some(0));
+        assert_eq!(infos[4].cache_trace, Some(0));
+    }
+
+    #[test]
+    fn test_opt_graph_to_dot_highlights_traces() {
+        let mut graph = OptGraph::default();
+        let a = graph.add_leaf(10);
+        let b = graph.add_leaf(10);
+        let c = graph.add_node(10, vec![a, b]);
+        let d = graph.add_node(10, vec![c, c]);
+        let _e = graph.add_node(10, vec![d, b]);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("node0 [shape=ellipse, label=\"0\\nlen: 10\"];"));
+        assert!(dot.contains(
+            "node3 [shape=box, style=filled, fillcolor=lightblue, label=\"3\\nlen: 10\\ntrace: 0\"];"
+        ));
+        assert!(dot.contains("node2 -> node3;"));
+        assert_eq!(dot.matches("node2 -> node3;").count(), 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_opt_graph_to_json() {
+        use crate::NodeInfo;
+
+        let mut graph = OptGraph::default();
+        let a = graph.add_leaf(10);
+        let _b = graph.add_node(10, vec![a, a]);
+
+        let json = graph.to_json().unwrap();
+        let infos: Vec<NodeInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(infos, graph.node_infos());
+    }
+}
@@ -1,3 +1,5 @@
+mod captured;
+mod ew_fusing;
 mod exec_iter;
 mod lazy_graph;
 #[cfg(feature = "graph")]
@@ -5,6 +7,7 @@ mod optimization;
 mod ty;
 mod wrapper;
 
+pub use captured::*;
 pub use ty::*;
 use wrapper::MaybeData;
 
@@ -33,7 +36,6 @@ pub use lazy_graph::*;
 type Buffers = crate::Buffers<Box<dyn BoxedShallowCopy>>;
 type AllocatedIds = HashSet<UniqueId, BuildHasherDefault<NoHasher>>;
 
-#[derive(Default)]
 pub struct Lazy<'a, Mods, T = f32> {
     pub modules: Mods,
     alloc_later: RefCell<Vec<(Id, fn(&mut Buffers, &mut AllocatedIds, Id, &dyn Any))>>, // could use D generic instead of dyn Any (required LazyModule structure)
@@ -43,15 +45,45 @@ pub struct Lazy<'a, Mods, T = f32> {
     // This ensures to only allocate a buffer once, without having to remove the ID/address collision check
     // TODO: remove this, fix id and address collision - then just use `buffers` for duplicate calls
     allocated_ids: RefCell<AllocatedIds>,
+    // ids of lazily allocated buffers that went out of scope
+    dropped_ids: RefCell<AllocatedIds>,
+    pruned_ops: RefCell<Vec<OperationInfo>>,
+    // ids that share the buffer of another id (id -> shared buffer id)
+    aliased_ids: RefCell<crate::Buffers<UniqueId>>,
+    exec_threads: Cell<usize>,
     pub graph: RefCell<LazyGraph<Box<dyn BoxedShallowCopy>, T>>,
     cursor: Cell<usize>,
     enabled: Cell<bool>,
     pd: PhantomData<Cell<&'a ()>>,
 }
 
+impl<Mods: Default, T> Default for Lazy<'_, Mods, T> {
+    #[inline]
+    fn default() -> Self {
+        Lazy {
+            modules: Mods::default(),
+            buffers: Default::default(),
+            replaced_buffers: Default::default(),
+            graph: Default::default(),
+            alloc_later: Default::default(),
+            allocated_ids: Default::default(),
+            dropped_ids: Default::default(),
+            pruned_ops: Default::default(),
+            aliased_ids: Default::default(),
+            exec_threads: Cell::new(1),
+            cursor: Default::default(),
+            enabled: Cell::new(true),
+            pd: Default::default(),
+        }
+    }
+}
+
 impl<Mods: Debug, T> Debug for Lazy<'_, Mods, T> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("Lazy").field("mods", &self.modules).finish()
+        f.debug_struct("Lazy")
+            .field("mods", &self.modules)
+            .field("pruned_ops", &self.pruned_ops.borrow())
+            .finish()
     }
 }
 
@@ -67,6 +99,12 @@ pub trait LazyRun {
     fn run(&self) -> crate::Result<()> {
         Ok(())
     }
+
+    /// Returns `true` if independent lazy operations can be executed concurrently on several threads.
+    #[inline]
+    fn supports_parallel_exec(&self) -> bool {
+        false
+    }
 }
 
 impl<'a, T, Mods: Module<'a, D>, D: LazySetup + Device + 'a> Module<'a, D> for Lazy<'a, Mods, T> {
@@ -82,6 +120,10 @@ impl<'a, T, Mods: Module<'a, D>, D: LazySetup + Device + 'a> Module<'a, D> for L
             graph: Default::default(),
             alloc_later: Default::default(),
             allocated_ids: Default::default(),
+            dropped_ids: Default::default(),
+            pruned_ops: Default::default(),
+            aliased_ids: Default::default(),
+            exec_threads: Cell::new(1),
             cursor: Default::default(),
             enabled: Cell::new(true),
             pd: Default::default(),
@@ -105,6 +147,21 @@ impl<T, Mods: AddOperation> AddOperation for Lazy<'_, Mods, T> {
         }
     }
 
+    unsafe fn add_parallel_op<Args: Parents<N> + crate::AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'a> Fn(Args::Replicated<'a>) -> crate::Result<()> + Send + Sync + 'static,
+    ) -> crate::Result<()> {
+        if self.enabled.get() {
+            self.graph.try_borrow_mut()
+            .expect("already borrowed: BorrowMutError - is the inner operation trying to add an operation as well?")
+            .add_parallel_operation(args, op);
+            Ok(())
+        } else {
+            self.modules.add_parallel_op(args, op)
+        }
+    }
+
     #[inline]
     fn ops_count(&self) -> usize {
         self.graph.borrow().ops_count()
@@ -158,6 +215,94 @@ impl<T, Mods> Lazy<'_, Mods, T> {
         Ok(())
     }
 
+    /// Removes all operations whose results cannot be observed anymore, because their lazily allocated output buffers were dropped.
+    /// Lazy allocations of these outputs are skipped.
+    /// The pruned operations are reported by [`Lazy::pruned_ops`].
+    pub fn prune_dead_ops(&self)
+    where
+        T: Default,
+    {
+        let mut dropped_ids = self.dropped_ids.borrow_mut();
+        let mut pruned_ops = self.pruned_ops.borrow_mut();
+        pruned_ops.clear();
+
+        if dropped_ids.is_empty() {
+            return;
+        }
+
+        let mut graph = self.graph.borrow_mut();
+        let pruned = {
+            let buffers = self.buffers.borrow();
+            // operations using dropped, not lazily allocated buffers are kept to report the error on run
+            graph.prune_dead_ops(
+                |id| !dropped_ids.contains(&id),
+                |id| buffers.contains_key(&id) || self.is_lazily_allocated(id),
+            )
+        };
+        pruned_ops.extend(pruned.iter().map(|(idx, op)| op.info(*idx)));
+
+        // dropped buffers that are not used anymore do not need to be allocated (or kept allocated)
+        let used_ids = graph.used_ids();
+        let mut buffers = self.buffers.borrow_mut();
+        let mut allocated_ids = self.allocated_ids.borrow_mut();
+        self.alloc_later
+            .borrow_mut()
+            .retain(|(id, _)| !dropped_ids.contains(&id.id) || used_ids.contains(&id.id));
+
+        dropped_ids.retain(|id| {
+            if used_ids.contains(id) {
+                return true;
+            }
+            buffers.remove(id);
+            allocated_ids.remove(id);
+            false
+        });
+    }
+
+    /// Executes independent operations on up to `threads` threads during [`Run::run`](crate::Run::run), if the device supports it (e.g. [`CPU`](crate::CPU)).
+    /// Only operations added by [`AddOperation::add_parallel_op`] are executed concurrently, e.g. the operations of the `Parallel` module.
+    /// The results are the same as for sequential execution. A value of `1` (default) disables parallel execution.
+    #[inline]
+    pub fn set_exec_threads(&self, threads: usize) {
+        self.exec_threads.set(threads.max(1));
+    }
+
+    #[inline]
+    pub fn exec_threads(&self) -> usize {
+        self.exec_threads.get().max(1)
+    }
+
+    /// Executes the operations on `threads` threads. Independent operations are executed concurrently (see [`LazyGraph::call_lazily_parallel`]).
+    pub fn call_lazily_parallel<D: Device + 'static>(
+        &self,
+        device: &D,
+        threads: usize,
+    ) -> crate::Result<()> {
+        let aliased_ids = self.aliased_ids.borrow();
+        self.graph.borrow_mut().call_lazily_parallel(
+            device,
+            &mut self.buffers.borrow_mut(),
+            threads,
+            |id| aliased_ids.get(&id).copied().unwrap_or(id),
+        )
+    }
+
+    /// Returns a report of the operations that were pruned during the last [`Lazy::prune_dead_ops`] call.
+    #[inline]
+    pub fn pruned_ops(&self) -> core::cell::Ref<'_, [OperationInfo]> {
+        core::cell::Ref::map(self.pruned_ops.borrow(), |ops| ops.as_slice())
+    }
+
+    #[inline]
+    fn is_lazily_allocated(&self, id: UniqueId) -> bool {
+        self.allocated_ids.borrow().contains(&id)
+            || self
+                .alloc_later
+                .borrow()
+                .iter()
+                .any(|(alloc_id, _)| alloc_id.id == id)
+    }
+
     pub fn alloc_later<D: 'static>(&self, device: &D) {
         let mut buffers = self.buffers.borrow_mut();
         let mut allocated_ids = self.allocated_ids.borrow_mut();
@@ -175,11 +320,19 @@ impl<T, D: LazySetup, Mods: Setup<D>> Setup<D> for Lazy<'_, Mods, T> {
     }
 }
 
-impl<T, Mods: RunModule<D>, D: LazyRun + Device + 'static> RunModule<D> for Lazy<'_, Mods, T> {
+impl<T: Default, Mods: RunModule<D>, D: LazyRun + Device + 'static> RunModule<D>
+    for Lazy<'_, Mods, T>
+{
     #[inline]
     fn run(&self, device: &D) -> crate::Result<()> {
+        self.prune_dead_ops();
         self.alloc_later(device);
-        self.call_lazily::<D>(device)?;
+        match self.exec_threads.get() {
+            threads if threads > 1 && device.supports_parallel_exec() => {
+                self.call_lazily_parallel(device, threads)?
+            }
+            _ => self.call_lazily::<D>(device)?,
+        }
         device.run()?;
         self.modules.run(device)
     }
@@ -192,7 +345,16 @@ impl<T2, Mods: OnDropBuffer> OnDropBuffer for Lazy<'_, Mods, T2> {
         device: &D,
         buf: &Buffer<T, D, S>,
     ) {
-        unregister_buf_copyable(&mut self.buffers.borrow_mut(), buf.id());
+        let id = buf.id();
+        // lazily allocated buffers may still be used by other operations
+        if self.is_lazily_allocated(id.id) {
+            self.dropped_ids.borrow_mut().insert(id.id);
+        } else {
+            unregister_buf_copyable(&mut self.buffers.borrow_mut(), id);
+        }
+        if buf.alias_of().is_some() {
+            self.aliased_ids.borrow_mut().remove(&id.id);
+        }
         self.modules.on_drop_buffer(device, buf)
     }
 }
@@ -208,6 +370,13 @@ where
     #[inline]
     unsafe fn on_new_buffer<'s>(&'s self, device: &'a D, new_buf: &'s Buffer<'a, T, D, S>) {
         unsafe { register_buf_copyable(&mut self.buffers.borrow_mut(), new_buf) };
+
+        // operations on a detached alias depend on the operations on the shared buffer
+        if let Some(shared_id) = new_buf.alias_of() {
+            let mut aliased_ids = self.aliased_ids.borrow_mut();
+            let shared_id = aliased_ids.get(&shared_id).copied().unwrap_or(shared_id);
+            aliased_ids.insert(new_buf.id().id, shared_id);
+        }
         self.modules.on_new_buffer(device, new_buf)
     }
 }
@@ -255,6 +424,27 @@ impl<Mods: crate::GradActions, U> crate::GradActions for Lazy<'_, Mods, U> {
     }
 }
 
+#[cfg(feature = "forward")]
+impl<Mods: crate::TangentActions, T> crate::TangentActions for Lazy<'_, Mods, T> {
+    #[inline]
+    fn tangents(&self) -> Option<core::cell::Ref<'_, crate::Tangents>> {
+        self.modules.tangents()
+    }
+
+    #[inline]
+    fn tangents_mut(&self) -> Option<core::cell::RefMut<'_, crate::Tangents>> {
+        self.modules.tangents_mut()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<Mods: crate::ThreadPoolActions, T> crate::ThreadPoolActions for Lazy<'_, Mods, T> {
+    #[inline]
+    fn thread_pool(&self) -> Option<&crate::ThreadPool> {
+        self.modules.thread_pool()
+    }
+}
+
 impl<T, Mods: crate::AddGradFn> crate::AddGradFn for Lazy<'_, Mods, T> {
     #[inline]
     fn add_grad_fn<Args: Parents<N> + AnyOp, const N: usize>(
@@ -265,6 +455,15 @@ impl<T, Mods: crate::AddGradFn> crate::AddGradFn for Lazy<'_, Mods, T> {
         self.modules.add_grad_fn(args, op)
     }
 
+    #[inline]
+    fn add_second_order_grad_fn<Args: Parents<N> + AnyOp, const N: usize>(
+        &self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + 'static,
+    ) {
+        self.modules.add_second_order_grad_fn(args, op)
+    }
+
     #[inline]
     fn backward(&mut self) {
         self.modules.backward()
@@ -274,6 +473,21 @@ impl<T, Mods: crate::AddGradFn> crate::AddGradFn for Lazy<'_, Mods, T> {
     fn set_grad_enabled(&self, enabled: bool) {
         self.modules.set_grad_enabled(enabled)
     }
+
+    #[inline]
+    fn is_grad_enabled(&self) -> bool {
+        self.modules.is_grad_enabled()
+    }
+
+    #[inline]
+    fn begin_checkpoint(&self) {
+        self.modules.begin_checkpoint()
+    }
+
+    #[inline]
+    fn end_checkpoint(&self) {
+        self.modules.end_checkpoint()
+    }
 }
 // pass_down_grad_fn!(Lazy);
 // impl_remove_layer!(Lazy);
@@ -295,6 +509,10 @@ impl<'a, T, NewMods, SD> AddLayer<NewMods, SD> for Lazy<'a, (), T> {
             graph: Default::default(),
             alloc_later: Default::default(),
             allocated_ids: Default::default(),
+            dropped_ids: Default::default(),
+            pruned_ops: Default::default(),
+            aliased_ids: Default::default(),
+            exec_threads: Cell::new(1),
             cursor: Default::default(),
             enabled: Cell::new(true),
             pd: Default::default(),
@@ -315,7 +533,7 @@ where
         &self,
         _device: &D,
         len: usize,
-        _parents: impl Parents<NUM_PARENTS>,
+        _parents: &impl Parents<NUM_PARENTS>,
     ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
     where
         S: Shape,
@@ -360,6 +578,9 @@ where
             buffers.insert(id.id, Box::new(buffer));
         }));
 
+        // the id may be reused, e.g. in a loop
+        self.dropped_ids.borrow_mut().remove(&id.id);
+
         unsafe { self.bump_cursor() };
 
         Ok(LazyWrapper {
@@ -369,14 +590,17 @@ where
     }
 
     #[inline]
-    fn on_retrieve_finish(&self, retrieved_buf: &Buffer<T, D, S>)
-    where
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
         D: Alloc<T>,
     {
         // unsafe { register_buf(&mut self.buffers.borrow_mut(), retrieved_buf) };
 
         // pass down
-        self.modules.on_retrieve_finish(retrieved_buf)
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
     }
 }
 
@@ -425,11 +649,12 @@ impl<T, Mods: UseGpuOrCpu> UseGpuOrCpu for Lazy<'_, Mods, T> {
         &self,
         location: crate::HashLocation<'static>,
         input_lengths: &[usize],
+        output_lengths: &[usize],
         cpu_op: impl FnMut(),
         gpu_op: impl FnMut(),
     ) -> crate::GpuOrCpuInfo {
         self.modules
-            .use_cpu_or_gpu(location, input_lengths, cpu_op, gpu_op)
+            .use_cpu_or_gpu(location, input_lengths, output_lengths, cpu_op, gpu_op)
     }
 
     #[inline]
@@ -470,6 +695,12 @@ impl<T: crate::Numeric + crate::CDatatype, Mods> crate::Optimize for Lazy<'_, Mo
         )?;
         Ok(())
     }
+
+    #[inline]
+    fn ew_fusing<D: crate::ElementWiseFusing + 'static>(&self, device: &D) -> crate::Result<()> {
+        self.fuse_ew_ops(device)?;
+        Ok(())
+    }
 }
 
 impl<T, Mods> CachedBuffers for Lazy<'_, Mods, T> {
@@ -584,6 +815,205 @@ mod tests {
             panic!("")
         }
     }
+
+    #[test]
+    #[cfg(feature = "cpu")]
+    fn test_lazy_run_prunes_dropped_outputs() {
+        use crate::{HasId, Run};
+
+        let device = CPU::<Lazy<Base, i32>>::new();
+
+        let buf = device.buffer([1, 2, 3]);
+        let tmp = device.apply_fn(&buf, |x| x.add(3));
+        let out = device.apply_fn(&tmp, |x| x.mul(2));
+        let unused = device.apply_fn(&buf, |x| x.mul(4));
+        let unused1 = device.apply_fn(&unused, |x| x.add(1));
+
+        let tmp_id = tmp.id().id;
+        let unused_id = unused.id().id;
+        drop(tmp);
+        drop(unused);
+        drop(unused1);
+
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [8, 10, 12]);
+
+        let pruned = device.modules.pruned_ops();
+        assert_eq!(pruned.len(), 2);
+        assert_eq!(pruned[0].idx, 2);
+        assert_eq!(pruned[0].name, "(x * 4)");
+        assert_eq!(pruned[1].idx, 3);
+
+        // still required by the remaining operations
+        assert!(device.modules.buffers.borrow().contains_key(&tmp_id));
+        assert!(!device.modules.buffers.borrow().contains_key(&unused_id));
+    }
+
+    #[test]
+    #[cfg(feature = "cpu")]
+    fn test_lazy_run_parallel_matches_sequential() {
+        use crate::{ApplyBinaryFunction, Run};
+
+        let mut results = vec![];
+        for threads in [1, 4] {
+            let device = CPU::<Lazy<Base, i32>>::new();
+            device.modules.set_exec_threads(threads);
+
+            let a = device.buffer([1, 2, 3, 4]);
+            let b = device.buffer([5, 6, 7, 8]);
+
+            let a1 = device.apply_fn(&a, |x| x.add(1));
+            let b1 = device.apply_fn(&b, |x| x.mul(2));
+            let c = device.apply_binary_fn(&a1, &b1, |a, b| a.add(b));
+            let a2 = device.apply_fn(&a, |x| x.mul(x));
+            let d = device.apply_binary_fn(&c, &a2, |c, a| c.sub(a));
+            let mut e = device.apply_fn(&d, |x| x.add(100));
+
+            // run concurrently with each other and with `c` on the calling thread
+            let (mut f, mut g) = (device.buffer([0; 4]), device.buffer([0; 4]));
+            for (src, dst) in [(&a1, &mut f), (&b1, &mut g)] {
+                // safety: only the data of the buffers is accessed
+                unsafe {
+                    device.add_parallel_op((src, dst), |(src, dst)| {
+                        dst.iter_mut().zip(src.iter()).for_each(|(d, s)| *d = s * 3);
+                        Ok(())
+                    })
+                }
+                .unwrap();
+            }
+            // overwrites e -> must run after e was computed and d was read
+            unsafe {
+                device.add_parallel_op((&d, &mut e), |(d, e)| {
+                    e.iter_mut().zip(d.iter()).for_each(|(e, d)| *e += d);
+                    Ok(())
+                })
+            }
+            .unwrap();
+
+            device.run().unwrap();
+            results.push((
+                d.replace().read().to_vec(),
+                e.replace().read().to_vec(),
+                f.read().to_vec(),
+                g.read().to_vec(),
+            ));
+        }
+
+        assert_eq!(
+            results[0],
+            (
+                vec![11, 11, 9, 5],
+                vec![122, 122, 118, 110],
+                vec![6, 9, 12, 15],
+                vec![30, 36, 42, 48]
+            )
+        );
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[test]
+    #[cfg(feature = "cpu")]
+    fn test_lazy_run_parallel_returns_first_error() {
+        use crate::Run;
+
+        let device = CPU::<Lazy<Base, i32>>::new();
+        device.modules.set_exec_threads(2);
+
+        let a = device.buffer([1, 2, 3]);
+        let mut b = device.buffer([1, 2, 3]);
+        let mut c = device.buffer([1, 2, 3]);
+        // safety: the operations do not access anything
+        unsafe {
+            device
+                .add_parallel_op((&a, &mut b), |_| {
+                    Err(crate::DeviceError::InvalidLazyBuf.into())
+                })
+                .unwrap();
+            device
+                .add_parallel_op((&a, &mut c), |_| {
+                    Err(crate::DeviceError::InvalidCaptureSlot.into())
+                })
+                .unwrap();
+        }
+
+        let err = device.run().unwrap_err();
+        assert_eq!(
+            *err.downcast_ref::<crate::DeviceError>().unwrap(),
+            crate::DeviceError::InvalidLazyBuf
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "cpu")]
+    fn test_lazy_run_parallel_stops_after_error() {
+        use crate::Run;
+
+        let device = CPU::<Lazy<Base, i32>>::new();
+        device.modules.set_exec_threads(2);
+
+        let a = device.buffer([1, 2, 3]);
+        let mut outs = [(); 4].map(|_| device.buffer([1, 2, 3]));
+        let (failing, rest) = outs.split_first_mut().unwrap();
+        // safety: only the data of the buffers is accessed
+        unsafe {
+            device
+                .add_parallel_op((&a, failing), |_| {
+                    Err(crate::DeviceError::InvalidLazyBuf.into())
+                })
+                .unwrap();
+            for out in rest {
+                device
+                    .add_parallel_op((&a, out), |(_, out)| {
+                        out.iter_mut().for_each(|x| *x = 0);
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+        }
+
+        assert!(device.run().is_err());
+        // the operation after the failing one runs on the same thread and must not be started
+        assert_eq!(outs[1].read(), [1, 2, 3]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "cpu", feature = "autograd"))]
+    fn test_lazy_detached_alias_depends_on_shared_buffer() {
+        use crate::{Autograd, HasId, Run};
+
+        let device = CPU::<Autograd<Lazy<Base>>>::new();
+        let x = device.buffer([1., 2., 3.]);
+        let mut detached = x.detach();
+
+        device
+            .add_op(&mut detached, |detached| {
+                detached.iter_mut().for_each(|x| *x = 0.);
+                Ok(())
+            })
+            .unwrap();
+        let out = device.apply_fn(&x, |x| x.add(1.));
+
+        let lazy = &device.modules.modules;
+        {
+            let aliased_ids = lazy.aliased_ids.borrow();
+            assert_eq!(aliased_ids.get(&detached.id().id), Some(&x.id().id));
+
+            // reading `x` must wait for the write through the alias
+            let levels = lazy
+                .graph
+                .borrow()
+                .exec_levels(|id| aliased_ids.get(&id).copied().unwrap_or(id));
+            assert_eq!(levels, [vec![0], vec![1]]);
+        }
+
+        lazy.set_exec_threads(2);
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [1.; 3]);
+
+        let detached_id = detached.id().id;
+        drop(detached);
+        assert!(lazy.aliased_ids.borrow().get(&detached_id).is_none());
+    }
     #[test]
     #[cfg(feature = "cpu")]
     fn test_lazy_apply_fn_with_run_cpu() {
@@ -0,0 +1,495 @@
+use core::{any::Any, cell::RefCell, marker::PhantomData};
+
+use crate::{
+    Buffer, Device, DeviceError, Downcast, HasId, Id, IsShapeIndep, Lazy, LazyGraph, ReplaceBuf,
+    ShallowCopy, Shape, Unit, WriteBuf,
+};
+
+use super::Buffers;
+
+type CopyFn = fn(&mut Buffers, &mut Buffers, Id, &dyn Any, bool);
+
+/// A typed handle to an input or output [`Buffer`] of a [`CapturedGraph`].
+#[derive(Debug)]
+pub struct Slot<T, D, S = ()> {
+    id: Id,
+    pd: PhantomData<(T, D, S)>,
+}
+
+impl<T, D, S> Slot<T, D, S> {
+    /// The id of the buffer that was used while recording.
+    #[inline]
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// The amount of elements a bound [`Buffer`] must have.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.id.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.id.len == 0
+    }
+}
+
+impl<T, D, S> Clone for Slot<T, D, S> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, D, S> Copy for Slot<T, D, S> {}
+
+/// A lazy graph that was taken out of a [`Lazy`] module in order to be executed many times.
+/// Declared input and output [`Slot`]s can be bound to different [`Buffer`]s between executions,
+/// while the allocations of all intermediate buffers are reused.
+///
+/// The captured graph takes ownership of the lazily allocated buffers, e.g. intermediate results.
+/// These stay allocated even if their [`Buffer`]s are dropped.
+/// Other buffers that were used while recording must outlive the captured graph unless they are rebound.
+///
+/// # Example
+#[cfg_attr(all(feature = "cpu", feature = "lazy"), doc = "```")]
+#[cfg_attr(not(all(feature = "cpu", feature = "lazy")), doc = "```ignore")]
+/// use custos::{ApplyFunction, Base, Combiner, Device, Lazy, CPU};
+///
+/// let device = CPU::<Lazy<Base>>::new();
+///
+/// let x = device.buffer([1., 2., 3.]);
+/// let out = device.apply_fn(&x, |x| x.mul(2.));
+///
+/// let mut captured = device.modules.capture(&device).unwrap();
+/// let x_slot = captured.input(&x).unwrap();
+/// let out_slot = captured.output(&out).unwrap();
+///
+/// let new_x = device.buffer([4., 5., 6.]);
+/// let new_out = device.buffer([0.; 3]);
+/// captured.bind(&x_slot, &new_x).unwrap();
+/// captured.bind(&out_slot, &new_out).unwrap();
+///
+/// captured.run(&device).unwrap();
+/// assert_eq!(new_out.read(), [8., 10., 12.]);
+/// ```
+pub struct CapturedGraph<'a, 'l, T = f32> {
+    graph: LazyGraph<Box<dyn crate::BoxedShallowCopy>, T>,
+    /// Shallow copies of every buffer that was used while recording.
+    buffers: Buffers,
+    /// The lazily allocated buffers that were used while recording.
+    /// Only kept to free them or hand them back on drop.
+    #[allow(dead_code)]
+    owned: OwnedBuffers<'l>,
+    /// Shallow copies of the buffers bound to a slot.
+    bound: Buffers,
+    inputs: Vec<(Id, CopyFn)>,
+    outputs: Vec<(Id, CopyFn)>,
+    backend: Option<Box<dyn Any>>,
+    pd: PhantomData<&'a ()>,
+}
+
+/// The lazily allocated buffers owned by a [`CapturedGraph`].
+struct OwnedBuffers<'l> {
+    buffers: Buffers,
+    /// The buffers of the [`Lazy`] module the graph was captured from.
+    lazy_buffers: &'l RefCell<Buffers>,
+}
+
+impl Drop for OwnedBuffers<'_> {
+    fn drop(&mut self) {
+        // buffers that are still referenced by a `Buffer` are handed back to the lazy module,
+        // which frees them when the `Buffer` is dropped
+        let mut lazy_buffers = self.lazy_buffers.borrow_mut();
+        for (id, buf) in self.buffers.drain() {
+            if let Some(lazy_buf) = lazy_buffers.get_mut(&id) {
+                *lazy_buf = buf;
+            }
+        }
+    }
+}
+
+impl<T, Mods> Lazy<'_, Mods, T> {
+    /// Takes all recorded operations out of this module and returns them as a [`CapturedGraph`].
+    /// Pending lazy allocations are performed beforehand.
+    /// The ownership of the lazily allocated buffers used by the operations moves to the captured graph.
+    pub fn capture<'a, 's, D: Device + 'static>(
+        &'s self,
+        device: &D,
+    ) -> crate::Result<CapturedGraph<'a, 's, T>> {
+        self.alloc_later(device);
+
+        let graph = core::mem::take(&mut *self.graph.borrow_mut());
+        let mut lazy_buffers = self.buffers.borrow_mut();
+        if graph
+            .operations
+            .iter()
+            .flat_map(|op| &op.arg_ids)
+            .any(|id| !lazy_buffers.contains_key(&id.id))
+        {
+            return Err(DeviceError::InvalidLazyBuf.into());
+        }
+
+        let mut allocated_ids = self.allocated_ids.borrow_mut();
+        let mut dropped_ids = self.dropped_ids.borrow_mut();
+
+        let mut buffers = Buffers::default();
+        let mut owned = Buffers::default();
+        for id in graph.operations.iter().flat_map(|op| &op.arg_ids) {
+            if buffers.contains_key(&id.id) {
+                continue;
+            }
+
+            // lazily allocated buffers are taken out of the module, which would free them once dropped
+            if !allocated_ids.remove(&id.id) {
+                buffers.insert(id.id, lazy_buffers[&id.id].shallow_copy());
+                continue;
+            }
+            let buf = if dropped_ids.remove(&id.id) {
+                lazy_buffers.remove(&id.id).unwrap()
+            } else {
+                // keeps the buffer accessible via `replace` as long as its `Buffer` is alive
+                let buf = lazy_buffers.get_mut(&id.id).unwrap();
+                core::mem::replace(buf, buf.shallow_copy())
+            };
+            buffers.insert(id.id, buf.shallow_copy());
+            owned.insert(id.id, buf);
+        }
+
+        Ok(CapturedGraph {
+            graph,
+            buffers,
+            owned: OwnedBuffers {
+                buffers: owned,
+                lazy_buffers: &self.buffers,
+            },
+            bound: Default::default(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            backend: None,
+            pd: PhantomData,
+        })
+    }
+}
+
+impl<'a, T> CapturedGraph<'a, '_, T> {
+    fn slot<T1, D, S>(&self, buf: &Buffer<T1, D, S>) -> crate::Result<Slot<T1, D, S>>
+    where
+        T1: Unit + 'static,
+        D: Device + 'static,
+        S: Shape,
+    {
+        let id = buf.id();
+        let captured = self
+            .buffers
+            .get(&id.id)
+            .ok_or(DeviceError::InvalidCaptureSlot)?;
+
+        if !captured.is::<Buffer<'static, T1, D, S>>() {
+            return Err(DeviceError::InvalidCaptureSlot.into());
+        }
+
+        Ok(Slot {
+            id,
+            pd: PhantomData,
+        })
+    }
+
+    /// Declares `buf` as an input of the captured graph.
+    /// Fails if `buf` was not used while recording.
+    pub fn input<T1, D, S>(&mut self, buf: &Buffer<T1, D, S>) -> crate::Result<Slot<T1, D, S>>
+    where
+        T1: Unit + 'static,
+        D: WriteBuf<T1, S, D> + 'static,
+        S: Shape,
+    {
+        let slot = self.slot(buf)?;
+        self.inputs.push((slot.id, copy_fn::<T1, D, S>));
+        Ok(slot)
+    }
+
+    /// Declares `buf` as an output of the captured graph.
+    /// Fails if `buf` was not used while recording.
+    pub fn output<T1, D, S>(&mut self, buf: &Buffer<T1, D, S>) -> crate::Result<Slot<T1, D, S>>
+    where
+        T1: Unit + 'static,
+        D: WriteBuf<T1, S, D> + 'static,
+        S: Shape,
+    {
+        let slot = self.slot(buf)?;
+        self.outputs.push((slot.id, copy_fn::<T1, D, S>));
+        Ok(slot)
+    }
+
+    /// Binds `buf` to an input or output `slot`.
+    /// Subsequent executions read from or write to `buf` instead of the recorded buffer.
+    pub fn bind<T1, D, S>(
+        &mut self,
+        slot: &Slot<T1, D, S>,
+        buf: &'a Buffer<T1, D, S>,
+    ) -> crate::Result<()>
+    where
+        T1: Unit + 'static,
+        D: Device + IsShapeIndep + ReplaceBuf<T1, D, S> + 'static,
+        D::Data<T1, S>: ShallowCopy,
+        S: Shape,
+    {
+        if buf.len() != slot.len() {
+            return Err(DeviceError::ShapeLengthMismatch.into());
+        }
+
+        // lazily allocated buffers only contain an id
+        let buf = buf.replace();
+
+        let buf: Buffer<T1, D, S> = Buffer {
+            data: unsafe { buf.data.shallow() },
+            device: None,
+        };
+        self.bound.insert(slot.id.id, Box::new(buf));
+        Ok(())
+    }
+
+    /// Removes the binding of `slot`. The recorded buffer is used again.
+    #[inline]
+    pub fn unbind<T1, D, S>(&mut self, slot: &Slot<T1, D, S>) {
+        self.bound.remove(&slot.id.id);
+    }
+
+    #[inline]
+    pub fn ops_count(&self) -> usize {
+        self.graph.ops_count()
+    }
+
+    #[inline]
+    pub fn graph(&self) -> &LazyGraph<Box<dyn crate::BoxedShallowCopy>, T> {
+        &self.graph
+    }
+
+    /// Executes the captured graph on `device`.
+    #[inline]
+    pub fn run<D: RunCaptured>(&mut self, device: &D) -> crate::Result<()> {
+        device.run_captured(self)
+    }
+
+    fn swap_bound(&mut self) {
+        for (id, bound) in self.bound.iter_mut() {
+            if let Some(captured) = self.buffers.get_mut(id) {
+                core::mem::swap(captured, bound);
+            }
+        }
+    }
+
+    /// Executes all operations in order, with the bound buffers replacing the recorded ones.
+    /// This is the portable way of executing a [`CapturedGraph`].
+    pub fn replay<D: Device + 'static>(&mut self, device: &D) -> crate::Result<()> {
+        self.swap_bound();
+        let res = self.graph.call_lazily(device, &mut self.buffers);
+        self.swap_bound();
+        res
+    }
+
+    /// Executes all operations in order on the recorded buffers, ignoring bindings.
+    /// Used by backends that bake buffer addresses into their own graph representation.
+    #[inline]
+    pub fn replay_recorded<D: Device + 'static>(&mut self, device: &D) -> crate::Result<()> {
+        self.graph.call_lazily(device, &mut self.buffers)
+    }
+
+    /// Copies the contents of bound input buffers into the recorded input buffers.
+    pub fn copy_bound_inputs<D: Device + 'static>(&mut self, device: &D) {
+        for (id, copy_fn) in &self.inputs {
+            if self.bound.contains_key(&id.id) {
+                copy_fn(&mut self.buffers, &mut self.bound, *id, device, true);
+            }
+        }
+    }
+
+    /// Copies the contents of the recorded output buffers into the bound output buffers.
+    pub fn copy_bound_outputs<D: Device + 'static>(&mut self, device: &D) {
+        for (id, copy_fn) in &self.outputs {
+            if self.bound.contains_key(&id.id) {
+                copy_fn(&mut self.buffers, &mut self.bound, *id, device, false);
+            }
+        }
+    }
+
+    /// Backend specific state, e.g. an instantiated CUDA graph.
+    #[inline]
+    pub fn backend_state<B: 'static>(&self) -> Option<&B> {
+        self.backend.as_ref()?.downcast_ref()
+    }
+
+    #[inline]
+    pub fn set_backend_state<B: 'static>(&mut self, state: B) {
+        self.backend = Some(Box::new(state));
+    }
+}
+
+fn copy_fn<T, D, S>(
+    recorded: &mut Buffers,
+    bound: &mut Buffers,
+    id: Id,
+    device: &dyn Any,
+    into_recorded: bool,
+) where
+    T: Unit + 'static,
+    D: WriteBuf<T, S, D> + 'static,
+    S: Shape,
+{
+    let device = device.downcast_ref::<D>().unwrap();
+    let recorded = recorded
+        .get_mut(&id.id)
+        .and_then(|buf| buf.downcast_mut::<Buffer<T, D, S>>())
+        .unwrap();
+    let bound = bound
+        .get_mut(&id.id)
+        .and_then(|buf| buf.downcast_mut::<Buffer<T, D, S>>())
+        .unwrap();
+
+    if into_recorded {
+        device.write_buf(recorded, bound)
+    } else {
+        device.write_buf(bound, recorded)
+    }
+}
+
+/// Executes a [`CapturedGraph`].
+/// By default, the operations are replayed one by one with the bound buffers.
+pub trait RunCaptured: Device + 'static {
+    #[inline]
+    fn run_captured<T>(&self, captured: &mut CapturedGraph<T>) -> crate::Result<()> {
+        captured.replay(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_captured_graph_rebind() {
+        use crate::{ApplyFunction, Base, Combiner, Device, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let tmp = device.apply_fn(&x, |x| x.mul(2.));
+        let out = device.apply_fn(&tmp, |x| x.add(1.));
+
+        let mut captured = device.modules.capture(&device).unwrap();
+        assert_eq!(captured.ops_count(), 2);
+
+        let x_slot = captured.input(&x).unwrap();
+        let out_slot = captured.output(&out).unwrap();
+
+        captured.run(&device).unwrap();
+        assert_eq!(out.replace().read(), [3., 5., 7.]);
+
+        let new_x = device.buffer([4., 5., 6.]);
+        let new_out = device.buffer([0.; 3]);
+        captured.bind(&x_slot, &new_x).unwrap();
+        captured.bind(&out_slot, &new_out).unwrap();
+
+        captured.run(&device).unwrap();
+        assert_eq!(new_out.read(), [9., 11., 13.]);
+        // recorded output is untouched
+        assert_eq!(out.replace().read(), [3., 5., 7.]);
+
+        let other_x = device.buffer([-1., 0., 1.]);
+        captured.bind(&x_slot, &other_x).unwrap();
+        captured.run(&device).unwrap();
+        assert_eq!(new_out.read(), [-1., 1., 3.]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_captured_graph_owns_intermediates() {
+        use crate::{ApplyFunction, Base, Combiner, Device, HasId, Lazy, Run, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let out = {
+            let tmp = device.apply_fn(&x, |x| x.mul(2.));
+            device.apply_fn(&tmp, |x| x.add(1.))
+        };
+        let out_id = out.id();
+
+        let mut captured = device.modules.capture(&device).unwrap();
+        // frees the lazily allocated buffers that were dropped and are not used anymore
+        device.run().unwrap();
+
+        captured.run(&device).unwrap();
+        assert_eq!(out.replace().read(), [3., 5., 7.]);
+
+        // the output stays allocated after its buffer is dropped
+        drop(out);
+        assert!(!device.modules.buffers.borrow().contains_key(&out_id));
+        captured.run(&device).unwrap();
+
+        let out = device.apply_fn(&x, |x| x.mul(3.));
+        let mut captured = device.modules.capture(&device).unwrap();
+        captured.run(&device).unwrap();
+
+        // the ownership is handed back to the module while the buffer is alive
+        drop(captured);
+        assert_eq!(out.replace().read(), [3., 6., 9.]);
+        drop(out);
+        assert!(device.modules.buffers.borrow().len() == 1);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_captured_graph_slot_checks() {
+        use crate::{ApplyFunction, Base, Combiner, Device, DeviceError, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let _out = device.apply_fn(&x, |x| x.mul(2.));
+        let unused = device.buffer([1., 2.]);
+
+        let mut captured = device.modules.capture(&device).unwrap();
+
+        let err = captured.input(&unused).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::InvalidCaptureSlot)
+        );
+
+        let x_slot = captured.input(&x).unwrap();
+        let too_short = device.buffer([1., 2.]);
+        let err = captured.bind(&x_slot, &too_short).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeviceError>(),
+            Some(&DeviceError::ShapeLengthMismatch)
+        );
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_captured_graph_copy_bound() {
+        use crate::{ApplyFunction, Base, Combiner, Device, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+
+        let x = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&x, |x| x.mul(3.));
+
+        let mut captured = device.modules.capture(&device).unwrap();
+        let x_slot = captured.input(&x).unwrap();
+        let out_slot = captured.output(&out).unwrap();
+
+        let new_x = device.buffer([2., 2., 2.]);
+        let new_out = device.buffer([0.; 3]);
+        captured.bind(&x_slot, &new_x).unwrap();
+        captured.bind(&out_slot, &new_out).unwrap();
+
+        // the static memory path used by graph backends
+        captured.copy_bound_inputs(&device);
+        assert_eq!(x.read(), [2., 2., 2.]);
+        captured.replay_recorded(&device).unwrap();
+        captured.copy_bound_outputs(&device);
+        assert_eq!(new_out.read(), [6., 6., 6.]);
+    }
+}
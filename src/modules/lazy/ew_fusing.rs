@@ -0,0 +1,347 @@
+use core::ops::Range;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    op_hint::OpHint, Buffer, Buffers, CDatatype, DeviceError, Downcast, ElementWiseFusing,
+    FusedElementWise, Id, Lazy, Numeric, Operation, OperationFn, UniqueId,
+};
+
+use super::AllocatedIds;
+
+type Buffers2 = Buffers<Box<dyn crate::BoxedShallowCopy>>;
+
+/// An operation that can be part of a fused element-wise sub-graph.
+struct EwOp<'a, T> {
+    hint: &'a OpHint<T>,
+    out: Id,
+    inputs: Vec<Id>,
+}
+
+impl<'a, T> EwOp<'a, T> {
+    fn new<B>(op: &'a Operation<B, T>) -> Option<Self> {
+        let input_count = match op.op_hint {
+            OpHint::Unary(_) => 1,
+            OpHint::Binary(_) => 2,
+            _ => return None,
+        };
+
+        if op.arg_ids.len() != input_count + 1 || op.mutable_args.len() != op.arg_ids.len() {
+            return None;
+        }
+
+        let mut out = None;
+        let mut inputs = Vec::with_capacity(input_count);
+        for (id, mutable) in op.arg_ids.iter().zip(&op.mutable_args) {
+            if *mutable {
+                if out.replace(*id).is_some() {
+                    return None;
+                }
+            } else {
+                inputs.push(*id);
+            }
+        }
+
+        let out = out?;
+        if inputs.iter().any(|id| id.len != out.len) {
+            return None;
+        }
+
+        Some(EwOp {
+            hint: &op.op_hint,
+            out,
+            inputs,
+        })
+    }
+}
+
+impl<T, Mods> Lazy<'_, Mods, T> {
+    /// Fuses runs of consecutive element-wise operations (tagged with [`OpHint::Unary`] or [`OpHint::Binary`]) into single operations.
+    /// Intermediate buffers, which are not used outside of a run and were dropped, are not written to anymore.
+    /// Runs that cannot be fused legally (aliasing or in-place buffers, mismatching types, too many variables) are kept unfused.
+    ///
+    /// Returns the number of fused runs.
+    pub fn fuse_ew_ops<D>(&self, device: &D) -> crate::Result<usize>
+    where
+        D: ElementWiseFusing + 'static,
+        T: CDatatype + Numeric,
+    {
+        // buffers are required to check aliasing and types
+        self.alloc_later(device);
+
+        let mut graph = self.graph.borrow_mut();
+        let buffers = self.buffers.borrow();
+        let dropped_ids = self.dropped_ids.borrow();
+
+        let mut fused_runs = 0;
+        let mut start = 0;
+
+        while start < graph.operations.len() {
+            // all buffers of a fused kernel must have the same length
+            let mut run_buf_len = None;
+            let run_len = graph.operations[start..]
+                .iter()
+                .map_while(EwOp::new)
+                .take_while(|op| *run_buf_len.get_or_insert(op.out.len) == op.out.len)
+                .count();
+
+            let range = start..start + run_len;
+            start += run_len.max(1);
+
+            if range.len() < 2 {
+                continue;
+            }
+
+            let Some(fused_op) =
+                fuse_range::<T, D>(&graph.operations, range.clone(), &buffers, &dropped_ids)
+            else {
+                continue;
+            };
+
+            graph.operations[range.start] = fused_op;
+            for idx in range.start + 1..range.end {
+                graph.operations[idx] = Operation::no_op();
+            }
+            fused_runs += 1;
+        }
+
+        Ok(fused_runs)
+    }
+}
+
+/// Builds a single operation, which computes all ops in `range`.
+/// Returns `None` if fusing is not legal.
+fn fuse_range<T, D>(
+    operations: &[Operation<Box<dyn crate::BoxedShallowCopy>, T>],
+    range: Range<usize>,
+    buffers: &Buffers2,
+    dropped_ids: &AllocatedIds,
+) -> Option<Operation<Box<dyn crate::BoxedShallowCopy>, T>>
+where
+    D: ElementWiseFusing + 'static,
+    T: CDatatype + Numeric,
+{
+    let mut fused = FusedElementWise::<T>::default();
+    let mut vars = HashMap::<UniqueId, usize>::new();
+    let mut input_ids = Vec::new();
+    let mut written_ids = Vec::<Id>::new();
+
+    for op in &operations[range.clone()] {
+        let ew_op = EwOp::new(op)?;
+
+        let mut args = Vec::with_capacity(ew_op.inputs.len());
+        for id in &ew_op.inputs {
+            let var = match vars.get(&id.id) {
+                Some(var) => *var,
+                None => {
+                    let var = fused.input()?;
+                    vars.insert(id.id, var);
+                    input_ids.push(*id);
+                    var
+                }
+            };
+            args.push(var);
+        }
+
+        // in-place operations and buffers written twice are not fused
+        if input_ids.iter().any(|id| id.id == ew_op.out.id)
+            || written_ids.iter().any(|id| id.id == ew_op.out.id)
+        {
+            return None;
+        }
+
+        let var = match (ew_op.hint, args.as_slice()) {
+            (OpHint::Unary(op), [x]) => fused.unary(op.clone(), *x)?,
+            (OpHint::Binary(op), [lhs, rhs]) => fused.binary(op.clone(), *lhs, *rhs)?,
+            _ => return None,
+        };
+        vars.insert(ew_op.out.id, var);
+        written_ids.push(ew_op.out);
+    }
+
+    let used_outside = operations[..range.start]
+        .iter()
+        .chain(&operations[range.end..])
+        .flat_map(|op| op.arg_ids.iter().map(|id| id.id))
+        .collect::<HashSet<_>>();
+
+    let output_ids = written_ids
+        .into_iter()
+        .filter(|id| !dropped_ids.contains(&id.id) || used_outside.contains(&id.id))
+        .collect::<Vec<_>>();
+
+    if output_ids.is_empty() {
+        return None;
+    }
+
+    // every buffer must be available, of type T and must not share its data with another buffer
+    let mut data_ids = HashSet::new();
+    for id in input_ids.iter().chain(&output_ids) {
+        let buf = buffers
+            .get(&id.id)?
+            .downcast_ref::<Buffer<'static, T, D, ()>>()?;
+        if !data_ids.insert(crate::HasId::id(buf).id) {
+            return None;
+        }
+    }
+
+    for id in &output_ids {
+        fused.output(vars[&id.id]);
+    }
+
+    let input_count = input_ids.len();
+    let output_count = output_ids.len();
+
+    let op: OperationFn<Box<dyn crate::BoxedShallowCopy>> = Box::new(move |ids, buffers, _dev| {
+        let (input_ids, output_ids) = ids.split_at(input_count);
+        let buffers = buffers as *mut Buffers2;
+
+        let inputs = input_ids
+            .iter()
+            .map(|id| {
+                unsafe { &*buffers }
+                    .get(&id.id)
+                    .and_then(|buf| buf.downcast_ref::<Buffer<'static, T, D, ()>>())
+                    .ok_or(DeviceError::InvalidLazyBuf)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut outputs = output_ids
+            .iter()
+            .map(|id| {
+                // safety: ids of outputs are unique and were checked to not alias any input
+                unsafe { &mut *buffers }
+                    .get_mut(&id.id)
+                    .and_then(|buf| buf.downcast_mut::<Buffer<'static, T, D, ()>>())
+                    .ok_or(DeviceError::InvalidLazyBuf)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let device = outputs[0].device();
+        device.fused_ew(&fused, &inputs, &mut outputs)
+    });
+
+    let mut mutable_args = vec![false; input_count];
+    mutable_args.extend(core::iter::repeat(true).take(output_count));
+
+    Some(Operation {
+        arg_ids: input_ids.into_iter().chain(output_ids).collect(),
+        mutable_args,
+        op,
+        op_hint: OpHint::None,
+        parallel_safe: false,
+    })
+}
+
+#[cfg(feature = "cpu")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        AddOperation, ApplyBinaryFunction, ApplyFunction, Base, Combiner, Device, Lazy, Run, CPU,
+    };
+
+    #[test]
+    fn test_fuse_ew_ops_drops_intermediates() {
+        let device = CPU::<Lazy<Base>>::new();
+
+        let a = device.buffer([1., -2., 3., -4.]);
+        let b = device.buffer([2., 3., 4., 5.]);
+        let c = device.buffer([1., 1., -20., 1.]);
+
+        let out = {
+            let ab = device.apply_binary_fn(&a, &b, |a, b| a.mul(b));
+            let abc = device.apply_binary_fn(&ab, &c, |ab, c| ab.add(c));
+            device.apply_fn(&abc, |x| x.max(0.))
+        };
+
+        assert_eq!(device.modules.fuse_ew_ops(&device).unwrap(), 1);
+        {
+            let graph = device.modules.graph.borrow();
+            assert_eq!(graph.ops_count(), 3);
+            // a, b, c -> out
+            assert_eq!(graph.operations[0].arg_ids.len(), 4);
+            assert!(graph.operations[1..].iter().all(|op| op.arg_ids.is_empty()));
+        }
+
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [3., 0., 0., 0.]);
+    }
+
+    #[test]
+    fn test_fuse_ew_ops_shared_intermediates() {
+        let device = CPU::<Lazy<Base>>::new();
+
+        let a = device.buffer([1., 2., 3.]);
+        let b = device.buffer([4., 5., 6.]);
+
+        let x = device.apply_binary_fn(&a, &b, |a, b| a.mul(b));
+        let y = device.apply_binary_fn(&x, &a, |x, a| x.add(a));
+        let z = device.apply_binary_fn(&x, &y, |x, y| x.sub(y));
+        let w = device.apply_fn(&z, |z| z.mul(2.));
+
+        assert_eq!(device.modules.fuse_ew_ops(&device).unwrap(), 1);
+        device.run().unwrap();
+
+        assert_eq!(x.replace().read(), [4., 10., 18.]);
+        assert_eq!(y.replace().read(), [5., 12., 21.]);
+        assert_eq!(z.replace().read(), [-1., -2., -3.]);
+        assert_eq!(w.replace().read(), [-2., -4., -6.]);
+    }
+
+    #[test]
+    fn test_fuse_ew_ops_keeps_non_ew_ops_unfused() {
+        let device = CPU::<Lazy<Base>>::new();
+
+        let a = device.buffer([1., 2., 3.]);
+        let x = device.apply_fn(&a, |a| a.add(1.));
+        let mut y = device.apply_fn(&x, |x| x.mul(3.));
+        device
+            .add_op(&mut y, |y| {
+                y.iter_mut().for_each(|y| *y += 1.);
+                Ok(())
+            })
+            .unwrap();
+        let z = device.apply_fn(&y, |y| y.neg());
+
+        // the single element-wise op after the custom op cannot be fused
+        assert_eq!(device.modules.fuse_ew_ops(&device).unwrap(), 1);
+        assert_eq!(device.modules.graph.borrow().operations[3].arg_ids.len(), 2);
+
+        device.run().unwrap();
+        assert_eq!(y.replace().read(), [7., 10., 13.]);
+        assert_eq!(z.replace().read(), [-7., -10., -13.]);
+    }
+
+    #[test]
+    fn test_fuse_ew_ops_mismatching_lengths() {
+        let device = CPU::<Lazy<Base>>::new();
+
+        let a = device.buffer([1., 2., 3.]);
+        let b = device.buffer([1., 2.]);
+        let x = device.apply_fn(&a, |a| a.add(1.));
+        let y = device.apply_fn(&b, |b| b.add(1.));
+
+        assert_eq!(device.modules.fuse_ew_ops(&device).unwrap(), 0);
+        device.run().unwrap();
+        assert_eq!(x.replace().read(), [2., 3., 4.]);
+        assert_eq!(y.replace().read(), [2., 3.]);
+    }
+
+    #[cfg(feature = "graph")]
+    #[test]
+    fn test_ew_fusing_optimize() {
+        use crate::{Graph, Optimize};
+
+        let device = CPU::<Graph<Lazy<Base>>>::new();
+
+        let a = device.buffer([1., 2., 3.]);
+        let b = device.buffer([4., 5., 6.]);
+        let out = {
+            let ab = device.apply_binary_fn(&a, &b, |a, b| a.add(b));
+            device.apply_fn(&ab, |x| x.mul(x))
+        };
+
+        device.ew_fusing(&device).unwrap();
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), [25., 49., 81.]);
+    }
+}
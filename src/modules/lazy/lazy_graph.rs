@@ -5,10 +5,19 @@ use crate::{
 use core::ops::RangeBounds;
 use std::collections::HashSet;
 
+mod export;
+mod prune;
+mod schedule;
+pub use export::*;
+
 pub struct Operation<B, T> {
     pub arg_ids: Vec<Id>,
+    /// Marks which of the `arg_ids` may be written to by `op`.
+    pub mutable_args: Vec<bool>,
     pub op: OperationFn<B>,
     pub op_hint: OpHint<T>,
+    /// Set if `op` may run concurrently with other operations on a different thread, see [`LazyGraph::add_parallel_operation`].
+    pub parallel_safe: bool,
 }
 
 impl<B, T> Operation<B, T> {
@@ -17,7 +26,9 @@ impl<B, T> Operation<B, T> {
         Self {
             op: Box::new(|_ids, _buffers, _dev| Ok(())),
             arg_ids: vec![],
+            mutable_args: vec![],
             op_hint: OpHint::None,
+            parallel_safe: false,
         }
     }
 
@@ -29,6 +40,18 @@ impl<B, T> Operation<B, T> {
     ) -> crate::Result<()> {
         (self.op)(&self.arg_ids, buffers, device)
     }
+
+    /// Returns the ids of all arguments that may be written to.
+    /// Arguments without mutability information are treated as written to.
+    pub fn written_ids(&self) -> impl Iterator<Item = &Id> {
+        self.arg_ids.iter().enumerate().filter_map(|(idx, id)| {
+            self.mutable_args
+                .get(idx)
+                .copied()
+                .unwrap_or(true)
+                .then_some(id)
+        })
+    }
 }
 
 pub struct LazyGraph<B = Box<dyn BoxedShallowCopy>, T = ()> {
@@ -124,8 +147,10 @@ impl<B: Downcast, T> LazyGraph<B, T> {
 
         Operation {
             arg_ids,
+            mutable_args: Args::MUTABLE_ARGS.to_vec(),
             op,
             op_hint: OpHint::None,
+            parallel_safe: false,
         }
     }
 
@@ -137,6 +162,21 @@ impl<B: Downcast, T> LazyGraph<B, T> {
         let operation = Self::convert_to_operation(args, op);
         self.operations.push(operation)
     }
+
+    /// Adds an operation that may be executed concurrently with other operations by [`LazyGraph::call_lazily_parallel`].
+    ///
+    /// # Safety
+    /// `op` is called on a different thread. Apart from the data of its buffers, it must not access the device of the buffers or any other state that is not [`Sync`].
+    /// The element types of the buffers must be [`Send`] and [`Sync`].
+    pub unsafe fn add_parallel_operation<Args: Parents<N> + AnyOp, const N: usize>(
+        &mut self,
+        args: Args,
+        op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + Send + Sync + 'static,
+    ) {
+        let mut operation = Self::convert_to_operation(args, op);
+        operation.parallel_safe = true;
+        self.operations.push(operation)
+    }
 }
 
 #[cfg(feature = "cpu")]
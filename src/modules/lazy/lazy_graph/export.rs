@@ -0,0 +1,189 @@
+use core::fmt::Write;
+
+use crate::{LazyGraph, Operation};
+
+/// A readable description of a single [`Operation`] recorded in a [`LazyGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperationInfo {
+    /// The position of the operation inside the graph.
+    pub idx: usize,
+    /// A readable name derived from the [`OpHint`](crate::op_hint::OpHint) of the operation.
+    pub name: String,
+    /// The ids of the argument buffers.
+    pub arg_ids: Vec<u64>,
+    /// The lengths of the argument buffers.
+    pub arg_lens: Vec<usize>,
+}
+
+impl<B, T: Default> Operation<B, T> {
+    /// Returns a readable description of this operation.
+    pub fn info(&self, idx: usize) -> OperationInfo {
+        OperationInfo {
+            idx,
+            name: self.op_hint.name(),
+            arg_ids: self.arg_ids.iter().map(|id| id.id).collect(),
+            arg_lens: self.arg_ids.iter().map(|id| id.len).collect(),
+        }
+    }
+}
+
+impl<B, T: Default> LazyGraph<B, T> {
+    /// Returns a readable description of every recorded operation, in execution order.
+    pub fn operation_infos(&self) -> Vec<OperationInfo> {
+        self.operations
+            .iter()
+            .enumerate()
+            .map(|(idx, op)| op.info(idx))
+            .collect()
+    }
+
+    /// Exports the recorded operations to the Graphviz DOT format.
+    /// Operations are drawn as ellipses, their argument buffers as boxes.
+    /// Consecutive operations are connected by dashed edges to show the execution order.
+    /// # Example
+    #[cfg_attr(all(feature = "cpu", feature = "lazy"), doc = "```")]
+    #[cfg_attr(not(all(feature = "cpu", feature = "lazy")), doc = "```ignore")]
+    /// use custos::{ApplyFunction, Base, Combiner, Device, Lazy, CPU};
+    ///
+    /// let device = CPU::<Lazy<Base>>::new();
+    /// let buf = device.buffer([1., 2., 3.]);
+    /// let _out = device.apply_fn(&buf, |x| x.sin());
+    ///
+    /// let dot = device.modules.graph.borrow().to_dot();
+    /// assert!(dot.contains("sin(x)"));
+    /// ```
+    pub fn to_dot(&self) -> String {
+        let infos = self.operation_infos();
+        let mut dot = String::from("digraph LazyGraph {\n");
+
+        let mut seen_bufs = std::collections::HashSet::new();
+        for info in &infos {
+            for (id, len) in info.arg_ids.iter().zip(&info.arg_lens) {
+                if seen_bufs.insert(*id) {
+                    writeln!(
+                        dot,
+                        "    buf{id} [shape=box, label=\"id: {id}\\nlen: {len}\"];"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        for info in &infos {
+            writeln!(
+                dot,
+                "    op{idx} [shape=ellipse, label=\"{idx}: {name}\"];",
+                idx = info.idx,
+                name = escape_dot(&info.name)
+            )
+            .unwrap();
+
+            for (arg_idx, id) in info.arg_ids.iter().enumerate() {
+                writeln!(dot, "    buf{id} -> op{} [label=\"{arg_idx}\"];", info.idx).unwrap();
+            }
+        }
+
+        for pair in infos.windows(2) {
+            writeln!(
+                dot,
+                "    op{} -> op{} [style=dashed];",
+                pair[0].idx, pair[1].idx
+            )
+            .unwrap();
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Exports the recorded operations as a JSON array of [`OperationInfo`]s.
+    #[cfg(feature = "json")]
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&self.operation_infos())
+    }
+}
+
+#[inline]
+pub(crate) fn escape_dot(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_lazy_graph_operation_infos() {
+        use crate::{ApplyFunction, Base, Combiner, Device, HasId, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let buf = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&buf, |x| x.sin());
+        let out1 = device.apply_fn(&out, |x| x.mul(2.));
+
+        let infos = device.modules.graph.borrow().operation_infos();
+        assert_eq!(infos.len(), 2);
+
+        assert_eq!(infos[0].name, "sin(x)");
+        assert_eq!(infos[0].arg_ids, [out.id().id, buf.id().id]);
+        assert_eq!(infos[0].arg_lens, [3, 3]);
+
+        assert_eq!(infos[1].name, "(x * 2.0)");
+        assert_eq!(infos[1].arg_ids, [out1.id().id, out.id().id]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_lazy_graph_to_dot() {
+        use crate::{ApplyFunction, Base, Combiner, Device, HasId, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let buf = device.buffer([1., 2., 3.]);
+        let out = device.apply_fn(&buf, |x| x.sin());
+        let _out = device.apply_fn(&out, |x| x.cos());
+
+        let dot = device.modules.graph.borrow().to_dot();
+        assert!(dot.starts_with("digraph LazyGraph {"));
+        assert!(dot.contains("op0 [shape=ellipse, label=\"0: sin(x)\"];"));
+        assert!(dot.contains("op1 [shape=ellipse, label=\"1: cos(x)\"];"));
+        assert!(dot.contains(&format!("buf{} -> op0", buf.id().id)));
+        assert!(dot.contains("op0 -> op1 [style=dashed];"));
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_tape_to_dot() {
+        use crate::{Autograd, Base, Combiner, Device, TapeActions, UnaryElementWiseMayGrad, CPU};
+
+        let device = CPU::<Autograd<Base>>::new();
+        let buf = device.buffer([1., 2., 3.]).require_grad();
+        let _out = device.unary_ew(&buf, |x| x.sin(), |x| x.cos());
+
+        let tape = unsafe { device.tape() }.unwrap();
+        assert_eq!(tape.lazy_graph.operation_infos().len(), 1);
+        assert!(tape
+            .to_dot()
+            .contains("op0 [shape=ellipse, label=\"0: None\"];"));
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "json")]
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lazy_graph_to_json() {
+        use crate::{ApplyFunction, Base, Combiner, Device, Lazy, OperationInfo, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let buf = device.buffer([1., 2., 3.]);
+        let _out = device.apply_fn(&buf, |x| x.exp());
+
+        let graph = device.modules.graph.borrow();
+        let json = graph.to_json().unwrap();
+        let infos: Vec<OperationInfo> = serde_json::from_str(&json).unwrap();
+        assert_eq!(infos, graph.operation_infos());
+        assert_eq!(infos[0].name, "exp(x)");
+    }
+}
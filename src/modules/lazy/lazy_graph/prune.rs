@@ -0,0 +1,75 @@
+use std::collections::HashSet;
+
+use crate::{LazyGraph, Operation, UniqueId};
+
+impl<B, T> LazyGraph<B, T> {
+    /// Replaces every operation whose results cannot be observed with a no-op.
+    /// A written buffer is observable if `is_live` returns `true` for its id or if it is used by a later, remaining operation.
+    /// Operations without written buffers are always kept.
+    /// Operations using a buffer for which `is_available` returns `false` are kept as well, running them reports the missing buffer.
+    ///
+    /// Returns the pruned operations together with their former position.
+    /// Positions of the remaining operations are not changed.
+    pub fn prune_dead_ops(
+        &mut self,
+        mut is_live: impl FnMut(UniqueId) -> bool,
+        mut is_available: impl FnMut(UniqueId) -> bool,
+    ) -> Vec<(usize, Operation<B, T>)> {
+        let mut used = HashSet::new();
+        let mut pruned = Vec::new();
+
+        for (idx, op) in self.operations.iter_mut().enumerate().rev() {
+            let observable = {
+                let mut written_ids = op.written_ids().peekable();
+                written_ids.peek().is_none()
+                    || written_ids.any(|id| used.contains(&id.id) || is_live(id.id))
+                    || op.arg_ids.iter().any(|id| !is_available(id.id))
+            };
+
+            if observable {
+                used.extend(op.arg_ids.iter().map(|id| id.id));
+            } else {
+                pruned.push((idx, core::mem::replace(op, Operation::no_op())));
+            }
+        }
+
+        pruned.reverse();
+        pruned
+    }
+
+    /// Returns the ids of all buffers used by the recorded operations.
+    pub fn used_ids(&self) -> HashSet<UniqueId> {
+        self.operations
+            .iter()
+            .flat_map(|op| op.arg_ids.iter().map(|id| id.id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_prune_dead_ops_keeps_used_intermediates() {
+        use crate::{ApplyFunction, Base, Combiner, Device, HasId, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let buf = device.buffer([1., 2., 3.]);
+        let tmp = device.apply_fn(&buf, |x| x.mul(2.));
+        let out = device.apply_fn(&tmp, |x| x.add(1.));
+        let dead = device.apply_fn(&buf, |x| x.sin());
+
+        let tmp_id = tmp.id().id;
+        let dead_id = dead.id().id;
+
+        let mut graph = device.modules.graph.borrow_mut();
+        // tmp is not live anymore, but still used by a live operation
+        let pruned = graph.prune_dead_ops(|id| id != tmp_id && id != dead_id, |_| true);
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].0, 2);
+
+        assert_eq!(graph.ops_count(), 3);
+        assert!(graph.used_ids().contains(&out.id().id));
+        assert!(!graph.used_ids().contains(&dead_id));
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{BoxedShallowCopy, Buffers, Device, LazyGraph, Operation, UniqueId};
+
+/// An operation of a level that is executed on a worker thread by [`LazyGraph::call_lazily_parallel`].
+/// It receives its own buffers (shallow copies), therefore no map is shared between threads.
+struct ParallelJob<'a, T, D> {
+    op: &'a Operation<Box<dyn BoxedShallowCopy>, T>,
+    buffers: Buffers<Box<dyn BoxedShallowCopy>>,
+    device: &'a D,
+}
+
+// safety: jobs are only created for operations added by `add_parallel_operation`,
+// whose contract guarantees that they access nothing but the data of their buffers.
+// Operations of the same level do not write data that is accessed by another operation of the level.
+// The buffers are created and dropped on the calling thread.
+unsafe impl<T, D> Send for ParallelJob<'_, T, D> {}
+
+impl<B, T> LazyGraph<B, T> {
+    /// Groups the indices of the operations into levels.
+    /// An operation is placed one level after the last operation it depends on:
+    /// - reading a buffer depends on the previous write to it
+    /// - writing a buffer depends on the previous write and on all reads since then
+    ///
+    /// Operations inside of a level are independent of each other and sorted by their position.
+    /// Executing the levels in order produces the same results as executing all operations sequentially.
+    ///
+    /// `data_id` maps the id of a buffer to the id of its underlying data, as different ids may share the same buffer.
+    pub fn exec_levels(&self, mut data_id: impl FnMut(UniqueId) -> UniqueId) -> Vec<Vec<usize>> {
+        // level of the last write and the highest level of all reads since then
+        let mut last_write = HashMap::<UniqueId, usize>::new();
+        let mut last_reads = HashMap::<UniqueId, usize>::new();
+        let mut levels: Vec<Vec<usize>> = Vec::new();
+
+        for (idx, op) in self.operations.iter().enumerate() {
+            let args = op
+                .arg_ids
+                .iter()
+                .enumerate()
+                .map(|(arg_idx, id)| {
+                    let written = op.mutable_args.get(arg_idx).copied().unwrap_or(true);
+                    (data_id(id.id), written)
+                })
+                .collect::<Vec<_>>();
+
+            let level = args
+                .iter()
+                .map(|(id, written)| {
+                    let after_write = last_write.get(id).map_or(0, |level| level + 1);
+                    if !written {
+                        return after_write;
+                    }
+                    let after_reads = last_reads.get(id).map_or(0, |level| level + 1);
+                    after_write.max(after_reads)
+                })
+                .max()
+                .unwrap_or(0);
+
+            for (id, written) in args {
+                if written {
+                    last_write.insert(id, level);
+                    last_reads.remove(&id);
+                } else {
+                    let read_level = last_reads.entry(id).or_default();
+                    *read_level = (*read_level).max(level);
+                }
+            }
+
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(idx);
+        }
+
+        levels
+    }
+}
+
+impl<T> LazyGraph<Box<dyn BoxedShallowCopy>, T> {
+    /// Executes the operations level by level (see [`LazyGraph::exec_levels`]).
+    /// Independent operations of a level that were added by [`LazyGraph::add_parallel_operation`] are distributed over at most `threads` scoped threads.
+    /// The other operations of a level are executed on the calling thread beforehand.
+    ///
+    /// After the first operation fails, no further operations are started and its error is returned.
+    /// Operations of the same level that are already running are finished.
+    /// If several running operations fail, the error of the one with the lowest position inside the level is returned.
+    pub fn call_lazily_parallel<D: Device + 'static>(
+        &mut self,
+        device: &D,
+        buffers: &mut Buffers<Box<dyn BoxedShallowCopy>>,
+        threads: usize,
+        data_id: impl FnMut(UniqueId) -> UniqueId,
+    ) -> crate::Result<()> {
+        let threads = threads.max(1);
+
+        for level in self.exec_levels(data_id) {
+            let (parallel, sequential): (Vec<usize>, Vec<usize>) = level
+                .into_iter()
+                .partition(|idx| self.operations[*idx].parallel_safe);
+
+            for idx in sequential {
+                self.operations[idx].call(buffers, device)?;
+            }
+
+            if parallel.len() <= 1 || threads == 1 {
+                for idx in parallel {
+                    self.operations[idx].call(buffers, device)?;
+                }
+                continue;
+            }
+
+            let mut jobs = parallel
+                .iter()
+                .map(|idx| {
+                    let op = &self.operations[*idx];
+                    let op_buffers = op
+                        .arg_ids
+                        .iter()
+                        .filter_map(|id| Some((id.id, buffers.get(&id.id)?.shallow_copy())))
+                        .collect::<Buffers<_>>();
+                    ParallelJob {
+                        op,
+                        buffers: op_buffers,
+                        device,
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let chunk_size = parallel.len().div_ceil(threads);
+            // set by the first failing operation, no further operations are started afterwards
+            let failed = &AtomicBool::new(false);
+
+            let results = std::thread::scope(|scope| {
+                let handles = jobs
+                    .chunks_mut(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            for job in chunk {
+                                if failed.load(Ordering::Acquire) {
+                                    break;
+                                }
+                                if let Err(err) = job.op.call(&mut job.buffers, job.device) {
+                                    failed.store(true, Ordering::Release);
+                                    return Err(err);
+                                }
+                            }
+                            Ok(())
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("A lazy operation panicked"))
+                    .collect::<Vec<crate::Result<()>>>()
+            });
+
+            drop(jobs);
+
+            // chunks are ordered by the position of their operations
+            for result in results {
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_exec_levels() {
+        use crate::{ApplyFunction, Base, Combiner, Device, HasId, Lazy, CPU};
+
+        let device = CPU::<Lazy<Base>>::new();
+        let a = device.buffer([1., 2., 3.]);
+        let b = device.buffer([4., 5., 6.]);
+
+        let a1 = device.apply_fn(&a, |x| x.add(1.));
+        let b1 = device.apply_fn(&b, |x| x.add(1.));
+        let _a2 = device.apply_fn(&a1, |x| x.mul(2.));
+        let _b2 = device.apply_fn(&b1, |x| x.mul(2.));
+        let _a3 = device.apply_fn(&a, |x| x.sin());
+
+        let graph = device.modules.graph.borrow();
+        assert_eq!(graph.exec_levels(|id| id), [vec![0, 1, 4], vec![2, 3]]);
+
+        // a1 and b1 share their data -> every write has to wait for the reads and writes before
+        let (a1, b1) = (a1.id().id, b1.id().id);
+        let levels = graph.exec_levels(|id| if id == b1 { a1 } else { id });
+        assert_eq!(levels, [vec![0, 4], vec![1], vec![2, 3]]);
+    }
+}
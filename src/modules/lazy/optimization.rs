@@ -37,6 +37,11 @@ impl<T, Mods> Lazy<'_, Mods, T> {
                     self.buffers
                         .borrow_mut()
                         .insert(*use_id_as_well_id, buf.shallow_copy());
+                    if *use_id_as_well_id != id.id {
+                        self.aliased_ids
+                            .borrow_mut()
+                            .insert(*use_id_as_well_id, id.id);
+                    }
                 }
             }
         }
@@ -47,6 +47,33 @@ impl<Data: HasId, T> HasId for LazyWrapper<Data, T> {
             MaybeData::None => unimplemented!(),
         }
     }
+
+    #[inline]
+    fn requires_grad(&self) -> bool {
+        match self.maybe_data {
+            MaybeData::Data(ref data) => data.requires_grad(),
+            _ => false,
+        }
+    }
+
+    #[inline]
+    fn set_requires_grad(&mut self, requires_grad: bool) {
+        if let MaybeData::Data(ref mut data) = self.maybe_data {
+            data.set_requires_grad(requires_grad)
+        }
+    }
+
+    #[inline]
+    fn set_detached_id(&mut self, id: crate::UniqueId) {
+        if let MaybeData::Data(ref mut data) = self.maybe_data {
+            data.set_detached_id(id)
+        }
+    }
+
+    #[inline]
+    fn alias_of(&self) -> Option<crate::UniqueId> {
+        self.maybe_data.data()?.alias_of()
+    }
 }
 
 impl<Data: PtrType, T> PtrType for LazyWrapper<Data, T> {
@@ -61,10 +88,11 @@ impl<Data: PtrType, T> PtrType for LazyWrapper<Data, T> {
 
     #[inline]
     fn flag(&self) -> AllocFlag {
-        self.maybe_data
-            .data()
-            .map(|data| data.flag())
-            .unwrap_or(AllocFlag::Lazy)
+        match self.maybe_data {
+            MaybeData::Data(ref data) => data.flag(),
+            MaybeData::Id(_) => AllocFlag::Id,
+            MaybeData::None => AllocFlag::Lazy,
+        }
     }
 
     #[inline]
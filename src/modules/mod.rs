@@ -65,6 +65,16 @@ mod fork;
 #[cfg(feature = "fork")]
 pub use fork::*;
 
+#[cfg(feature = "forward")]
+mod forward;
+#[cfg(feature = "forward")]
+pub use forward::*;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "parallel")]
+pub use parallel::*;
+
 #[cfg(feature = "std")]
 use crate::{Buffer, Device, HasId, Id, ShallowCopy, Shape, UniqueId};
 #[cfg(feature = "std")]
@@ -143,3 +153,219 @@ pub(crate) fn unregister_buf_copyable(
 ) {
     cache.remove(&id);
 }
+
+#[cfg(test)]
+#[cfg(all(
+    feature = "cpu",
+    feature = "cached",
+    feature = "autograd",
+    feature = "lazy",
+    feature = "fork",
+    feature = "graph"
+))]
+mod tests {
+    use crate::{
+        Autograd, Base, Buffer, Cached, CachedModule, Cursor, Device, Fork, Graph, HasId, Lazy,
+        Retriever, Shape, Unit, CPU,
+    };
+
+    /// Asserts that every module of a stack processed a retrieved buffer in `on_retrieve_finish`.
+    trait AssertRetrieved {
+        fn assert_retrieved<T: Unit, D: Device + Cursor, S: Shape>(
+            &self,
+            buf: &Buffer<T, D, S>,
+            requires_grad: bool,
+            stack: &str,
+        );
+    }
+
+    impl AssertRetrieved for Base {
+        #[inline]
+        fn assert_retrieved<T: Unit, D: Device + Cursor, S: Shape>(
+            &self,
+            _buf: &Buffer<T, D, S>,
+            _requires_grad: bool,
+            _stack: &str,
+        ) {
+        }
+    }
+
+    impl<Mods: AssertRetrieved> AssertRetrieved for Autograd<'_, Mods> {
+        fn assert_retrieved<T: Unit, D: Device + Cursor, S: Shape>(
+            &self,
+            buf: &Buffer<T, D, S>,
+            requires_grad: bool,
+            stack: &str,
+        ) {
+            let grads = unsafe { &*self.grads.get() };
+            assert!(grads.no_grads_pool.contains_key(&buf.id()), "{stack}");
+            assert_eq!(
+                grads.buf_requires_grad.get(&buf.id()),
+                Some(&requires_grad),
+                "{stack}"
+            );
+            self.modules.assert_retrieved(buf, requires_grad, stack)
+        }
+    }
+
+    impl<Mods: AssertRetrieved> AssertRetrieved for Graph<Mods> {
+        fn assert_retrieved<T: Unit, D: Device + Cursor, S: Shape>(
+            &self,
+            buf: &Buffer<T, D, S>,
+            requires_grad: bool,
+            stack: &str,
+        ) {
+            // retrieved buffers are only tracked with a cursor bumping module
+            if buf.device().cursor() > 0 {
+                let graph_trans = self.graph_trans.borrow();
+                assert!(graph_trans.buf_id_to_idx.contains_key(&buf.id()), "{stack}");
+            }
+            self.modules.assert_retrieved(buf, requires_grad, stack)
+        }
+    }
+
+    macro_rules! impl_pass_down_assert_retrieved {
+        ($($module:ty $(, $generic:ident $(: $bound:path)?)?);*) => {
+            $(
+                impl<Mods: AssertRetrieved $(, $generic $(: $bound)?)?> AssertRetrieved for $module {
+                    #[inline]
+                    fn assert_retrieved<T: Unit, D: Device + Cursor, S: Shape>(
+                        &self,
+                        buf: &Buffer<T, D, S>,
+                        requires_grad: bool,
+                        stack: &str,
+                    ) {
+                        self.modules.assert_retrieved(buf, requires_grad, stack)
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_pass_down_assert_retrieved!(CachedModule<Mods, SD>, SD: Device; Lazy<'_, Mods, T2>, T2; Fork<Mods>);
+
+    macro_rules! assert_retrieve_order {
+        ($($stack:ty),+ $(,)?) => {
+            $({
+                let device = CPU::<$stack>::new();
+                let lhs = device.buffer([1f32, 2., 3.]).require_grad();
+                let rhs = device.buffer([1f32, 2., 3.]).no_grad();
+
+                for _ in device.range(0..2) {
+                    let out: Buffer<f32, _> = device.retrieve(3, (&lhs, &rhs)).unwrap();
+                    device.modules.assert_retrieved(&out, true, stringify!($stack));
+
+                    let out: Buffer<f32, _> = device.retrieve(3, &rhs).unwrap();
+                    device.modules.assert_retrieved(&out, false, stringify!($stack));
+                }
+            })+
+        };
+    }
+
+    #[test]
+    fn test_retrieve_finish_two_module_permutations() {
+        assert_retrieve_order!(
+            Cached<Autograd<Base>>,
+            Cached<Lazy<Base>>,
+            Cached<Fork<Base>>,
+            Cached<Graph<Base>>,
+            Autograd<Cached<Base>>,
+            Autograd<Lazy<Base>>,
+            Autograd<Fork<Base>>,
+            Autograd<Graph<Base>>,
+            Lazy<Cached<Base>>,
+            Lazy<Autograd<Base>>,
+            Lazy<Fork<Base>>,
+            Lazy<Graph<Base>>,
+            Fork<Cached<Base>>,
+            Fork<Autograd<Base>>,
+            Fork<Lazy<Base>>,
+            Fork<Graph<Base>>,
+            Graph<Cached<Base>>,
+            Graph<Autograd<Base>>,
+            Graph<Lazy<Base>>,
+            Graph<Fork<Base>>,
+        );
+    }
+
+    #[test]
+    fn test_retrieve_finish_three_module_permutations() {
+        assert_retrieve_order!(
+            Cached<Autograd<Lazy<Base>>>,
+            Cached<Autograd<Fork<Base>>>,
+            Cached<Autograd<Graph<Base>>>,
+            Cached<Lazy<Autograd<Base>>>,
+            Cached<Lazy<Fork<Base>>>,
+            Cached<Lazy<Graph<Base>>>,
+            Cached<Fork<Autograd<Base>>>,
+            Cached<Fork<Lazy<Base>>>,
+            Cached<Fork<Graph<Base>>>,
+            Cached<Graph<Autograd<Base>>>,
+            Cached<Graph<Lazy<Base>>>,
+            Cached<Graph<Fork<Base>>>,
+            Autograd<Cached<Lazy<Base>>>,
+            Autograd<Cached<Fork<Base>>>,
+            Autograd<Cached<Graph<Base>>>,
+            Autograd<Lazy<Cached<Base>>>,
+            Autograd<Lazy<Fork<Base>>>,
+            Autograd<Lazy<Graph<Base>>>,
+            Autograd<Fork<Cached<Base>>>,
+            Autograd<Fork<Lazy<Base>>>,
+            Autograd<Fork<Graph<Base>>>,
+            Autograd<Graph<Cached<Base>>>,
+            Autograd<Graph<Lazy<Base>>>,
+            Autograd<Graph<Fork<Base>>>,
+            Lazy<Cached<Autograd<Base>>>,
+            Lazy<Cached<Fork<Base>>>,
+            Lazy<Cached<Graph<Base>>>,
+            Lazy<Autograd<Cached<Base>>>,
+            Lazy<Autograd<Fork<Base>>>,
+            Lazy<Autograd<Graph<Base>>>,
+            Lazy<Fork<Cached<Base>>>,
+            Lazy<Fork<Autograd<Base>>>,
+            Lazy<Fork<Graph<Base>>>,
+            Lazy<Graph<Cached<Base>>>,
+            Lazy<Graph<Autograd<Base>>>,
+            Lazy<Graph<Fork<Base>>>,
+            Fork<Cached<Autograd<Base>>>,
+            Fork<Cached<Lazy<Base>>>,
+            Fork<Cached<Graph<Base>>>,
+            Fork<Autograd<Cached<Base>>>,
+            Fork<Autograd<Lazy<Base>>>,
+            Fork<Autograd<Graph<Base>>>,
+            Fork<Lazy<Cached<Base>>>,
+            Fork<Lazy<Autograd<Base>>>,
+            Fork<Lazy<Graph<Base>>>,
+            Fork<Graph<Cached<Base>>>,
+            Fork<Graph<Autograd<Base>>>,
+            Fork<Graph<Lazy<Base>>>,
+            Graph<Cached<Autograd<Base>>>,
+            Graph<Cached<Lazy<Base>>>,
+            Graph<Cached<Fork<Base>>>,
+            Graph<Autograd<Cached<Base>>>,
+            Graph<Autograd<Lazy<Base>>>,
+            Graph<Autograd<Fork<Base>>>,
+            Graph<Lazy<Cached<Base>>>,
+            Graph<Lazy<Autograd<Base>>>,
+            Graph<Lazy<Fork<Base>>>,
+            Graph<Fork<Cached<Base>>>,
+            Graph<Fork<Autograd<Base>>>,
+            Graph<Fork<Lazy<Base>>>,
+        );
+    }
+
+    #[test]
+    fn test_cached_before_autograd_requires_grad() {
+        let device = CPU::<Cached<Autograd<Base>>>::new();
+        let lhs = device.buffer([1f32, 2., 3.]).require_grad();
+        let rhs = device.buffer([1f32, 2., 3.]).no_grad();
+
+        for _ in device.range(0..2) {
+            let out: Buffer<f32, _> = device.retrieve(3, (&lhs, &rhs)).unwrap();
+            assert!(out.requires_grad());
+
+            let out: Buffer<f32, _> = device.retrieve(3, &rhs).unwrap();
+            assert!(!out.requires_grad());
+        }
+    }
+}
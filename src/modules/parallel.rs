@@ -0,0 +1,282 @@
+mod ops;
+mod thread_pool;
+
+pub use ops::*;
+pub use thread_pool::*;
+
+use crate::{
+    impl_remove_layer, impl_wrapped_data, pass_down_add_operation, pass_down_cached_buffers,
+    pass_down_cursor, pass_down_exec_now_module, pass_down_grad_fn, pass_down_replace_buf_module,
+    pass_down_tape_actions, pass_down_use_gpu_or_cpu, AddLayer, Alloc, Buffer, Device, HasModules,
+    Module, OnDropBuffer, OnNewBuffer, Parents, Retrieve, RunModule, Setup, Shape,
+    ThreadPoolActions, Unit,
+};
+
+/// Splits element-wise and slice operations of the [`CPU`](crate::CPU) across a [`ThreadPool`].
+/// The parallel operations are provided by [`ParApplyFunction`], [`ParApplyBinaryFunction`], [`ParUnaryGrad`], [`ParClearBuf`] and [`ParCopySlice`], which require their closures to be [`Send`] and [`Sync`].
+/// The regular operations, e.g. [`ApplyFunction`](crate::ApplyFunction), accept any closure and therefore run on the calling thread.
+/// Operations on fewer elements than the [`ThreadPool::threshold`] run on the calling thread.
+/// The closures of fused operations (see [`UnaryFusing`](crate::UnaryFusing)) are not [`Send`], hence they run on the calling thread.
+/// # Example
+#[cfg_attr(feature = "cpu", doc = "```")]
+#[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+/// use custos::{Base, Buffer, Combiner, Device, ParApplyFunction, Parallel, CPU};
+///
+/// let mut device = CPU::<Parallel<Base>>::new();
+/// device.modules.pool.set_threads(4);
+/// device.modules.pool.set_threshold(1000);
+///
+/// let x: Buffer<f32, _> = device.buffer(vec![2.; 100_000]);
+/// let out = device.par_apply_fn(&x, |x| x.mul(3.));
+/// assert_eq!(out.read(), vec![6.; 100_000]);
+/// ```
+#[derive(Debug, Default)]
+pub struct Parallel<Mods> {
+    pub modules: Mods,
+    pub pool: ThreadPool,
+}
+
+impl<'a, Mods: Module<'a, D>, D: Device + 'a> Module<'a, D> for Parallel<Mods> {
+    type Module = Parallel<Mods::Module>;
+
+    #[inline]
+    fn new() -> Self::Module {
+        Parallel {
+            modules: Mods::new(),
+            pool: ThreadPool::default(),
+        }
+    }
+}
+
+impl<Mods: Setup<D>, D> Setup<D> for Parallel<Mods> {
+    #[inline]
+    fn setup(device: &mut D) -> crate::Result<()> {
+        Mods::setup(device)
+    }
+}
+
+impl_wrapped_data!(Parallel);
+
+impl<Mods> ThreadPoolActions for Parallel<Mods> {
+    #[inline]
+    fn thread_pool(&self) -> Option<&ThreadPool> {
+        Some(&self.pool)
+    }
+}
+
+impl<'a, Mods: OnNewBuffer<'a, T, D, S>, T: Unit, D: Device, S: Shape> OnNewBuffer<'a, T, D, S>
+    for Parallel<Mods>
+{
+    #[inline]
+    unsafe fn on_new_buffer(&self, device: &'a D, new_buf: &Buffer<'a, T, D, S>) {
+        self.modules.on_new_buffer(device, new_buf)
+    }
+}
+
+impl<Mods: OnDropBuffer> OnDropBuffer for Parallel<Mods> {
+    #[inline]
+    fn on_drop_buffer<T: Unit, D: Device, S: Shape>(&self, device: &D, buf: &Buffer<T, D, S>) {
+        self.modules.on_drop_buffer(device, buf)
+    }
+}
+
+impl<T: Unit, Mods: Retrieve<D, T, S>, D, S: Shape> Retrieve<D, T, S> for Parallel<Mods> {
+    #[inline]
+    unsafe fn retrieve<const NUM_PARENTS: usize>(
+        &self,
+        device: &D,
+        len: usize,
+        parents: &impl Parents<NUM_PARENTS>,
+    ) -> crate::Result<Self::Wrap<T, D::Base<T, S>>>
+    where
+        D: Device + Alloc<T>,
+    {
+        self.modules.retrieve(device, len, parents)
+    }
+
+    #[inline]
+    fn on_retrieve_finish<const NUM_PARENTS: usize>(
+        &self,
+        parents: &impl Parents<NUM_PARENTS>,
+        retrieved_buf: &mut Buffer<T, D, S>,
+    ) where
+        D: Alloc<T>,
+    {
+        self.modules.on_retrieve_finish(parents, retrieved_buf)
+    }
+}
+
+impl<Mods: RunModule<D>, D> RunModule<D> for Parallel<Mods> {
+    #[inline]
+    fn run(&self, device: &D) -> crate::Result<()> {
+        self.modules.run(device)
+    }
+}
+
+pass_down_cursor!(Parallel);
+pass_down_add_operation!(Parallel);
+pass_down_exec_now_module!(Parallel);
+pass_down_cached_buffers!(Parallel);
+pass_down_replace_buf_module!(Parallel);
+pass_down_use_gpu_or_cpu!(Parallel);
+pass_down_grad_fn!(Parallel);
+pass_down_tape_actions!(Parallel);
+crate::pass_down_tangent_actions!(Parallel);
+#[cfg(feature = "cached")]
+crate::pass_down_unified_mem_chain!(Parallel);
+
+impl_remove_layer!(Parallel);
+
+impl<NewMods, SD> AddLayer<NewMods, SD> for Parallel<()> {
+    type Wrapped = crate::Parallel<NewMods>;
+
+    #[inline]
+    fn wrap_layer(inner_mods: NewMods) -> Self::Wrapped {
+        Parallel {
+            modules: inner_mods,
+            pool: ThreadPool::default(),
+        }
+    }
+}
+
+impl<Mods> HasModules for Parallel<Mods> {
+    type Mods = Mods;
+
+    #[inline]
+    fn modules(&self) -> &Self::Mods {
+        &self.modules
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ApplyFunction, Base, Buffer, Combiner, Device, ParApplyBinaryFunction, ParApplyFunction,
+        ParClearBuf, ParCopySlice, Parallel, CPU,
+    };
+
+    fn parallel_device() -> CPU<Parallel<Base>> {
+        let mut device = CPU::<Parallel<Base>>::new();
+        device.modules.pool.set_threads(4);
+        device.modules.pool.set_threshold(16);
+        device
+    }
+
+    #[test]
+    fn test_parallel_apply_fn() {
+        let device = parallel_device();
+
+        let x: Buffer<f32, _> = device.buffer((0..1001).map(|x| x as f32).collect::<Vec<_>>());
+        let out = device.par_apply_fn(&x, |x| x.mul(2.).add(1.));
+        assert_eq!(
+            out.read(),
+            (0..1001).map(|x| x as f32 * 2. + 1.).collect::<Vec<_>>()
+        );
+
+        let out = device.par_apply_binary_fn(&x, &out, |x, y| y.sub(x));
+        assert_eq!(
+            out.read(),
+            (0..1001).map(|x| x as f32 + 1.).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_thread_pool_is_sync() {
+        // operations added by `add_parallel_op` access the pool from several threads
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<super::ThreadPool>();
+    }
+
+    #[test]
+    fn test_parallel_apply_fn_non_send_closure() {
+        use core::cell::Cell;
+
+        let device = parallel_device();
+
+        // `Cell` is not `Sync`, therefore the closure runs on the calling thread
+        let factor: &'static Cell<f32> = Box::leak(Box::new(Cell::new(3.)));
+        let x: Buffer<f32, _> = device.buffer(vec![2.; 100]);
+        let out = device.apply_fn(&x, move |x| x.mul(factor.get()));
+        assert_eq!(out.read(), vec![6.; 100]);
+    }
+
+    #[test]
+    fn test_parallel_clear_and_copy_slice() {
+        let device = parallel_device();
+
+        let source = device.buffer((0..100).collect::<Vec<i32>>());
+        let mut dest = device.buffer(vec![-1; 200]);
+        device.par_copy_slice_to(&source, 10..90, &mut dest, 50..130);
+        assert_eq!(&dest[50..130], &source[10..90]);
+        assert_eq!(&dest[..50], &[-1; 50]);
+
+        device.par_clear(&mut dest);
+        assert_eq!(dest.read(), vec![0; 200]);
+    }
+
+    #[test]
+    fn test_parallel_unary_grad() {
+        use crate::ParUnaryGrad;
+
+        let device = parallel_device();
+
+        let lhs: Buffer<f32, _> = device.buffer(vec![3.; 100]);
+        let out_grad: Buffer<f32, _> = device.buffer(vec![2.; 100]);
+        let mut lhs_grad: Buffer<f32, _> = device.buffer(vec![1.; 100]);
+        device.par_add_unary_grad(&lhs, &mut lhs_grad, &out_grad, |x| x.mul(2.));
+        assert_eq!(lhs_grad.read(), vec![13.; 100]);
+    }
+
+    #[cfg(feature = "cached")]
+    #[test]
+    fn test_parallel_cached() {
+        use crate::Cached;
+
+        let mut device = CPU::<Cached<Parallel<Base>>>::new();
+        device.modules.modules.pool.set_threshold(16);
+
+        let x: Buffer<i32, _> = device.buffer(vec![3; 100]);
+        for _ in 0..2 {
+            let out = device.par_apply_fn(&x, |x| x.add(1));
+            assert_eq!(out.read(), vec![4; 100]);
+        }
+    }
+
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_parallel_lazy() {
+        use crate::{Lazy, Run};
+
+        let mut device = CPU::<Lazy<Parallel<Base>>>::new();
+        device.modules.modules.pool.set_threshold(16);
+
+        device.modules.set_exec_threads(2);
+
+        let x: Buffer<f32, _> = device.buffer(vec![3.; 100]);
+        let y: Buffer<f32, _> = device.buffer(vec![2.; 100]);
+        let out = device.par_apply_fn(&x, |x| x.add(1.));
+        // independent of each other, hence executed concurrently
+        let sum = device.par_apply_binary_fn(&x, &y, |x, y| x.add(y));
+        let product = device.par_apply_binary_fn(&x, &y, |x, y| x.mul(y));
+        device.run().unwrap();
+        assert_eq!(out.replace().read(), vec![4.; 100]);
+        assert_eq!(sum.replace().read(), vec![5.; 100]);
+        assert_eq!(product.replace().read(), vec![6.; 100]);
+    }
+
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_parallel_autograd() {
+        use crate::{Autograd, UnaryElementWiseMayGrad};
+
+        let mut device = CPU::<Autograd<Parallel<Base>>>::new();
+        device.modules.modules.pool.set_threshold(16);
+
+        let x: Buffer<f32, _> = device.buffer(vec![3.; 100]).require_grad();
+        let out = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+        assert_eq!(out.read(), vec![9.; 100]);
+
+        out.backward().unwrap();
+        assert_eq!(x.grad().read(), vec![6.; 100]);
+    }
+}
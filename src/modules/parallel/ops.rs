@@ -0,0 +1,285 @@
+use core::ops::{AddAssign, Deref, DerefMut, Index, Range, RangeBounds};
+
+use crate::{
+    bounds_to_range,
+    cpu_stack_ops::{add_unary_grad, apply_binary_fn_slice, apply_fn_slice, clear_slice},
+    op_hint::{binary, unary},
+    AddOperation, AnyOp, Buffer, Device, Eval, MayTangentActions, MayToCLSource, OnDropBuffer,
+    Parents, Resolve, Retrieve, Retriever, SetOpHint, Shape, ThreadPoolActions, ToVal, TwoWay,
+    Unit, CPU,
+};
+
+/// Applies a function to a buffer on the [`ThreadPool`](crate::ThreadPool) of the [`Parallel`](crate::Parallel) module and returns a new buffer.
+/// Unlike [`ApplyFunction`](crate::ApplyFunction), the function must be [`Send`] and [`Sync`].
+pub trait ParApplyFunction<T: Unit, S: Shape = (), D: Device = Self>: Device {
+    /// Applies a function to a buffer and returns a new buffer.
+    /// Without a thread pool or below its threshold, the function runs on the calling thread.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{Base, Combiner, Device, ParApplyFunction, Parallel, CPU};
+    ///
+    /// let device = CPU::<Parallel<Base>>::new();
+    /// let a = device.buffer([1., 2., 3., 3., 2., 1.]);
+    ///
+    /// let out = device.par_apply_fn(&a, |x| x.mul(2.));
+    /// assert_eq!(out.read(), [2., 4., 6., 6., 4., 2.]);
+    /// ```
+    fn par_apply_fn<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) -> Buffer<'_, T, Self, S>
+    where
+        F: TwoWay<T> + 'static;
+}
+
+/// Applies a function to two buffers element-wise on the [`ThreadPool`](crate::ThreadPool) of the [`Parallel`](crate::Parallel) module and returns a new buffer.
+/// Unlike [`ApplyBinaryFunction`](crate::ApplyBinaryFunction), the function must be [`Send`] and [`Sync`].
+pub trait ParApplyBinaryFunction<T: Unit, S: Shape = (), D: Device = Self>: Device {
+    /// Applies a function to two buffers element-wise and returns a new buffer.
+    /// Without a thread pool or below its threshold, the function runs on the calling thread.
+    fn par_apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) -> Buffer<'_, T, Self, S>
+    where
+        F: TwoWay<T> + 'static;
+}
+
+/// Writes the unary gradient (with chainrule) to the lhs_grad buffer on the [`ThreadPool`](crate::ThreadPool) of the [`Parallel`](crate::Parallel) module.
+/// Unlike [`UnaryGrad`](crate::UnaryGrad), the gradient function must be [`Send`] and [`Sync`].
+pub trait ParUnaryGrad<T: Unit, S: Shape = (), D: Device = Self>: Device {
+    /// Write the unary gradient to the lhs_grad buffer.
+    fn par_add_unary_grad<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        lhs_grad: &mut Buffer<T, D, S>,
+        out_grad: &Buffer<T, D, S>,
+        lhs_grad_fn: impl Fn(Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) where
+        F: Eval<T> + MayToCLSource;
+}
+
+/// Sets all elements of a buffer to zero on the [`ThreadPool`](crate::ThreadPool) of the [`Parallel`](crate::Parallel) module.
+pub trait ParClearBuf<T: Unit, S: Shape = (), D: Device = Self> {
+    /// Sets all elements of the buffer to zero.
+    fn par_clear(&self, buf: &mut Buffer<T, D, S>);
+}
+
+/// Copies a slice of a buffer on the [`ThreadPool`](crate::ThreadPool) of the [`Parallel`](crate::Parallel) module.
+pub trait ParCopySlice<T: Unit, D: Device = Self>: Sized + Device {
+    /// Copies the `source_range` of `source` to the `dest_range` of `dest`.
+    fn par_copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+        &self,
+        source: &Buffer<T, D>,
+        source_range: SR,
+        dest: &mut Buffer<T, Self>,
+        dest_range: DR,
+    );
+}
+
+/// Calls `f` with the offset and the chunk for every chunk of `out`.
+/// The chunks are processed by the thread pool of the [`Parallel`](crate::Parallel) module, if there is one.
+#[inline]
+fn for_each_chunk<D: ThreadPoolActions, T: Send>(
+    device: &D,
+    out: &mut [T],
+    f: impl Fn(usize, &mut [T]) + Sync,
+) {
+    match device.thread_pool() {
+        Some(pool) => pool.for_each_chunk(out, f),
+        None => f(0, out),
+    }
+}
+
+/// Adds an element-wise operation, see [`AddOperation::add_parallel_op`].
+/// With the `forward` feature, `op` may access the tangents of the device, therefore it is not executed concurrently with other operations.
+///
+/// # Safety
+/// Apart from the tangents, the contract of [`AddOperation::add_parallel_op`] applies.
+#[inline]
+unsafe fn add_chunked_op<D: AddOperation, Args: Parents<N> + AnyOp, const N: usize>(
+    device: &D,
+    args: Args,
+    op: impl for<'b> Fn(Args::Replicated<'b>) -> crate::Result<()> + Send + Sync + 'static,
+) -> crate::Result<()> {
+    #[cfg(feature = "forward")]
+    return device.add_op(args, op);
+
+    #[cfg(not(feature = "forward"))]
+    unsafe {
+        device.add_parallel_op(args, op)
+    }
+}
+
+impl<Mods, T, D, S> ParApplyFunction<T, S, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + 'static,
+    Self: MayTangentActions + ThreadPoolActions,
+    T: Unit + Copy + Default + ToVal + Send + Sync + 'static,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn par_apply_fn<F>(
+        &self,
+        buf: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) -> Buffer<'_, T, Self, S>
+    where
+        F: TwoWay<T> + 'static,
+    {
+        let mut out = self.retrieve(buf.len(), buf).unwrap();
+
+        // safety: apart from the tangents, only the data of the buffers and the thread pool are accessed
+        unsafe {
+            add_chunked_op(self, (&mut out, buf), move |(out, buf)| {
+                #[cfg(feature = "forward")]
+                if let Some(mut tangents) = crate::TangentActions::tangents_mut(out.device()) {
+                    if let Some((tangent, out_tangent)) = tangents.tangent_pair(buf, out) {
+                        crate::cpu_stack_ops::apply_fn_tangent_slice(
+                            buf,
+                            tangent,
+                            out,
+                            out_tangent,
+                            f,
+                        );
+                        return Ok(());
+                    }
+                }
+                let x: &[T] = buf;
+                for_each_chunk(out.device(), out, |offset, out| {
+                    apply_fn_slice(&x[offset..offset + out.len()], out, f)
+                });
+                Ok(())
+            })
+        }
+        .unwrap();
+
+        self.set_op_hint(unary(f));
+
+        out
+    }
+}
+
+impl<Mods, T, D, S> ParApplyBinaryFunction<T, S, D> for CPU<Mods>
+where
+    Mods: Retrieve<Self, T, S> + AddOperation + SetOpHint<T> + 'static,
+    Self: ThreadPoolActions,
+    T: Unit + Copy + Default + ToVal + Send + Sync + 'static,
+    D: Device + 'static,
+    D::Base<T, S>: Deref<Target = [T]>,
+    S: Shape,
+{
+    fn par_apply_binary_fn<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        rhs: &Buffer<T, D, S>,
+        f: impl Fn(Resolve<T>, Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) -> Buffer<'_, T, Self, S>
+    where
+        F: TwoWay<T> + 'static,
+    {
+        let mut out = self.retrieve(lhs.len(), (lhs, rhs)).unwrap();
+
+        // safety: only the data of the buffers and the thread pool are accessed
+        unsafe {
+            self.add_parallel_op((&mut out, lhs, rhs), move |(out, lhs, rhs)| {
+                let (lhs, rhs): (&[T], &[T]) = (lhs, rhs);
+                for_each_chunk(out.device(), out, |offset, out| {
+                    let range = offset..offset + out.len();
+                    apply_binary_fn_slice(&lhs[range.clone()], &rhs[range], out, f)
+                });
+                Ok(())
+            })
+        }
+        .unwrap();
+
+        self.set_op_hint(binary(f));
+
+        out
+    }
+}
+
+impl<Mods, T, D, S> ParUnaryGrad<T, S, D> for CPU<Mods>
+where
+    Mods: AddOperation + OnDropBuffer,
+    Self: ThreadPoolActions,
+    T: Unit + AddAssign + Copy + core::ops::Mul<Output = T> + Send + Sync + 'static,
+    S: Shape,
+    D: Device + ThreadPoolActions + 'static,
+    D::Base<T, S>: Deref<Target = [T]> + DerefMut<Target = [T]>,
+{
+    #[inline]
+    fn par_add_unary_grad<F>(
+        &self,
+        lhs: &Buffer<T, D, S>,
+        lhs_grad: &mut Buffer<T, D, S>,
+        out: &Buffer<T, D, S>,
+        lhs_grad_fn: impl Fn(Resolve<T>) -> F + Copy + Send + Sync + 'static,
+    ) where
+        F: Eval<T> + MayToCLSource,
+    {
+        // safety: only the data of the buffers and the thread pool are accessed
+        unsafe {
+            self.add_parallel_op::<_, 3>((lhs, lhs_grad, out), move |(lhs, lhs_grad, out)| {
+                let (lhs, out): (&[T], &[T]) = (lhs, out);
+                for_each_chunk(lhs_grad.device(), lhs_grad, |offset, lhs_grad| {
+                    let range = offset..offset + lhs_grad.len();
+                    add_unary_grad(&lhs[range.clone()], &out[range], lhs_grad, lhs_grad_fn);
+                });
+                Ok(())
+            })
+        }
+        .unwrap();
+    }
+}
+
+impl<Mods, T, D, S> ParClearBuf<T, S, D> for CPU<Mods>
+where
+    Mods: OnDropBuffer,
+    Self: ThreadPoolActions,
+    T: Unit + Default + Send,
+    D: Device,
+    D::Base<T, S>: DerefMut<Target = [T]>,
+    S: Shape,
+{
+    #[inline]
+    fn par_clear(&self, buf: &mut Buffer<T, D, S>) {
+        for_each_chunk(self, buf, |_, buf| clear_slice(buf));
+    }
+}
+
+impl<Mods, T, D> ParCopySlice<T, D> for CPU<Mods>
+where
+    [T]: Index<Range<usize>, Output = [T]>,
+    Mods: OnDropBuffer,
+    Self: ThreadPoolActions,
+    T: Unit + Copy + Send + Sync,
+    D: Device,
+    D::Base<T, ()>: Deref<Target = [T]>,
+{
+    fn par_copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
+        &self,
+        source: &Buffer<T, D>,
+        source_range: SR,
+        dest: &mut Buffer<T, Self>,
+        dest_range: DR,
+    ) {
+        let source_range = bounds_to_range(source_range, source.len());
+        let dest_range = bounds_to_range(dest_range, dest.len());
+
+        assert_eq!(
+            source_range.end - source_range.start,
+            dest_range.end - dest_range.start,
+        );
+
+        let source = &source[source_range];
+        for_each_chunk(self, &mut dest[dest_range], |offset, dest| {
+            dest.copy_from_slice(&source[offset..offset + dest.len()])
+        });
+    }
+}
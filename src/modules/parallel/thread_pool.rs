@@ -0,0 +1,264 @@
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Condvar, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// The number of elements from which on operations are split across threads.
+pub const DEFAULT_PARALLEL_THRESHOLD: usize = 1 << 15;
+
+/// A pool of std threads that executes slice operations in chunks.
+/// The calling thread processes a chunk as well, hence `threads - 1` threads are spawned.
+pub struct ThreadPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    threshold: usize,
+}
+
+impl core::fmt::Debug for ThreadPool {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ThreadPool")
+            .field("threads", &self.threads())
+            .field("threshold", &self.threshold)
+            .finish()
+    }
+}
+
+impl Default for ThreadPool {
+    /// Uses the number of threads given by the environment variable `CUSTOS_CPU_THREADS`.
+    /// If it is not set, [`std::thread::available_parallelism`] is used.
+    fn default() -> Self {
+        let threads = std::env::var("CUSTOS_CPU_THREADS")
+            .ok()
+            .and_then(|threads| threads.parse().ok())
+            .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(1);
+        ThreadPool::new(threads, DEFAULT_PARALLEL_THRESHOLD)
+    }
+}
+
+impl ThreadPool {
+    /// Operations on fewer than `threshold` elements run on the calling thread.
+    pub fn new(threads: usize, threshold: usize) -> Self {
+        let mut pool = ThreadPool {
+            sender: None,
+            workers: Vec::new(),
+            threshold,
+        };
+        pool.set_threads(threads);
+        pool
+    }
+
+    /// The number of threads executing an operation, including the calling thread.
+    #[inline]
+    pub fn threads(&self) -> usize {
+        self.workers.len() + 1
+    }
+
+    #[inline]
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    #[inline]
+    pub fn set_threshold(&mut self, threshold: usize) {
+        self.threshold = threshold;
+    }
+
+    /// Replaces the spawned threads.
+    pub fn set_threads(&mut self, threads: usize) {
+        self.join();
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        self.workers = (1..threads.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                std::thread::spawn(move || loop {
+                    let job = receiver.lock().unwrap().recv();
+                    let Ok(job) = job else {
+                        break;
+                    };
+                    // a panic is reported to the calling thread by the latch
+                    let _ = catch_unwind(AssertUnwindSafe(job));
+                })
+            })
+            .collect();
+        self.sender = Some(sender);
+    }
+
+    fn join(&mut self) {
+        // closes the channel, hence the workers return
+        self.sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+
+    /// Calls `f` with the offset and the chunk for every chunk of `slice`.
+    /// The chunks are processed in parallel if `slice` contains at least [`ThreadPool::threshold`] elements.
+    /// # Panics
+    /// If `f` panics for any chunk.
+    pub fn for_each_chunk<T: Send>(&self, slice: &mut [T], f: impl Fn(usize, &mut [T]) + Sync) {
+        let threads = self.threads();
+        if threads == 1 || slice.len() < self.threshold.max(threads) {
+            f(0, slice);
+            return;
+        }
+
+        let chunk_len = slice.len().div_ceil(threads);
+        let latch = Arc::new(Latch::default());
+        let sender = self.sender.as_ref().unwrap();
+
+        let f = &f;
+        let mut chunks = slice.chunks_mut(chunk_len).enumerate();
+        let (_, first) = chunks.next().unwrap();
+
+        // waits for the jobs even if `f` panics on the calling thread
+        let wait = WaitGuard(&latch);
+
+        for (idx, chunk) in chunks {
+            let done = Done(latch.clone());
+            latch.add();
+            let job: Box<dyn FnOnce() + Send + '_> = Box::new(move || {
+                let _done = done;
+                f(idx * chunk_len, chunk)
+            });
+            // Safety: the wait guard blocks until all jobs finished, hence the borrowed data outlives the job.
+            let job: Job = unsafe { core::mem::transmute(job) };
+            sender.send(job).unwrap();
+        }
+
+        f(0, first);
+        drop(wait);
+
+        assert!(
+            !latch.panicked.load(Ordering::Acquire),
+            "An operation panicked on a thread of the thread pool."
+        );
+    }
+}
+
+impl Drop for ThreadPool {
+    #[inline]
+    fn drop(&mut self) {
+        self.join()
+    }
+}
+
+#[derive(Default)]
+struct Latch {
+    remaining: Mutex<usize>,
+    finished: Condvar,
+    panicked: AtomicBool,
+}
+
+impl Latch {
+    #[inline]
+    fn add(&self) {
+        *self.remaining.lock().unwrap() += 1;
+    }
+
+    fn wait(&self) {
+        let mut remaining = self.remaining.lock().unwrap();
+        while *remaining > 0 {
+            remaining = self.finished.wait(remaining).unwrap();
+        }
+    }
+}
+
+/// Counts down the latch when a job is finished or unwinds.
+struct Done(Arc<Latch>);
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.panicked.store(true, Ordering::Release);
+        }
+        let mut remaining = self.0.remaining.lock().unwrap();
+        *remaining -= 1;
+        if *remaining == 0 {
+            self.0.finished.notify_all();
+        }
+    }
+}
+
+struct WaitGuard<'a>(&'a Latch);
+
+impl Drop for WaitGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::ThreadPool;
+
+    #[test]
+    fn test_for_each_chunk_covers_slice() {
+        let pool = ThreadPool::new(4, 8);
+        let mut data = vec![0usize; 103];
+        pool.for_each_chunk(&mut data, |offset, chunk| {
+            for (idx, value) in chunk.iter_mut().enumerate() {
+                *value = offset + idx;
+            }
+        });
+        assert_eq!(data, (0..103).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_for_each_chunk_threshold() {
+        let pool = ThreadPool::new(4, 100);
+        let calls = AtomicUsize::new(0);
+
+        pool.for_each_chunk(&mut [0; 99], |_, _| {
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(calls.swap(0, Ordering::Relaxed), 1);
+
+        pool.for_each_chunk(&mut [0; 100], |_, _| {
+            calls.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn test_set_threads() {
+        let mut pool = ThreadPool::new(2, 0);
+        assert_eq!(pool.threads(), 2);
+        pool.set_threads(1);
+        assert_eq!(pool.threads(), 1);
+
+        let mut data = [1; 10];
+        pool.for_each_chunk(&mut data, |_, chunk| chunk.fill(2));
+        assert_eq!(data, [2; 10]);
+    }
+
+    #[test]
+    fn test_worker_panic_is_propagated() {
+        let pool = ThreadPool::new(2, 0);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.for_each_chunk(&mut [0; 10], |offset, _| {
+                if offset > 0 {
+                    panic!("worker");
+                }
+            })
+        }));
+        assert!(result.is_err());
+
+        // the worker survives the panic
+        let mut data = [0; 10];
+        pool.for_each_chunk(&mut data, |_, chunk| chunk.fill(1));
+        assert_eq!(data, [1; 10]);
+    }
+}
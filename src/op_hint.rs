@@ -5,6 +5,8 @@ use crate::Resolve;
 pub enum OpHint<T> {
     #[cfg(feature = "std")]
     Unary(std::rc::Rc<dyn Fn(Resolve<T>) -> Box<dyn crate::TwoWay<T>>>),
+    #[cfg(feature = "std")]
+    Binary(crate::BinaryFn<T>),
     None,
     UnaryFused,
     PhantomData(PhantomData<T>),
@@ -16,6 +18,8 @@ impl<T> Debug for OpHint<T> {
         match self {
             #[cfg(feature = "std")]
             OpHint::Unary(_) => write!(f, "Unary(...)"),
+            #[cfg(feature = "std")]
+            OpHint::Binary(_) => write!(f, "Binary(...)"),
             OpHint::None => write!(f, "None"),
             OpHint::PhantomData(_) => write!(f, "PhantomData"),
             OpHint::UnaryFused => write!(f, "UnaryFused"),
@@ -23,6 +27,26 @@ impl<T> Debug for OpHint<T> {
     }
 }
 
+impl<T> OpHint<T> {
+    /// Returns a human readable name of the hinted operation.
+    /// Unary and binary hints are resolved to their OpenCL C source, using `x` and `y` as variable names.
+    #[cfg(feature = "std")]
+    pub fn name(&self) -> String
+    where
+        T: Default,
+    {
+        match self {
+            OpHint::Unary(op) => op(Resolve::with_marker("x")).to_cl_source(),
+            OpHint::Binary(op) => {
+                op(Resolve::with_marker("x"), Resolve::with_marker("y")).to_cl_source()
+            }
+            OpHint::None => "None".to_string(),
+            OpHint::UnaryFused => "UnaryFused".to_string(),
+            OpHint::PhantomData(_) => "PhantomData".to_string(),
+        }
+    }
+}
+
 #[cfg(feature = "std")]
 pub fn unary<T, O: crate::TwoWay<T> + 'static>(
     op: impl Fn(Resolve<T>) -> O + 'static,
@@ -34,10 +58,38 @@ pub fn unary<T, O: crate::TwoWay<T> + 'static>(
     OpHint::Unary(std::rc::Rc::new(dyn_op))
 }
 
+#[cfg(feature = "std")]
+pub fn binary<T, O: crate::TwoWay<T> + 'static>(
+    op: impl Fn(Resolve<T>, Resolve<T>) -> O + 'static,
+) -> OpHint<T> {
+    let dyn_op = move |lhs: Resolve<T>, rhs: Resolve<T>| {
+        let op: Box<dyn crate::TwoWay<T>> = Box::new(op(lhs, rhs));
+        op
+    };
+    OpHint::Binary(std::rc::Rc::new(dyn_op))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{op_hint::OpHint, Resolve};
 
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "lazy")]
+    #[test]
+    fn test_op_hint_binary() {
+        use crate::{ApplyBinaryFunction, Base, Combiner, Device, Lazy, CPU};
+
+        let dev = CPU::<Lazy<Base>>::new();
+
+        let lhs = dev.buffer([1., 2., 3.]);
+        let rhs = dev.buffer([4., 5., 6.]);
+        let _out = dev.apply_binary_fn(&lhs, &rhs, |lhs, rhs| lhs.add(rhs).mul(lhs));
+
+        let ops = &dev.modules.graph.borrow().operations;
+        assert!(matches!(ops[0].op_hint, OpHint::Binary(_)));
+        assert_eq!(ops[0].op_hint.name(), "((x + y) * x)");
+    }
+
     #[cfg(feature = "cpu")]
     #[cfg(feature = "lazy")]
     #[test]
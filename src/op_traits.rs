@@ -22,10 +22,31 @@ pub trait ClearBuf<T: Unit, S: Shape = (), D: Device = Self> {
 
 pub trait ZeroGrad<T: Unit>: Device {
     fn zero_grad<S: Shape>(&self, data: &mut Self::Base<T, S>);
+
+    /// Returns the sum of the squared elements of a gradient.
+    /// Used to compute the global gradient norm.
+    #[inline]
+    fn grad_squared_norm<S: Shape>(&self, _data: &Self::Base<T, S>) -> crate::Result<f64>
+    where
+        T: 'static,
+    {
+        Err(crate::DeviceError::GradOpUnsupported.into())
+    }
+
+    /// Multiplies every element of a gradient by `factor`.
+    /// Used to clip and average gradients.
+    #[inline]
+    fn scale_grad<S: Shape>(&self, _data: &mut Self::Base<T, S>, _factor: f64) -> crate::Result<()>
+    where
+        T: 'static,
+    {
+        Err(crate::DeviceError::GradOpUnsupported.into())
+    }
 }
 
 /// Trait for copying a slice of a buffer, to implement the slice() operation.
-pub trait CopySlice<T: Unit, D: Device = Self>: Sized + Device {
+/// The shape `S` of the source and destination buffers is only relevant for devices with shape dependent buffers, e.g. [`Stack`](crate::Stack).
+pub trait CopySlice<T: Unit, D: Device = Self, S: Shape = ()>: Sized + Device {
     /// Copy a slice of the given buffer into a new buffer.
     /// # Example
     ///
@@ -44,11 +65,11 @@ pub trait CopySlice<T: Unit, D: Device = Self>: Sized + Device {
         range: R,
     ) -> Buffer<'a, T, Self>
     where
-        Self: Alloc<T> + OnDropBuffer + OnNewBuffer<'a, T, Self, ()>,
+        Self: CopySlice<T, D> + Alloc<T> + OnDropBuffer + OnNewBuffer<'a, T, Self, ()>,
     {
         let range = bounds_to_range(range, buf.len());
         let mut copied = Buffer::new(self, range.end - range.start);
-        self.copy_slice_to(buf, range, &mut copied, ..);
+        CopySlice::<T, D>::copy_slice_to(self, buf, range, &mut copied, ..);
         copied
     }
 
@@ -67,9 +88,9 @@ pub trait CopySlice<T: Unit, D: Device = Self>: Sized + Device {
     /// ```
     fn copy_slice_to<SR: RangeBounds<usize>, DR: RangeBounds<usize>>(
         &self,
-        source: &Buffer<T, D>,
+        source: &Buffer<T, D, S>,
         source_range: SR,
-        dest: &mut Buffer<T, Self>,
+        dest: &mut Buffer<T, Self, S>,
         dest_range: DR,
     );
 
@@ -94,8 +115,8 @@ pub trait CopySlice<T: Unit, D: Device = Self>: Sized + Device {
     ///```
     fn copy_slice_all<I: IntoIterator<Item = (Range<usize>, Range<usize>)>>(
         &self,
-        source: &Buffer<T, D>,
-        dest: &mut Buffer<T, Self>,
+        source: &Buffer<T, D, S>,
+        dest: &mut Buffer<T, Self, S>,
         ranges: I,
     );
 }
@@ -0,0 +1,196 @@
+//! Optimizers that update parameters with the gradients computed by the [`Autograd`](crate::Autograd) module.
+//!
+//! The update of a parameter and its state buffers is described by an [`UpdateRule`] and executed as one fused kernel via [`ApplyUpdate`].
+//! # Example
+#![cfg_attr(feature = "cpu", doc = "```")]
+#![cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+//! use custos::{optim::{Optimizer, Sgd, SgdConfig}, Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU};
+//!
+//! let device = CPU::<Autograd<Base>>::new();
+//! let mut weights = device.buffer([1., 2., 3.]).require_grad();
+//! let mut sgd = Sgd::new(&device, &[&weights], SgdConfig::new(0.1));
+//!
+//! // loss = weights^2 -> grad = 2 * weights
+//! let loss = device.unary_ew(&weights, |x| x.mul(x), |x| x.mul(2.));
+//! loss.backward().unwrap();
+//!
+//! sgd.step(&mut [&mut weights]).unwrap();
+//! assert_eq!(weights.read(), [0.8, 1.6, 2.4]);
+//! // gradients are zeroed after each step
+//! assert_eq!(weights.grad().read(), [0.; 3]);
+//! ```
+
+mod adam;
+mod sgd;
+mod update;
+
+pub use adam::*;
+pub use sgd::*;
+pub use update::*;
+
+use crate::{
+    Alloc, Buffer, Device, DeviceError, GradActions, HasId, Id, OnNewBuffer, Read, Shape, Unit,
+    WriteBuf,
+};
+
+/// Updates parameters with their gradients.
+pub trait Optimizer<'a, T: Unit, D: Device, S: Shape = ()> {
+    /// Updates the parameters and zeroes all gradients afterwards via [`Gradients::zero_grad`](crate::Gradients::zero_grad).
+    /// The parameters must be passed in the same order as on creation. Parameters without a gradient are skipped.
+    ///
+    /// If gradients are accumulated (see [`Gradients::set_accumulation_steps`](crate::Gradients::set_accumulation_steps)),
+    /// nothing happens until enough backward passes were accumulated. The parameters are then updated with the mean gradients.
+    fn step(&mut self, params: &mut [&mut Buffer<'a, T, D, S>]) -> crate::Result<()>;
+
+    /// Returns the amount of performed steps.
+    fn steps(&self) -> usize;
+}
+
+/// The serialisable state of an optimizer.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptimizerState<T> {
+    /// The amount of performed steps.
+    pub steps: usize,
+    /// The contents of the state buffers of every parameter.
+    pub buffers: Vec<Vec<Vec<T>>>,
+}
+
+/// The parameters of an optimizer and their state buffers, allocated on the device of the parameters.
+pub(crate) struct ParamStates<'a, T: Unit, D: Device, S: Shape> {
+    device: &'a D,
+    ids: Vec<Id>,
+    states: Vec<Vec<Buffer<'a, T, D, S>>>,
+    steps: usize,
+}
+
+impl<'a, T: Unit, D: Device, S: Shape> ParamStates<'a, T, D, S> {
+    pub fn new(device: &'a D, params: &[&Buffer<'a, T, D, S>], states: usize) -> Self
+    where
+        T: Default + Clone,
+        D: Alloc<T> + OnNewBuffer<'a, T, D, S>,
+    {
+        ParamStates {
+            device,
+            ids: params.iter().map(|param| param.id()).collect(),
+            states: params
+                .iter()
+                .map(|param| {
+                    (0..states)
+                        .map(|_| Buffer::from_vec(device, vec![T::default(); param.len()]))
+                        .collect()
+                })
+                .collect(),
+            steps: 0,
+        }
+    }
+
+    /// Applies `rule` to every parameter with a gradient and zeroes all gradients afterwards.
+    /// `params` must have been validated with [`ParamStates::check_params`].
+    pub fn update(
+        &mut self,
+        rule: &UpdateRule<T>,
+        params: &mut [&mut Buffer<'a, T, D, S>],
+        hyper: &[T],
+    ) -> crate::Result<()>
+    where
+        T: 'static,
+        D: ApplyUpdate<T, S> + GradActions + Alloc<T> + 'static,
+    {
+        if let Some(gradients) = unsafe { self.device.gradients_mut() } {
+            gradients.average_accumulated_grads()?;
+        }
+
+        for (param, states) in params.iter_mut().zip(&mut self.states) {
+            let Some(grad) = param.try_grad() else {
+                continue;
+            };
+            self.device.apply_update(rule, param, grad, states, hyper)?;
+        }
+
+        if let Some(gradients) = unsafe { self.device.gradients_mut() } {
+            gradients.zero_grad();
+        }
+        Ok(())
+    }
+
+    /// Returns [`DeviceError::OptimizerParamMismatch`] if `params` are not the parameters the optimizer was created with.
+    pub fn check_params(&self, params: &[&mut Buffer<'a, T, D, S>]) -> crate::Result<()> {
+        if params.len() != self.ids.len()
+            || params
+                .iter()
+                .zip(&self.ids)
+                .any(|(param, id)| param.id() != *id)
+        {
+            return Err(DeviceError::OptimizerParamMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if more backward passes must be accumulated before the next update.
+    #[inline]
+    pub fn is_accumulating(&self) -> bool
+    where
+        D: GradActions,
+    {
+        unsafe { self.device.gradients() }
+            .is_some_and(|gradients| !gradients.is_accumulation_complete())
+    }
+
+    /// Starts a new step and returns its number, starting at 1.
+    #[inline]
+    pub fn next_step(&mut self) -> usize {
+        self.steps += 1;
+        self.steps
+    }
+
+    #[inline]
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn state(&self) -> OptimizerState<T>
+    where
+        T: Default + Clone,
+        D: Read<T, S>,
+    {
+        OptimizerState {
+            steps: self.steps,
+            buffers: self
+                .states
+                .iter()
+                .map(|states| states.iter().map(|buf| buf.read_to_vec()).collect())
+                .collect(),
+        }
+    }
+
+    pub fn load_state(&mut self, state: &OptimizerState<T>) -> crate::Result<()>
+    where
+        D: WriteBuf<T, S, D>,
+    {
+        let matches = state.buffers.len() == self.states.len()
+            && state
+                .buffers
+                .iter()
+                .zip(&self.states)
+                .all(|(vals, states)| {
+                    vals.len() == states.len()
+                        && vals
+                            .iter()
+                            .zip(states)
+                            .all(|(vals, buf)| vals.len() == buf.len())
+                });
+
+        if !matches {
+            return Err(DeviceError::OptimizerParamMismatch.into());
+        }
+
+        for (vals, states) in state.buffers.iter().zip(&mut self.states) {
+            for (vals, buf) in vals.iter().zip(states) {
+                buf.write(vals);
+            }
+        }
+        self.steps = state.steps;
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{
+    Alloc, Buffer, Combiner, Device, Float, GradActions, OnNewBuffer, Read, Shape, Unit, WriteBuf,
+};
+
+use super::{ApplyUpdate, Optimizer, OptimizerState, ParamStates, UpdateRule};
+
+/// The hyperparameters of [`Adam`] and [`AdamW`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdamConfig<T> {
+    pub lr: T,
+    pub beta1: T,
+    pub beta2: T,
+    pub eps: T,
+    /// [`Adam`] adds the L2 penalty to the gradient, [`AdamW`] decays the parameters directly.
+    pub weight_decay: T,
+}
+
+impl<T: Float> AdamConfig<T> {
+    /// Uses the learning rate `lr`, betas of `(0.9, 0.999)`, an epsilon of `1e-8` and no weight decay.
+    #[inline]
+    pub fn new(lr: T) -> Self {
+        AdamConfig {
+            lr,
+            beta1: T::from_f64(0.9),
+            beta2: T::from_f64(0.999),
+            eps: T::from_f64(1e-8),
+            weight_decay: T::zero(),
+        }
+    }
+
+    #[inline]
+    pub fn with_betas(mut self, beta1: T, beta2: T) -> Self {
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        self
+    }
+
+    #[inline]
+    pub fn with_eps(mut self, eps: T) -> Self {
+        self.eps = eps;
+        self
+    }
+
+    #[inline]
+    pub fn with_weight_decay(mut self, weight_decay: T) -> Self {
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    /// Computes the hyperparameters of the update rule for the given step (starting at 1).
+    /// The bias corrections are folded into the learning rate and epsilon.
+    fn hyper(&self, step: usize, decoupled: bool) -> [T; 8] {
+        let one = T::one();
+        let bias_correction1 = one - self.beta1.powi(step as i32);
+        let bias_correction2 = Float::sqrt(&(one - self.beta2.powi(step as i32)));
+
+        let (l2_decay, param_decay) = if decoupled {
+            (T::zero(), one - self.lr * self.weight_decay)
+        } else {
+            (self.weight_decay, one)
+        };
+
+        [
+            self.beta1,
+            one - self.beta1,
+            self.beta2,
+            one - self.beta2,
+            self.lr * bias_correction2 / bias_correction1,
+            self.eps * bias_correction2,
+            l2_decay,
+            param_decay,
+        ]
+    }
+}
+
+/// The update rule shared by [`Adam`] and [`AdamW`].
+fn adam_rule<T: Float>() -> UpdateRule<T> {
+    // hyperparameters: [beta1, 1 - beta1, beta2, 1 - beta2, corrected lr, corrected eps, l2 decay, param decay]
+    UpdateRule::new(2, 8)
+        .state(0, |v| {
+            v.hyper[0]
+                .mul(v.states[0])
+                .add(v.hyper[1].mul(v.grad.add(v.hyper[6].mul(v.param))))
+        })
+        .state(1, |v| {
+            let grad = || v.grad.add(v.hyper[6].mul(v.param));
+            v.hyper[2]
+                .mul(v.states[1])
+                .add(v.hyper[3].mul(grad()).mul(grad()))
+        })
+        .param(|v| {
+            v.hyper[7].mul(v.param).sub(
+                v.hyper[4]
+                    .mul(v.states[0])
+                    .div(v.states[1].sqrt().add(v.hyper[5])),
+            )
+        })
+}
+
+/// The Adam optimizer. The first and second moments are stored in two state buffers per parameter.
+pub struct Adam<'a, T: Unit, D: Device, S: Shape = ()> {
+    config: AdamConfig<T>,
+    decoupled: bool,
+    rule: UpdateRule<T>,
+    params: ParamStates<'a, T, D, S>,
+}
+
+impl<'a, T, D, S> Adam<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: Device + Alloc<T> + OnNewBuffer<'a, T, D, S>,
+    S: Shape,
+{
+    #[inline]
+    pub fn new(device: &'a D, params: &[&Buffer<'a, T, D, S>], config: AdamConfig<T>) -> Self {
+        Self::with_decoupling(device, params, config, false)
+    }
+
+    fn with_decoupling(
+        device: &'a D,
+        params: &[&Buffer<'a, T, D, S>],
+        config: AdamConfig<T>,
+        decoupled: bool,
+    ) -> Self {
+        let rule = adam_rule();
+        Adam {
+            params: ParamStates::new(device, params, rule.states()),
+            config,
+            decoupled,
+            rule,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &AdamConfig<T> {
+        &self.config
+    }
+
+    /// Changes the learning rate, e.g. for learning rate schedules.
+    #[inline]
+    pub fn set_lr(&mut self, lr: T) {
+        self.config.lr = lr;
+    }
+
+    /// Returns the step count and the moment buffers.
+    #[inline]
+    pub fn state(&self) -> OptimizerState<T>
+    where
+        D: Read<T, S>,
+    {
+        self.params.state()
+    }
+
+    /// Loads a state previously returned by [`Adam::state`].
+    #[inline]
+    pub fn load_state(&mut self, state: &OptimizerState<T>) -> crate::Result<()>
+    where
+        D: WriteBuf<T, S, D>,
+    {
+        self.params.load_state(state)
+    }
+}
+
+impl<'a, T, D, S> Optimizer<'a, T, D, S> for Adam<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: ApplyUpdate<T, S> + GradActions + Alloc<T> + 'static,
+    S: Shape,
+{
+    fn step(&mut self, params: &mut [&mut Buffer<'a, T, D, S>]) -> crate::Result<()> {
+        if self.params.is_accumulating() {
+            return Ok(());
+        }
+        self.params.check_params(params)?;
+        let step = self.params.next_step();
+        let hyper = self.config.hyper(step, self.decoupled);
+        self.params.update(&self.rule, params, &hyper)
+    }
+
+    #[inline]
+    fn steps(&self) -> usize {
+        self.params.steps()
+    }
+}
+
+/// Adam with decoupled weight decay: the parameters are decayed directly instead of adding an L2 penalty to the gradient.
+pub struct AdamW<'a, T: Unit, D: Device, S: Shape = ()>(Adam<'a, T, D, S>);
+
+impl<'a, T, D, S> AdamW<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: Device + Alloc<T> + OnNewBuffer<'a, T, D, S>,
+    S: Shape,
+{
+    #[inline]
+    pub fn new(device: &'a D, params: &[&Buffer<'a, T, D, S>], config: AdamConfig<T>) -> Self {
+        AdamW(Adam::with_decoupling(device, params, config, true))
+    }
+
+    #[inline]
+    pub fn config(&self) -> &AdamConfig<T> {
+        self.0.config()
+    }
+
+    /// Changes the learning rate, e.g. for learning rate schedules.
+    #[inline]
+    pub fn set_lr(&mut self, lr: T) {
+        self.0.set_lr(lr)
+    }
+
+    /// Returns the step count and the moment buffers.
+    #[inline]
+    pub fn state(&self) -> OptimizerState<T>
+    where
+        D: Read<T, S>,
+    {
+        self.0.state()
+    }
+
+    /// Loads a state previously returned by [`AdamW::state`].
+    #[inline]
+    pub fn load_state(&mut self, state: &OptimizerState<T>) -> crate::Result<()>
+    where
+        D: WriteBuf<T, S, D>,
+    {
+        self.0.load_state(state)
+    }
+}
+
+impl<'a, T, D, S> Optimizer<'a, T, D, S> for AdamW<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: ApplyUpdate<T, S> + GradActions + Alloc<T> + 'static,
+    S: Shape,
+{
+    #[inline]
+    fn step(&mut self, params: &mut [&mut Buffer<'a, T, D, S>]) -> crate::Result<()> {
+        self.0.step(params)
+    }
+
+    #[inline]
+    fn steps(&self) -> usize {
+        self.0.steps()
+    }
+}
+
+#[cfg(feature = "cpu")]
+#[cfg(test)]
+mod tests {
+    use crate::{
+        optim::{Adam, AdamConfig, AdamW, Optimizer},
+        Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU,
+    };
+
+    fn reference_adam(steps: usize, decoupled: bool) -> [f64; 3] {
+        let (lr, beta1, beta2, eps, wd) = (0.1, 0.9, 0.999, 1e-8, 0.1);
+        let mut w = [1f64, -2., 3.];
+        let mut m = [0f64; 3];
+        let mut v = [0f64; 3];
+
+        for step in 1..=steps {
+            for i in 0..3 {
+                let mut grad = 2. * w[i];
+                if decoupled {
+                    w[i] *= 1. - lr * wd;
+                } else {
+                    grad += wd * w[i];
+                }
+                m[i] = beta1 * m[i] + (1. - beta1) * grad;
+                v[i] = beta2 * v[i] + (1. - beta2) * grad * grad;
+                let m_hat = m[i] / (1. - beta1.powi(step as i32));
+                let v_hat = v[i] / (1. - beta2.powi(step as i32));
+                w[i] -= lr * m_hat / (v_hat.sqrt() + eps);
+            }
+        }
+        w
+    }
+
+    type Dev = CPU<Autograd<'static, Base>>;
+
+    fn train<'a>(
+        device: &'a Dev,
+        w: &mut crate::Buffer<'a, f64, Dev>,
+        optim: &mut impl Optimizer<'a, f64, Dev>,
+        steps: usize,
+    ) {
+        for _ in 0..steps {
+            // loss = w^2 -> grad = 2w
+            let loss = device.unary_ew(w, |x| x.mul(x), |x| x.mul(2.));
+            loss.backward().unwrap();
+            optim.step(&mut [w]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_adam_matches_reference() {
+        let device = CPU::<Autograd<Base>>::new();
+        let mut w = device.buffer([1f64, -2., 3.]).require_grad();
+        let mut adam = Adam::new(&device, &[&w], AdamConfig::new(0.1).with_weight_decay(0.1));
+
+        train(&device, &mut w, &mut adam, 5);
+        for (w, expected) in w.iter().zip(reference_adam(5, false)) {
+            assert!((w - expected).abs() < 1e-12);
+        }
+        assert_eq!(w.grad().as_slice(), [0.; 3]);
+    }
+
+    #[test]
+    fn test_adamw_matches_reference() {
+        let device = CPU::<Autograd<Base>>::new();
+        let mut w = device.buffer([1f64, -2., 3.]).require_grad();
+        let mut adamw = AdamW::new(&device, &[&w], AdamConfig::new(0.1).with_weight_decay(0.1));
+
+        train(&device, &mut w, &mut adamw, 5);
+        for (w, expected) in w.iter().zip(reference_adam(5, true)) {
+            assert!((w - expected).abs() < 1e-12);
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_adam_state_serde() {
+        use crate::optim::OptimizerState;
+
+        let device = CPU::<Autograd<Base>>::new();
+        let mut w = device.buffer([1f64, -2., 3.]).require_grad();
+        let config = AdamConfig::new(0.1).with_weight_decay(0.1);
+        let mut adam = Adam::new(&device, &[&w], config);
+        train(&device, &mut w, &mut adam, 3);
+
+        let json = serde_json::to_string(&adam.state()).unwrap();
+        let config_json = serde_json::to_string(adam.config()).unwrap();
+        drop(adam);
+
+        // resume training with a new optimizer
+        let mut adam = Adam::new(&device, &[&w], serde_json::from_str(&config_json).unwrap());
+        let state: OptimizerState<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.steps, 3);
+        adam.load_state(&state).unwrap();
+
+        train(&device, &mut w, &mut adam, 2);
+        for (w, expected) in w.iter().zip(reference_adam(5, false)) {
+            assert!((w - expected).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    Alloc, Buffer, Combiner, Device, Float, GradActions, OnNewBuffer, Read, Shape, Unit, WriteBuf,
+};
+
+use super::{ApplyUpdate, Optimizer, OptimizerState, ParamStates, UpdateRule};
+
+/// The hyperparameters of [`Sgd`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SgdConfig<T> {
+    pub lr: T,
+    pub momentum: T,
+    /// L2 penalty, which is added to the gradient.
+    pub weight_decay: T,
+    pub nesterov: bool,
+}
+
+impl<T: Float> SgdConfig<T> {
+    /// Plain stochastic gradient descent with the learning rate `lr`.
+    #[inline]
+    pub fn new(lr: T) -> Self {
+        SgdConfig {
+            lr,
+            momentum: T::zero(),
+            weight_decay: T::zero(),
+            nesterov: false,
+        }
+    }
+
+    #[inline]
+    pub fn with_momentum(mut self, momentum: T) -> Self {
+        self.momentum = momentum;
+        self
+    }
+
+    #[inline]
+    pub fn with_weight_decay(mut self, weight_decay: T) -> Self {
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    #[inline]
+    pub fn with_nesterov(mut self, nesterov: bool) -> Self {
+        self.nesterov = nesterov;
+        self
+    }
+}
+
+/// Stochastic gradient descent with optional (nesterov) momentum and weight decay.
+/// A velocity buffer per parameter is allocated if momentum is used.
+pub struct Sgd<'a, T: Unit, D: Device, S: Shape = ()> {
+    config: SgdConfig<T>,
+    rule: UpdateRule<T>,
+    params: ParamStates<'a, T, D, S>,
+}
+
+impl<'a, T, D, S> Sgd<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: Device + Alloc<T> + OnNewBuffer<'a, T, D, S>,
+    S: Shape,
+{
+    pub fn new(device: &'a D, params: &[&Buffer<'a, T, D, S>], config: SgdConfig<T>) -> Self {
+        // hyperparameters: [lr, momentum, weight_decay]
+        let rule = if config.momentum == T::zero() {
+            UpdateRule::new(0, 3).param(|v| {
+                v.param
+                    .sub(v.hyper[0].mul(v.grad.add(v.hyper[2].mul(v.param))))
+            })
+        } else {
+            let rule = UpdateRule::new(1, 3).state(0, |v| {
+                v.hyper[1]
+                    .mul(v.states[0])
+                    .add(v.grad.add(v.hyper[2].mul(v.param)))
+            });
+            if config.nesterov {
+                rule.param(|v| {
+                    v.param.sub(
+                        v.hyper[0].mul(
+                            v.grad
+                                .add(v.hyper[2].mul(v.param))
+                                .add(v.hyper[1].mul(v.states[0])),
+                        ),
+                    )
+                })
+            } else {
+                rule.param(|v| v.param.sub(v.hyper[0].mul(v.states[0])))
+            }
+        };
+
+        Sgd {
+            params: ParamStates::new(device, params, rule.states()),
+            config,
+            rule,
+        }
+    }
+
+    #[inline]
+    pub fn config(&self) -> &SgdConfig<T> {
+        &self.config
+    }
+
+    /// Changes the learning rate, e.g. for learning rate schedules.
+    #[inline]
+    pub fn set_lr(&mut self, lr: T) {
+        self.config.lr = lr;
+    }
+
+    /// Returns the step count and the velocity buffers.
+    #[inline]
+    pub fn state(&self) -> OptimizerState<T>
+    where
+        D: Read<T, S>,
+    {
+        self.params.state()
+    }
+
+    /// Loads a state previously returned by [`Sgd::state`].
+    #[inline]
+    pub fn load_state(&mut self, state: &OptimizerState<T>) -> crate::Result<()>
+    where
+        D: WriteBuf<T, S, D>,
+    {
+        self.params.load_state(state)
+    }
+}
+
+impl<'a, T, D, S> Optimizer<'a, T, D, S> for Sgd<'a, T, D, S>
+where
+    T: Unit + Float,
+    D: ApplyUpdate<T, S> + GradActions + Alloc<T> + 'static,
+    S: Shape,
+{
+    fn step(&mut self, params: &mut [&mut Buffer<'a, T, D, S>]) -> crate::Result<()> {
+        if self.params.is_accumulating() {
+            return Ok(());
+        }
+        self.params.check_params(params)?;
+        self.params.next_step();
+        let SgdConfig {
+            lr,
+            momentum,
+            weight_decay,
+            ..
+        } = self.config;
+        self.params
+            .update(&self.rule, params, &[lr, momentum, weight_decay])
+    }
+
+    #[inline]
+    fn steps(&self) -> usize {
+        self.params.steps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_sgd_momentum() {
+        use crate::{
+            optim::{Optimizer, Sgd, SgdConfig},
+            Autograd, Base, Combiner, Device, UnaryElementWiseMayGrad, CPU,
+        };
+
+        let device = CPU::<Autograd<Base>>::new();
+        let mut w = device.buffer([1f64, -2., 3.]).require_grad();
+        let config = SgdConfig::new(0.1)
+            .with_momentum(0.9)
+            .with_weight_decay(0.01);
+        let mut sgd = Sgd::new(&device, &[&w], config);
+
+        let mut expected = [1f64, -2., 3.];
+        let mut velocity = [0f64; 3];
+
+        for _ in 0..3 {
+            // loss = w^2 -> grad = 2w
+            let loss = device.unary_ew(&w, |x| x.mul(x), |x| x.mul(2.));
+            loss.backward().unwrap();
+            sgd.step(&mut [&mut w]).unwrap();
+
+            for (w, v) in expected.iter_mut().zip(&mut velocity) {
+                let grad = 2. * *w + 0.01 * *w;
+                *v = 0.9 * *v + grad;
+                *w -= 0.1 * *v;
+            }
+        }
+
+        assert_eq!(sgd.steps(), 3);
+        for (w, expected) in w.iter().zip(expected) {
+            assert!((w - expected).abs() < 1e-12);
+        }
+        assert_eq!(sgd.state().buffers[0][0].len(), 3);
+        assert_eq!(w.grad().as_slice(), [0.; 3]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_sgd_grad_accumulation() {
+        use crate::{
+            optim::{Optimizer, Sgd, SgdConfig},
+            Autograd, Base, Combiner, Device, GradActions, UnaryElementWiseMayGrad, CPU,
+        };
+
+        let device = CPU::<Autograd<Base>>::new();
+        let mut w = device.buffer([1f64, -2., 3.]).require_grad();
+        let mut sgd = Sgd::new(&device, &[&w], SgdConfig::new(0.1));
+        device.set_grad_accumulation_steps(2);
+
+        for step in 0..2 {
+            // loss = w^2 -> grad = 2w
+            let loss = device.unary_ew(&w, |x| x.mul(x), |x| x.mul(2.));
+            loss.backward().unwrap();
+            sgd.step(&mut [&mut w]).unwrap();
+
+            if step == 0 {
+                assert_eq!(sgd.steps(), 0);
+                assert_eq!(w.read(), [1., -2., 3.]);
+                assert_eq!(w.grad().read(), [2., -4., 6.]);
+            }
+        }
+
+        // mean gradient of both backward passes: 2w
+        assert_eq!(sgd.steps(), 1);
+        assert_eq!(w.read(), [0.8, -1.6, 2.4]);
+        assert_eq!(w.grad().read(), [0.; 3]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[test]
+    fn test_sgd_param_mismatch() {
+        use crate::{
+            optim::{Optimizer, Sgd, SgdConfig},
+            Autograd, Base, Device, DeviceError, CPU,
+        };
+
+        let device = CPU::<Autograd<Base>>::new();
+        let w = device.buffer([1f32, 2.]).require_grad();
+        let mut other = device.buffer([1f32, 2.]).require_grad();
+        let mut sgd = Sgd::new(&device, &[&w], SgdConfig::new(0.1));
+
+        let err = sgd.step(&mut [&mut other]).unwrap_err();
+        assert_eq!(
+            *err.downcast_ref::<DeviceError>().unwrap(),
+            DeviceError::OptimizerParamMismatch
+        );
+        // a rejected step must not be counted
+        assert_eq!(sgd.steps(), 0);
+    }
+}
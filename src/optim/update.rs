@@ -0,0 +1,245 @@
+use std::rc::Rc;
+
+use crate::{Buffer, Device, Resolve, Shape, TwoWay, Unit};
+
+/// The maximum amount of state buffers per parameter an [`UpdateRule`] can use.
+pub const MAX_STATES: usize = 4;
+/// The maximum amount of hyperparameters an [`UpdateRule`] can use.
+pub const MAX_HYPER: usize = 8;
+
+const STATE_MARKERS: [&str; MAX_STATES] = ["s0", "s1", "s2", "s3"];
+const C_HYPER_MARKERS: [&str; MAX_HYPER] = ["hp0", "hp1", "hp2", "hp3", "hp4", "hp5", "hp6", "hp7"];
+const WGSL_HYPER_MARKERS: [&str; MAX_HYPER] = [
+    "hp[0]", "hp[1]", "hp[2]", "hp[3]", "hp[4]", "hp[5]", "hp[6]", "hp[7]",
+];
+
+/// The values an update expression can use.
+/// `states` and `hyper` contain at least the amount of states and hyperparameters of the [`UpdateRule`].
+#[derive(Clone, Copy)]
+pub struct UpdateVars<T> {
+    pub param: Resolve<T>,
+    pub grad: Resolve<T>,
+    pub states: [Resolve<T>; MAX_STATES],
+    pub hyper: [Resolve<T>; MAX_HYPER],
+}
+
+type UpdateExpr<T> = Rc<dyn Fn(UpdateVars<T>) -> Box<dyn TwoWay<T>>>;
+
+/// The value that is assigned by an update step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateTarget {
+    Param,
+    State(usize),
+}
+
+/// An element-wise parameter update, e.g. of an optimizer.
+/// The steps are executed in order. A step sees the values assigned by previous steps.
+/// Hyperparameters are passed as kernel arguments, hence changing them does not require a new kernel.
+/// # Example
+/// ```
+/// use custos::{optim::UpdateRule, Combiner};
+///
+/// // momentum: v = 0.9 * v + g; p = p - lr * v
+/// let rule = UpdateRule::<f32>::new(1, 1)
+///     .state(0, |v| v.states[0].mul(0.9).add(v.grad))
+///     .param(|v| v.param.sub(v.hyper[0].mul(v.states[0])));
+///
+/// let mut param = 1.;
+/// let mut states = [1.];
+/// rule.eval(&mut param, 2., &mut states, &[0.5]);
+/// assert_eq!(states, [2.9]);
+/// assert_eq!(param, 1. - 0.5 * 2.9);
+/// ```
+pub struct UpdateRule<T> {
+    states: usize,
+    hyper: usize,
+    steps: Vec<(UpdateTarget, UpdateExpr<T>)>,
+}
+
+impl<T> UpdateRule<T> {
+    /// Creates an update rule using `states` state buffers and `hyper` hyperparameters.
+    /// # Panics
+    /// If `states` exceeds [`MAX_STATES`] or `hyper` exceeds [`MAX_HYPER`].
+    pub fn new(states: usize, hyper: usize) -> Self {
+        assert!(
+            states <= MAX_STATES,
+            "An update rule supports up to {MAX_STATES} states."
+        );
+        assert!(
+            hyper <= MAX_HYPER,
+            "An update rule supports up to {MAX_HYPER} hyperparameters."
+        );
+        UpdateRule {
+            states,
+            hyper,
+            steps: Vec::new(),
+        }
+    }
+
+    fn step<F: TwoWay<T> + 'static>(
+        mut self,
+        target: UpdateTarget,
+        f: impl Fn(UpdateVars<T>) -> F + 'static,
+    ) -> Self {
+        self.steps
+            .push((target, Rc::new(move |vars| Box::new(f(vars)))));
+        self
+    }
+
+    /// Assigns the result of `f` to the state with index `idx`.
+    #[inline]
+    pub fn state<F: TwoWay<T> + 'static>(
+        self,
+        idx: usize,
+        f: impl Fn(UpdateVars<T>) -> F + 'static,
+    ) -> Self {
+        assert!(idx < self.states, "State index out of range.");
+        self.step(UpdateTarget::State(idx), f)
+    }
+
+    /// Assigns the result of `f` to the parameter.
+    #[inline]
+    pub fn param<F: TwoWay<T> + 'static>(self, f: impl Fn(UpdateVars<T>) -> F + 'static) -> Self {
+        self.step(UpdateTarget::Param, f)
+    }
+
+    /// The amount of state buffers per parameter.
+    #[inline]
+    pub fn states(&self) -> usize {
+        self.states
+    }
+
+    /// The amount of hyperparameters.
+    #[inline]
+    pub fn hyper(&self) -> usize {
+        self.hyper
+    }
+
+    /// Updates a single element.
+    pub fn eval(&self, param: &mut T, grad: T, states: &mut [T], hyper: &[T])
+    where
+        T: Copy + Default + 'static,
+    {
+        let resolve = |val| Resolve { val, marker: "" };
+
+        let mut hyper_vals = [T::default(); MAX_HYPER];
+        hyper_vals[..self.hyper].copy_from_slice(&hyper[..self.hyper]);
+
+        for (target, expr) in &self.steps {
+            let mut state_vals = [T::default(); MAX_STATES];
+            state_vals[..self.states].copy_from_slice(&states[..self.states]);
+
+            let val = expr(UpdateVars {
+                param: resolve(*param),
+                grad: resolve(grad),
+                states: state_vals.map(resolve),
+                hyper: hyper_vals.map(resolve),
+            })
+            .eval();
+
+            match target {
+                UpdateTarget::Param => *param = val,
+                UpdateTarget::State(idx) => states[*idx] = val,
+            }
+        }
+    }
+
+    fn marked_vars(&self, hyper_markers: [&'static str; MAX_HYPER]) -> UpdateVars<T>
+    where
+        T: Default,
+    {
+        UpdateVars {
+            param: Resolve::with_marker("p"),
+            grad: Resolve::with_marker("g"),
+            states: STATE_MARKERS.map(Resolve::with_marker),
+            hyper: hyper_markers.map(Resolve::with_marker),
+        }
+    }
+
+    fn to_src(&self, vars: UpdateVars<T>, src: impl Fn(&dyn TwoWay<T>) -> String) -> String
+    where
+        T: Copy,
+    {
+        self.steps
+            .iter()
+            .map(|(target, expr)| {
+                let target = match target {
+                    UpdateTarget::Param => "p",
+                    UpdateTarget::State(idx) => STATE_MARKERS[*idx],
+                };
+                format!("{target} = {};\n", src(&*expr(vars)))
+            })
+            .collect()
+    }
+
+    /// Generates C (OpenCL / CUDA) statements.
+    /// The parameter is named `p`, the gradient `g`, states `s0`, `s1`, .. and hyperparameters `hp0`, `hp1`, ..
+    #[inline]
+    pub fn to_c_src(&self) -> String
+    where
+        T: Copy + Default,
+    {
+        self.to_src(self.marked_vars(C_HYPER_MARKERS), |expr| {
+            expr.to_cl_source()
+        })
+    }
+
+    /// Generates WGSL statements.
+    /// The parameter is named `p`, the gradient `g`, states `s0`, `s1`, .. and hyperparameters are read from the array `hp`.
+    #[inline]
+    pub fn to_wgsl_src(&self) -> String
+    where
+        T: Copy + Default,
+    {
+        self.to_src(self.marked_vars(WGSL_HYPER_MARKERS), |expr| {
+            expr.to_wgsl_source()
+        })
+    }
+}
+
+/// Applies an [`UpdateRule`] to every element of a parameter in a single (fused) kernel.
+pub trait ApplyUpdate<T: Unit, S: Shape = (), D: Device = Self>: Device {
+    /// Updates `param` and its `states` in place, using `grad` and the hyperparameters `hyper`.
+    /// # Example
+    #[cfg_attr(feature = "cpu", doc = "```")]
+    #[cfg_attr(not(feature = "cpu"), doc = "```ignore")]
+    /// use custos::{optim::{ApplyUpdate, UpdateRule}, Base, Combiner, Device, CPU};
+    ///
+    /// let device = CPU::<Base>::new();
+    /// let mut param = device.buffer([1., 2., 3.]);
+    /// let grad = device.buffer([1., 1., 2.]);
+    ///
+    /// let rule = UpdateRule::new(0, 1).param(|v| v.param.sub(v.hyper[0].mul(v.grad)));
+    /// device.apply_update(&rule, &mut param, &grad, &mut [], &[0.5]).unwrap();
+    /// assert_eq!(param.read(), [0.5, 1.5, 2.]);
+    /// ```
+    fn apply_update(
+        &self,
+        rule: &UpdateRule<T>,
+        param: &mut Buffer<T, D, S>,
+        grad: &Buffer<T, D, S>,
+        states: &mut [Buffer<T, D, S>],
+        hyper: &[T],
+    ) -> crate::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{optim::UpdateRule, Combiner};
+
+    #[test]
+    fn test_update_rule_src() {
+        let rule = UpdateRule::<f32>::new(1, 2)
+            .state(0, |v| v.hyper[0].mul(v.states[0]).add(v.grad))
+            .param(|v| v.param.sub(v.hyper[1].mul(v.states[0]).sqrt()));
+
+        assert_eq!(
+            rule.to_c_src(),
+            "s0 = ((hp0 * s0) + g);\np = (p - sqrt((hp1 * s0)));\n"
+        );
+        assert_eq!(
+            rule.to_wgsl_src(),
+            "s0 = ((hp[0] * s0) + g);\np = (p - sqrt((hp[1] * s0)));\n"
+        );
+    }
+}
@@ -70,6 +70,8 @@ macro_rules! impl_parents {
         impl<$($to_impl: $crate::Replicate + $crate::HasId, )+> $crate::AnyOp for ($($to_impl,)+) {
             type Replicated<'a> = ($($to_impl::Replication<'a>,)+);
 
+            const MUTABLE_ARGS: &'static [bool] = &[$($to_impl::MUTABLE,)+];
+
             #[cfg(feature = "std")]
             fn replication_fn<B: $crate::Downcast>(
                 op: impl for<'a> Fn(Self::Replicated<'a>) -> $crate::Result<()> + 'static,
@@ -1,6 +1,6 @@
 use super::ops::{
-    Abs, Add, Cos, Div, Eq, Exp, GEq, Identity, LEq, Ln, Max, Min, Mul, Neg, Pow, Sin, Sub, Tan,
-    Tanh,
+    Abs, Add, Cos, Div, Eq, Exp, GEq, Identity, LEq, Ln, Max, Min, Mul, Neg, Pow, Sin, Sqrt, Sub,
+    Tan, Tanh,
 };
 
 /// A trait that allows combining math operations.
@@ -113,4 +113,9 @@ pub trait Combiner: Sized {
     fn abs(self) -> Abs<Self> {
         Abs { comb: self }
     }
+
+    #[inline]
+    fn sqrt(self) -> Sqrt<Self> {
+        Sqrt { comb: self }
+    }
 }
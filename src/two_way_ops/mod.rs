@@ -2,6 +2,11 @@ mod eval;
 mod ops;
 mod resolve;
 pub use eval::*;
+pub use ops::{EvalTangent, MayEvalTangent, MayToCLTangentSource, MayToWgslTangentSource};
+#[cfg(feature = "std")]
+pub use ops::{ToCLTangentSource, ToWgslTangentSource};
+#[cfg(all(feature = "std", feature = "forward"))]
+pub(crate) use ops::tangent_src;
 
 #[cfg(feature = "std")]
 mod to_cl_source;
@@ -30,9 +35,27 @@ pub trait MayToWgslSource {}
 #[cfg(not(feature = "std"))]
 impl<T> MayToWgslSource for T {}
 
-pub trait TwoWay<T>: Eval<T> + MayToCLSource + MayToWgslSource {}
+/// A math operations chain that can be evaluated on the host and compiled to OpenCL C or WGSL.
+/// With the `forward` feature, its tangent (forward-mode derivative) can be evaluated and compiled as well.
+pub trait TwoWay<T>:
+    Eval<T>
+    + MayEvalTangent<T>
+    + MayToCLSource
+    + MayToWgslSource
+    + MayToCLTangentSource
+    + MayToWgslTangentSource
+{
+}
 
-impl<T, A: Eval<T> + MayToCLSource + MayToWgslSource> TwoWay<T> for A {}
+impl<T, A> TwoWay<T> for A where
+    A: Eval<T>
+        + MayEvalTangent<T>
+        + MayToCLSource
+        + MayToWgslSource
+        + MayToCLTangentSource
+        + MayToWgslTangentSource
+{
+}
 
 // impl<T> dyn TwoWay<T> + '_ {
 //     pub fn eval(&self) -> T
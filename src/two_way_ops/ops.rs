@@ -1,4 +1,5 @@
 mod cmps;
+mod tangent;
 mod unary;
 
 use crate::prelude::Float;
@@ -8,6 +9,9 @@ use crate::{ToCLSource, ToWgslSource};
 
 use super::{Combiner, Eval};
 pub use cmps::*;
+pub use tangent::*;
+#[cfg(all(feature = "std", feature = "forward"))]
+pub(crate) use tangent::src as tangent_src;
 pub use unary::*;
 
 // TODO: maybe use a macro to generate these
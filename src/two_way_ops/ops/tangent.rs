@@ -0,0 +1,646 @@
+//! Forward-mode differentiation of combined (via [`Combiner`](crate::Combiner)) math operations chains.
+//! Every [`Resolve`] leaf is treated as the input variable, constants have a tangent of zero.
+
+use crate::{prelude::Number, Eval, Float, Resolve};
+
+use super::*;
+
+/// Evaluates a combined (via [`Combiner`](crate::Combiner)) math operations chain to a value and its derivative (dual number).
+pub trait EvalTangent<T>: Eval<T> {
+    /// Evaluates the value and the tangent of a math operations chain.
+    /// `tangent` is the tangent of the input variable.
+    /// # Example
+    /// ```
+    /// use custos::{Combiner, EvalTangent, ToVal};
+    ///
+    /// let x = 3f32.to_val();
+    /// let (val, tangent) = x.mul(x).add(x.mul(2.)).eval_tangent(1.);
+    ///
+    /// assert_eq!(val, 15.);
+    /// assert_eq!(tangent, 8.);
+    /// ```
+    fn eval_tangent(&self, tangent: T) -> (T, T);
+}
+
+/// Generates the source of the tangent of a combined (via [`Combiner`](crate::Combiner)) math operations chain for OpenCL C (and possibly CUDA).
+#[cfg(feature = "std")]
+pub trait ToCLTangentSource: crate::ToCLSource {
+    /// Returns the source of the tangent expression. `tangent` is the variable name of the input variable's tangent.
+    /// # Example
+    /// ```
+    /// use custos::{Combiner, Resolve, ToCLTangentSource};
+    ///
+    /// let x = Resolve::<f32>::with_marker("x");
+    ///
+    /// assert_eq!(x.mul(3.).sin().to_cl_tangent_source("dx"), "(cos((x * 3.0)) * (dx * 3.0))");
+    /// ```
+    fn to_cl_tangent_source(&self, tangent: &str) -> String;
+}
+
+/// Generates the source of the tangent of a combined (via [`Combiner`](crate::Combiner)) math operations chain for WGSL.
+#[cfg(feature = "std")]
+pub trait ToWgslTangentSource: crate::ToWgslSource {
+    /// Returns the source of the tangent expression. `tangent` is the variable name of the input variable's tangent.
+    fn to_wgsl_tangent_source(&self, tangent: &str) -> String;
+}
+
+/// If the `forward` feature is enabled, this trait is implemented for all types that implement [`EvalTangent`].
+/// Otherwise, it is implemented for all types.
+#[cfg(feature = "forward")]
+pub trait MayEvalTangent<T>: EvalTangent<T> {}
+#[cfg(feature = "forward")]
+impl<T, A: EvalTangent<T>> MayEvalTangent<T> for A {}
+
+#[cfg(not(feature = "forward"))]
+pub trait MayEvalTangent<T> {}
+#[cfg(not(feature = "forward"))]
+impl<T, A> MayEvalTangent<T> for A {}
+
+/// If the `forward` feature is enabled and the `no-std` feature is disabled, this trait is implemented for all types that implement [`ToCLTangentSource`].
+#[cfg(all(feature = "std", feature = "forward"))]
+pub trait MayToCLTangentSource: ToCLTangentSource {}
+#[cfg(all(feature = "std", feature = "forward"))]
+impl<T: ToCLTangentSource> MayToCLTangentSource for T {}
+
+#[cfg(not(all(feature = "std", feature = "forward")))]
+pub trait MayToCLTangentSource {}
+#[cfg(not(all(feature = "std", feature = "forward")))]
+impl<T> MayToCLTangentSource for T {}
+
+/// If the `forward` feature is enabled and the `no-std` feature is disabled, this trait is implemented for all types that implement [`ToWgslTangentSource`].
+#[cfg(all(feature = "std", feature = "forward"))]
+pub trait MayToWgslTangentSource: ToWgslTangentSource {}
+#[cfg(all(feature = "std", feature = "forward"))]
+impl<T: ToWgslTangentSource> MayToWgslTangentSource for T {}
+
+#[cfg(not(all(feature = "std", feature = "forward")))]
+pub trait MayToWgslTangentSource {}
+#[cfg(not(all(feature = "std", feature = "forward")))]
+impl<T> MayToWgslTangentSource for T {}
+
+impl<T: Copy + Default + 'static> EvalTangent<T> for T {
+    #[inline]
+    fn eval_tangent(&self, _tangent: T) -> (T, T) {
+        (*self, T::default())
+    }
+}
+
+impl<T: Copy + 'static> EvalTangent<T> for Resolve<T> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        (self.val, tangent)
+    }
+}
+
+impl<C, R, T> EvalTangent<T> for Add<C, R>
+where
+    C: EvalTangent<T>,
+    R: EvalTangent<T>,
+    T: core::ops::Add<Output = T> + Copy,
+{
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        (a + b, da + db)
+    }
+}
+
+impl<C, R, T> EvalTangent<T> for Sub<C, R>
+where
+    C: EvalTangent<T>,
+    R: EvalTangent<T>,
+    T: core::ops::Sub<Output = T> + Copy,
+{
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        (a - b, da - db)
+    }
+}
+
+impl<C, R, T> EvalTangent<T> for Mul<C, R>
+where
+    C: EvalTangent<T>,
+    R: EvalTangent<T>,
+    T: core::ops::Mul<Output = T> + core::ops::Add<Output = T> + Copy,
+{
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        (a * b, da * b + a * db)
+    }
+}
+
+impl<C, R, T> EvalTangent<T> for Div<C, R>
+where
+    C: EvalTangent<T>,
+    R: EvalTangent<T>,
+    T: core::ops::Div<Output = T> + core::ops::Mul<Output = T> + core::ops::Sub<Output = T> + Copy,
+{
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        (a / b, (da * b - a * db) / (b * b))
+    }
+}
+
+impl<C: EvalTangent<T>, R: EvalTangent<T>, T: Float> EvalTangent<T> for Pow<C, R> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        let val = a.powf(b);
+        let mut dval = b * a.powf(b - T::one()) * da;
+        // avoids NaNs for negative bases raised to constant exponents
+        if db != T::zero() {
+            dval += val * Float::ln(&a) * db;
+        }
+        (val, dval)
+    }
+}
+
+impl<C: EvalTangent<T>, R: EvalTangent<T>, T: Float> EvalTangent<T> for Min<C, R> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        if a <= b {
+            (a, da)
+        } else {
+            (b, db)
+        }
+    }
+}
+
+impl<C: EvalTangent<T>, R: EvalTangent<T>, T: Float> EvalTangent<T> for Max<C, R> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let (b, db) = self.rhs.eval_tangent(tangent);
+        if a >= b {
+            (a, da)
+        } else {
+            (b, db)
+        }
+    }
+}
+
+macro_rules! impl_zero_tangent_cmps {
+    ($($cmp:ident),*) => {
+        $(
+            impl<C: Eval<T>, R: Eval<T>, T: Number> EvalTangent<T> for $cmp<C, R> {
+                #[inline]
+                fn eval_tangent(&self, _tangent: T) -> (T, T) {
+                    (self.eval(), T::zero())
+                }
+            }
+
+            #[cfg(feature = "std")]
+            impl<C: crate::ToCLSource, R: crate::ToCLSource> ToCLTangentSource for $cmp<C, R> {
+                #[inline]
+                fn to_cl_tangent_source(&self, _tangent: &str) -> String {
+                    "0".to_string()
+                }
+            }
+
+            #[cfg(feature = "std")]
+            impl<C: crate::ToWgslSource, R: crate::ToWgslSource> ToWgslTangentSource for $cmp<C, R> {
+                #[inline]
+                fn to_wgsl_tangent_source(&self, _tangent: &str) -> String {
+                    "0".to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_zero_tangent_cmps!(GEq, LEq, Eq);
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Identity<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        self.comb.eval_tangent(tangent)
+    }
+}
+
+impl<T: core::ops::Neg<Output = T>, C: EvalTangent<T>> EvalTangent<T> for Neg<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        (-a, -da)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Exp<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let val = Float::exp(&a);
+        (val, val * da)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Sin<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        (Float::sin(&a), Float::cos(&a) * da)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Cos<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        (Float::cos(&a), -Float::sin(&a) * da)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Tan<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let cos = Float::cos(&a);
+        (Float::tan(&a), da / (cos * cos))
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Tanh<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let val = Float::tanh(&a);
+        (val, (T::one() - val * val) * da)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Ln<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        (Float::ln(&a), da / a)
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Abs<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        if a < T::zero() {
+            (-a, -da)
+        } else {
+            (a, da)
+        }
+    }
+}
+
+impl<T: Float, C: EvalTangent<T>> EvalTangent<T> for Sqrt<C> {
+    #[inline]
+    fn eval_tangent(&self, tangent: T) -> (T, T) {
+        let (a, da) = self.comb.eval_tangent(tangent);
+        let val = Float::sqrt(&a);
+        (val, da / (T::two() * val))
+    }
+}
+
+/// Builds the tangent source strings. Zero tangents (of constants) are folded away.
+/// The arithmetic is shared by OpenCL C and WGSL.
+#[cfg(feature = "std")]
+pub(crate) mod src {
+    #[inline]
+    pub fn is_zero(src: &str) -> bool {
+        src == "0"
+    }
+
+    pub fn add(lhs: String, rhs: String) -> String {
+        match (is_zero(&lhs), is_zero(&rhs)) {
+            (true, _) => rhs,
+            (_, true) => lhs,
+            _ => format!("({lhs} + {rhs})"),
+        }
+    }
+
+    pub fn sub(lhs: String, rhs: String) -> String {
+        match (is_zero(&lhs), is_zero(&rhs)) {
+            (_, true) => lhs,
+            (true, _) => neg(rhs),
+            _ => format!("({lhs} - {rhs})"),
+        }
+    }
+
+    pub fn mul(lhs: String, rhs: String) -> String {
+        if is_zero(&lhs) || is_zero(&rhs) {
+            return "0".to_string();
+        }
+        format!("({lhs} * {rhs})")
+    }
+
+    pub fn div(lhs: String, rhs: String) -> String {
+        if is_zero(&lhs) {
+            return lhs;
+        }
+        format!("({lhs} / {rhs})")
+    }
+
+    pub fn neg(src: String) -> String {
+        if is_zero(&src) {
+            return src;
+        }
+        format!("-({src})")
+    }
+}
+
+/// Implements the tangent source generation for OpenCL C and WGSL.
+/// `$val` and `$tangent` are the source-generating methods of the respective language, `$select` builds a conditional expression.
+#[cfg(feature = "std")]
+macro_rules! impl_tangent_source {
+    ($trait:ident, $src_trait:ident, $tangent_fn:ident, $val:ident, $select:expr) => {
+        impl<T: crate::Numeric + $src_trait> $trait for T {
+            #[inline]
+            fn $tangent_fn(&self, _tangent: &str) -> String {
+                "0".to_string()
+            }
+        }
+
+        impl<T> $trait for Resolve<T> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                tangent.to_string()
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Add<C, R> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::add(
+                    self.comb.$tangent_fn(tangent),
+                    self.rhs.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Sub<C, R> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::sub(
+                    self.comb.$tangent_fn(tangent),
+                    self.rhs.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Mul<C, R> {
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::add(
+                    src::mul(self.comb.$tangent_fn(tangent), self.rhs.$val()),
+                    src::mul(self.comb.$val(), self.rhs.$tangent_fn(tangent)),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Div<C, R> {
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let (a, b) = (self.comb.$val(), self.rhs.$val());
+                src::div(
+                    src::sub(
+                        src::mul(self.comb.$tangent_fn(tangent), b.clone()),
+                        src::mul(a, self.rhs.$tangent_fn(tangent)),
+                    ),
+                    format!("({b} * {b})"),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Pow<C, R> {
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let (a, b) = (self.comb.$val(), self.rhs.$val());
+                src::add(
+                    src::mul(
+                        format!("({b} * pow({a}, ({b} - 1)))"),
+                        self.comb.$tangent_fn(tangent),
+                    ),
+                    src::mul(
+                        format!("(pow({a}, {b}) * log({a}))"),
+                        self.rhs.$tangent_fn(tangent),
+                    ),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Min<C, R> {
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let select: fn(String, String, String) -> String = $select;
+                select(
+                    format!("({} <= {})", self.comb.$val(), self.rhs.$val()),
+                    self.comb.$tangent_fn(tangent),
+                    self.rhs.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait, R: $trait> $trait for Max<C, R> {
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let select: fn(String, String, String) -> String = $select;
+                select(
+                    format!("({} >= {})", self.comb.$val(), self.rhs.$val()),
+                    self.comb.$tangent_fn(tangent),
+                    self.rhs.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait> $trait for Identity<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                self.comb.$tangent_fn(tangent)
+            }
+        }
+
+        impl<C: $trait> $trait for Neg<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::neg(self.comb.$tangent_fn(tangent))
+            }
+        }
+
+        impl<C: $trait> $trait for Exp<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::mul(self.$val(), self.comb.$tangent_fn(tangent))
+            }
+        }
+
+        impl<C: $trait> $trait for Sin<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::mul(
+                    format!("cos({})", self.comb.$val()),
+                    self.comb.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait> $trait for Cos<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::neg(src::mul(
+                    format!("sin({})", self.comb.$val()),
+                    self.comb.$tangent_fn(tangent),
+                ))
+            }
+        }
+
+        impl<C: $trait> $trait for Tan<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let cos = format!("cos({})", self.comb.$val());
+                src::div(self.comb.$tangent_fn(tangent), format!("({cos} * {cos})"))
+            }
+        }
+
+        impl<C: $trait> $trait for Tanh<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                let tanh = self.$val();
+                src::mul(
+                    format!("(1 - ({tanh} * {tanh}))"),
+                    self.comb.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait> $trait for Ln<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::div(self.comb.$tangent_fn(tangent), self.comb.$val())
+            }
+        }
+
+        impl<C: $trait> $trait for Abs<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::mul(
+                    format!("sign({})", self.comb.$val()),
+                    self.comb.$tangent_fn(tangent),
+                )
+            }
+        }
+
+        impl<C: $trait> $trait for Sqrt<C> {
+            #[inline]
+            fn $tangent_fn(&self, tangent: &str) -> String {
+                src::div(
+                    self.comb.$tangent_fn(tangent),
+                    format!("(2 * {})", self.$val()),
+                )
+            }
+        }
+    };
+}
+
+#[cfg(feature = "std")]
+impl_tangent_source!(
+    ToCLTangentSource,
+    ToCLSource,
+    to_cl_tangent_source,
+    to_cl_source,
+    |cond, lhs, rhs| format!("({cond} ? {lhs} : {rhs})")
+);
+
+#[cfg(feature = "std")]
+impl_tangent_source!(
+    ToWgslTangentSource,
+    ToWgslSource,
+    to_wgsl_tangent_source,
+    to_wgsl_source,
+    |cond, lhs, rhs| format!("select({rhs}, {lhs}, {cond})")
+);
+
+#[cfg(feature = "std")]
+use crate::{ToCLSource, ToWgslSource};
+
+#[cfg(test)]
+mod tests {
+    use crate::{Combiner, EvalTangent, Resolve, ToVal};
+
+    #[cfg(feature = "std")]
+    use crate::{ToCLTangentSource, ToWgslTangentSource};
+
+    fn central_difference<O: EvalTangent<f64>>(f: impl Fn(Resolve<f64>) -> O, x: f64) -> f64 {
+        let eps = 1e-6;
+        (f((x + eps).to_val()).eval() - f((x - eps).to_val()).eval()) / (2. * eps)
+    }
+
+    fn check_tangent<O: EvalTangent<f64>>(f: impl Fn(Resolve<f64>) -> O, xs: &[f64]) {
+        for &x in xs {
+            let (val, tangent) = f(x.to_val()).eval_tangent(1.);
+            assert_eq!(val, f(x.to_val()).eval());
+            let numerical = central_difference(&f, x);
+            assert!(
+                (tangent - numerical).abs() < 1e-5,
+                "x = {x}: tangent {tangent} != numerical {numerical}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_tangent_unary_ops() {
+        let xs = [0.3, 0.7, 1.4, 2.1];
+        check_tangent(|x| x.sin().mul(x.cos()), &xs);
+        check_tangent(|x| x.tan().add(x.tanh()), &xs);
+        check_tangent(|x| x.exp().sub(x.ln()), &xs);
+        check_tangent(|x| x.sqrt().div(x.add(1.)), &xs);
+        check_tangent(|x| x.sub(1.).abs().neg(), &xs);
+        check_tangent(|x| x.pow(3.).add(x.pow(x)), &xs);
+        check_tangent(|x| x.max(1.).min(2.).mul(x), &xs);
+    }
+
+    #[test]
+    fn test_eval_tangent_scales_with_seed() {
+        let (val, tangent) = 2f64.to_val().mul(2f64.to_val()).eval_tangent(0.5);
+        assert_eq!(val, 4.);
+        assert_eq!(tangent, 2.);
+
+        let (_, tangent) = 2f64.to_val().geq(1.).mul(3.).eval_tangent(1.);
+        assert_eq!(tangent, 0.);
+    }
+
+    #[test]
+    fn test_eval_tangent_pow_negative_base() {
+        let (val, tangent) = (-2f64).to_val().pow(2.).eval_tangent(1.);
+        assert_eq!(val, 4.);
+        assert_eq!(tangent, -4.);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_tangent_sources() {
+        let x = Resolve::<f32>::with_marker("x");
+
+        assert_eq!(x.mul(x).to_cl_tangent_source("dx"), "((dx * x) + (x * dx))");
+        assert_eq!(
+            x.add(2.).ln().to_cl_tangent_source("dx"),
+            "(dx / (x + 2.0))"
+        );
+        assert_eq!(
+            x.geq(0.).mul(x).to_cl_tangent_source("dx"),
+            "((x >= 0.0) * dx)"
+        );
+        assert_eq!(
+            x.min(1.).to_cl_tangent_source("dx"),
+            "((x <= 1.0) ? dx : 0)"
+        );
+        assert_eq!(
+            x.min(1f32).to_wgsl_tangent_source("dx"),
+            "select(0, dx, (x <= f32(1.0)))"
+        );
+        assert_eq!(
+            x.exp().neg().to_wgsl_tangent_source("dx"),
+            "-((exp(x) * dx))"
+        );
+    }
+}
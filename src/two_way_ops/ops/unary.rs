@@ -265,3 +265,32 @@ impl<C: ToWgslSource> ToWgslSource for Abs<C> {
         format!("abs({})", self.comb.to_wgsl_source())
     }
 }
+
+pub struct Sqrt<C> {
+    pub comb: C,
+}
+
+impl<C> Combiner for Sqrt<C> {}
+
+impl<T: Float, C: Eval<T>> Eval<T> for Sqrt<C> {
+    #[inline]
+    fn eval(&self) -> T {
+        Float::sqrt(&self.comb.eval())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: ToCLSource> ToCLSource for Sqrt<C> {
+    #[inline]
+    fn to_cl_source(&self) -> String {
+        format!("sqrt({})", self.comb.to_cl_source())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<C: ToWgslSource> ToWgslSource for Sqrt<C> {
+    #[inline]
+    fn to_wgsl_source(&self) -> String {
+        format!("sqrt({})", self.comb.to_wgsl_source())
+    }
+}
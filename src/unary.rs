@@ -1,6 +1,6 @@
 use crate::{
     AddGradFn, AddOperation, Alloc, Buffer, Device, Eval, HasId, MayGradActions, MayToCLSource,
-    Resolve, Shape, TwoWay, Unit, ZeroGrad,
+    Resolve, Retriever, Shape, TwoWay, Unit, WriteBuf, ZeroGrad,
 };
 
 /// Applies a function to a buffer and returns a new buffer.
@@ -92,6 +92,22 @@ pub trait UnaryElementWiseMayGrad<T: Unit, D: Device, S: Shape>: Device {
     where
         FO: TwoWay<T>,
         GO: Eval<T> + MayToCLSource + 'static;
+
+    /// Like [`UnaryElementWiseMayGrad::unary_ew`], but additionally takes the second derivative.
+    /// This is required to compute higher-order gradients (see [`Buffer::backward_create_graph`]).
+    /// [`UnaryElementWiseMayGrad::unary_ew`] returns an error during such a backward pass.
+    fn unary_ew_second_order<'a, FO, GO, GGO>(
+        &'a self,
+        buf: &Buffer<'a, T, D, S>,
+        forward_fn: impl Fn(Resolve<T>) -> FO + Copy + 'static,
+        grad_fn: fn(Resolve<T>) -> GO,
+        grad_grad_fn: fn(Resolve<T>) -> GGO,
+    ) -> Buffer<T, Self, S>
+    where
+        T: Copy,
+        FO: TwoWay<T>,
+        GO: Eval<T> + MayToCLSource + 'static,
+        GGO: Eval<T> + MayToCLSource + 'static;
 }
 
 impl<T, D, S> UnaryElementWiseMayGrad<T, D, S> for D
@@ -113,32 +129,176 @@ where
         FO: TwoWay<T>,
         GO: Eval<T> + MayToCLSource + 'static,
     {
-        let out = self.apply_fn(buf, forward_fn);
+        unary_ew_with_grad(self, buf, forward_fn, grad_fn, |_, _| {
+            Err(crate::DeviceError::SecondOrderGradUnavailable.into())
+        })
+    }
+
+    #[inline(always)]
+    fn unary_ew_second_order<'a, FO, GO, GGO>(
+        &'a self,
+        buf: &Buffer<'a, T, D, S>,
+        forward_fn: impl Fn(Resolve<T>) -> FO + Copy + 'static,
+        grad_fn: fn(Resolve<T>) -> GO,
+        grad_grad_fn: fn(Resolve<T>) -> GGO,
+    ) -> Buffer<T, Self, S>
+    where
+        T: Copy,
+        FO: TwoWay<T>,
+        GO: Eval<T> + MayToCLSource + 'static,
+        GGO: Eval<T> + MayToCLSource + 'static,
+    {
+        unary_ew_with_grad(self, buf, forward_fn, grad_fn, move |buf, out| {
+            add_unary_second_order_grad_fn(buf, out, grad_fn, grad_grad_fn);
+            Ok(())
+        })
+    }
+}
 
-        self.add_grad_fn((buf, &out), move |(buf, out)| {
-            if !buf.requires_grad() {
+/// Passes the values of a buffer forward, but stops the gradient flow in the backward pass.
+pub trait StopGradient<T: Unit, S: Shape = ()>: Device {
+    /// Returns a copy of `buf` that does not require a gradient.
+    /// No gradient function is recorded, i.e. the gradient of `buf` with respect to the returned buffer is zero.
+    /// Unlike [`Buffer::detach`], the returned buffer does not share its memory with `buf`.
+    /// # Example
+    #[cfg_attr(all(feature = "autograd", feature = "cpu"), doc = "```")]
+    #[cfg_attr(not(all(feature = "autograd", feature = "cpu")), doc = "```ignore")]
+    /// use custos::{Autograd, Base, Combiner, Device, StopGradient, UnaryElementWiseMayGrad, CPU};
+    ///
+    /// let device = CPU::<Autograd<Base>>::new();
+    /// let x = device.buffer([1., 2., 3.]).require_grad();
+    ///
+    /// let squared = device.unary_ew(&x, |x| x.mul(x), |x| x.mul(2.));
+    /// let stopped = device.stop_gradient(&squared).unwrap();
+    /// assert_eq!(stopped.read(), [1., 4., 9.]);
+    ///
+    /// let out = device.unary_ew(&stopped, |x| x.mul(3.), |_| 3.);
+    /// out.backward().unwrap();
+    /// assert_eq!(x.grad().read(), [0.; 3]);
+    /// ```
+    fn stop_gradient<'a>(
+        &'a self,
+        buf: &Buffer<'a, T, Self, S>,
+    ) -> crate::Result<Buffer<'a, T, Self, S>>;
+}
+
+impl<T, D, S> StopGradient<T, S> for D
+where
+    T: Unit + 'static,
+    D: Retriever<T, S> + AddOperation + WriteBuf<T, S> + 'static,
+    S: Shape,
+{
+    fn stop_gradient<'a>(
+        &'a self,
+        buf: &Buffer<'a, T, Self, S>,
+    ) -> crate::Result<Buffer<'a, T, Self, S>> {
+        // without parents, the output does not require a gradient
+        let mut out = self.retrieve(buf.len(), ())?;
+
+        self.add_op((&mut out, buf), |(out, buf)| {
+            out.device().write_buf(out, buf);
+            Ok(())
+        })?;
+
+        Ok(out)
+    }
+}
+
+/// `second_order` is called instead of recording the gradient function's own gradient function if the graph of the gradient computation is created.
+fn unary_ew_with_grad<'a, T, D, S, FO, GO>(
+    device: &'a D,
+    buf: &Buffer<'a, T, D, S>,
+    forward_fn: impl Fn(Resolve<T>) -> FO + Copy + 'static,
+    grad_fn: fn(Resolve<T>) -> GO,
+    second_order: impl Fn(&Buffer<T, D, S>, &Buffer<T, D, S>) -> crate::Result<()> + 'static,
+) -> Buffer<'a, T, D, S>
+where
+    T: Unit + 'static,
+    D: AddGradFn + ApplyFunction<T, S, D> + UnaryGrad<T, S, D> + AddOperation + MayGradActions,
+    D: Alloc<T> + ZeroGrad<T> + 'static,
+    S: Shape,
+    FO: TwoWay<T>,
+    GO: Eval<T> + MayToCLSource + 'static,
+{
+    let out = device.apply_fn(buf, forward_fn);
+
+    device.add_second_order_grad_fn((buf, &out), move |(buf, out)| {
+        if !buf.requires_grad() {
+            return Ok(());
+        }
+        // lazy execution is already disabled during backward pass
+        buf.device().eagerly(|| unsafe {
+            buf.device()
+                .add_unary_grad(buf, buf.grad_mut_unbound(), out.grad(), grad_fn);
+        });
+
+        if creates_graph(buf.device()) {
+            second_order(buf, out)?;
+        }
+        Ok(())
+    });
+
+    // #[cfg(feature = "autograd")]
+    // {
+    //     let ids = (buf.id(), out.id());
+    //     self.add_grad_fn(move |grads| {
+    //         let (lhs, lhs_grad, out_grad) = grads.get_double::<T, S, S, D>(ids);
+    //         lhs.device()
+    //             .add_unary_grad(lhs, lhs_grad, out_grad, _grad_fn);
+    //     });
+    // }
+
+    out
+}
+
+/// Records the gradient function of `buf.grad() += out.grad() * grad_fn(buf)`.
+/// The gradients are passed explicitly, as the next backward pass computes new gradients for `buf` and `out`.
+fn add_unary_second_order_grad_fn<T, D, S, GO, GGO>(
+    buf: &Buffer<T, D, S>,
+    out: &Buffer<T, D, S>,
+    grad_fn: fn(Resolve<T>) -> GO,
+    grad_grad_fn: fn(Resolve<T>) -> GGO,
+) where
+    T: Unit + Copy + 'static,
+    D: AddGradFn + ApplyFunction<T, S, D> + UnaryGrad<T, S, D> + AddOperation + MayGradActions,
+    D: Alloc<T> + ZeroGrad<T> + 'static,
+    S: Shape,
+    GO: Eval<T> + MayToCLSource + 'static,
+    GGO: Eval<T> + MayToCLSource + 'static,
+{
+    buf.device().add_grad_fn(
+        (buf, buf.grad(), out.grad()),
+        move |(buf, buf_grad, out_grad)| {
+            let Some(grad_grad) = buf_grad.try_grad() else {
                 return Ok(());
-            }
-            // lazy execution is already disabled during backward pass
-            buf.device().eagerly(|| unsafe {
-                buf.device()
-                    .add_unary_grad(buf, buf.grad_mut_unbound(), out.grad(), grad_fn);
+            };
+            let device = buf.device();
+
+            device.eagerly(|| unsafe {
+                // d buf.grad() / d out.grad() = grad_fn(buf)
+                device.add_unary_grad(buf, out_grad.grad_mut_unbound(), grad_grad, grad_fn);
+
+                // d buf.grad() / d buf = out.grad() * grad_grad_fn(buf)
+                let mut scaled_grad = device.apply_fn(out_grad, |x| x);
+                device.zero_grad(scaled_grad.base_mut());
+                device.add_unary_grad(out_grad, &mut scaled_grad, grad_grad, |x| x);
+                device.add_unary_grad(buf, buf.grad_mut_unbound(), &scaled_grad, grad_grad_fn);
             });
             Ok(())
-        });
+        },
+    );
+}
 
-        // #[cfg(feature = "autograd")]
-        // {
-        //     let ids = (buf.id(), out.id());
-        //     self.add_grad_fn(move |grads| {
-        //         let (lhs, lhs_grad, out_grad) = grads.get_double::<T, S, S, D>(ids);
-        //         lhs.device()
-        //             .add_unary_grad(lhs, lhs_grad, out_grad, _grad_fn);
-        //     });
-        // }
-
-        out
+/// Returns `true` if gradient functions are executed by [`Tape::backward_create_graph`](crate::Tape::backward_create_graph).
+#[inline]
+pub(crate) fn creates_graph<D: MayGradActions>(_device: &D) -> bool {
+    #[cfg(feature = "autograd")]
+    {
+        unsafe { _device.gradients() }.is_some_and(|gradients| gradients.create_graph.get())
     }
+
+    #[cfg(not(feature = "autograd"))]
+    false
 }
 
 #[cfg(test)]
@@ -251,6 +411,99 @@ mod tests {
         test_unary_autograd(&device);
     }
 
+    #[cfg(feature = "autograd")]
+    fn test_unary_gradcheck<'a, 'b, D>(device: &'a D)
+    where
+        D: 'static
+            + crate::WriteBuf<f64>
+            + crate::Read<f64>
+            + crate::GradActions
+            + crate::AddGradFn
+            + crate::TapeActions<'b>
+            + crate::HasAutograd
+            + crate::UnaryElementWiseMayGrad<f64, D, ()>
+            + crate::Alloc<f64>
+            + crate::CachedBuffers
+            + crate::AddOperation
+            + crate::ZeroGrad<f64>
+            + crate::OnNewBuffer<'a, f64, D, ()>,
+    {
+        use crate::{gradcheck, Combiner, GradCheckConfig};
+
+        let mut buf = device
+            .buffer([0.5, -1.3, 2.1, 0.7, -0.2, 1.9])
+            .require_grad();
+        let config = GradCheckConfig::default();
+
+        let report = gradcheck(
+            device,
+            &mut [&mut buf],
+            |inputs| device.unary_ew(inputs[0], |x| x.sin(), |x| x.cos()),
+            config,
+        )
+        .unwrap();
+        assert!(report.passed(), "{report}");
+
+        let report = gradcheck(
+            device,
+            &mut [&mut buf],
+            |inputs| device.unary_ew(inputs[0], |x| x.exp().mul(x), |x| x.exp().mul(x.add(1.))),
+            config,
+        )
+        .unwrap();
+        assert!(report.passed(), "{report}");
+
+        let report = gradcheck(
+            device,
+            &mut [&mut buf],
+            |inputs| {
+                device.unary_ew(
+                    inputs[0],
+                    |x| x.tanh(),
+                    |x| x.tanh().mul(x.tanh()).neg().add(1.),
+                )
+            },
+            config,
+        )
+        .unwrap();
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.checked, 6);
+
+        // sin'(x) != sin(x)
+        let report = gradcheck(
+            device,
+            &mut [&mut buf],
+            |inputs| device.unary_ew(inputs[0], |x| x.sin(), |x| x.sin()),
+            config,
+        )
+        .unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.worst.len(), config.max_reported);
+
+        // the inputs are restored
+        assert_eq!(buf.read_to_vec(), [0.5, -1.3, 2.1, 0.7, -0.2, 1.9]);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_unary_elementwise_gradcheck() {
+        use crate::{Autograd, Base, CPU};
+
+        let device = CPU::<Autograd<Base>>::new();
+        test_unary_gradcheck(&device);
+    }
+
+    #[cfg(feature = "cpu")]
+    #[cfg(feature = "autograd")]
+    #[test]
+    fn test_unary_elementwise_gradcheck_cached() {
+        use crate::{Autograd, Base, Cached, CPU};
+
+        let device = CPU::<Autograd<Cached<Base>>>::new();
+        test_unary_gradcheck(&device);
+    }
+
     #[cfg(feature = "cpu")]
     #[cfg(feature = "autograd")]
     #[test]
@@ -1,15 +1,19 @@
-use custos::prelude::*;
-use cuwanto_server as cuw;
+use custos::{network::RemoteServer, prelude::*, ApplyFunction, Combiner};
 
 #[test]
 fn test_network_device() -> custos::Result<()> {
-    let device = Network::new("127.0.0.1:11001", cuw::DeviceType::CPU)?;
-    //let device = CPU::<Base>::new();
-    //let device = Stack::new().unwrap();
-    //let buf = device.cuw_client.borrow_mut().alloc_buf::<f64>(4)?;
-    let buf = Buffer::<f64, _>::from((&device, &[1., 2., 3., 4.]));
-    // ....
-    println!("buf: {:?}", buf.read());
-    //buf.clear();
-    Ok(())
+    let server = RemoteServer::bind("127.0.0.1:0")?;
+    let addr = server.local_addr()?;
+    let server = std::thread::spawn(move || server.serve_once(&CPU::<Base>::new()));
+
+    {
+        let device = Remote::<Base>::new(addr)?;
+        let buf = Buffer::<f64, _>::from((&device, &[1., 2., 3., 4.]));
+        assert_eq!(buf.read(), [1., 2., 3., 4.]);
+
+        let out = device.apply_fn(&buf, |x| x.mul(x));
+        assert_eq!(out.read(), [1., 4., 9., 16.]);
+    }
+
+    server.join().unwrap()
 }